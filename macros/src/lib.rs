@@ -0,0 +1,379 @@
+//! Procedural macro front end for [rustfsm](https://docs.rs/rustfsm).
+//!
+//! Use it through the `macros` feature of `rustfsm`, which re-exports
+//! [`fsm!`].
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    braced,
+    ext::IdentExt,
+    parenthesized,
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+    punctuated::Punctuated,
+    Error, Expr, Ident, Result, Token, Type,
+};
+
+/// State markers understood by the `rustfsm!` back end
+const STATE_MARKERS: &[&str] = &["final", "fault"];
+
+/// Event markers understood by the `rustfsm!` back end
+const EVENT_MARKERS: &[&str] = &["internal"];
+
+/// Fields of the generated machine, and methods of its builder, which the
+/// members can't be named after, as in `rustfsm::MACHINE_FIELDS`
+const MACHINE_FIELDS: &[&str] = &[
+    "current_state",
+    "context",
+    "status",
+    "paused",
+    "source",
+    "last_fault",
+    "coverage",
+    "trace",
+    "metrics",
+    "stay",
+    "queue",
+    "output",
+    "guard",
+    "timers",
+    "filter",
+    "interceptors",
+    "rng",
+    "state",
+    "build",
+];
+
+/// Optional sections following the context
+const SECTIONS: &[&str] = &[
+    "Transitions",
+    "Clock",
+    "Trace",
+    "Watchdog",
+    "Timers",
+    "Filter",
+    "Interceptors",
+    "Rng",
+    "Derive",
+    "Aliases",
+    "Queue",
+    "Views",
+    "Changes",
+    "Output",
+    "Reply",
+    "Hibernate",
+    "Invariant",
+    "Validate",
+    "Initial",
+    "Config",
+    "Dispatch",
+];
+
+/// `name: Type = default` entry of the members or the context
+struct Field {
+    name: Ident,
+    ty: Type,
+    default: Expr,
+}
+
+impl Parse for Field {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let name = input.parse()?;
+        input.parse::<Token![:]>()?;
+        let ty = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let default = input.parse()?;
+        Ok(Self { name, ty, default })
+    }
+}
+
+impl Field {
+    fn to_tokens(&self) -> TokenStream2 {
+        let Self { name, ty, default } = self;
+        quote!(#name: #ty = #default)
+    }
+}
+
+/// `@marker Name(Data, ...)` entry of the states or events
+struct Variant {
+    markers: Vec<Ident>,
+    name: Ident,
+    data: Option<Punctuated<Type, Token![,]>>,
+}
+
+impl Parse for Variant {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut markers = Vec::new();
+        while input.peek(Token![@]) {
+            input.parse::<Token![@]>()?;
+            markers.push(input.call(Ident::parse_any)?);
+        }
+        let name = input.parse()?;
+        let data = if input.peek(syn::token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            Some(content.parse_terminated(Type::parse, Token![,])?)
+        } else {
+            None
+        };
+        Ok(Self {
+            markers,
+            name,
+            data,
+        })
+    }
+}
+
+impl Variant {
+    fn to_tokens(&self) -> TokenStream2 {
+        let markers = &self.markers;
+        let name = &self.name;
+        let data = self.data.as_ref().map(|data| quote!((#data)));
+        quote!(#(@#markers)* #name #data)
+    }
+}
+
+/// `Name { entry, ... }` section
+struct Section<T> {
+    name: Ident,
+    entries: Punctuated<T, Token![,]>,
+}
+
+impl<T: Parse> Parse for Section<T> {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let name = input.parse()?;
+        let content;
+        braced!(content in input);
+        let entries = content.parse_terminated(T::parse, Token![,])?;
+        Ok(Self { name, entries })
+    }
+}
+
+/// Whole state machine definition
+struct Machine {
+    name: Ident,
+    members: Option<Punctuated<Field, Token![,]>>,
+    states: Section<Variant>,
+    events: Section<Variant>,
+    context: Ident,
+    context_fields: Option<Punctuated<Field, Token![,]>>,
+    sections: TokenStream2,
+}
+
+impl Parse for Machine {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let name = input.parse()?;
+        let members = if input.peek(syn::token::Brace) {
+            let content;
+            braced!(content in input);
+            Some(content.parse_terminated(Field::parse, Token![,])?)
+        } else {
+            None
+        };
+        input.parse::<Token![,]>()?;
+        let states = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let events = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let context = input.parse()?;
+        let context_fields = if input.peek(syn::token::Brace) {
+            let content;
+            braced!(content in input);
+            Some(content.parse_terminated(Field::parse, Token![,])?)
+        } else {
+            None
+        };
+
+        // Optional sections are checked for their name only and forwarded
+        // as they are.
+        let mut sections = TokenStream2::new();
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let section: Ident = input.parse()?;
+            if !SECTIONS.contains(&section.to_string().as_str()) {
+                return Err(Error::new(
+                    section.span(),
+                    format!(
+                        "unknown section `{}`, expected one of: {}",
+                        section,
+                        SECTIONS.join(", ")
+                    ),
+                ));
+            }
+            let content: proc_macro2::Group = input.parse()?;
+            sections.extend(quote!(, #section #content));
+        }
+
+        Ok(Self {
+            name,
+            members,
+            states,
+            events,
+            context,
+            context_fields,
+            sections,
+        })
+    }
+}
+
+/// Reject entries whose name was already used in the same list
+fn check_duplicates<'a>(what: &str, names: impl Iterator<Item = &'a Ident>) -> Result<()> {
+    let mut seen: Vec<&Ident> = Vec::new();
+    for name in names {
+        if seen.contains(&name) {
+            return Err(Error::new(
+                name.span(),
+                format!("{} `{}` is defined multiple times", what, name),
+            ));
+        }
+        seen.push(name);
+    }
+    Ok(())
+}
+
+impl Machine {
+    fn validate(&self) -> Result<()> {
+        let Some(initial_state) = self.states.entries.first() else {
+            return Err(Error::new(
+                self.states.name.span(),
+                format!("`{}` must list at least one state", self.states.name),
+            ));
+        };
+        if let Some(data) = &initial_state.data {
+            return Err(Error::new_spanned(
+                data,
+                format!("initial state `{}` can't carry data", initial_state.name),
+            ));
+        }
+        for state in &self.states.entries {
+            for marker in &state.markers {
+                if !STATE_MARKERS.contains(&marker.to_string().as_str()) {
+                    return Err(Error::new(
+                        marker.span(),
+                        format!(
+                            "unknown state marker `@{}`, expected one of: @{}",
+                            marker,
+                            STATE_MARKERS.join(", @")
+                        ),
+                    ));
+                }
+            }
+        }
+        for event in &self.events.entries {
+            for marker in &event.markers {
+                if !EVENT_MARKERS.contains(&marker.to_string().as_str()) {
+                    return Err(Error::new(
+                        marker.span(),
+                        format!(
+                            "unknown event marker `@{}`, expected one of: @{}",
+                            marker,
+                            EVENT_MARKERS.join(", @")
+                        ),
+                    ));
+                }
+            }
+        }
+
+        check_duplicates("state", self.states.entries.iter().map(|state| &state.name))?;
+        check_duplicates("event", self.events.entries.iter().map(|event| &event.name))?;
+        if let Some(members) = &self.members {
+            check_duplicates("member", members.iter().map(|field| &field.name))?;
+        }
+        if let Some(fields) = &self.context_fields {
+            check_duplicates("context field", fields.iter().map(|field| &field.name))?;
+        }
+
+        let members = self.members.iter().flatten();
+        if let Some(member) = members
+            .clone()
+            .find(|member| MACHINE_FIELDS.contains(&member.name.to_string().as_str()))
+        {
+            return Err(Error::new(
+                member.name.span(),
+                format!("member `{}` is reserved by the machine", member.name),
+            ));
+        }
+        if let Some(field) = self
+            .context_fields
+            .iter()
+            .flatten()
+            .find(|field| members.clone().any(|member| member.name == field.name))
+        {
+            return Err(Error::new(
+                field.name.span(),
+                format!("context field `{}` shadows a member", field.name),
+            ));
+        }
+        Ok(())
+    }
+
+    fn to_tokens(&self) -> TokenStream2 {
+        let name = &self.name;
+        let members = self.members.as_ref().map(|members| {
+            let members = members.iter().map(Field::to_tokens);
+            quote!({ #(#members),* })
+        });
+        let states_name = &self.states.name;
+        let states = self.states.entries.iter().map(Variant::to_tokens);
+        let events_name = &self.events.name;
+        let events = self.events.entries.iter().map(Variant::to_tokens);
+        let context = &self.context;
+        let context_fields = self.context_fields.as_ref().map(|fields| {
+            let fields = fields.iter().map(Field::to_tokens);
+            quote!({ #(#fields),* })
+        });
+        let sections = &self.sections;
+
+        // The back end expects a comma after the initial state.
+        quote! {
+            ::rustfsm::rustfsm!(
+                #name #members,
+                #states_name { #(#states,)* },
+                #events_name { #(#events),* },
+                #context #context_fields
+                #sections
+            );
+        }
+    }
+}
+
+/// # fsm!
+///
+/// Procedural macro front end for `rustfsm!`, taking the exact same input.
+///
+/// The definition is parsed and validated up front, so mistakes are reported
+/// with a span pointing at the offending state, event or field instead of an
+/// opaque "no rules expected this token". It then expands to the `rustfsm!`
+/// macro.
+///
+/// ```rust,ignore
+/// use rustfsm::{fsm, StateBehavior};
+///
+/// fsm!(
+///     FooName,
+///     FooStates {
+///         FooStateA,
+///         @final FooStateB,
+///     },
+///     Events {
+///         FooEvent1,
+///         FooEvent2,
+///     },
+///     Context {
+///         context_foo_data: u8 = 0,
+///     }
+/// );
+/// ```
+#[proc_macro]
+pub fn fsm(input: TokenStream) -> TokenStream {
+    let machine = parse_macro_input!(input as Machine);
+    match machine.validate() {
+        Ok(()) => machine.to_tokens().into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
@@ -0,0 +1,49 @@
+use rustfsm::*;
+
+fsm!(
+    Turnstile {
+        price: u8 = 2,
+    },
+    TurnstileStates {
+        Locked,
+        Unlocked,
+    },
+    Events {
+        Coin(u8),
+        Push,
+    },
+    Context {
+        paid: u8 = 0,
+    },
+    Transitions {
+        Locked + Coin(_) => Unlocked,
+        Locked + Push => ignore,
+        Unlocked + Coin(_) => ignore,
+        Unlocked + Push => Locked,
+    }
+);
+
+impl StateBehavior for TurnstileStates {
+    type State = TurnstileStates;
+    type Event = Events;
+    type Context = Context;
+
+    fn handle(&self, event: &Self::Event, context: &mut Self::Context) -> Option<Self::State> {
+        if let Events::Coin(coins) = event {
+            context.paid += coins;
+        }
+        self.next_state(event)
+    }
+}
+
+#[test]
+fn fsm_test() {
+    let mut turnstile = Turnstile::new();
+    assert_eq!(*turnstile.price(), 2);
+
+    turnstile.dispatch(Events::Coin(2)).unwrap();
+    assert_eq!(turnstile.get_current_state(), TurnstileStates::Unlocked);
+    turnstile.dispatch(Events::Push).unwrap();
+    assert_eq!(turnstile.get_current_state(), TurnstileStates::Locked);
+    assert_eq!(*turnstile.context().paid(), 2);
+}
@@ -0,0 +1,74 @@
+//! Tracking of the context fields changed by the handlers.
+//!
+//! A telemetry layer publishing the context would otherwise compare it with
+//! the last published copy every cycle. The fields listed in a `Changes`
+//! section get a `set_<field>` setter on the context, marking the field
+//! changed, and the context a `take_changes()` returning the fields marked
+//! since the last call:
+//!
+//! ```rust,ignore
+//! rustfsm!(
+//!     Boiler,
+//!     BoilerStates { Idle, Heating },
+//!     Events { Sample(u16) },
+//!     Context { temperature: u16 = 0, pressure: u16 = 0 },
+//!     Changes { temperature, pressure }
+//! );
+//!
+//! // In a handler
+//! context.set_temperature(*celsius);
+//!
+//! // In the telemetry task
+//! let changes = boiler.context_mut().take_changes();
+//! if changes.contains("temperature") {
+//!     mqtt.publish("boiler/temperature", boiler.context().temperature());
+//! }
+//! ```
+//!
+//! A field is marked by its setter whether or not the value differs, and
+//! not at all when written directly or through `context_mut()`. Up to 32
+//! fields can be tracked. The marks being kept in a hidden field, such a
+//! context is created with `Default` and the setters rather than with a
+//! struct literal.
+
+/// Context fields changed since the last `take_changes()`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Changes {
+    bits: u32,
+    fields: &'static [&'static str],
+}
+
+impl Changes {
+    /// Set of the `fields` whose bit is set in `bits`, in the order of the
+    /// `Changes` section.
+    #[doc(hidden)]
+    pub const fn new(bits: u32, fields: &'static [&'static str]) -> Self {
+        Self { bits, fields }
+    }
+
+    /// Whether no field changed
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0
+    }
+
+    /// Whether the field named `field` changed
+    pub fn contains(&self, field: &str) -> bool {
+        self.iter().any(|changed| changed == field)
+    }
+
+    /// Bitmask of the changed fields, bit `n` for the `n`th field of the
+    /// `Changes` section
+    pub fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    /// Names of the changed fields, in the order of the `Changes` section
+    pub fn iter(&self) -> impl Iterator<Item = &'static str> {
+        let bits = self.bits;
+        self.fields
+            .iter()
+            .zip(0..u32::BITS)
+            .filter(move |&(_, bit)| bits & (1 << bit) != 0)
+            .map(|(field, _)| *field)
+    }
+}
@@ -0,0 +1,245 @@
+//! Time source for the timing features.
+//!
+//! Tick counters wrap around, a 1 kHz one every 49 days or so. The timing
+//! features only ever compare durations, ticks elapsed since an instant, so
+//! that timeouts, watchdogs and dwell times are unaffected by the
+//! wraparound. [`elapsed`] and [`is_reached`] do the same for application
+//! code.
+//!
+//! With the `std` feature, `MockClock` is a clock advanced by hand for
+//! tests.
+//!
+//! With the `fugit` feature, HAL timers can be used as clocks through
+//! `FugitClock`. `embedded-hal` 1.0 doesn't define timer traits, HALs
+//! expose their monotonic timers as `fugit` instants instead.
+
+/// Monotonic tick counter
+///
+/// The tick unit is up to the implementation: a millisecond counter, a
+/// SysTick count, ... A machine picks its clock in its `Clock` section.
+///
+/// ```rust,ignore
+/// struct SysTick;
+///
+/// impl Clock for SysTick {
+///     fn now() -> u32 {
+///         TICKS.load(Ordering::Relaxed)
+///     }
+/// }
+/// ```
+pub trait Clock {
+    /// Current tick count
+    fn now() -> u32;
+}
+
+/// Ticks elapsed from tick `since` to tick `now`, across a wraparound of the
+/// counter
+///
+/// Exact as long as the two ticks are less than a full counter period
+/// apart.
+pub const fn elapsed(since: u32, now: u32) -> u32 {
+    now.wrapping_sub(since)
+}
+
+/// Whether tick `deadline` is reached at tick `now`, across a wraparound of
+/// the counter
+///
+/// Deadlines are told from past ticks by being less than half a counter
+/// period ahead, `2^31` ticks.
+pub const fn is_reached(now: u32, deadline: u32) -> bool {
+    (now.wrapping_sub(deadline) as i32) >= 0
+}
+
+/// Clock of machines without a `Clock` section, always at tick 0
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct NoClock;
+
+impl Clock for NoClock {
+    fn now() -> u32 {
+        0
+    }
+}
+
+/// Clock whose time only moves when told to, for deterministic tests
+///
+/// Timeouts, watchdogs and timers of a machine whose `Clock` section gives
+/// it are tested without sleeping, the test advancing the time past them.
+/// Every thread has a time of its own, starting at tick 0, so tests running
+/// in parallel don't interfere.
+///
+/// ```rust,ignore
+/// rustfsm!(..., Clock { MockClock }, Timers { slots: 1 });
+///
+/// machine.post_after(100, Events::Timeout)?;
+/// MockClock::advance(100);
+/// machine.tick()?;
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct MockClock;
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    static MOCK_TICKS: core::cell::Cell<u32> = const { core::cell::Cell::new(0) };
+}
+
+#[cfg(feature = "std")]
+impl MockClock {
+    /// Move the time of the current thread forward by `ticks`, wrapping
+    /// around like a hardware counter.
+    pub fn advance(ticks: u32) {
+        MOCK_TICKS.with(|now| now.set(now.get().wrapping_add(ticks)));
+    }
+
+    /// Set the time of the current thread to `tick`, for instance right
+    /// before a wraparound.
+    pub fn set(tick: u32) {
+        MOCK_TICKS.with(|now| now.set(tick));
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clock for MockClock {
+    fn now() -> u32 {
+        MOCK_TICKS.with(core::cell::Cell::get)
+    }
+}
+
+/// Source of `fugit` instants ticking at `HZ`, such as a HAL monotonic timer
+///
+/// Most HALs expose their timers through `fugit`, wrap them in a
+/// [`FugitClock`] to use them as a machine's clock.
+#[cfg(feature = "fugit")]
+pub trait InstantSource<const HZ: u32> {
+    /// Current instant
+    fn now() -> fugit::TimerInstantU32<HZ>;
+}
+
+/// [`Clock`] ticking at `HZ` on top of an [`InstantSource`]
+///
+/// ```rust,ignore
+/// struct Mono;
+///
+/// impl InstantSource<1_000> for Mono {
+///     fn now() -> fugit::TimerInstantU32<1_000> {
+///         hal::timer::Monotonic::now()
+///     }
+/// }
+///
+/// rustfsm!(
+///     ...
+///     Clock { FugitClock<Mono, 1_000> },
+///     Watchdog {
+///         Connecting: ticks::<1_000, 1, 1>(10.secs()) => Timeout,
+///     }
+/// );
+/// ```
+#[cfg(feature = "fugit")]
+pub struct FugitClock<S, const HZ: u32>(core::marker::PhantomData<S>);
+
+#[cfg(feature = "fugit")]
+impl<S: InstantSource<HZ>, const HZ: u32> Clock for FugitClock<S, HZ> {
+    fn now() -> u32 {
+        S::now().ticks()
+    }
+}
+
+/// Number of ticks of a clock running at `HZ` in a `fugit` duration, to
+/// express watchdog limits and timer delays in time units
+#[cfg(feature = "fugit")]
+pub const fn ticks<const HZ: u32, const NOM: u32, const DENOM: u32>(
+    duration: fugit::Duration<u32, NOM, DENOM>,
+) -> u32 {
+    duration.convert::<1, HZ>().ticks()
+}
+
+/// Pause of a machine, with the tick it started at on machines with a clock
+#[doc(hidden)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Pause<T> {
+    pub policy: crate::PausePolicy,
+    pub since: T,
+}
+
+/// Pause of a machine, if any, as kept by machines with the `pausable`
+/// dispatch option or not
+#[doc(hidden)]
+pub trait PauseSlot {
+    type Tick;
+
+    /// Running pause, if any
+    fn pause(&self) -> Option<Pause<Self::Tick>>;
+
+    /// End the running pause, if any
+    fn end(&mut self);
+}
+
+impl<T: Copy> PauseSlot for Option<Pause<T>> {
+    type Tick = T;
+
+    #[inline(always)]
+    fn pause(&self) -> Option<Pause<T>> {
+        *self
+    }
+
+    #[inline(always)]
+    fn end(&mut self) {
+        *self = None;
+    }
+}
+
+/// Stand-in for the pause of machines without the `pausable` dispatch
+/// option, never paused
+#[doc(hidden)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct NoPause;
+
+impl PauseSlot for NoPause {
+    type Tick = ();
+
+    #[inline(always)]
+    fn pause(&self) -> Option<Pause<()>> {
+        None
+    }
+
+    #[inline(always)]
+    fn end(&mut self) {}
+}
+
+/// Stay of a machine in its current state
+#[doc(hidden)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Stay {
+    since: u32,
+    watchdog_fired: bool,
+}
+
+impl Stay {
+    /// Start a stay at tick `now`.
+    pub fn new(now: u32) -> Self {
+        Self {
+            since: now,
+            watchdog_fired: false,
+        }
+    }
+
+    /// Ticks elapsed since the start of the stay.
+    pub fn elapsed(&self, now: u32) -> u32 {
+        elapsed(self.since, now)
+    }
+
+    /// Leave `ticks` out of the stay, spent paused.
+    pub fn postpone(&mut self, ticks: u32) {
+        self.since = self.since.wrapping_add(ticks);
+    }
+
+    /// Whether the stay lasted more than `limit` ticks at `now`, reported
+    /// once per stay.
+    pub fn watchdog_expired(&mut self, now: u32, limit: u32) -> bool {
+        if self.watchdog_fired || self.elapsed(now) <= limit {
+            return false;
+        }
+        self.watchdog_fired = true;
+        true
+    }
+}
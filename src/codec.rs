@@ -0,0 +1,220 @@
+//! Wire codec for events.
+//!
+//! Every generated event type gets `encode` and `decode`, so host tools can
+//! drive a device's state machines over UART or USB with compact frames:
+//!
+//! | byte | content |
+//! |------|---------|
+//! | 0    | [`VERSION`] of the framing |
+//! | 1    | event id, its declaration position starting at 0 |
+//! | 2..  | event fields, each encoded with [`Wire`] |
+//!
+//! Event ids only change when the declarations are reordered. Event data
+//! types must implement [`Wire`], which is provided for the primitive types
+//! and arrays of them.
+//!
+//! ```rust,ignore
+//! let mut frame = [0; 8];
+//! let len = Events::SetSpeed(3).encode(&mut frame);
+//! assert_eq!(Events::decode(&frame[..len]), Ok(Events::SetSpeed(3)));
+//! ```
+
+/// Version of the framing, sent as the first byte of every frame
+pub const VERSION: u8 = 1;
+
+/// Error returned when a frame cannot be decoded
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DecodeError {
+    /// The frame ended before the event was complete
+    Truncated,
+    /// The frame was encoded with another version of the framing
+    UnsupportedVersion(u8),
+    /// No event is declared at this id
+    UnknownEvent(u8),
+    /// An event field holds an invalid value
+    InvalidPayload,
+    /// Bytes are left after the event
+    TrailingBytes,
+}
+
+/// Fixed-size binary encoding of an event field
+///
+/// ```rust,ignore
+/// impl Wire for Direction {
+///     const SIZE: usize = 1;
+///
+///     fn write(&self, buf: &mut [u8]) {
+///         buf[0] = *self as u8;
+///     }
+///
+///     fn read(buf: &[u8]) -> Option<Self> {
+///         match buf[0] {
+///             0 => Some(Direction::Up),
+///             1 => Some(Direction::Down),
+///             _ => None,
+///         }
+///     }
+/// }
+/// ```
+pub trait Wire: Sized {
+    /// Encoded size, in bytes
+    const SIZE: usize;
+
+    /// Write the value to `buf`, exactly `SIZE` bytes long.
+    fn write(&self, buf: &mut [u8]);
+
+    /// Read a value from `buf`, exactly `SIZE` bytes long, or `None` if the
+    /// bytes don't hold a valid value.
+    fn read(buf: &[u8]) -> Option<Self>;
+}
+
+macro_rules! impl_wire_le {
+    ($($type:ty),*) => {
+        $(
+            impl Wire for $type {
+                const SIZE: usize = core::mem::size_of::<$type>();
+
+                fn write(&self, buf: &mut [u8]) {
+                    if let Some(buf) = buf.get_mut(..Self::SIZE) {
+                        buf.copy_from_slice(&self.to_le_bytes());
+                    }
+                }
+
+                fn read(buf: &[u8]) -> Option<Self> {
+                    Some(<$type>::from_le_bytes(buf.try_into().ok()?))
+                }
+            }
+        )*
+    };
+}
+
+impl_wire_le!(u8, u16, u32, u64, i8, i16, i32, i64, f32, f64);
+
+impl Wire for bool {
+    const SIZE: usize = 1;
+
+    fn write(&self, buf: &mut [u8]) {
+        if let Some(byte) = buf.first_mut() {
+            *byte = *self as u8;
+        }
+    }
+
+    fn read(buf: &[u8]) -> Option<Self> {
+        match buf.first()? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+}
+
+impl Wire for char {
+    const SIZE: usize = 4;
+
+    fn write(&self, buf: &mut [u8]) {
+        (*self as u32).write(buf);
+    }
+
+    fn read(buf: &[u8]) -> Option<Self> {
+        char::from_u32(u32::read(buf)?)
+    }
+}
+
+impl<T: Wire + Copy, const N: usize> Wire for [T; N] {
+    const SIZE: usize = T::SIZE * N;
+
+    fn write(&self, buf: &mut [u8]) {
+        for (index, value) in self.iter().enumerate() {
+            if let Some(buf) = buf.get_mut(index * T::SIZE..(index + 1) * T::SIZE) {
+                value.write(buf);
+            }
+        }
+    }
+
+    fn read(buf: &[u8]) -> Option<Self> {
+        let mut chunks = (0..N).map(|index| buf.get(index * T::SIZE..(index + 1) * T::SIZE));
+        // The first value fills the array before the others are read, an
+        // empty array being converted from an empty slice
+        let Some(first) = chunks.next() else {
+            return <[T; N]>::try_from(&[][..]).ok();
+        };
+        let mut values = [T::read(first?)?; N];
+        for (value, chunk) in values.iter_mut().skip(1).zip(chunks) {
+            *value = T::read(chunk?)?;
+        }
+        Some(values)
+    }
+}
+
+/// Encoded size of the value `field` points to, for the generated
+/// `HIBERNATE_SIZE`
+#[doc(hidden)]
+pub const fn wire_size<C, T: Wire>(_field: fn(&C) -> &T) -> usize {
+    T::SIZE
+}
+
+/// Frame writer used by the generated `encode`
+#[doc(hidden)]
+pub struct Writer<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+    overflow: bool,
+}
+
+impl<'a> Writer<'a> {
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        Self {
+            buf,
+            len: 0,
+            overflow: false,
+        }
+    }
+
+    pub fn put<T: Wire>(&mut self, value: &T) {
+        match self.buf.get_mut(self.len..self.len + T::SIZE) {
+            Some(buf) if !self.overflow => {
+                value.write(buf);
+                self.len += T::SIZE;
+            }
+            _ => self.overflow = true,
+        }
+    }
+
+    /// Length of the frame, or 0 if it didn't fit.
+    pub fn finish(self) -> usize {
+        if self.overflow {
+            0
+        } else {
+            self.len
+        }
+    }
+}
+
+/// Frame reader used by the generated `decode`
+#[doc(hidden)]
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    pub fn take<T: Wire>(&mut self) -> Result<T, DecodeError> {
+        if self.bytes.len() < T::SIZE {
+            return Err(DecodeError::Truncated);
+        }
+        let (value, rest) = self.bytes.split_at(T::SIZE);
+        self.bytes = rest;
+        T::read(value).ok_or(DecodeError::InvalidPayload)
+    }
+
+    pub fn finish<T>(self, value: T) -> Result<T, DecodeError> {
+        if self.bytes.is_empty() {
+            Ok(value)
+        } else {
+            Err(DecodeError::TrailingBytes)
+        }
+    }
+}
@@ -0,0 +1,791 @@
+//! Build-time generation of machines from definition files.
+//!
+//! With the `codegen` feature, a build script turns a machine described in a
+//! TOML file, maintained outside Rust, into the `rustfsm!` invocation
+//! declaring it:
+//!
+//! ```toml
+//! name = "Turnstile"
+//! states = ["Locked", "Unlocked"]
+//! events = ["Coin", "Push", "SetFare(u32)"]
+//!
+//! [context]
+//! fare = { type = "u32", default = "100" }
+//!
+//! [[transition]]
+//! from = "Locked"
+//! event = "Coin"
+//! to = "Unlocked"
+//!
+//! [[transition]]
+//! from = "_"
+//! event = "SetFare(_)"
+//! to = "ignore"
+//! ```
+//!
+//! ```rust,ignore
+//! // build.rs
+//! fn main() {
+//!     rustfsm::codegen::generate("turnstile.toml", "turnstile.rs").unwrap();
+//! }
+//!
+//! // src/main.rs
+//! use rustfsm::*;
+//!
+//! include!(concat!(env!("OUT_DIR"), "/turnstile.rs"));
+//! ```
+//!
+//! The first state is the initial one. `state_type`, `event_type` and
+//! `context_type` name the generated types, by default `TurnstileStates`,
+//! `Events` and `Context`. A transition can have a `guard` and an `action`,
+//! and `dispatch` lists the options of the `Dispatch` section, such as
+//! `["behavior"]` to generate the state behavior from the table.
+//!
+//! Only the part of TOML needed for these files is understood: strings,
+//! arrays of strings and inline tables of strings, in `[context]` and
+//! `[[transition]]` tables. States, events and transitions are checked
+//! against each other, so mistakes in the file are reported by the build
+//! script with their line rather than as errors in the generated code.
+//!
+//! Machines can also be imported from the state diagrams they're drawn in,
+//! PlantUML with [`Definition::from_plantuml`] and SCXML with
+//! [`Definition::from_scxml`], `generate` picking the format from the file
+//! extension. Diagrams only give the skeleton of the machine: states, events
+//! and transitions, with their guards and actions, while the context is left
+//! empty and the events a state has no transition for are ignored.
+
+use std::borrow::ToOwned;
+use std::format;
+use std::io;
+use std::path::Path;
+use std::string::String;
+use std::vec::Vec;
+
+/// Error returned when a definition can't be turned into code
+#[derive(Debug)]
+pub enum Error {
+    /// The definition file couldn't be read, or the generated file written
+    Io(io::Error),
+    /// A line isn't understood
+    Syntax { line: usize, message: &'static str },
+    /// A required key is missing
+    Missing(&'static str),
+    /// A transition names an undeclared state or event
+    Undeclared { line: usize, name: String },
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::Io(error) => write!(f, "{error}"),
+            Error::Syntax { line, message } => write!(f, "line {line}: {message}"),
+            Error::Missing(key) => write!(f, "missing `{key}`"),
+            Error::Undeclared { line, name } => write!(f, "line {line}: undeclared `{name}`"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Context field of a definition
+#[derive(Clone, PartialEq, Debug)]
+pub struct Field {
+    /// Name of the field
+    pub name: String,
+    /// Type of the field, as Rust code
+    pub ty: String,
+    /// Default value of the field, as Rust code
+    pub default: String,
+}
+
+/// Transition rule of a definition
+#[derive(Clone, PartialEq, Debug)]
+pub struct Transition {
+    /// Source state, `_` for every state
+    pub from: String,
+    /// Event pattern, or patterns separated by `|`
+    pub event: String,
+    /// Next state, or `ignore`
+    pub to: String,
+    /// Guard function of the rule
+    pub guard: Option<String>,
+    /// Action function of the rule
+    pub action: Option<String>,
+}
+
+/// Machine described by a definition file
+#[derive(Clone, PartialEq, Debug)]
+pub struct Definition {
+    /// Name of the machine
+    pub name: String,
+    /// Name of the state type
+    pub state_type: String,
+    /// Name of the event type
+    pub event_type: String,
+    /// Name of the context type
+    pub context_type: String,
+    /// States, the initial one first
+    pub states: Vec<String>,
+    /// Events, with their data types as in `SetFare(u32)`
+    pub events: Vec<String>,
+    /// Fields of the context
+    pub context: Vec<Field>,
+    /// Rules of the `Transitions` section
+    pub transitions: Vec<Transition>,
+    /// Options of the `Dispatch` section
+    pub dispatch: Vec<String>,
+}
+
+/// Value of a key
+#[derive(Clone, PartialEq, Debug)]
+enum Value {
+    Str(String),
+    Array(Vec<String>),
+    Table(Vec<(String, String)>),
+}
+
+/// Table a key belongs to
+enum Section {
+    Root,
+    Context,
+    Transition,
+}
+
+impl Definition {
+    /// Parse a definition from the text of a TOML file.
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let mut name = None;
+        let mut state_type = None;
+        let mut event_type = None;
+        let mut context_type = None;
+        let mut states = None;
+        let mut events = None;
+        let mut dispatch = Vec::new();
+        let mut context = Vec::new();
+        // Transitions, with the line of their header
+        let mut transitions: Vec<(usize, Vec<(String, String)>)> = Vec::new();
+        let mut section = Section::Root;
+
+        let mut lines = text.lines().enumerate();
+        while let Some((index, line)) = lines.next() {
+            let line_number = index + 1;
+            let syntax = |message| Error::Syntax {
+                line: line_number,
+                message,
+            };
+            let mut line = strip_comment(line).trim().to_owned();
+            if line.is_empty() {
+                continue;
+            }
+            match line.as_str() {
+                "[context]" => {
+                    section = Section::Context;
+                    continue;
+                }
+                "[[transition]]" => {
+                    section = Section::Transition;
+                    transitions.push((line_number, Vec::new()));
+                    continue;
+                }
+                _ if line.starts_with('[') => return Err(syntax("unknown table")),
+                _ => {}
+            }
+            // Arrays can span several lines
+            while line.matches('[').count() > line.matches(']').count() {
+                match lines.next() {
+                    Some((_, next)) => {
+                        line.push(' ');
+                        line.push_str(strip_comment(next).trim());
+                    }
+                    None => return Err(syntax("unclosed array")),
+                }
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or(syntax("expected `key = value`"))?;
+            let key = key.trim();
+            let value = parse_value(value.trim()).ok_or(syntax("invalid value"))?;
+            match (&section, key, value) {
+                (Section::Root, "name", Value::Str(value)) => name = Some(value),
+                (Section::Root, "state_type", Value::Str(value)) => state_type = Some(value),
+                (Section::Root, "event_type", Value::Str(value)) => event_type = Some(value),
+                (Section::Root, "context_type", Value::Str(value)) => context_type = Some(value),
+                (Section::Root, "states", Value::Array(value)) => states = Some(value),
+                (Section::Root, "events", Value::Array(value)) => events = Some(value),
+                (Section::Root, "dispatch", Value::Array(value)) => dispatch = value,
+                (Section::Context, _, Value::Table(entries)) => {
+                    let entry = |wanted| {
+                        entries
+                            .iter()
+                            .find(|(key, _)| key == wanted)
+                            .map(|(_, value)| value.clone())
+                    };
+                    context.push(Field {
+                        name: key.to_owned(),
+                        ty: entry("type").ok_or(syntax("missing field `type`"))?,
+                        default: entry("default").ok_or(syntax("missing field `default`"))?,
+                    });
+                }
+                (Section::Transition, _, Value::Str(value)) => {
+                    if let Some((_, keys)) = transitions.last_mut() {
+                        keys.push((key.to_owned(), value));
+                    }
+                }
+                _ => return Err(syntax("unexpected key")),
+            }
+        }
+
+        let name = name.ok_or(Error::Missing("name"))?;
+        let states = states.ok_or(Error::Missing("states"))?;
+        let events = events.ok_or(Error::Missing("events"))?;
+        let transitions = transitions
+            .into_iter()
+            .map(|(line, keys)| transition(line, keys, &states, &events))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            state_type: state_type.unwrap_or_else(|| format!("{name}States")),
+            event_type: event_type.unwrap_or_else(|| "Events".to_owned()),
+            context_type: context_type.unwrap_or_else(|| "Context".to_owned()),
+            name,
+            states,
+            events,
+            context,
+            transitions,
+            dispatch,
+        })
+    }
+
+    /// The `rustfsm!` invocation declaring the machine.
+    pub fn to_rust(&self) -> String {
+        let context = if self.context.is_empty() {
+            "{}".to_owned()
+        } else {
+            let fields = self
+                .context
+                .iter()
+                .map(|field| format!("{}: {} = {}", field.name, field.ty, field.default))
+                .collect::<Vec<_>>();
+            format!("{{ {} }}", fields.join(", "))
+        };
+        let mut code = format!(
+            "rustfsm!(\n    {},\n    {} {{ {} }},\n    {} {{ {} }},\n    {} {}",
+            self.name,
+            self.state_type,
+            self.states.join(", "),
+            self.event_type,
+            self.events.join(", "),
+            self.context_type,
+            context,
+        );
+        if !self.transitions.is_empty() {
+            code.push_str(",\n    Transitions {\n");
+            for transition in &self.transitions {
+                code.push_str(&format!(
+                    "        {} + {} => {}",
+                    transition.from, transition.event, transition.to
+                ));
+                if let Some(guard) = &transition.guard {
+                    code.push_str(&format!(" if {guard}"));
+                }
+                if let Some(action) = &transition.action {
+                    code.push_str(&format!(" / {action}"));
+                }
+                code.push_str(",\n");
+            }
+            code.push_str("    }");
+        }
+        if !self.dispatch.is_empty() {
+            code.push_str(&format!(
+                ",\n    Dispatch {{ {} }}",
+                self.dispatch.join(", ")
+            ));
+        }
+        code.push_str("\n);\n");
+        code
+    }
+}
+
+/// Machine imported from a state diagram
+struct Diagram {
+    name: String,
+    initial: Option<String>,
+    states: Vec<String>,
+    events: Vec<String>,
+    transitions: Vec<Transition>,
+}
+
+impl Diagram {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            initial: None,
+            states: Vec::new(),
+            events: Vec::new(),
+            transitions: Vec::new(),
+        }
+    }
+
+    /// Declare `state`, checking it's a valid name.
+    fn state(&mut self, line: usize, state: &str) -> Result<String, Error> {
+        declare(&mut self.states, line, state)
+    }
+
+    fn transition(
+        &mut self,
+        line: usize,
+        from: &str,
+        events: &[&str],
+        to: Option<&str>,
+        guard: Option<&str>,
+        action: Option<&str>,
+    ) -> Result<(), Error> {
+        let from = self.state(line, from)?;
+        let to = match to {
+            Some(to) => self.state(line, to)?,
+            None => "ignore".to_owned(),
+        };
+        let events = events
+            .iter()
+            .map(|event| declare(&mut self.events, line, event))
+            .collect::<Result<Vec<_>, _>>()?;
+        if events.is_empty() {
+            return Err(Error::Syntax {
+                line,
+                message: "a transition needs an event",
+            });
+        }
+        let optional = |text: Option<&str>| {
+            text.map(str::trim)
+                .filter(|text| !text.is_empty())
+                .map(str::to_owned)
+        };
+        self.transitions.push(Transition {
+            from,
+            event: events.join(" | "),
+            to,
+            guard: optional(guard),
+            action: optional(action),
+        });
+        Ok(())
+    }
+
+    /// Definition of the machine, the events a state has no transition for
+    /// ignored.
+    fn finish(mut self) -> Result<Definition, Error> {
+        if let Some(initial) = &self.initial {
+            let index = self.states.iter().position(|state| state == initial);
+            if let Some(index) = index {
+                let initial = self.states.remove(index);
+                self.states.insert(0, initial);
+            }
+        }
+        if self.states.is_empty() {
+            return Err(Error::Missing("states"));
+        }
+        let behavior = self
+            .transitions
+            .iter()
+            .any(|transition| transition.guard.is_some() || transition.action.is_some());
+        self.transitions.push(Transition {
+            from: "_".to_owned(),
+            event: "_".to_owned(),
+            to: "ignore".to_owned(),
+            guard: None,
+            action: None,
+        });
+        Ok(Definition {
+            state_type: format!("{}States", self.name),
+            event_type: "Events".to_owned(),
+            context_type: "Context".to_owned(),
+            name: self.name,
+            states: self.states,
+            events: self.events,
+            context: Vec::new(),
+            transitions: self.transitions,
+            dispatch: if behavior {
+                std::vec!["behavior".to_owned()]
+            } else {
+                Vec::new()
+            },
+        })
+    }
+}
+
+/// Add `name` to `list` if it's not there yet, checking it's a valid name.
+fn declare(list: &mut Vec<String>, line: usize, name: &str) -> Result<String, Error> {
+    let name = name.trim();
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(Error::Syntax {
+            line,
+            message: "states and events must be valid Rust names",
+        });
+    }
+    if !list.iter().any(|declared| declared == name) {
+        list.push(name.to_owned());
+    }
+    Ok(name.to_owned())
+}
+
+impl Definition {
+    /// Import the machine `name` from a PlantUML state diagram.
+    ///
+    /// Transitions are read from `From --> To : Event [guard] / action`
+    /// lines, any arrow direction, and the initial state from `[*] --> State`.
+    /// Transitions to `[*]`, notes, styling and descriptions are skipped,
+    /// composite states refused. Events a state has no transition for are
+    /// ignored.
+    pub fn from_plantuml(name: &str, text: &str) -> Result<Self, Error> {
+        let mut diagram = Diagram::new(name);
+        let mut in_note = false;
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if in_note {
+                in_note = !line.starts_with("end note");
+                continue;
+            }
+            if line.is_empty() || line.starts_with('\'') || line.starts_with('@') {
+                continue;
+            }
+            if line.ends_with('{') {
+                return Err(Error::Syntax {
+                    line: line_number,
+                    message: "composite states aren't supported",
+                });
+            }
+            let keyword = line.split_whitespace().next().unwrap_or_default();
+            match keyword {
+                "state" => {
+                    let state = line["state".len()..].split(':').next().unwrap_or_default();
+                    diagram.state(line_number, state)?;
+                    continue;
+                }
+                "note" => {
+                    // Single line notes have their text after a colon
+                    in_note = !line.contains(':');
+                    continue;
+                }
+                "hide" | "skinparam" | "title" | "left" | "top" => continue,
+                _ => {}
+            }
+            let (arrow, label) = match line.split_once(':') {
+                Some((arrow, label)) => (arrow, Some(label.trim())),
+                None => (line, None),
+            };
+            let Some((from, to)) = split_arrow(arrow) else {
+                // State descriptions, `State : text`
+                continue;
+            };
+            match (from, to) {
+                ("[*]", "[*]") => {}
+                ("[*]", to) => diagram.initial = Some(diagram.state(line_number, to)?),
+                (from, "[*]") => {
+                    diagram.state(line_number, from)?;
+                }
+                (from, to) => {
+                    let label = label.ok_or(Error::Syntax {
+                        line: line_number,
+                        message: "a transition needs an event",
+                    })?;
+                    let (trigger, action) = match label.split_once('/') {
+                        Some((trigger, action)) => (trigger, Some(action)),
+                        None => (label, None),
+                    };
+                    let (event, guard) = match trigger.split_once('[') {
+                        Some((event, guard)) => (event, guard.trim().strip_suffix(']')),
+                        None => (trigger, None),
+                    };
+                    diagram.transition(line_number, from, &[event], Some(to), guard, action)?;
+                }
+            }
+        }
+        diagram.finish()
+    }
+
+    /// Import a machine from an SCXML document.
+    ///
+    /// The machine is named after the `name` attribute of the `<scxml>`
+    /// element, or `name` without it. `<state>` and `<final>` elements give
+    /// the states, `initial` the initial one, and their `<transition>`
+    /// elements the rules: space-separated events, `cond` as the guard, and
+    /// no `target` for transitions staying in the state. Executable content
+    /// is skipped, nested states refused. Events a state has no transition
+    /// for are ignored.
+    pub fn from_scxml(name: &str, text: &str) -> Result<Self, Error> {
+        let mut diagram = Diagram::new(name);
+        // State whose transitions are being read
+        let mut parent: Option<String> = None;
+        let mut rest = text;
+        while let Some(start) = rest.find('<') {
+            let line = text.len() - rest.len() + start;
+            let line = text[..line].matches('\n').count() + 1;
+            let syntax = |message| Error::Syntax { line, message };
+            rest = &rest[start + 1..];
+            if let Some(comment) = rest.strip_prefix("!--") {
+                let end = comment.find("-->").ok_or(syntax("unclosed comment"))?;
+                rest = &comment[end + 3..];
+                continue;
+            }
+            let end = rest.find('>').ok_or(syntax("unclosed tag"))?;
+            let tag = rest[..end].trim_end_matches('/');
+            let closing = rest[..end].ends_with('/');
+            rest = &rest[end + 1..];
+
+            let element = tag.split_whitespace().next().unwrap_or_default();
+            match element {
+                "scxml" => {
+                    if let Some(machine) = attribute(tag, "name") {
+                        diagram.name = machine.to_owned();
+                    }
+                    diagram.initial = attribute(tag, "initial").map(str::to_owned);
+                }
+                "state" | "final" | "parallel" => {
+                    if parent.is_some() || element == "parallel" {
+                        return Err(syntax("nested states aren't supported"));
+                    }
+                    let id = attribute(tag, "id").ok_or(syntax("a state needs an `id`"))?;
+                    let state = diagram.state(line, id)?;
+                    if !closing {
+                        parent = Some(state);
+                    }
+                }
+                "/state" | "/final" => parent = None,
+                "transition" => {
+                    let from = parent.clone().ok_or(syntax("transition outside a state"))?;
+                    let events =
+                        attribute(tag, "event").ok_or(syntax("a transition needs an event"))?;
+                    let events = events.split_whitespace().collect::<Vec<_>>();
+                    let target = attribute(tag, "target");
+                    diagram.transition(
+                        line,
+                        &from,
+                        &events,
+                        target,
+                        attribute(tag, "cond"),
+                        None,
+                    )?;
+                }
+                _ => {}
+            }
+        }
+        diagram.finish()
+    }
+}
+
+/// States on both ends of an arrow, as in `A --> B` or `A -left-> B`
+fn split_arrow(text: &str) -> Option<(&str, &str)> {
+    let start = text.find('-')?;
+    let end = start + text[start..].find('>')?;
+    let arrow = &text[start..=end];
+    arrow
+        .chars()
+        .all(|c| {
+            c == '-' || c == '>' || c.is_ascii_alphabetic() || c == '[' || c == ']' || c == '#'
+        })
+        .then(|| (text[..start].trim(), text[end + 1..].trim()))
+}
+
+/// Value of the attribute `name` of a tag, if any
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = tag;
+    while let Some(index) = rest.find(name) {
+        let before = &tag[..tag.len() - rest.len() + index];
+        rest = &rest[index + name.len()..];
+        if !before.ends_with(char::is_whitespace) {
+            continue;
+        }
+        let Some(value) = rest.trim_start().strip_prefix('=') else {
+            continue;
+        };
+        let value = value.trim_start();
+        let quote = value.chars().next()?;
+        if quote != '"' && quote != '\'' {
+            return None;
+        }
+        let value = &value[1..];
+        return value.find(quote).map(|end| &value[..end]);
+    }
+    None
+}
+
+/// Generate the machine described by the `definition` file into `file` in
+/// `OUT_DIR`, from a build script.
+///
+/// Files ending in `.puml` or `.plantuml` are imported as PlantUML state
+/// diagrams, `.scxml` as SCXML documents, both naming the machine after the
+/// file, and any other as TOML definitions. The build script is rerun
+/// whenever the definition changes.
+pub fn generate(definition: impl AsRef<Path>, file: &str) -> Result<(), Error> {
+    let definition = definition.as_ref();
+    std::println!("cargo:rerun-if-changed={}", definition.display());
+    let text = std::fs::read_to_string(definition)?;
+    let name = definition
+        .file_stem()
+        .map(|stem| camel_case(&stem.to_string_lossy()))
+        .unwrap_or_default();
+    let extension = definition
+        .extension()
+        .and_then(|extension| extension.to_str());
+    let code = match extension {
+        Some("puml" | "plantuml") => Definition::from_plantuml(&name, &text)?,
+        Some("scxml") => Definition::from_scxml(&name, &text)?,
+        _ => Definition::parse(&text)?,
+    }
+    .to_rust();
+    let out_dir = std::env::var_os("OUT_DIR")
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "OUT_DIR is not set"))?;
+    let header = format!(
+        "// Generated by rustfsm::codegen from {}, do not edit.\n",
+        definition.display()
+    );
+    std::fs::write(Path::new(&out_dir).join(file), header + &code)?;
+    Ok(())
+}
+
+/// Check a transition against the declared states and events.
+fn transition(
+    line: usize,
+    keys: Vec<(String, String)>,
+    states: &[String],
+    events: &[String],
+) -> Result<Transition, Error> {
+    let key = |wanted: &str| {
+        keys.iter()
+            .find(|(key, _)| key == wanted)
+            .map(|(_, value)| value.clone())
+    };
+    let required = |wanted| {
+        key(wanted).ok_or(Error::Syntax {
+            line,
+            message: "a transition needs `from`, `event` and `to`",
+        })
+    };
+    let undeclared = |name: &str| Error::Undeclared {
+        line,
+        name: name.to_owned(),
+    };
+    let declared = |list: &[String], name: &str| {
+        list.iter()
+            .any(|declared| variant_name(declared) == variant_name(name))
+    };
+
+    let transition = Transition {
+        from: required("from")?,
+        event: required("event")?,
+        to: required("to")?,
+        guard: key("guard"),
+        action: key("action"),
+    };
+    if transition.from != "_" && !declared(states, &transition.from) {
+        return Err(undeclared(&transition.from));
+    }
+    if transition.to != "ignore" && !declared(states, &transition.to) {
+        return Err(undeclared(&transition.to));
+    }
+    for event in transition.event.split('|').map(str::trim) {
+        let event = event.trim_start_matches('(').trim_end_matches(')').trim();
+        if event != "_" && !declared(events, event) {
+            return Err(undeclared(event));
+        }
+    }
+    Ok(transition)
+}
+
+/// `door_lock` as `DoorLock`
+fn camel_case(name: &str) -> String {
+    name.split(['_', '-', ' '])
+        .flat_map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase())
+                .into_iter()
+                .chain(chars)
+        })
+        .collect()
+}
+
+/// Name of a variant, without its data
+fn variant_name(variant: &str) -> &str {
+    variant.split(['(', '{']).next().unwrap_or(variant).trim()
+}
+
+/// Drop the comment ending a line, if any.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (index, c) in line.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..index],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Parse a string, an array of strings or an inline table of strings.
+fn parse_value(text: &str) -> Option<Value> {
+    if let Some(items) = text
+        .strip_prefix('[')
+        .and_then(|text| text.strip_suffix(']'))
+    {
+        split_items(items)
+            .into_iter()
+            .map(parse_string)
+            .collect::<Option<_>>()
+            .map(Value::Array)
+    } else if let Some(items) = text
+        .strip_prefix('{')
+        .and_then(|text| text.strip_suffix('}'))
+    {
+        split_items(items)
+            .into_iter()
+            .map(|item| {
+                let (key, value) = item.split_once('=')?;
+                Some((key.trim().to_owned(), parse_string(value.trim())?))
+            })
+            .collect::<Option<_>>()
+            .map(Value::Table)
+    } else {
+        parse_string(text).map(Value::Str)
+    }
+}
+
+/// Items separated by commas outside strings, without the trailing comma
+fn split_items(text: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut in_string = false;
+    let mut start = 0;
+    for (index, c) in text.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            ',' if !in_string => {
+                items.push(text[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    let last = text[start..].trim();
+    if !last.is_empty() {
+        items.push(last);
+    }
+    items
+}
+
+/// Parse a basic string, without escapes.
+fn parse_string(text: &str) -> Option<String> {
+    let text = text.strip_prefix('"')?.strip_suffix('"')?;
+    (!text.contains('"') && !text.contains('\\')).then(|| text.to_owned())
+}
@@ -0,0 +1,113 @@
+//! Transition table coverage tracking.
+
+use crate::table::TableRule;
+
+/// Record of the transition table rules fired by a state machine with `N`
+/// rules
+///
+/// Without the `coverage` feature it holds nothing and records nothing.
+#[derive(Clone, Debug)]
+pub struct Coverage<const N: usize> {
+    #[cfg(feature = "coverage")]
+    fired: [bool; N],
+}
+
+impl<const N: usize> Coverage<N> {
+    /// Create an empty record.
+    #[doc(hidden)]
+    pub const fn new() -> Self {
+        Self {
+            #[cfg(feature = "coverage")]
+            fired: [false; N],
+        }
+    }
+
+    /// Record the rule matching a handled event, given by `rule`, if the
+    /// machine ended up where the rule says: `to` is the index of the new
+    /// state, or `None` when no transition occurred.
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn record(
+        &mut self,
+        _table: &'static [TableRule],
+        _rule: impl FnOnce() -> Option<&'static TableRule>,
+        _to: Option<usize>,
+    ) {
+        #[cfg(feature = "coverage")]
+        if let Some(rule) = _rule() {
+            if rule.to == _to || (rule.to.is_none() && _to == Some(rule.from)) {
+                let index = _table.iter().position(|entry| entry == rule);
+                if let Some(fired) = index.and_then(|index| self.fired.get_mut(index)) {
+                    *fired = true;
+                }
+            }
+        }
+    }
+}
+
+impl<const N: usize> Default for Coverage<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Summary of the transition coverage
+#[cfg(feature = "coverage")]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CoverageReport {
+    /// Number of rules fired at least once
+    pub covered: usize,
+    /// Number of rules in the transition table
+    pub total: usize,
+    /// Number of `ignore` rules fired at least once, among `covered`
+    pub ignores_covered: usize,
+    /// Number of `ignore` rules, among `total`
+    pub ignores: usize,
+}
+
+#[cfg(feature = "coverage")]
+impl CoverageReport {
+    /// Whether every rule of the transition table fired
+    pub fn is_complete(&self) -> bool {
+        self.covered == self.total
+    }
+
+    /// Whether every rule of the transition table leading to a state fired,
+    /// whether the `ignore` rules did or not
+    pub fn transitions_complete(&self) -> bool {
+        self.covered - self.ignores_covered == self.total - self.ignores
+    }
+}
+
+#[cfg(feature = "coverage")]
+impl<const N: usize> Coverage<N> {
+    /// Summary of the rules fired so far.
+    #[doc(hidden)]
+    pub fn report(&self, table: &'static [TableRule]) -> CoverageReport {
+        let ignores = || {
+            table
+                .iter()
+                .zip(self.fired.iter())
+                .filter(|(rule, _)| rule.to.is_none())
+        };
+        CoverageReport {
+            covered: self.fired.iter().filter(|fired| **fired).count(),
+            total: N,
+            ignores_covered: ignores().filter(|(_, fired)| **fired).count(),
+            ignores: ignores().count(),
+        }
+    }
+
+    /// Rules of `table` that haven't fired yet.
+    #[doc(hidden)]
+    pub fn uncovered<'a>(
+        &'a self,
+        table: &'static [TableRule],
+    ) -> impl Iterator<Item = &'static TableRule> + 'a {
+        table
+            .iter()
+            .zip(self.fired.iter())
+            .filter(|(_, fired)| !**fired)
+            .map(|(rule, _)| rule)
+    }
+}
@@ -0,0 +1,93 @@
+//! Structured differences between two machines of the same type.
+//!
+//! With the `diff` feature, every machine gets a `diff(&other)` method
+//! comparing the current states and, field by field, the contexts, so a
+//! reference simulation run in lockstep with a device's machine tells where
+//! they diverge:
+//!
+//! ```rust,ignore
+//! let diff = simulated.diff(&device);
+//! for change in diff.fields() {
+//!     println!("{}: {:?} != {:?}", change.name, change.left, change.right);
+//! }
+//! assert!(diff.is_empty());
+//! ```
+//!
+//! Contexts declared in the macro compare their fields when they're all
+//! `PartialEq`. Existing context types implement [`FieldDiff`] themselves.
+
+use core::fmt;
+
+/// Field by field comparison of a context
+pub trait FieldDiff {
+    /// Names of the fields, in declaration order
+    const FIELDS: &'static [&'static str];
+
+    /// Whether the field at `index` is equal in both contexts.
+    fn field_eq(&self, other: &Self, index: usize) -> bool;
+
+    /// The field at `index`, for display.
+    fn field(&self, index: usize) -> &dyn fmt::Debug;
+}
+
+/// Differing field of two contexts
+#[derive(Clone, Copy, Debug)]
+pub struct FieldChange<'a> {
+    /// Name of the field
+    pub name: &'static str,
+    /// Value of the field in the machine `diff` was called on
+    pub left: &'a dyn fmt::Debug,
+    /// Value of the field in the other machine
+    pub right: &'a dyn fmt::Debug,
+}
+
+/// Difference between two machines
+pub struct Diff<'a, S, C> {
+    /// Current states of both machines, if they differ
+    pub state: Option<(S, S)>,
+    left: &'a C,
+    right: &'a C,
+}
+
+impl<'a, S, C: FieldDiff> Diff<'a, S, C> {
+    #[doc(hidden)]
+    pub fn new(left_state: S, right_state: S, left: &'a C, right: &'a C) -> Self
+    where
+        S: PartialEq,
+    {
+        Self {
+            state: (left_state != right_state).then_some((left_state, right_state)),
+            left,
+            right,
+        }
+    }
+
+    /// Differing context fields, in declaration order
+    pub fn fields(&self) -> impl Iterator<Item = FieldChange<'a>> + '_ {
+        C::FIELDS
+            .iter()
+            .enumerate()
+            .filter(|&(index, _)| !self.left.field_eq(self.right, index))
+            .map(|(index, &name)| FieldChange {
+                name,
+                left: self.left.field(index),
+                right: self.right.field(index),
+            })
+    }
+
+    /// Whether both machines are in the same state with equal contexts
+    pub fn is_empty(&self) -> bool {
+        self.state.is_none() && self.fields().next().is_none()
+    }
+}
+
+impl<S: fmt::Debug, C: FieldDiff> fmt::Debug for Diff<'_, S, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut diff = f.debug_struct("Diff");
+        diff.field("state", &self.state);
+        for change in self.fields() {
+            diff.field(change.name, &(change.left, change.right));
+        }
+        diff.finish()
+    }
+}
@@ -0,0 +1,369 @@
+//! Machines defined at runtime.
+//!
+//! With the `alloc` feature, a [`DynFsm`] has its states, events and
+//! transitions built at runtime, for workflows loaded from configuration
+//! rather than known at compile time. The hooks of [`StateBehavior`] are
+//! given as closures: `enter` and `exit` per state, a guard and an action per
+//! transition.
+//!
+//! ```rust,ignore
+//! let mut workflow = DynFsm::builder(Approvals::default())
+//!     .state("Draft")
+//!     .state("Review")
+//!     .final_state("Published")
+//!     .event("Submit")
+//!     .event("Approve")
+//!     .transition("Draft", "Submit", "Review")
+//!     .transition("Review", "Approve", "Published")
+//!     .guard(|approvals| approvals.count >= 2)
+//!     .on_enter("Review", |approvals| approvals.count = 0)
+//!     .build()?;
+//!
+//! workflow.dispatch("Submit")?;
+//! assert_eq!(workflow.current_state(), "Review");
+//! ```
+//!
+//! Dispatching follows the order of the generated machines: the guard of
+//! the first rule matching the current state and the event, the action,
+//! then `exit` and `enter` on a transition. A rule whose guard refuses the
+//! event gives way to the next matching one, rules from any state coming
+//! after those of the state. Events matching no rule are ignored.
+//!
+//! [`StateBehavior`]: crate::StateBehavior
+
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{DispatchError, Status};
+
+/// Error returned when a [`DynFsm`] definition is invalid
+#[derive(Clone, PartialEq, Debug)]
+pub enum DefinitionError {
+    /// No state was declared
+    NoStates,
+    /// A state is declared twice
+    DuplicateState(String),
+    /// An event is declared twice
+    DuplicateEvent(String),
+    /// A state is used before being declared
+    UnknownState(String),
+    /// An event is used before being declared
+    UnknownEvent(String),
+    /// A guard or an action is given before any transition
+    NoTransition,
+}
+
+/// Handle to an event of a [`DynFsm`], dispatched without looking its name up
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct EventId(usize);
+
+/// `enter`, `exit` or action closure
+type Hook<C> = Option<Box<dyn FnMut(&mut C)>>;
+
+/// Guard closure
+type Guard<C> = Option<Box<dyn Fn(&C) -> bool>>;
+
+/// State of a [`DynFsm`]
+struct State<C> {
+    name: String,
+    is_final: bool,
+    enter: Hook<C>,
+    exit: Hook<C>,
+}
+
+/// Transition rule of a [`DynFsm`]
+struct Rule<C> {
+    /// Source state, `None` for every state
+    from: Option<usize>,
+    event: usize,
+    /// Next state, `None` to stay without leaving the state
+    to: Option<usize>,
+    guard: Guard<C>,
+    action: Hook<C>,
+}
+
+/// State machine defined at runtime, see the [module](self) documentation
+pub struct DynFsm<C> {
+    states: Vec<State<C>>,
+    events: Vec<String>,
+    rules: Vec<Rule<C>>,
+    current_state: usize,
+    context: C,
+    status: Status,
+}
+
+impl<C> DynFsm<C> {
+    /// Start the definition of a machine with `context`.
+    pub fn builder(context: C) -> DynFsmBuilder<C> {
+        DynFsmBuilder {
+            machine: DynFsm {
+                states: Vec::new(),
+                events: Vec::new(),
+                rules: Vec::new(),
+                current_state: 0,
+                context,
+                status: Status::Idle,
+            },
+            error: None,
+        }
+    }
+
+    /// Name of the current state
+    pub fn current_state(&self) -> &str {
+        self.states
+            .get(self.current_state)
+            .map_or("", |state| &state.name)
+    }
+
+    /// Names of the states, in declaration order
+    pub fn states(&self) -> impl Iterator<Item = &str> {
+        self.states.iter().map(|state| state.name.as_str())
+    }
+
+    /// Names of the events, in declaration order
+    pub fn events(&self) -> impl Iterator<Item = &str> {
+        self.events.iter().map(String::as_str)
+    }
+
+    /// Get a reference to the context.
+    pub fn context(&self) -> &C {
+        &self.context
+    }
+
+    /// Get a mutable reference to the context.
+    pub fn context_mut(&mut self) -> &mut C {
+        &mut self.context
+    }
+
+    /// Get the lifecycle status.
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    /// Whether the machine reached a final state
+    pub fn is_finished(&self) -> bool {
+        self.states
+            .get(self.current_state)
+            .is_some_and(|state| state.is_final)
+    }
+
+    /// Start the machine, running the initial state's `enter` function.
+    pub fn start(&mut self) {
+        if self.status != Status::Running {
+            self.run_hook(self.current_state, |state| &mut state.enter);
+            self.status = Status::Running;
+        }
+    }
+
+    /// Stop the machine. Events are refused until the next `start()`.
+    pub fn stop(&mut self) {
+        self.status = Status::Stopped;
+    }
+
+    /// Handle to the event named `name`, if declared
+    pub fn event_id(&self, name: &str) -> Option<EventId> {
+        self.events.iter().position(|event| event == name).map(EventId)
+    }
+
+    /// Dispatch the event named `name`, refused with
+    /// [`DispatchError::UnexpectedEvent`] if it isn't declared.
+    pub fn dispatch(&mut self, name: &str) -> Result<(), DispatchError> {
+        let event = self.event_id(name).ok_or(DispatchError::UnexpectedEvent)?;
+        self.dispatch_id(event)
+    }
+
+    /// Dispatch the event `event` is the handle of.
+    pub fn dispatch_id(&mut self, event: EventId) -> Result<(), DispatchError> {
+        if self.status == Status::Stopped {
+            return Err(DispatchError::Stopped);
+        }
+        if self.is_finished() {
+            return Err(DispatchError::Finished);
+        }
+        let current_state = self.current_state;
+        let context = &self.context;
+        // Rules of the state first, then those of any state
+        let rule = [Some(current_state), None].into_iter().find_map(|from| {
+            self.rules.iter().position(|rule| {
+                rule.from == from
+                    && rule.event == event.0
+                    && rule.guard.as_ref().is_none_or(|guard| guard(context))
+            })
+        });
+        let Some(rule) = rule.and_then(|rule| self.rules.get_mut(rule)) else {
+            return Ok(());
+        };
+
+        if let Some(action) = &mut rule.action {
+            action(&mut self.context);
+        }
+        if let Some(next_state) = rule.to {
+            self.run_hook(current_state, |state| &mut state.exit);
+            self.current_state = next_state;
+            self.run_hook(next_state, |state| &mut state.enter);
+        }
+        Ok(())
+    }
+
+    /// Run the `enter` or `exit` hook of the state at `index`, as picked by
+    /// `hook`.
+    fn run_hook(&mut self, index: usize, hook: impl FnOnce(&mut State<C>) -> &mut Hook<C>) {
+        if let Some(run) = self
+            .states
+            .get_mut(index)
+            .and_then(|state| hook(state).as_mut())
+        {
+            run(&mut self.context);
+        }
+    }
+}
+
+/// Definition of a [`DynFsm`], created by [`DynFsm::builder`]
+///
+/// Names are checked as they're used, the first mistake being reported by
+/// [`build`](Self::build).
+pub struct DynFsmBuilder<C> {
+    machine: DynFsm<C>,
+    error: Option<DefinitionError>,
+}
+
+impl<C> DynFsmBuilder<C> {
+    /// Declare a state, the first one being the initial state.
+    pub fn state(self, name: &str) -> Self {
+        self.declare_state(name, false)
+    }
+
+    /// Declare a final state, refusing events once reached.
+    pub fn final_state(self, name: &str) -> Self {
+        self.declare_state(name, true)
+    }
+
+    /// Declare an event.
+    pub fn event(mut self, name: &str) -> Self {
+        if self.machine.event_id(name).is_some() {
+            return self.fail(DefinitionError::DuplicateEvent(name.to_owned()));
+        }
+        self.machine.events.push(name.to_owned());
+        self
+    }
+
+    /// Run `enter` whenever `state` is entered.
+    pub fn on_enter(mut self, state: &str, enter: impl FnMut(&mut C) + 'static) -> Self {
+        match self.state_mut(state) {
+            Some(state) => state.enter = Some(Box::new(enter)),
+            None => return self.fail(DefinitionError::UnknownState(state.to_owned())),
+        }
+        self
+    }
+
+    /// Run `exit` whenever `state` is left.
+    pub fn on_exit(mut self, state: &str, exit: impl FnMut(&mut C) + 'static) -> Self {
+        match self.state_mut(state) {
+            Some(state) => state.exit = Some(Box::new(exit)),
+            None => return self.fail(DefinitionError::UnknownState(state.to_owned())),
+        }
+        self
+    }
+
+    /// Add a transition from `from` to `to` on `event`.
+    pub fn transition(self, from: &str, event: &str, to: &str) -> Self {
+        let from = self.state_index(from).ok_or(from);
+        self.rule(from.map(Some), event, Some(to))
+    }
+
+    /// Add a transition from every state to `to` on `event`, for the states
+    /// without a rule of their own taking it.
+    pub fn transition_from_any(self, event: &str, to: &str) -> Self {
+        self.rule(Ok(None), event, Some(to))
+    }
+
+    /// Add a rule handling `event` in `state` without leaving it, for an
+    /// action.
+    pub fn internal(self, state: &str, event: &str) -> Self {
+        let state = self.state_index(state).ok_or(state);
+        self.rule(state.map(Some), event, None)
+    }
+
+    /// Guard the last rule added with `guard`.
+    pub fn guard(mut self, guard: impl Fn(&C) -> bool + 'static) -> Self {
+        match self.machine.rules.last_mut() {
+            Some(rule) => rule.guard = Some(Box::new(guard)),
+            None => return self.fail(DefinitionError::NoTransition),
+        }
+        self
+    }
+
+    /// Run `action` when the last rule added is taken.
+    pub fn action(mut self, action: impl FnMut(&mut C) + 'static) -> Self {
+        match self.machine.rules.last_mut() {
+            Some(rule) => rule.action = Some(Box::new(action)),
+            None => return self.fail(DefinitionError::NoTransition),
+        }
+        self
+    }
+
+    /// Build the machine, in its initial state, without calling its `enter`
+    /// function.
+    pub fn build(self) -> Result<DynFsm<C>, DefinitionError> {
+        match self.error {
+            Some(error) => Err(error),
+            None if self.machine.states.is_empty() => Err(DefinitionError::NoStates),
+            None => Ok(self.machine),
+        }
+    }
+
+    fn declare_state(mut self, name: &str, is_final: bool) -> Self {
+        if self.state_index(name).is_some() {
+            return self.fail(DefinitionError::DuplicateState(name.to_owned()));
+        }
+        self.machine.states.push(State {
+            name: name.to_owned(),
+            is_final,
+            enter: None,
+            exit: None,
+        });
+        self
+    }
+
+    fn rule(mut self, from: Result<Option<usize>, &str>, event: &str, to: Option<&str>) -> Self {
+        let from = match from {
+            Ok(from) => from,
+            Err(name) => return self.fail(DefinitionError::UnknownState(name.to_owned())),
+        };
+        let Some(EventId(event)) = self.machine.event_id(event) else {
+            return self.fail(DefinitionError::UnknownEvent(event.to_owned()));
+        };
+        let to = match to.map(|to| (to, self.state_index(to))) {
+            None => None,
+            Some((_, Some(index))) => Some(index),
+            Some((name, None)) => return self.fail(DefinitionError::UnknownState(name.to_owned())),
+        };
+        self.machine.rules.push(Rule {
+            from,
+            event,
+            to,
+            guard: None,
+            action: None,
+        });
+        self
+    }
+
+    fn state_index(&self, name: &str) -> Option<usize> {
+        self.machine.states.iter().position(|state| state.name == name)
+    }
+
+    fn state_mut(&mut self, name: &str) -> Option<&mut State<C>> {
+        self.machine
+            .states
+            .iter_mut()
+            .find(|state| state.name == name)
+    }
+
+    /// Record the first mistake of the definition.
+    fn fail(mut self, error: DefinitionError) -> Self {
+        self.error.get_or_insert(error);
+        self
+    }
+}
@@ -0,0 +1,291 @@
+//! State and context export with `serde`.
+//!
+//! With the `serde` feature, the generated state and event enums and
+//! contexts implement `Serialize` and `Deserialize` when their data does,
+//! and every machine implements [`Export`]. A context given by name only
+//! must implement them itself.
+//!
+//! The `json` and `cbor` features, which need `std`, add conversions of the
+//! exported [`Snapshot`] so backend services mirroring a device's state can
+//! ingest it directly:
+//!
+//! ```rust,ignore
+//! use rustfsm::export::Export;
+//!
+//! let json = mario.to_json()?;
+//! // {"state":"SuperMario","context":{"size":"Large","alive":true}}
+//! let mirror = Mario::from_json(&json)?;
+//! ```
+
+use core::fmt;
+use core::marker::PhantomData;
+
+use serde::de::{self, DeserializeSeed, MapAccess, SeqAccess, VariantAccess, Visitor};
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Exported state of a machine: its current state and context
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Snapshot<S, C> {
+    /// Current state
+    pub state: S,
+    /// Context
+    pub context: C,
+}
+
+impl<S: Serialize, C: Serialize> Serialize for Snapshot<S, C> {
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        let mut snapshot = serializer.serialize_struct("Snapshot", 2)?;
+        snapshot.serialize_field("state", &self.state)?;
+        snapshot.serialize_field("context", &self.context)?;
+        snapshot.end()
+    }
+}
+
+impl<'de, S: Deserialize<'de>, C: Deserialize<'de>> Deserialize<'de> for Snapshot<S, C> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        const FIELDS: &[&str] = &["state", "context"];
+
+        struct SnapshotVisitor<S, C>(PhantomData<(S, C)>);
+
+        impl<'de, S: Deserialize<'de>, C: Deserialize<'de>> Visitor<'de> for SnapshotVisitor<S, C> {
+            type Value = Snapshot<S, C>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("struct Snapshot")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut state = None;
+                let mut context = None;
+                while let Some(index) = map.next_key_seed(Identifier(FIELDS))? {
+                    match index {
+                        0 => state = Some(map.next_value()?),
+                        _ => context = Some(map.next_value()?),
+                    }
+                }
+                Ok(Snapshot {
+                    state: state.ok_or_else(|| de::Error::missing_field("state"))?,
+                    context: context.ok_or_else(|| de::Error::missing_field("context"))?,
+                })
+            }
+        }
+
+        deserializer.deserialize_struct("Snapshot", FIELDS, SnapshotVisitor(PhantomData))
+    }
+}
+
+/// Export and import of a machine's [`Snapshot`], implemented by every
+/// generated machine
+///
+/// Importing creates a new machine in the exported state, with the
+/// exported context. Its other members take their default values.
+pub trait Export: Sized {
+    type State;
+    type Context;
+
+    /// Export the current state and the context.
+    fn export(&self) -> Snapshot<&Self::State, &Self::Context>;
+
+    /// Create a new state machine from an exported snapshot.
+    fn import(snapshot: Snapshot<Self::State, Self::Context>) -> Self;
+
+    /// Export the current state and the context as JSON.
+    #[cfg(feature = "json")]
+    fn to_json(&self) -> Result<std::string::String, serde_json::Error>
+    where
+        Self::State: Serialize,
+        Self::Context: Serialize,
+    {
+        serde_json::to_string(&self.export())
+    }
+
+    /// Create a new state machine from a snapshot exported as JSON.
+    #[cfg(feature = "json")]
+    fn from_json(json: &str) -> Result<Self, serde_json::Error>
+    where
+        Self::State: de::DeserializeOwned,
+        Self::Context: de::DeserializeOwned,
+    {
+        serde_json::from_str(json).map(Self::import)
+    }
+
+    /// Export the current state and the context as CBOR.
+    #[cfg(feature = "cbor")]
+    fn to_cbor(&self) -> Result<std::vec::Vec<u8>, ciborium::ser::Error<std::io::Error>>
+    where
+        Self::State: Serialize,
+        Self::Context: Serialize,
+    {
+        let mut cbor = std::vec::Vec::new();
+        ciborium::into_writer(&self.export(), &mut cbor)?;
+        Ok(cbor)
+    }
+
+    /// Create a new state machine from a snapshot exported as CBOR.
+    #[cfg(feature = "cbor")]
+    fn from_cbor(cbor: &[u8]) -> Result<Self, ciborium::de::Error<std::io::Error>>
+    where
+        Self::State: de::DeserializeOwned,
+        Self::Context: de::DeserializeOwned,
+    {
+        ciborium::from_reader(cbor).map(Self::import)
+    }
+}
+
+/// Variant or field name, deserialized as its position in a list
+struct Identifier(&'static [&'static str]);
+
+impl<'de> DeserializeSeed<'de> for Identifier {
+    type Value = usize;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<usize, D::Error> {
+        deserializer.deserialize_identifier(self)
+    }
+}
+
+impl Visitor<'_> for Identifier {
+    type Value = usize;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an identifier")
+    }
+
+    fn visit_u64<E: de::Error>(self, index: u64) -> Result<usize, E> {
+        match usize::try_from(index) {
+            Ok(index) if index < self.0.len() => Ok(index),
+            _ => Err(E::invalid_value(
+                de::Unexpected::Unsigned(index),
+                &"a valid index",
+            )),
+        }
+    }
+
+    fn visit_str<E: de::Error>(self, name: &str) -> Result<usize, E> {
+        self.0
+            .iter()
+            .position(|candidate| *candidate == name)
+            .ok_or_else(|| E::invalid_value(de::Unexpected::Str(name), &self))
+    }
+
+    fn visit_bytes<E: de::Error>(self, name: &[u8]) -> Result<usize, E> {
+        match core::str::from_utf8(name) {
+            Ok(name) => self.visit_str(name),
+            Err(_) => Err(E::invalid_value(de::Unexpected::Bytes(name), &self)),
+        }
+    }
+}
+
+/// Enum deserialized variant by variant, implemented by the generated
+/// enums
+#[doc(hidden)]
+pub trait EnumVariants<'de>: Sized {
+    const NAME: &'static str;
+    const VARIANTS: &'static [&'static str];
+
+    /// Number of fields of a variant
+    fn arity(variant: usize) -> usize;
+
+    /// Build a variant from its fields, an empty sequence for unit variants
+    fn from_seq<A: SeqAccess<'de>>(variant: usize, seq: A) -> Result<Self, A::Error>;
+}
+
+#[doc(hidden)]
+pub fn deserialize_enum<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: EnumVariants<'de>,
+    D: Deserializer<'de>,
+{
+    struct EnumVisitor<T>(PhantomData<T>);
+
+    impl<'de, T: EnumVariants<'de>> Visitor<'de> for EnumVisitor<T> {
+        type Value = T;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            write!(formatter, "enum {}", T::NAME)
+        }
+
+        fn visit_enum<A: de::EnumAccess<'de>>(self, data: A) -> Result<T, A::Error> {
+            let (variant, access) = data.variant_seed(Identifier(T::VARIANTS))?;
+            match T::arity(variant) {
+                0 => {
+                    access.unit_variant()?;
+                    T::from_seq(
+                        variant,
+                        de::value::SeqDeserializer::new(core::iter::empty::<()>()),
+                    )
+                }
+                arity => access.tuple_variant(arity, FieldsVisitor(variant, PhantomData)),
+            }
+        }
+    }
+
+    struct FieldsVisitor<T>(usize, PhantomData<T>);
+
+    impl<'de, T: EnumVariants<'de>> Visitor<'de> for FieldsVisitor<T> {
+        type Value = T;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            let variant = T::VARIANTS.get(self.0).copied().unwrap_or_default();
+            write!(formatter, "variant {}::{}", T::NAME, variant)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<T, A::Error> {
+            T::from_seq(self.0, seq)
+        }
+    }
+
+    deserializer.deserialize_enum(T::NAME, T::VARIANTS, EnumVisitor(PhantomData))
+}
+
+/// Next field of a variant, counting them for error messages
+#[doc(hidden)]
+pub fn next_field<'de, T, A>(seq: &mut A, count: &mut usize) -> Result<T, A::Error>
+where
+    T: Deserialize<'de>,
+    A: SeqAccess<'de>,
+{
+    let field = seq
+        .next_element()?
+        .ok_or_else(|| de::Error::invalid_length(*count, &"more fields"))?;
+    *count += 1;
+    Ok(field)
+}
+
+/// Struct deserialized field by field over its default value, implemented
+/// by the generated contexts
+#[doc(hidden)]
+pub trait StructFields<'de>: Default {
+    const NAME: &'static str;
+    const FIELDS: &'static [&'static str];
+
+    /// Read the value of a field
+    fn field<A: MapAccess<'de>>(&mut self, field: usize, map: &mut A) -> Result<(), A::Error>;
+}
+
+#[doc(hidden)]
+pub fn deserialize_struct<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: StructFields<'de>,
+    D: Deserializer<'de>,
+{
+    struct StructVisitor<T>(PhantomData<T>);
+
+    impl<'de, T: StructFields<'de>> Visitor<'de> for StructVisitor<T> {
+        type Value = T;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            write!(formatter, "struct {}", T::NAME)
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<T, A::Error> {
+            let mut value = T::default();
+            while let Some(field) = map.next_key_seed(Identifier(T::FIELDS))? {
+                value.field(field, &mut map)?;
+            }
+            Ok(value)
+        }
+    }
+
+    deserializer.deserialize_struct(T::NAME, T::FIELDS, StructVisitor(PhantomData))
+}
@@ -0,0 +1,62 @@
+//! C FFI status codes.
+//!
+//! With the `ffi` feature, every generated machine also gets `extern "C"`
+//! functions prefixed with its name in snake case, `foo_name_` for `FooName`:
+//!
+//! - `foo_name_size()` and `foo_name_align()`: size and alignment of the
+//!   storage to provide for a machine.
+//! - `foo_name_new(machine)`: initialize a machine in that storage.
+//! - `foo_name_dispatch(machine, event_id, payload)`: dispatch the event
+//!   declared at position `event_id`, reading its data from `payload`, laid
+//!   out as a C struct of the variant's fields. Returns one of the status
+//!   codes below.
+//! - `foo_name_current_state(machine)`: position of the current state.
+//!
+//! State and event ids are their declaration positions, starting at 0, and
+//! only change when the declarations are reordered.
+
+use crate::DispatchError;
+
+/// The event was dispatched
+pub const OK: i32 = 0;
+/// The state machine is stopped
+pub const STOPPED: i32 = -1;
+/// The state machine reached a final state
+pub const FINISHED: i32 = -2;
+/// A fault was raised
+pub const FAULT: i32 = -3;
+/// Unknown event id, or missing machine or payload
+pub const UNEXPECTED_EVENT: i32 = -4;
+/// The event was dropped by the machine's filter
+pub const FILTERED: i32 = -5;
+/// The event was vetoed by one of the machine's interceptors
+pub const VETOED: i32 = -6;
+/// The machine was already handling an event
+pub const REENTRANT: i32 = -7;
+/// The event led to no transition, and the machine refuses those
+pub const UNHANDLED: i32 = -8;
+/// The current state's guard refused the event
+pub const GUARD_REJECTED: i32 = -9;
+/// The state machine is paused
+pub const PAUSED: i32 = -10;
+/// The event's payload was refused by the machine's `Validate` section
+pub const INVALID: i32 = -11;
+
+/// Status code of a dispatch result, as returned by the generated
+/// `_dispatch` functions
+pub const fn status(result: Result<(), DispatchError>) -> i32 {
+    match result {
+        Ok(()) => OK,
+        Err(DispatchError::Stopped) => STOPPED,
+        Err(DispatchError::Finished) => FINISHED,
+        Err(DispatchError::Fault(_)) => FAULT,
+        Err(DispatchError::UnexpectedEvent) => UNEXPECTED_EVENT,
+        Err(DispatchError::Filtered) => FILTERED,
+        Err(DispatchError::Vetoed) => VETOED,
+        Err(DispatchError::Reentrant) => REENTRANT,
+        Err(DispatchError::Unhandled) => UNHANDLED,
+        Err(DispatchError::GuardRejected { .. }) => GUARD_REJECTED,
+        Err(DispatchError::Paused) => PAUSED,
+        Err(DispatchError::Invalid(_)) => INVALID,
+    }
+}
@@ -0,0 +1,52 @@
+//! Pre-dispatch event filtering.
+
+/// Filter applied to the events of a state machine before they are handled
+///
+/// Returning `None` drops the event, which is then refused with
+/// [`DispatchError::Filtered`](crate::DispatchError). A machine picks its
+/// filter in its `Filter` section.
+pub trait Filter<E> {
+    /// Filter `event`, dispatched at tick `now`, possibly replacing it.
+    fn filter(&mut self, event: E, now: u32) -> Option<E>;
+}
+
+/// Filter of machines without a `Filter` section, letting every event through
+impl<E> Filter<E> for () {
+    #[inline(always)]
+    fn filter(&mut self, event: E, _now: u32) -> Option<E> {
+        Some(event)
+    }
+}
+
+/// Filter dropping an event equal to the last one let through, if less than
+/// `TICKS` ticks elapsed since
+///
+/// ```rust,ignore
+/// rustfsm!(
+///     ...
+///     Clock { SysTick },
+///     Filter { Debounce<Events, 50> }
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct Debounce<E, const TICKS: u32> {
+    last: Option<(E, u32)>,
+}
+
+impl<E, const TICKS: u32> Default for Debounce<E, TICKS> {
+    fn default() -> Self {
+        Self { last: None }
+    }
+}
+
+impl<E: Copy + PartialEq, const TICKS: u32> Filter<E> for Debounce<E, TICKS> {
+    fn filter(&mut self, event: E, now: u32) -> Option<E> {
+        if let Some((last, at)) = self.last {
+            if last == event && now.wrapping_sub(at) < TICKS {
+                return None;
+            }
+        }
+        self.last = Some((event, now));
+        Some(event)
+    }
+}
@@ -0,0 +1,72 @@
+//! Event interceptor chain.
+
+use crate::{DispatchError, Source};
+
+/// Interceptor observing, transforming or vetoing the events of a state
+/// machine in state `S`, with event `E` and context `C`
+///
+/// A machine lists its interceptors in its `Interceptors` section. They run in
+/// that order, after the machine's filter, and each one sees the event as
+/// returned by the previous one.
+pub trait Interceptor<S, E, C> {
+    /// Learn where the event about to be inspected by `before` came from,
+    /// always `Source::Host` without the `tagged` dispatch option.
+    fn on_source(&mut self, _source: Source) {}
+
+    /// Inspect `event` before it is handled, possibly replacing it, or veto
+    /// it by returning `None`.
+    fn before(&mut self, _state: &S, _context: &C, event: E) -> Option<E> {
+        Some(event)
+    }
+
+    /// Observe a transition from `from` to `to` caused by `event`.
+    ///
+    /// It's called during the transition, at the point chosen with the
+    /// `notify` option of the `Dispatch` section, after the new state's
+    /// `enter` function by default.
+    fn on_transition(&mut self, _event: &E, _from: &S, _to: &S, _context: &C) {}
+
+    /// Observe the outcome of a handled or vetoed event.
+    fn after(&mut self, _event: &E, _from: &S, _to: &S, _result: &Result<(), DispatchError>) {}
+}
+
+impl<S, E, C> Interceptor<S, E, C> for () {}
+
+macro_rules! impl_interceptor_chain {
+    ($($interceptor:ident),+) => {
+        #[allow(non_snake_case)]
+        impl<S, E, C, $($interceptor: Interceptor<S, E, C>),+> Interceptor<S, E, C>
+            for ($($interceptor,)+)
+        {
+            fn on_source(&mut self, source: Source) {
+                let ($($interceptor,)+) = self;
+                $($interceptor.on_source(source);)+
+            }
+
+            fn before(&mut self, state: &S, context: &C, event: E) -> Option<E> {
+                let ($($interceptor,)+) = self;
+                $(let event = $interceptor.before(state, context, event)?;)+
+                Some(event)
+            }
+
+            fn on_transition(&mut self, event: &E, from: &S, to: &S, context: &C) {
+                let ($($interceptor,)+) = self;
+                $($interceptor.on_transition(event, from, to, context);)+
+            }
+
+            fn after(&mut self, event: &E, from: &S, to: &S, result: &Result<(), DispatchError>) {
+                let ($($interceptor,)+) = self;
+                $($interceptor.after(event, from, to, result);)+
+            }
+        }
+    };
+}
+
+impl_interceptor_chain!(I1);
+impl_interceptor_chain!(I1, I2);
+impl_interceptor_chain!(I1, I2, I3);
+impl_interceptor_chain!(I1, I2, I3, I4);
+impl_interceptor_chain!(I1, I2, I3, I4, I5);
+impl_interceptor_chain!(I1, I2, I3, I4, I5, I6);
+impl_interceptor_chain!(I1, I2, I3, I4, I5, I6, I7);
+impl_interceptor_chain!(I1, I2, I3, I4, I5, I6, I7, I8);
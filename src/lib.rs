@@ -1,185 +1,3951 @@
 #![no_std]
+#![cfg_attr(feature = "audit", forbid(unsafe_code))]
+#![cfg_attr(
+    feature = "audit",
+    deny(
+        clippy::indexing_slicing,
+        clippy::unwrap_used,
+        clippy::expect_used,
+        clippy::panic,
+        clippy::unreachable,
+        clippy::todo,
+        clippy::unimplemented
+    )
+)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "std")]
+extern crate std;
+
+use core::any::Any;
 use core::fmt;
 
+pub mod changes;
+pub mod clock;
+pub mod codec;
+#[cfg(feature = "codegen")]
+pub mod codegen;
+pub mod coverage;
+#[cfg(feature = "diff")]
+pub mod diff;
+#[cfg(feature = "alloc")]
+pub mod dynamic;
+#[cfg(feature = "serde")]
+pub mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod filter;
+pub mod interceptor;
+pub mod lockstep;
+pub mod metrics;
+pub mod output;
+mod pool;
+pub mod queue;
+pub mod remote;
+pub mod rng;
+#[cfg(feature = "heapless")]
+pub mod spsc;
+pub mod stats;
+mod supervisor;
+#[cfg(feature = "std")]
+mod sync;
+pub mod table;
+pub mod testing;
+pub mod timer;
+pub mod trace;
+mod translate;
+mod typestate;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use pool::Pool;
+pub use supervisor::{Recovery, Strategy, Supervisor};
+#[cfg(feature = "std")]
+pub use sync::{PoisonPolicy, SyncFsm};
+pub use translate::Translate;
+pub use typestate::TypeState;
+
+#[cfg(feature = "macros")]
+pub use rustfsm_macros::fsm;
+
+#[doc(hidden)]
+pub use paste;
+
+#[cfg(feature = "arbitrary")]
+#[doc(hidden)]
+pub use arbitrary;
+
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+pub use serde;
+
 /// Trait for the state behavior
 pub trait StateBehavior {
     type State: Clone + Copy + PartialEq + fmt::Debug;
-    type Event: Clone + Copy + PartialEq + fmt::Debug;
+    type Event: fmt::Debug;
     type Context: Default + fmt::Debug;
 
     /// Handle an event and return next state (if a transition occurs)
     fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State>;
 
+    /// Fallible event handling, defaulting to `handle`.
+    ///
+    /// An error escalates the machine to its `@fault` state.
+    fn try_handle(
+        &self,
+        event: &Self::Event,
+        context: &mut Self::Context,
+    ) -> Result<Option<Self::State>, &'static str> {
+        Ok(self.handle(event, context))
+    }
+
+    /// Event handling posting follow-up events to `outbox`, defaulting to
+    /// `try_handle`.
+    ///
+    /// The posted events are dispatched before the `dispatch` call handling
+    /// `event` returns, provided the machine has a `Queue` section. See the
+    /// [`queue`] module.
+    fn try_handle_with(
+        &self,
+        event: &Self::Event,
+        context: &mut Self::Context,
+        _outbox: &mut queue::Outbox<'_, Self::Event>,
+    ) -> Result<Option<Self::State>, &'static str> {
+        self.try_handle(event, context)
+    }
+
+    /// Guard check, run before handling an event.
+    ///
+    /// Refusing the event fails the dispatch with
+    /// [`DispatchError::GuardRejected`], telling a refused command apart from
+    /// an event the state doesn't handle.
+    fn guard(&self, _event: &Self::Event, _context: &Self::Context) -> bool {
+        true
+    }
+
+    /// Guard check drawing from the machine's random number source,
+    /// defaulting to `guard`.
+    ///
+    /// Run instead of `guard` on machines with an `Rng` section. See the
+    /// [`rng`] module.
+    fn guard_with_rng(
+        &self,
+        event: &Self::Event,
+        context: &Self::Context,
+        _rng: &mut dyn rng::Rng,
+    ) -> bool {
+        self.guard(event, context)
+    }
+
+    /// Hook for an event leading to no transition, on machines with the
+    /// `unhandled(hook)` or `unhandled(assert)` policy
+    fn unhandled(&self, _event: &Self::Event, _context: &mut Self::Context) {}
+
+    /// Invariant check, run after every handled event.
+    ///
+    /// An error escalates the machine to its `@fault` state.
+    fn check_invariant(&self, _context: &Self::Context) -> Result<(), &'static str> {
+        Ok(())
+    }
+
     /// State entry
     fn enter(&self, _context: &mut Self::Context) {}
 
     /// State exit
     fn exit(&self, _context: &mut Self::Context) {}
+
+    /// Entry hook common to all the states, run before the `enter` of the
+    /// state entered
+    fn on_any_enter(&self, _context: &mut Self::Context) {}
+
+    /// Exit hook common to all the states, run after the `exit` of the state
+    /// left
+    fn on_any_exit(&self, _context: &mut Self::Context) {}
 }
 
-/// # RustFSM
-///
-/// A full static Rust finite state machine library.
-///
-/// ## Usage
+/// Lifecycle status of a state machine
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Status {
+    /// Created or reset, but not explicitly started yet. Events are still
+    /// accepted, without the initial state's `enter` having run.
+    Idle,
+    /// Started with `start()`.
+    Running,
+    /// Stopped with `stop()`. Events are refused until the next `start()`.
+    Stopped,
+}
+
+/// Context handling when resetting a state machine
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ResetPolicy {
+    /// Restore the context to its default values
+    RestoreDefaults,
+    /// Keep the context as it is
+    PreserveContext,
+}
+
+/// Event handling while a state machine is paused with `pause`
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PausePolicy {
+    /// Queue the events in the machine's `Queue`, to be dispatched on
+    /// `resume`
+    Queue,
+    /// Refuse the events with [`DispatchError::Paused`]
+    Reject,
+}
+
+/// Where a dispatched event came from, as told by `dispatch_tagged`
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Source {
+    /// Dispatched by the application, the default
+    #[default]
+    Host,
+    /// Injected from an interrupt handler
+    Isr,
+    /// Delivered by an expired timer
+    Timer,
+    /// Taken from the machine's queue
+    Queued,
+    /// Application-defined source
+    Other(u8),
+}
+
+/// Source of the event being dispatched, as kept by machines with the
+/// `tagged` dispatch option or not
+#[doc(hidden)]
+pub trait SourceSlot {
+    /// Source of the event being dispatched, or of the last one
+    fn get(&self) -> Source;
+
+    /// Set the source of the event being dispatched
+    fn set(&mut self, source: Source);
+}
+
+impl SourceSlot for Source {
+    #[inline(always)]
+    fn get(&self) -> Source {
+        *self
+    }
+
+    #[inline(always)]
+    fn set(&mut self, source: Source) {
+        *self = source;
+    }
+}
+
+/// Stand-in for the source of machines without the `tagged` dispatch
+/// option, every event coming from [`Source::Host`]
+#[doc(hidden)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct NoSource;
+
+impl SourceSlot for NoSource {
+    #[inline(always)]
+    fn get(&self) -> Source {
+        Source::Host
+    }
+
+    #[inline(always)]
+    fn set(&mut self, _source: Source) {}
+}
+
+/// Read-only view of a state machine, returned by its `view()`
 ///
-/// The `rustfsm` macro takes as input the state machine's name, list of
-/// states, list of events and context.
+/// Display and telemetry code given a view reads the current state and the
+/// context, but can neither dispatch events nor mutate the context.
+#[derive(Debug)]
+pub struct MachineView<'a, S, C> {
+    state: S,
+    status: Status,
+    finished: bool,
+    context: &'a C,
+}
+
+impl<'a, S: Copy, C> MachineView<'a, S, C> {
+    #[doc(hidden)]
+    pub fn new(state: S, status: Status, finished: bool, context: &'a C) -> Self {
+        Self {
+            state,
+            status,
+            finished,
+            context,
+        }
+    }
+
+    /// Current state of the machine
+    pub fn state(&self) -> S {
+        self.state
+    }
+
+    /// Context of the machine
+    pub fn context(&self) -> &'a C {
+        self.context
+    }
+
+    /// Lifecycle status of the machine
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    /// Whether the machine reached a final state
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
+
+impl<S: Copy, C> Clone for MachineView<'_, S, C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<S: Copy, C> Copy for MachineView<'_, S, C> {}
+
+/// Entry handling when forcing a state with `force_state_checked`
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ForceMode {
+    /// Switch states without running `exit` and `enter`
+    Silent,
+    /// Run the current state's `exit` and the forced state's `enter`
+    RunHooks,
+}
+
+/// Error returned when a state cannot be forced
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ForceError {
+    /// The transition table has no path from the current state to the
+    /// forced one
+    Unreachable,
+}
+
+/// Cause of a fault escalation
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Fault {
+    /// A fallible handler returned an error
+    Handler(&'static str),
+    /// An invariant check failed
+    Invariant(&'static str),
+    /// The named state outlived its watchdog limit
+    Watchdog(&'static str),
+}
+
+/// Invariant violated, as reported by the check of an `Invariant` section
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct InvariantViolation(pub &'static str);
+
+/// Malformed event payload, as reported by the check of a `Validate` section
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ValidationError(pub &'static str);
+
+/// Error returned when an event cannot be dispatched
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DispatchError {
+    /// The state machine is stopped
+    Stopped,
+    /// The state machine reached a final state
+    Finished,
+    /// The event was dropped by the machine's filter
+    Filtered,
+    /// The event was vetoed by one of the machine's interceptors
+    Vetoed,
+    /// Handling the event raised a fault
+    Fault(Fault),
+    /// A type-erased event is not of the state machine's event type
+    UnexpectedEvent,
+    /// An event was dispatched while the machine was handling another one,
+    /// detected in debug builds
+    Reentrant,
+    /// The event led to no transition, on a machine with the
+    /// `unhandled(error)` policy
+    Unhandled,
+    /// The current state's guard refused the event
+    GuardRejected {
+        /// Name of the state refusing the event
+        state: &'static str,
+        /// Name of the refused event
+        event: &'static str,
+    },
+    /// The machine is paused, and refuses events or has no room left to
+    /// queue them
+    Paused,
+    /// The event's payload was refused by the machine's `Validate` section
+    Invalid(ValidationError),
+}
+
+/// Whether `names` contains `name`, for the checks of the macro's input
+#[doc(hidden)]
+pub const fn names_contain(mut names: &[&str], name: &str) -> bool {
+    while let [candidate, rest @ ..] = names {
+        let (mut candidate, mut name) = (candidate.as_bytes(), name.as_bytes());
+        loop {
+            match (candidate, name) {
+                ([], []) => return true,
+                ([left, left_rest @ ..], [right, right_rest @ ..]) if *left == *right => {
+                    candidate = left_rest;
+                    name = right_rest;
+                }
+                _ => break,
+            }
+        }
+        names = rest;
+    }
+    false
+}
+
+/// Fields of a generated machine, which its members can't be named after
+#[doc(hidden)]
+pub const MACHINE_FIELDS: &[&str] = &[
+    "current_state",
+    "context",
+    "status",
+    "paused",
+    "source",
+    "last_fault",
+    "coverage",
+    "trace",
+    "metrics",
+    "stay",
+    "queue",
+    "output",
+    "guard",
+    "timers",
+    "filter",
+    "interceptors",
+    "rng",
+    "state",
+    "build",
+];
+
+/// Detection of nested dispatches on a state machine, in debug builds only
+#[doc(hidden)]
+#[derive(Debug, Default)]
+pub struct DispatchGuard {
+    #[cfg(debug_assertions)]
+    busy: core::sync::atomic::AtomicBool,
+}
+
+impl DispatchGuard {
+    #[doc(hidden)]
+    pub const fn new() -> Self {
+        Self {
+            #[cfg(debug_assertions)]
+            busy: core::sync::atomic::AtomicBool::new(false),
+        }
+    }
+
+    /// Mark a dispatch as running, returning `false` if one already is.
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn enter(&self) -> bool {
+        #[cfg(debug_assertions)]
+        {
+            use core::sync::atomic::Ordering;
+
+            if self.busy.load(Ordering::Acquire) {
+                return false;
+            }
+            self.busy.store(true, Ordering::Release);
+        }
+        true
+    }
+
+    /// Mark the running dispatch as done.
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn exit(&self) {
+        #[cfg(debug_assertions)]
+        self.busy
+            .store(false, core::sync::atomic::Ordering::Release);
+    }
+}
+
+/// Trait implemented by every state machine generated with [`rustfsm`]
+pub trait StateMachine {
+    type State;
+    type Event;
+    type Context;
+
+    /// Create a new state machine
+    fn new() -> Self;
+
+    /// Get a copy of the current state
+    fn get_current_state(&self) -> Self::State;
+
+    /// Get a reference to the context
+    fn context(&self) -> &Self::Context;
+
+    /// Whether the state machine reached a final state
+    fn is_finished(&self) -> bool;
+
+    /// Dispatch event and transition if necessary
+    fn dispatch(&mut self, event: Self::Event) -> Result<(), DispatchError>;
+
+    /// Handle event and transition if necessary, ignoring dispatch errors
+    fn handle(&mut self, event: Self::Event) {
+        let _ = self.dispatch(event);
+    }
+}
+
+/// Run `step` on `context`, rolling the context back if it fails.
 ///
-/// The state machine's name can be just an ident if no other member is desired
-/// to the struct:
+/// Lets handlers try a risky multi-field mutation without leaving the
+/// context half updated:
 ///
 /// ```rust,ignore
-/// use rustfsm::{rustfsm, StateBehavior};
-///
-/// rustfsm!(
-///     FooName,
-///     FooStates {
-///         FooStateA,
-///         FooStateB,
-///     },
-///     Events {
-///         FooEvent1,
-///         FooEvent2,
-///     },
-///     Context {
-///         context_foo_data: u8 = 0,
-///         context_boo_data: bool = true,
-///     }
-/// );
+/// rustfsm::transaction(context, |context| {
+///     context.offset = compute_offset()?;
+///     context.gain = compute_gain(context.offset)?;
+///     Ok(())
+/// })
 /// ```
+pub fn transaction<C: Clone, T, E>(
+    context: &mut C,
+    step: impl FnOnce(&mut C) -> Result<T, E>,
+) -> Result<T, E> {
+    let checkpoint = context.clone();
+    let result = step(context);
+    if result.is_err() {
+        *context = checkpoint;
+    }
+    result
+}
+
+/// Object-safe trait implemented by every state machine generated with
+/// [`rustfsm`]
 ///
-/// The state machine's name can also be a struct with default values if data
-/// other than the cotext is desired:
-///
-/// ```rust,ignore
-/// use rustfsm::{rustfsm, StateBehavior};
-///
-/// rustfsm!(
-///     FooName {
-///         foo_data: u16 = 0,
-///         boo_data: boo = false,
-///     },
-///     FooStates {
-///         FooStateA,
-///         FooStateB,
-///     },
-///     Events {
-///         FooEvent1,
-///         FooEvent2,
-///     },
-///     Context {
-///         foo_data: u8 = 0,
-///         boo_data: bool = true,
-///     }
-/// );
-/// ```
+/// Supervisory code, schedulers or debug consoles can hold machines of
+/// different types behind `&mut dyn FsmCore`.
+pub trait FsmCore {
+    /// Name of the state machine
+    fn name(&self) -> &'static str;
+
+    /// Name of the current state
+    fn current_state_name(&self) -> &'static str;
+
+    /// Get the lifecycle status
+    fn status(&self) -> Status;
+
+    /// Whether the state machine reached a final state
+    fn is_finished(&self) -> bool;
+
+    /// Get the cause of the last fault, if any
+    fn last_fault(&self) -> Option<Fault>;
+
+    /// Start the state machine
+    fn start(&mut self);
+
+    /// Stop the state machine
+    fn stop(&mut self);
+
+    /// Reset the state machine to its initial state
+    fn reset(&mut self, policy: ResetPolicy);
+
+    /// Dispatch a type-erased event, refused with
+    /// [`DispatchError::UnexpectedEvent`] if it isn't of the machine's event
+    /// type
+    fn dispatch_erased(&mut self, event: &dyn Any) -> Result<(), DispatchError>;
+}
+
+// Generation of the reachability helpers for a state type with a transition
+// table, compiled out without the `reachability` feature.
+#[cfg(feature = "reachability")]
+#[doc(hidden)]
 #[macro_export]
-macro_rules! rustfsm {
-    // Case 1: With additional members for the state machine struct
-    (
-        $state_machine_name:ident {
-            $($member_field:ident: $member_field_type:ty = $member_default:expr),* $(,)?
-        },
-        $state_type:ident {
-            $first_state:ident $(($($first_state_data:ty),*))?,
-            $($remaining_states:ident $(($($remaining_state_data:ty),*))? ),* $(,)?
-        },
-        $event_type:ident {
-            $($event_variant:ident $(($($event_variant_data:ty),*))? ),* $(,)?
-        },
-        $context_type:ident {
-            $($context_field:ident: $context_field_type:ty = $context_default:expr),* $(,)?
+macro_rules! __rustfsm_reachability {
+    ($state_type:ident) => {
+        impl $state_type {
+            /// Whether `to` can be reached from this state by following the
+            /// transition table.
+            pub fn is_reachable(&self, to: &Self) -> bool {
+                $crate::table::shortest_path::<{ $state_type::NAMES.len() }>(
+                    $state_type::TRANSITION_TABLE,
+                    self.index(),
+                    to.index(),
+                )
+                .is_some()
+            }
+
+            /// Shortest sequence of events, as written in the transition
+            /// table, leading from this state to `to`.
+            pub fn shortest_event_path(
+                &self,
+                to: &Self,
+            ) -> Option<$crate::table::EventPath<{ $state_type::NAMES.len() }>> {
+                $crate::table::shortest_path(
+                    $state_type::TRANSITION_TABLE,
+                    self.index(),
+                    to.index(),
+                )
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "reachability"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __rustfsm_reachability {
+    ($state_type:ident) => {};
+}
+
+// Generation of the coverage accessors of a state machine, compiled out
+// without the `coverage` feature.
+#[cfg(feature = "coverage")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __rustfsm_coverage {
+    ($state_machine_name:ident, $state_type:ident) => {
+        impl $state_machine_name {
+            /// Summary of the transition table rules fired so far.
+            pub fn coverage_report(&self) -> $crate::coverage::CoverageReport {
+                self.coverage.report($state_type::TRANSITION_TABLE)
+            }
+
+            /// Transition table rules that haven't fired yet.
+            pub fn uncovered_transitions(
+                &self,
+            ) -> impl Iterator<Item = &'static $crate::table::TableRule> + '_ {
+                self.coverage.uncovered($state_type::TRANSITION_TABLE)
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "coverage"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __rustfsm_coverage {
+    ($state_machine_name:ident, $state_type:ident) => {};
+}
+
+// Generation of the machine comparison, and of the field by field comparison
+// of the contexts declared in the macro, compiled out without the `diff`
+// feature.
+#[cfg(feature = "diff")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __rustfsm_diff {
+    (@context $context_type:ident { $($context_field:ident: $context_field_type:ty),* }) => {
+        impl $crate::diff::FieldDiff for $context_type
+        where
+            $(for<'a> $context_field_type: PartialEq,)*
+        {
+            const FIELDS: &'static [&'static str] = &[$(stringify!($context_field)),*];
+
+            fn field_eq(&self, _other: &Self, index: usize) -> bool {
+                #[allow(dead_code, non_camel_case_types)]
+                enum Index {
+                    $($context_field),*
+                }
+                match index {
+                    $(index if index == Index::$context_field as usize => {
+                        self.$context_field == _other.$context_field
+                    })*
+                    _ => true,
+                }
+            }
+
+            fn field(&self, index: usize) -> &dyn core::fmt::Debug {
+                #[allow(dead_code, non_camel_case_types)]
+                enum Index {
+                    $($context_field),*
+                }
+                match index {
+                    $(index if index == Index::$context_field as usize => &self.$context_field,)*
+                    _ => &(),
+                }
+            }
+        }
+    };
+    (@machine $state_machine_name:ident, $state_type:ident, $context_type:ident) => {
+        impl $state_machine_name {
+            /// Compare the current state and the context with those of
+            /// `other`, see the [`diff`]($crate::diff) module.
+            pub fn diff<'a>(&'a self, other: &'a Self) -> $crate::diff::Diff<'a, $state_type, $context_type>
+            where
+                for<'b> $context_type: $crate::diff::FieldDiff,
+            {
+                $crate::diff::Diff::new(
+                    self.current_state,
+                    other.current_state,
+                    &self.context,
+                    &other.context,
+                )
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "diff"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __rustfsm_diff {
+    ($($tokens:tt)*) => {};
+}
+
+// Generation of the per-state metrics accessor, compiled out without the
+// `metrics` feature.
+#[cfg(feature = "metrics")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __rustfsm_metrics {
+    ($state_machine_name:ident, $state_type:ident) => {
+        impl $state_machine_name {
+            /// Per-state metrics recorded so far, indexed by the states'
+            /// `index()`.
+            pub fn metrics(&self) -> &$crate::metrics::Metrics<{ $state_type::NAMES.len() }> {
+                &self.metrics
+            }
         }
+    };
+}
+
+#[cfg(not(feature = "metrics"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __rustfsm_metrics {
+    ($state_machine_name:ident, $state_type:ident) => {};
+}
+
+// Generation of the C FFI functions, compiled out without the `ffi`
+// feature.
+#[cfg(feature = "ffi")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __rustfsm_ffi {
+    (
+        $state_machine_name:ident, $event_type:ident,
+        events { $($event_variant:ident $(($($event_variant_data:ty),*))?),* }
     ) => {
-        rustfsm!(@generate $state_machine_name, $state_type, $event_type, $context_type,
-            states { $first_state $(($($first_state_data),*))?, $($remaining_states $(($($remaining_state_data),*))? ),* },
-            events { $($event_variant $(($($event_variant_data),*))? ),* },
-            context { $($context_field: $context_field_type = $context_default),* },
-            members { $($member_field: $member_field_type = $member_default),* },
-            initial_state = $first_state
-        );
+        $crate::paste::paste! {
+            /// Size of the storage of a state machine, in bytes.
+            #[no_mangle]
+            pub extern "C" fn [<$state_machine_name:snake _size>]() -> usize {
+                ::core::mem::size_of::<$state_machine_name>()
+            }
+
+            /// Alignment of the storage of a state machine, in bytes.
+            #[no_mangle]
+            pub extern "C" fn [<$state_machine_name:snake _align>]() -> usize {
+                ::core::mem::align_of::<$state_machine_name>()
+            }
+
+            /// Initialize a state machine in `machine`.
+            ///
+            /// # Safety
+            ///
+            /// `machine` must be valid for writes and properly aligned. Any
+            /// machine it held is overwritten without being dropped.
+            #[no_mangle]
+            pub unsafe extern "C" fn [<$state_machine_name:snake _new>](
+                machine: *mut $state_machine_name,
+            ) {
+                machine.write($state_machine_name::new());
+            }
+
+            /// Dispatch the event declared at position `event_id`, with its
+            /// data read from `payload`.
+            ///
+            /// # Safety
+            ///
+            /// `machine` must point to an initialized machine, and `payload`
+            /// to the event's fields laid out as a C struct, if it has any.
+            #[no_mangle]
+            pub unsafe extern "C" fn [<$state_machine_name:snake _dispatch>](
+                machine: *mut $state_machine_name,
+                event_id: u32,
+                payload: *const ::core::ffi::c_void,
+            ) -> i32 {
+                #[allow(dead_code)]
+                enum Index {
+                    $($event_variant),*
+                }
+                let Some(machine) = machine.as_mut() else {
+                    return $crate::ffi::UNEXPECTED_EVENT;
+                };
+                $(
+                    if event_id == Index::$event_variant as u32 {
+                        return $crate::__rustfsm_ffi!(@dispatch machine, payload,
+                            $event_type::$event_variant $(($($event_variant_data),*))?
+                        );
+                    }
+                )*
+                $crate::ffi::UNEXPECTED_EVENT
+            }
+
+            /// Position of the current state in its declaration order.
+            ///
+            /// # Safety
+            ///
+            /// `machine` must point to an initialized machine.
+            #[no_mangle]
+            pub unsafe extern "C" fn [<$state_machine_name:snake _current_state>](
+                machine: *const $state_machine_name,
+            ) -> u32 {
+                (*machine).get_current_state().index() as u32
+            }
+        }
     };
 
-    // Case 1: Without additional members for the state machine struct
+    // Dispatch an event variant, reading its data from the payload
+    (@dispatch $machine:ident, $payload:ident, $event_type:ident::$event_variant:ident) => {
+        $crate::ffi::status($machine.dispatch($event_type::$event_variant))
+    };
     (
-        $state_machine_name:ident,
-        $state_type:ident {
-            $first_state:ident $(($($first_state_data:ty),*))?,
-            $($remaining_states:ident $(($($remaining_state_data:ty),*))? ),* $(,)?
-        },
-        $event_type:ident {
-            $($event_variant:ident $(($($event_variant_data:ty),*))? ),* $(,)?
-        },
-        $context_type:ident {
-            $($context_field:ident: $context_field_type:ty = $context_default:expr),* $(,)?
+        @dispatch $machine:ident, $payload:ident,
+        $event_type:ident::$event_variant:ident($($event_variant_data:ty),*)
+    ) => {
+        if $payload.is_null() {
+            $crate::ffi::UNEXPECTED_EVENT
+        } else {
+            let event = $crate::__rustfsm_ffi!(@bind $event_type::$event_variant, $payload;
+                [];
+                $($event_variant_data),*
+            );
+            $crate::ffi::status($machine.dispatch(event))
         }
+    };
+
+    // Bind a name to each field of an event variant read from a C struct,
+    // the `field` of every step being a distinct binding
+    (
+        @bind $event_type:ident::$event_variant:ident, $payload:ident;
+        [$($bound:ident: $bound_type:ty),*];
+    ) => {{
+        #[repr(C)]
+        struct Payload($($bound_type),*);
+        let Payload($($bound),*) = ::core::ptr::read($payload as *const Payload);
+        $event_type::$event_variant($($bound),*)
+    }};
+    (
+        @bind $event_type:ident::$event_variant:ident, $payload:ident;
+        [$($bound:ident: $bound_type:ty),*];
+        $data:ty $(, $rest:ty)*
     ) => {
-        rustfsm!(@generate $state_machine_name, $state_type, $event_type, $context_type,
-            states { $first_state $(($($first_state_data),*))?, $($remaining_states $(($($remaining_state_data),*))? ),* },
-            events { $($event_variant $(($($event_variant_data),*))? ),* },
-            context { $($context_field: $context_field_type = $context_default),* },
-            members { },
-            initial_state = $first_state
-        );
+        $crate::__rustfsm_ffi!(@bind $event_type::$event_variant, $payload;
+            [$($bound: $bound_type,)* field: $data];
+            $($rest),*
+        )
     };
+}
 
-    // Internal implementation for generating the state machine
+#[cfg(not(feature = "ffi"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __rustfsm_ffi {
+    ($($tokens:tt)*) => {};
+}
+
+// Generation of the WASM wrapper, compiled out without the `wasm` feature.
+#[cfg(feature = "wasm")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __rustfsm_wasm {
     (
-        @generate $state_machine_name:ident, $state_type:ident, $event_type:ident, $context_type:ident,
-        states { $($state_variant:ident $(($($state_variant_data:ty),*))? ),* },
-        events { $($event_variant:ident $(($($event_variant_data:ty),*))? ),* },
-        context { $($context_field:ident: $context_field_type:ty = $context_default:expr),* },
-        members { $($member_field:ident: $member_field_type:ty = $member_default:expr),* },
-        initial_state = $initial_state:ident
+        $state_machine_name:ident, $event_type:ident,
+        events { $($event_variant:ident $(($($event_variant_data:ty),*))?),* }
     ) => {
-        /// State machine state type.
-        ///
-        /// - The first state in the list is the state machine's initial state.
-        #[derive(Clone, Copy, PartialEq, Debug)]
-        pub enum $state_type {
-            $(
-                $state_variant $(($($state_variant_data),*))?
-            ),*
+        $crate::paste::paste! {
+            // The wrapper lives in its own module, where the code generated by
+            // `wasm_bindgen` finds its crate through the re-export.
+            mod [<__ $state_machine_name:snake _wasm>] {
+                use super::*;
+                use $crate::wasm::wasm_bindgen;
+
+                #[doc = concat!("`", stringify!($state_machine_name), "` exported to JavaScript.")]
+                #[wasm_bindgen::prelude::wasm_bindgen(wasm_bindgen = wasm_bindgen)]
+                pub struct [<$state_machine_name Wasm>] {
+                    machine: $state_machine_name,
+                }
+
+                #[wasm_bindgen::prelude::wasm_bindgen(wasm_bindgen = wasm_bindgen)]
+                impl [<$state_machine_name Wasm>] {
+                    /// Create a new state machine.
+                    #[wasm_bindgen(constructor)]
+                    pub fn new() -> Self {
+                        Self {
+                            machine: $state_machine_name::new(),
+                        }
+                    }
+
+                    /// Dispatch the data-less event named `event`.
+                    pub fn dispatch(
+                        &mut self,
+                        event: &str,
+                    ) -> Result<(), $crate::wasm::wasm_bindgen::JsValue> {
+                        let event = match event {
+                            $(
+                                stringify!($event_variant) => $crate::__rustfsm_wasm!(@event
+                                    $event_type::$event_variant $(($($event_variant_data),*))?
+                                ),
+                            )*
+                            _ => None,
+                        }
+                        .ok_or_else(|| $crate::wasm::unknown_event(event))?;
+                        self.machine.dispatch(event).map_err($crate::wasm::dispatch_error)
+                    }
+
+                    /// Name of the current state.
+                    pub fn current_state(&self) -> $crate::wasm::JsString {
+                        $crate::wasm::name(self.machine.get_current_state().name())
+                    }
+
+                    /// Position of the current state in its declaration order.
+                    pub fn current_state_id(&self) -> u32 {
+                        self.machine.get_current_state().index() as u32
+                    }
+
+                    /// Whether the state machine reached a final state.
+                    pub fn is_finished(&self) -> bool {
+                        self.machine.is_finished()
+                    }
+
+                    /// Start the state machine.
+                    pub fn start(&mut self) {
+                        self.machine.start();
+                    }
+
+                    /// Stop the state machine.
+                    pub fn stop(&mut self) {
+                        self.machine.stop();
+                    }
+
+                    /// Reset the state machine, restoring the context defaults.
+                    pub fn reset(&mut self) {
+                        self.machine.reset($crate::ResetPolicy::RestoreDefaults);
+                    }
+                }
+            }
+
+            pub use [<__ $state_machine_name:snake _wasm>]::[<$state_machine_name Wasm>];
+        }
+    };
+
+    // Data-less events can be dispatched by name, others can't
+    (@event $event_type:ident::$event_variant:ident) => {
+        Some($event_type::$event_variant)
+    };
+    (@event $event_type:ident::$event_variant:ident($($event_variant_data:ty),*)) => {
+        None
+    };
+}
+
+#[cfg(not(feature = "wasm"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __rustfsm_wasm {
+    ($($tokens:tt)*) => {};
+}
+
+// Generation of `arbitrary::Arbitrary` for a state or event enum, compiled
+// out without the `arbitrary` feature.
+#[cfg(feature = "arbitrary")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __rustfsm_arbitrary {
+    ($enum_type:ident { $($variant:ident $(($($variant_data:ty),*))?),* }) => {
+        // The bounds keep the impl out of the way when the data doesn't
+        // implement `Arbitrary`.
+        impl<'a> $crate::arbitrary::Arbitrary<'a> for $enum_type
+        where
+            $($($($variant_data: $crate::arbitrary::Arbitrary<'a>,)*)?)*
+        {
+            fn arbitrary(
+                u: &mut $crate::arbitrary::Unstructured<'a>,
+            ) -> $crate::arbitrary::Result<Self> {
+                #[allow(dead_code)]
+                enum Index {
+                    $($variant),*
+                }
+                let index = u.choose_index([$(stringify!($variant)),*].len())?;
+                $(
+                    if index == Index::$variant as usize {
+                        return Ok($enum_type::$variant $(($(u.arbitrary::<$variant_data>()?),*))?);
+                    }
+                )*
+                Err($crate::arbitrary::Error::IncorrectFormat)
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "arbitrary"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __rustfsm_arbitrary {
+    ($($tokens:tt)*) => {};
+}
+
+// Generation of the `serde` implementations of the state and event enums,
+// the context and the machine, compiled out without the `serde` feature.
+// The bounds keep them out of the way when the data doesn't implement
+// `Serialize` or `Deserialize`.
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __rustfsm_serde {
+    (@enum $enum_type:ident { $($variant:ident $(($($variant_data:ty),*))?),* }) => {
+        impl $crate::serde::Serialize for $enum_type
+        where
+            $($($(for<'a> $variant_data: $crate::serde::Serialize,)*)?)*
+        {
+            #[allow(irrefutable_let_patterns)]
+            fn serialize<S: $crate::serde::Serializer>(
+                &self,
+                serializer: S,
+            ) -> Result<S::Ok, S::Error> {
+                #[allow(dead_code)]
+                enum Index {
+                    $($variant),*
+                }
+                $(
+                    $crate::__rustfsm_serde!(@serialize self, serializer, Index,
+                        $enum_type::$variant $(($($variant_data),*))?
+                    );
+                )*
+                Err($crate::serde::ser::Error::custom("unknown variant"))
+            }
+        }
+
+        impl<'de> $crate::export::EnumVariants<'de> for $enum_type
+        where
+            $($($($variant_data: $crate::serde::Deserialize<'de>,)*)?)*
+        {
+            const NAME: &'static str = stringify!($enum_type);
+            const VARIANTS: &'static [&'static str] = &[$(stringify!($variant)),*];
+
+            fn arity(variant: usize) -> usize {
+                #[allow(dead_code)]
+                enum Index {
+                    $($variant),*
+                }
+                $(
+                    if variant == Index::$variant as usize {
+                        return <[&str]>::len(&[$($(stringify!($variant_data)),*)?]);
+                    }
+                )*
+                0
+            }
+
+            #[allow(unused_mut)]
+            fn from_seq<A: $crate::serde::de::SeqAccess<'de>>(
+                variant: usize,
+                mut seq: A,
+            ) -> Result<Self, A::Error> {
+                #[allow(dead_code)]
+                enum Index {
+                    $($variant),*
+                }
+                let mut _count = 0;
+                $(
+                    if variant == Index::$variant as usize {
+                        return Ok($enum_type::$variant $(($(
+                            $crate::export::next_field::<$variant_data, A>(&mut seq, &mut _count)?
+                        ),*))?);
+                    }
+                )*
+                Err($crate::serde::de::Error::custom("unknown variant"))
+            }
+        }
+
+        impl<'de> $crate::serde::Deserialize<'de> for $enum_type
+        where
+            $($($($variant_data: $crate::serde::Deserialize<'de>,)*)?)*
+        {
+            fn deserialize<D: $crate::serde::Deserializer<'de>>(
+                deserializer: D,
+            ) -> Result<Self, D::Error> {
+                $crate::export::deserialize_enum(deserializer)
+            }
+        }
+    };
+
+    (@struct $struct_type:ident { $($field:ident: $field_type:ty),* }) => {
+        impl $crate::serde::Serialize for $struct_type
+        where
+            $(for<'a> $field_type: $crate::serde::Serialize,)*
+        {
+            fn serialize<S: $crate::serde::Serializer>(
+                &self,
+                serializer: S,
+            ) -> Result<S::Ok, S::Error> {
+                use $crate::serde::ser::SerializeStruct;
+                let mut _fields = serializer.serialize_struct(
+                    stringify!($struct_type),
+                    <[&str]>::len(&[$(stringify!($field)),*]),
+                )?;
+                $(_fields.serialize_field(stringify!($field), &self.$field)?;)*
+                _fields.end()
+            }
+        }
+
+        impl<'de> $crate::export::StructFields<'de> for $struct_type
+        where
+            $($field_type: $crate::serde::Deserialize<'de>,)*
+        {
+            const NAME: &'static str = stringify!($struct_type);
+            const FIELDS: &'static [&'static str] = &[$(stringify!($field)),*];
+
+            fn field<A: $crate::serde::de::MapAccess<'de>>(
+                &mut self,
+                _field: usize,
+                _map: &mut A,
+            ) -> Result<(), A::Error> {
+                #[allow(dead_code, non_camel_case_types)]
+                enum Index {
+                    $($field),*
+                }
+                $(
+                    if _field == Index::$field as usize {
+                        self.$field = _map.next_value()?;
+                    }
+                )*
+                Ok(())
+            }
+        }
+
+        impl<'de> $crate::serde::Deserialize<'de> for $struct_type
+        where
+            $($field_type: $crate::serde::Deserialize<'de>,)*
+        {
+            fn deserialize<D: $crate::serde::Deserializer<'de>>(
+                deserializer: D,
+            ) -> Result<Self, D::Error> {
+                $crate::export::deserialize_struct(deserializer)
+            }
+        }
+    };
+
+    (@machine $state_machine_name:ident, $state_type:ident, $context_type:ident) => {
+        impl $crate::export::Export for $state_machine_name {
+            type State = $state_type;
+            type Context = $context_type;
+
+            fn export(&self) -> $crate::export::Snapshot<&$state_type, &$context_type> {
+                $crate::export::Snapshot {
+                    state: &self.current_state,
+                    context: &self.context,
+                }
+            }
+
+            fn import(snapshot: $crate::export::Snapshot<$state_type, $context_type>) -> Self {
+                let mut state_machine = Self::new();
+                state_machine.current_state = snapshot.state;
+                state_machine.context = snapshot.context;
+                state_machine
+            }
+        }
+    };
+
+    // Serialize a variant, binding a name to each of its fields, the `field`
+    // of every step being a distinct binding
+    (@serialize $value:ident, $serializer:ident, $index:ident, $enum_type:ident::$variant:ident) => {
+        if let $enum_type::$variant = $value {
+            return $serializer.serialize_unit_variant(
+                stringify!($enum_type),
+                $index::$variant as u32,
+                stringify!($variant),
+            );
+        }
+    };
+    (
+        @serialize $value:ident, $serializer:ident, $index:ident,
+        $enum_type:ident::$variant:ident($($variant_data:ty),*)
+    ) => {
+        $crate::__rustfsm_serde!(@serialize $value, $serializer, $index, $enum_type::$variant;
+            [];
+            $($variant_data),*
+        )
+    };
+    (
+        @serialize $value:ident, $serializer:ident, $index:ident, $enum_type:ident::$variant:ident;
+        [$($bound:ident)*];
+    ) => {
+        if let $enum_type::$variant($($bound),*) = $value {
+            use $crate::serde::ser::SerializeTupleVariant;
+            let mut variant = $serializer.serialize_tuple_variant(
+                stringify!($enum_type),
+                $index::$variant as u32,
+                stringify!($variant),
+                <[&str]>::len(&[$(stringify!($bound)),*]),
+            )?;
+            $(variant.serialize_field($bound)?;)*
+            return variant.end();
+        }
+    };
+    (
+        @serialize $value:ident, $serializer:ident, $index:ident, $enum_type:ident::$variant:ident;
+        [$($bound:ident)*];
+        $data:ty $(, $rest:ty)*
+    ) => {
+        $crate::__rustfsm_serde!(@serialize $value, $serializer, $index, $enum_type::$variant;
+            [$($bound)* field];
+            $($rest),*
+        )
+    };
+}
+
+#[cfg(not(feature = "serde"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __rustfsm_serde {
+    ($($tokens:tt)*) => {};
+}
+
+/// # RustFSM
+///
+/// A full static Rust finite state machine library.
+///
+/// ## Usage
+///
+/// The `rustfsm` macro takes as input the state machine's name, list of
+/// states, list of events and context.
+///
+/// The state machine's name can be just an ident if no other member is desired
+/// to the struct:
+///
+/// ```rust,ignore
+/// use rustfsm::{rustfsm, StateBehavior};
+///
+/// rustfsm!(
+///     FooName,
+///     FooStates {
+///         FooStateA,
+///         FooStateB,
+///     },
+///     Events {
+///         FooEvent1,
+///         FooEvent2,
+///     },
+///     Context {
+///         context_foo_data: u8 = 0,
+///         context_boo_data: bool = true,
+///     }
+/// );
+/// ```
+///
+/// The state machine's name can also be a struct with default values if data
+/// other than the cotext is desired:
+///
+/// ```rust,ignore
+/// use rustfsm::{rustfsm, StateBehavior};
+///
+/// rustfsm!(
+///     FooName {
+///         foo_data: u16 = 0,
+///         boo_data: boo = false,
+///     },
+///     FooStates {
+///         FooStateA,
+///         FooStateB,
+///     },
+///     Events {
+///         FooEvent1,
+///         FooEvent2,
+///     },
+///     Context {
+///         context_foo_data: u8 = 0,
+///         context_boo_data: bool = true,
+///     }
+/// );
+/// ```
+///
+/// Mistakes in the definition are reported at expansion time, naming the
+/// offender: an empty list of states, a member named after one of the
+/// machine's own fields such as `context` or `trace`, or a context field
+/// shadowing a member. States and events defined twice are reported by the
+/// compiler on the generated enums, and by the `fsm!` front end with the
+/// `macros` feature.
+///
+/// ```rust,compile_fail
+/// rustfsm::rustfsm!(
+///     FooName { count: u8 = 0 },
+///     FooStates { FooStateA, FooStateB },
+///     Events { FooEvent1 },
+///     Context { count: u8 = 0 }
+/// );
+/// ```
+///
+/// States can be prefixed with markers. A state marked `@final` ends the
+/// machine: once it is reached, `is_finished()` returns `true` and further
+/// events are refused. A state marked `@fault` is where the machine escalates
+/// to when a fallible handler (`try_handle`) or an invariant check
+/// (`check_invariant`) fails, with the cause available via `last_fault()`.
+///
+/// Events can be marked too. Events marked `@internal`, such as timeouts and
+/// completions the machine posts itself, are split from the others, the
+/// commands: the machine also gets a `FooCommand` enum of the commands,
+/// dispatched with `command(FooCommand::Start)`, and a crate-private
+/// `FooInternal` enum of the internal events, both converting into the events
+/// the states handle. Public APIs taking a `FooCommand` then can't be handed
+/// an internal event.
+///
+/// Invariants of the whole machine can be checked in one place by an
+/// `Invariant` section, as in `Invariant { check: no_power_when_off }`. The
+/// check is a `fn(&FooStates, &Context) -> Result<(), InvariantViolation>`
+/// run after every handled event, along with the state's `check_invariant`.
+/// A violation escalates to the `@fault` state, unless `on_violation: panic`
+/// panics instead, or `on_violation: assert` panics in debug builds only.
+///
+/// Event payloads are range-checked in one place too by a `Validate`
+/// section, as in `Validate { check: setpoint_in_range }`. The check is a
+/// `fn(&Events, &Context) -> Result<(), ValidationError>` run before the
+/// event is handled, refusing it with `DispatchError::Invalid` without
+/// touching the state or the context.
+///
+/// ```rust,ignore
+/// rustfsm!(
+///     FooName,
+///     FooStates {
+///         FooStateA,
+///         @final FooStateB,
+///     },
+///     ...
+/// );
+/// ```
+///
+/// The context can also be given by name only, without fields, to reuse an
+/// existing context type instead of generating one. Together with
+/// `dispatch_with`, which runs a machine against an externally owned context,
+/// this lets several state machines operate on the same context:
+///
+/// ```rust,ignore
+/// rustfsm!(
+///     BarName,
+///     BarStates {
+///         BarStateA,
+///         BarStateB,
+///     },
+///     BarEvents {
+///         BarEvent1,
+///     },
+///     Context
+/// );
+///
+/// let mut context = Context::default();
+/// foo.dispatch_with(FooEvent1, &mut context);
+/// bar.dispatch_with(BarEvent1, &mut context);
+/// ```
+///
+/// Each machine still owns a default context of its own, which everything
+/// but `dispatch_with` works on: `start`, `tick`, `process_all`, `reset`,
+/// the enter and exit hooks they run, `context()`, and the exports and
+/// snapshots. A machine only ever dispatched with an external context
+/// carries that copy unused, `size_of::<Context>()` bytes. It's kept rather
+/// than left out, as every one of these entry points would otherwise need a
+/// borrowed context as well.
+///
+/// ## Transition table
+///
+/// A `Transitions` section can follow the context to declare the machine's
+/// transitions as `State + EventPattern => NextState` rules, with `ignore` as
+/// next state for pairs that intentionally do nothing. It generates a
+/// `next_state()` lookup on the state type, to be called from `handle`.
+///
+/// Events handled alike can be grouped in one rule, as in
+/// `Idle + (Start | Resume) => Running`. A group is an or-pattern, matched
+/// at no extra cost.
+///
+/// A rule written `_ + EventPattern` applies to every state, for global
+/// events such as an emergency stop. Rules naming the state take precedence
+/// over it wherever they're declared.
+///
+/// Rules can be named for traceability, as in `t_connect: Idle + Start =>
+/// Connecting`. The name is kept in the rule's [`TableRule`](table::TableRule),
+/// found by [`rule_named`](table::rule_named), returned by
+/// `dispatch_named(event)` for the rule handling the event, and recorded in
+/// the trace. Interceptors find it from the state and event they observe,
+/// with `from.table_rule(event)`.
+///
+/// Flappy inputs are kept from toggling states too fast by a minimum
+/// interval, written last as in `Connected + LinkDown => Disconnected @
+/// min_interval(200)`. The rule only applies once the machine has spent that
+/// many ticks of its `Clock` in the source state. Before, the event is
+/// dropped without running the handler, and without counting as unhandled
+/// whatever the `unhandled` policy: no error, hook call, coverage or
+/// metrics. It takes no rule either, `dispatch_named` and the trace
+/// reporting none, and gets no reply. Pairing both directions of a toggle this way gives a hysteresis
+/// without counters in the context.
+///
+/// The state type also tells whether it `accepts(&event)`, mapped to a
+/// transition rather than ignored, and lists its `legal_events()` among the
+/// events without data, for user interfaces and command validation. Its
+/// `transitions_from()` walks the rules leading out of it, so tooling can
+/// follow the graph at runtime.
+///
+/// Every (state, event) pair must be covered by a rule: a forgotten pair is a
+/// compile error naming the missing pattern. States that can't be reached
+/// from the initial state are listed in the generated `UNREACHABLE_STATES`
+/// constant, and with the `reachability` feature the state type also gets
+/// `is_reachable()` and `shortest_event_path()` helpers. With the `coverage`
+/// feature, the machine records which rules fired, reported by
+/// `coverage_report()` and `uncovered_transitions()`. The report counts the
+/// `ignore` rules apart, and `ignores(&event)` tells whether the state ignores
+/// an event on purpose.
+///
+/// With the `arbitrary` feature, the state and event enums implement
+/// `arbitrary::Arbitrary`, provided their data types do, so property-based
+/// tests can generate event sequences directly.
+///
+/// ```rust,ignore
+/// rustfsm!(
+///     FooName,
+///     FooStates {
+///         FooStateA,
+///         FooStateB,
+///     },
+///     Events {
+///         FooEvent1,
+///         FooEvent2,
+///     },
+///     Context {},
+///     Transitions {
+///         FooStateA + FooEvent1 => FooStateB,
+///         FooStateA + FooEvent2 => ignore,
+///         FooStateB + _ => FooStateA,
+///     }
+/// );
+///
+/// impl StateBehavior for FooStates {
+///     ...
+///     fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+///         self.next_state(event)
+///     }
+/// }
+/// ```
+///
+/// `next_state()` is a nested match on the state and the event. For machines
+/// with many states and events, a `Dispatch { jump_table }` section turns it
+/// into a table of per-state functions indexed by the state, which small
+/// cores like the Cortex-M0 run faster than the compiler's lowering of a
+/// large match.
+///
+/// The same section controls the inlining of the dispatch path: `inline`,
+/// `inline(always)` or `inline(never)` mark the generated `dispatch()`
+/// functions accordingly, trading code size for speed per machine. Options
+/// are separated by commas, as in `Dispatch { jump_table, inline(never) }`.
+///
+/// For machines fully described by their table, `Dispatch { behavior }`
+/// generates the `impl StateBehavior` of the state type, its `handle`
+/// returning `next_state()`. Rules can then name a guard and an action,
+/// written after the target as in `Idle + Start => Running if ready / spin_up`.
+/// The guard is a `fn(&Context, &Events) -> bool`, refusing the event with
+/// `DispatchError::GuardRejected` when it returns false, and the action a
+/// `fn(&mut Context, &Events)` run before the transition. Without the option
+/// the impl is written by hand, which is the way to go for machines only
+/// partly described by their table.
+///
+/// ## Clock and trace
+///
+/// A `Clock` section gives the [`Clock`](clock::Clock) timestamping the
+/// machine's timing features. The machine then also tells how long it has
+/// been in its current state with `time_in_current_state()`. Timeouts,
+/// watchdogs and dwell times are measured in ticks elapsed, unaffected by
+/// the counter wrapping around, see the [`clock`] module. With the `std`
+/// feature, tests give the machine a `MockClock` advanced by hand, checking
+/// the time-based behavior deterministically without sleeping.
+///
+/// A `Trace` section keeps the last `depth` dispatches in a ring buffer,
+/// returned by `trace()`. The records can be dumped through the `log` crate
+/// with the `log` feature, for instance once a fault is raised.
+///
+/// With `Dispatch { tagged }`, every dispatch carries the [`Source`] of its
+/// event, recorded in the trace to tell apart the events injected from an
+/// interrupt handler, the host commands and the timeouts.
+/// `dispatch_tagged(event, Source::Isr)` gives it, `dispatch` standing for
+/// `Source::Host`. The handlers read it from their [`Outbox`](queue::Outbox)
+/// and the interceptors in `on_source`. Without the option, the machine keeps
+/// no room for it and all events are told to come from `Source::Host`.
+///
+/// Instrumentation costs nothing unless asked for: without a `Trace` section
+/// and the `coverage` and `metrics` features, a machine holds its state,
+/// context, status and last fault, and nothing else.
+///
+/// A `Watchdog` section limits the ticks spent in some states. Exceeding it,
+/// as checked by calling `tick()` periodically, dispatches the given event,
+/// or raises a `Fault::Watchdog` escalating to the `@fault` state for
+/// `fault`.
+///
+/// A `Filter` section gives a [`Filter`](filter::Filter) run on every event
+/// before it is handled, which can replace it or drop it, such as a
+/// [`Debounce`](filter::Debounce).
+///
+/// An `Interceptors` section lists [`Interceptor`](interceptor::Interceptor)s
+/// run in order after the filter. Each one can replace or veto the event
+/// before it is handled, and observes the outcome.
+///
+/// An `Rng` section gives the machine a random number source implementing
+/// [`Rng`](rng::Rng), such as the deterministic [`Seeded`](rng::Seeded) for
+/// reproducible tests, reseeded through `machine.rng_mut()`. Guards draw from
+/// it in [`StateBehavior::guard_with_rng`] and handlers through the `Outbox`
+/// of [`StateBehavior::try_handle_with`], for randomized backoffs or chaos
+/// injection. See the [`rng`] module.
+///
+/// A `Derive` section replaces the derives of the events enum, `Clone, Copy,
+/// PartialEq, Debug` by default. Each derived impl takes flash, so events
+/// with large payloads can keep only `Derive { events: Debug }`, and are then
+/// handed to the states by reference, without being copied, all the way from
+/// `dispatch_ref(&event)` for those too large to move around. Their machines
+/// can't have a `Filter` or an `Interceptors` section, which take events by
+/// value and need them to be `Copy`, and only implement [`FsmCore`] with
+/// `Clone` events.
+///
+/// An `Aliases` section names events with a fixed payload, such as
+/// `ShortPress = ButtonPress(Duration::Short)`. Each alias is a constant of
+/// the events enum, `Events::ShortPress`, which external code can post while
+/// the handlers match on the event it stands for.
+///
+/// A `Timers` section gives `slots` timers to `post_after(delay, event)`,
+/// delivering `event` on the first `tick()` after `delay` ticks. The
+/// returned token cancels it with `cancel_timer(token)`, and
+/// `cancel_all_matching(&event)` cancels every pending timer and queued event
+/// of that variant. Pending timers and queued events are dropped on reset.
+///
+/// A `Queue { depth: N }` section lets the states post up to `N` follow-up
+/// events from [`StateBehavior::try_handle_with`], dispatched in order
+/// before the `dispatch` call returns, and external code `post` events for
+/// `process_all()`. An error drops the events still queued. An optional
+/// `priority` function has higher priority events skip ahead, and
+/// `flush_below(priority)` drops the others. For debugging, `step()`
+/// dispatches a single queued event and `queued()` lists those pending.
+/// In a superloop, `process_for(max_events)` and, with a `Clock` section,
+/// `process_budget(ticks)` bound the work done in one pass, reporting the
+/// events left. Scripts pump sequences with
+/// `machine.extend([Start, Stop])` followed by `process_all()`, or dispatch
+/// them in a chain with `machine.feed(Start)?.feed(Stop)?`, `feed` taking
+/// anything convertible into an event. See the [`queue`] module.
+///
+/// Expired timers are delivered by `tick()` before the queue is looked at.
+/// `Dispatch { timeouts(order) }` interleaves them with the queued events
+/// instead, both in `tick()` and `process_all()`, in a fixed order:
+/// `first` delivers the expired timers before any queued event, `arrival`
+/// takes whichever of the next timer and the next queued event is the
+/// oldest, from the timer's expiry and the event's posting, and `priority`
+/// ranks the timer events with the queue's `priority` function. Ties go to
+/// the timer.
+///
+/// A `Views` section restricts which context fields a state works on, as in
+/// `Views { Idle { count }, Calibrating { offset, gain } }`. It generates a
+/// `CalibratingView` struct borrowing only those fields, returned by
+/// `context.calibrating_view()`. Handlers written against the views, as
+/// functions taking the view rather than the whole context, then can't touch
+/// the fields of other states.
+///
+/// A `Changes` section tracks which context fields the handlers set, as in
+/// `Changes { temperature, pressure }`. Those fields get a
+/// `context.set_temperature(value)` setter marking them changed, and
+/// `context.take_changes()` returns the fields marked since it was last
+/// called, for a telemetry layer to publish only those. See the [`changes`]
+/// module.
+///
+/// An `Output { Frame }` section makes the machine Mealy-style: the states
+/// implement [`output::Mealy`], returning a `Frame` along with the next
+/// state, which `dispatch_output(event)` gives back to the caller. See the
+/// [`output`] module.
+///
+/// A `Reply { Ack }` section has the states implement [`output::Respond`]
+/// instead, replying to every event, and `dispatch_with_reply(event)` returns
+/// the reply.
+///
+/// A `Config` section sets the sizes and policies in one place, as in
+/// `Config { queue_depth = 8, history = 16, strict_unhandled = true }`. Each
+/// key stands for the section or option it sets: `queue_depth` for `Queue {
+/// depth }`, `history` for `Trace { depth }`, `timer_slots` for `Timers {
+/// slots }`, and `strict_unhandled` and `jump_table`, true or false, for the
+/// `unhandled(error)` and `jump_table` options of `Dispatch`. A key can't be
+/// combined with the section it stands for, nor the `Dispatch` keys with a
+/// `Dispatch` section.
+///
+/// ```rust,ignore
+/// rustfsm!(
+///     ...
+///     Context {},
+///     Clock { SysTick },
+///     Trace { depth: 16 },
+///     Watchdog {
+///         Connecting: 10_000 => Timeout,
+///         Flashing: 60_000 => fault,
+///     },
+///     Timers { slots: 4 },
+///     Filter { Debounce<Events, 50> },
+///     Interceptors { Authorization, Logger }
+/// );
+///
+/// if foo.last_fault().is_some() {
+///     foo.trace().log_dump();
+/// }
+/// ```
+///
+/// ## Wire codec
+///
+/// Events get `encode` and `decode`, turning them into compact versioned
+/// frames for serial command protocols, as described in the [`codec`]
+/// module. Event data types must implement [`codec::Wire`] for them to be
+/// available.
+///
+/// ## Hibernation
+///
+/// For devices spending most of their time in deep sleep, `hibernate` writes
+/// the least needed to resume, `HIBERNATE_SIZE` bytes to keep in retention
+/// RAM: the current state id and the context fields listed in the
+/// `Hibernate` section, which must implement [`codec::Wire`]. `wake` rebuilds
+/// the machine from them, the other fields at their defaults. Only states
+/// without data can be saved, `hibernate` writing nothing in the others:
+///
+/// ```rust,ignore
+/// rustfsm!(
+///     Node,
+///     NodeStates { Idle, Measuring, Reporting },
+///     Events { Wake, Done },
+///     Context { samples: u16 = 0, scratch: [u8; 64] = [0; 64] },
+///     Hibernate { samples }
+/// );
+///
+/// let mut image = [0; Node::HIBERNATE_SIZE];
+/// node.hibernate(&mut image);
+/// // Deep sleep
+/// let node = Node::wake_started(&image)?;
+/// ```
+///
+/// `wake_started` runs the saved state's `enter` function again, for states
+/// configuring peripherals lost during the sleep.
+///
+/// ## Export
+///
+/// With the `serde` feature, the state and event enums and the context
+/// implement `Serialize` and `Deserialize` when their data does, and the
+/// machine implements [`export::Export`]. The `json` and `cbor` features add
+/// `to_json` and `from_json`, `to_cbor` and `from_cbor` on std.
+///
+/// ## Definition files
+///
+/// With the `codegen` feature, build scripts generate machines from TOML
+/// definition files maintained outside Rust, or import them from PlantUML
+/// and SCXML state diagrams, as described in the [`codegen`] module.
+///
+/// ## Dynamic machines
+///
+/// With the `alloc` feature, a [`dynamic::DynFsm`] is defined at runtime,
+/// its states, events and transitions loaded from configuration and its
+/// hooks given as closures.
+///
+/// ## Remote console
+///
+/// The machine implements [`remote::Remote`], so a [`remote::Server`] can
+/// query its state, dump its context, inject events and force states from
+/// requests read over a byte transport.
+///
+/// ## Metrics
+///
+/// With the `metrics` feature, the machine counts for every state the times
+/// it was entered, the events handled with and without a transition, and the
+/// ticks spent in it according to the `Clock` section, returned by
+/// `metrics()`.
+///
+/// ```rust,ignore
+/// let error = foo.metrics().get(FooStates::Error.index());
+/// log::info!("{} ticks in Error", error.dwell);
+/// ```
+///
+/// For fleet monitoring, `stats::<N>()` assembles a compact
+/// [`Stats`](stats::Stats) snapshot: the current state id, the ticks spent
+/// in it, the transition count and the last `N` transitions, encoded with
+/// [`Wire`](codec::Wire) into a single telemetry payload. It needs a `Clock`
+/// and a `Trace` section and the `metrics` feature.
+///
+/// ## Diff
+///
+/// With the `diff` feature, `diff(&other)` compares a machine with another
+/// one of the same type, giving the differing current states and context
+/// fields. See the [`diff`] module.
+///
+/// ## C FFI
+///
+/// With the `ffi` feature, the machine can be driven from C through
+/// generated `extern "C"` functions, described in the [`ffi`] module.
+///
+/// ## WASM
+///
+/// With the `wasm` feature, a `FooNameWasm` wrapper is exported to
+/// JavaScript with `wasm_bindgen`, to power a web simulator from the same
+/// definition. It dispatches data-less events by name and tells the current
+/// state.
+///
+/// ## Lifecycle
+///
+/// A machine created with `new()` sits in its initial state without that
+/// state's `enter` function having run. Call `start()`, or create the machine
+/// with `new_started()`, to run it. `stop()` runs the current state's `exit`
+/// and refuses further events, and `reset(policy)` goes back to the initial
+/// state, either restoring the context defaults or preserving it.
+///
+/// The initial state is the first one listed, unless an `Initial` section
+/// gives it, possibly with data as in `Initial { Moving(0) }`. It's available
+/// as the `FooStates::INITIAL` constant. `new_at(state, context)` creates a
+/// machine at another entry point, and the builder sets any of the state,
+/// the context and the members. `respawn_as(state)` resets a machine to
+/// another entry point, keeping its context, and [`Pool::from_fn`] fills a
+/// pool with machines starting in different states.
+///
+/// With `Dispatch { pausable }`, `pause(policy)` makes the machine stand
+/// still, for instance during a firmware update, until `resume()`: events
+/// are queued or refused per [`PausePolicy`], and the time spent paused
+/// counts neither towards the watchdog limits and timers nor the dwell
+/// times. `resume()` then dispatches the events queued meanwhile. Machines
+/// without the option can't be paused and keep no room for it.
+///
+/// `view()` returns a [`MachineView`], the current state, status and a
+/// shared reference to the context, to hand to display or telemetry code
+/// which then can't dispatch events nor mutate the context.
+///
+/// ## Transition order
+///
+/// Dispatching an event runs, in order:
+///
+/// 1. the `Filter`, then the interceptors' `before`, either of which can
+///    drop or replace the event,
+/// 2. the `Validate` section's check and the current state's `guard`,
+///    either of which can refuse the event, then its `try_handle`, the
+///    transition's action, giving the next state,
+/// 3. on a transition, the current state's `exit` and `on_any_exit`, then
+///    the new state is committed and its `on_any_enter` and `enter` run,
+/// 4. the new state's `check_invariant`, then the `Invariant` section's
+///    check,
+/// 5. the interceptors' `after`, then the trace record.
+///
+/// The interceptors' `on_transition` is called during step 3, after `enter`
+/// by default. The `notify` option of the `Dispatch` section moves it:
+/// `Dispatch { notify(before_enter) }` calls it once the new state is
+/// committed but before its `enter` side effects, and `notify(before_exit)`
+/// before anything changes.
+///
+/// [`StateBehavior::on_any_enter`] and [`on_any_exit`](StateBehavior::on_any_exit)
+/// wrap the `enter` and `exit` of every state, starting, stopping, resetting
+/// and faulting included, for concerns common to all of them such as kicking
+/// a watchdog or timestamping.
+///
+/// An event leading to no transition is silently ignored by default. The
+/// `unhandled` option of the `Dispatch` section picks another policy:
+/// `unhandled(hook)` calls the current state's [`StateBehavior::unhandled`],
+/// `unhandled(error)` fails the dispatch with `DispatchError::Unhandled`, and
+/// `unhandled(assert)` panics in debug builds, calling the hook in release
+/// builds. Pairs the transition table marks `ignore` lead nowhere on purpose
+/// and are exempt from the policy, telling them from forgotten ones.
+///
+/// In debug builds, an event dispatched while the machine is still handling
+/// another one is refused with `DispatchError::Reentrant`. Safe code can't
+/// do that, but an interrupt handler sharing a `static mut` machine with the
+/// main loop can, and would otherwise corrupt its state.
+#[macro_export]
+macro_rules! rustfsm {
+    // Case 1: With additional members for the state machine struct
+    (
+        $state_machine_name:ident {
+            $($member_field:ident: $member_field_type:ty = $member_default:expr),* $(,)?
+        },
+        $($rest:tt)*
+    ) => {
+        rustfsm!(@parse $state_machine_name,
+            members { $($member_field: $member_field_type = $member_default),* },
+            $($rest)*
+        );
+    };
+
+    // Case 2: Without additional members for the state machine struct
+    (
+        $state_machine_name:ident,
+        $($rest:tt)*
+    ) => {
+        rustfsm!(@parse $state_machine_name, members { }, $($rest)*);
+    };
+
+    // Internal parsing of the states, events and context
+    (
+        @parse $state_machine_name:ident,
+        members { $($members:tt)* },
+        $state_type:ident {
+            $(@$first_state_marker:ident)* $first_state:ident $(($($first_state_data:ty),*))?,
+            $($(@$remaining_state_marker:ident)* $remaining_states:ident $(($($remaining_state_data:ty),*))? ),* $(,)?
+        },
+        $event_type:ident {
+            $($(@$event_marker:ident)* $event_variant:ident $(($($event_variant_data:ty),*))? ),* $(,)?
+        },
+        $context_type:ident $({
+            $($context_field:ident: $context_field_type:ty = $context_default:expr),* $(,)?
+        })?
+        $(, $section:ident $section_body:tt)* $(,)?
+    ) => {
+        rustfsm!(@sections {
+                $state_machine_name, $state_type, $event_type, $context_type,
+                states {
+                    $(@$first_state_marker)* $first_state $(($($first_state_data),*))?,
+                    $($(@$remaining_state_marker)* $remaining_states $(($($remaining_state_data),*))? ),*
+                },
+                events { $($(@$event_marker)* $event_variant $(($($event_variant_data),*))? ),* },
+                context { $context_type $({ $($context_field: $context_field_type = $context_default),* })? },
+                members { $($members)* },
+                initial_state = $first_state
+            }
+            transitions { }
+            clock { $crate::clock::NoClock; }
+            trace { 0 }
+            watchdog { }
+            timers { }
+            filter { }
+            interceptors { }
+            rng { }
+            derive { Clone, Copy, PartialEq, Debug }
+            aliases { }
+            queue { 0 }
+            views { }
+            changes { }
+            output { }
+            reply { }
+            hibernate { }
+            invariant { }
+            validate { }
+            initial { }
+            dispatch { };
+            $($section $section_body)*
+        );
+    };
+    (
+        @parse $state_machine_name:ident,
+        members $members:tt,
+        $state_type:ident { $(,)? },
+        $($rest:tt)*
+    ) => {
+        compile_error!(concat!("`", stringify!($state_type), "` must list at least one state"));
+    };
+
+    // Internal sorting of the optional sections following the context
+    (
+        @sections $args:tt transitions { } clock $clock:tt trace $trace:tt watchdog $watchdog:tt timers $timers:tt filter $filter:tt interceptors $interceptors:tt rng $rng:tt derive $derive:tt aliases $aliases:tt queue $queue:tt views $views:tt changes $changes:tt output $output:tt reply $reply:tt hibernate $hibernate:tt invariant $invariant:tt validate $validate:tt initial $initial:tt dispatch $dispatch:tt;
+        Transitions { $($rules:tt)* } $($rest:tt)*
+    ) => {
+        rustfsm!(@rule_names [$args] [clock $clock trace $trace watchdog $watchdog timers $timers filter $filter interceptors $interceptors rng $rng derive $derive aliases $aliases queue $queue views $views changes $changes output $output reply $reply hibernate $hibernate invariant $invariant validate $validate initial $initial dispatch $dispatch];
+            { $($rules)* } $($rest)*
+        );
+    };
+    (
+        @sections $args:tt transitions $transitions:tt clock { $default_clock:ty; } trace $trace:tt watchdog $watchdog:tt timers $timers:tt filter $filter:tt interceptors $interceptors:tt rng $rng:tt derive $derive:tt aliases $aliases:tt queue $queue:tt views $views:tt changes $changes:tt output $output:tt reply $reply:tt hibernate $hibernate:tt invariant $invariant:tt validate $validate:tt initial $initial:tt dispatch $dispatch:tt;
+        Clock { $clock_type:ty } $($rest:tt)*
+    ) => {
+        rustfsm!(@sections $args
+            transitions $transitions clock { $clock_type; $clock_type } trace $trace watchdog $watchdog timers $timers filter $filter interceptors $interceptors rng $rng derive $derive aliases $aliases queue $queue views $views changes $changes output $output reply $reply hibernate $hibernate invariant $invariant validate $validate initial $initial dispatch $dispatch;
+            $($rest)*
+        );
+    };
+    (
+        @sections $args:tt transitions $transitions:tt clock $clock:tt trace { 0 } watchdog $watchdog:tt timers $timers:tt filter $filter:tt interceptors $interceptors:tt rng $rng:tt derive $derive:tt aliases $aliases:tt queue $queue:tt views $views:tt changes $changes:tt output $output:tt reply $reply:tt hibernate $hibernate:tt invariant $invariant:tt validate $validate:tt initial $initial:tt dispatch $dispatch:tt;
+        Trace { depth: $depth:expr $(,)? } $($rest:tt)*
+    ) => {
+        rustfsm!(@sections $args
+            transitions $transitions clock $clock trace { $depth } watchdog $watchdog timers $timers filter $filter interceptors $interceptors rng $rng derive $derive aliases $aliases queue $queue views $views changes $changes output $output reply $reply hibernate $hibernate invariant $invariant validate $validate initial $initial dispatch $dispatch;
+            $($rest)*
+        );
+    };
+    (
+        @sections $args:tt transitions $transitions:tt clock $clock:tt trace $trace:tt watchdog { } timers $timers:tt filter $filter:tt interceptors $interceptors:tt rng $rng:tt derive $derive:tt aliases $aliases:tt queue $queue:tt views $views:tt changes $changes:tt output $output:tt reply $reply:tt hibernate $hibernate:tt invariant $invariant:tt validate $validate:tt initial $initial:tt dispatch $dispatch:tt;
+        Watchdog { $($rules:tt)* } $($rest:tt)*
+    ) => {
+        rustfsm!(@sections $args
+            transitions $transitions clock $clock trace $trace watchdog { $($rules)* } timers $timers filter $filter interceptors $interceptors rng $rng derive $derive aliases $aliases queue $queue views $views changes $changes output $output reply $reply hibernate $hibernate invariant $invariant validate $validate initial $initial dispatch $dispatch;
+            $($rest)*
+        );
+    };
+    (
+        @sections $args:tt transitions $transitions:tt clock $clock:tt trace $trace:tt watchdog $watchdog:tt timers { } filter $filter:tt interceptors $interceptors:tt rng $rng:tt derive $derive:tt aliases $aliases:tt queue $queue:tt views $views:tt changes $changes:tt output $output:tt reply $reply:tt hibernate $hibernate:tt invariant $invariant:tt validate $validate:tt initial $initial:tt dispatch $dispatch:tt;
+        Timers { slots: $timer_slots:expr $(,)? } $($rest:tt)*
+    ) => {
+        rustfsm!(@sections $args
+            transitions $transitions clock $clock trace $trace watchdog $watchdog timers { $timer_slots } filter $filter interceptors $interceptors rng $rng derive $derive aliases $aliases queue $queue views $views changes $changes output $output reply $reply hibernate $hibernate invariant $invariant validate $validate initial $initial dispatch $dispatch;
+            $($rest)*
+        );
+    };
+    (
+        @sections $args:tt transitions $transitions:tt clock $clock:tt trace $trace:tt watchdog $watchdog:tt timers $timers:tt filter { } interceptors $interceptors:tt rng $rng:tt derive $derive:tt aliases $aliases:tt queue $queue:tt views $views:tt changes $changes:tt output $output:tt reply $reply:tt hibernate $hibernate:tt invariant $invariant:tt validate $validate:tt initial $initial:tt dispatch $dispatch:tt;
+        Filter { $filter_type:ty } $($rest:tt)*
+    ) => {
+        rustfsm!(@sections $args
+            transitions $transitions clock $clock trace $trace watchdog $watchdog timers $timers filter { $filter_type } interceptors $interceptors rng $rng derive $derive aliases $aliases queue $queue views $views changes $changes output $output reply $reply hibernate $hibernate invariant $invariant validate $validate initial $initial dispatch $dispatch;
+            $($rest)*
+        );
+    };
+    (
+        @sections $args:tt transitions $transitions:tt clock $clock:tt trace $trace:tt watchdog $watchdog:tt timers $timers:tt filter $filter:tt interceptors { } rng $rng:tt derive $derive:tt aliases $aliases:tt queue $queue:tt views $views:tt changes $changes:tt output $output:tt reply $reply:tt hibernate $hibernate:tt invariant $invariant:tt validate $validate:tt initial $initial:tt dispatch $dispatch:tt;
+        Interceptors { $($interceptor_type:ty),* $(,)? } $($rest:tt)*
+    ) => {
+        rustfsm!(@sections $args
+            transitions $transitions clock $clock trace $trace watchdog $watchdog timers $timers filter $filter interceptors { $($interceptor_type),* } rng $rng derive $derive aliases $aliases queue $queue views $views changes $changes output $output reply $reply hibernate $hibernate invariant $invariant validate $validate initial $initial dispatch $dispatch;
+            $($rest)*
+        );
+    };
+    (
+        @sections $args:tt transitions $transitions:tt clock $clock:tt trace $trace:tt watchdog $watchdog:tt timers $timers:tt filter $filter:tt interceptors $interceptors:tt rng { } derive $derive:tt aliases $aliases:tt queue $queue:tt views $views:tt changes $changes:tt output $output:tt reply $reply:tt hibernate $hibernate:tt invariant $invariant:tt validate $validate:tt initial $initial:tt dispatch $dispatch:tt;
+        Rng { $rng_type:ty } $($rest:tt)*
+    ) => {
+        rustfsm!(@sections $args
+            transitions $transitions clock $clock trace $trace watchdog $watchdog timers $timers filter $filter interceptors $interceptors rng { $rng_type } derive $derive aliases $aliases queue $queue views $views changes $changes output $output reply $reply hibernate $hibernate invariant $invariant validate $validate initial $initial dispatch $dispatch;
+            $($rest)*
+        );
+    };
+    (
+        @sections $args:tt transitions $transitions:tt clock $clock:tt trace $trace:tt watchdog $watchdog:tt timers $timers:tt filter $filter:tt interceptors $interceptors:tt rng $rng:tt derive { Clone, Copy, PartialEq, Debug } aliases $aliases:tt queue $queue:tt views $views:tt changes $changes:tt output $output:tt reply $reply:tt hibernate $hibernate:tt invariant $invariant:tt validate $validate:tt initial $initial:tt dispatch $dispatch:tt;
+        Derive { events: $($event_derive:ident),+ $(,)? } $($rest:tt)*
+    ) => {
+        rustfsm!(@sections $args
+            transitions $transitions clock $clock trace $trace watchdog $watchdog timers $timers filter $filter interceptors $interceptors rng $rng derive { $($event_derive),+ } aliases $aliases queue $queue views $views changes $changes output $output reply $reply hibernate $hibernate invariant $invariant validate $validate initial $initial dispatch $dispatch;
+            $($rest)*
+        );
+    };
+    (
+        @sections $args:tt transitions $transitions:tt clock $clock:tt trace $trace:tt watchdog $watchdog:tt timers $timers:tt filter $filter:tt interceptors $interceptors:tt rng $rng:tt derive $derive:tt aliases { } queue $queue:tt views $views:tt changes $changes:tt output $output:tt reply $reply:tt hibernate $hibernate:tt invariant $invariant:tt validate $validate:tt initial $initial:tt dispatch $dispatch:tt;
+        Aliases { $($alias:ident = $alias_event:expr),* $(,)? } $($rest:tt)*
+    ) => {
+        rustfsm!(@sections $args
+            transitions $transitions clock $clock trace $trace watchdog $watchdog timers $timers filter $filter interceptors $interceptors rng $rng derive $derive aliases { $($alias = $alias_event),* } queue $queue views $views changes $changes output $output reply $reply hibernate $hibernate invariant $invariant validate $validate initial $initial dispatch $dispatch;
+            $($rest)*
+        );
+    };
+    (
+        @sections $args:tt transitions $transitions:tt clock $clock:tt trace $trace:tt watchdog $watchdog:tt timers $timers:tt filter $filter:tt interceptors $interceptors:tt rng $rng:tt derive $derive:tt aliases $aliases:tt queue { 0 } views $views:tt changes $changes:tt output $output:tt reply $reply:tt hibernate $hibernate:tt invariant $invariant:tt validate $validate:tt initial $initial:tt dispatch $dispatch:tt;
+        Queue { depth: $queue_depth:expr $(, priority: $queue_priority:expr)? $(,)? } $($rest:tt)*
+    ) => {
+        rustfsm!(@sections $args
+            transitions $transitions clock $clock trace $trace watchdog $watchdog timers $timers filter $filter interceptors $interceptors rng $rng derive $derive aliases $aliases queue { $queue_depth $(; $queue_priority)? } views $views changes $changes output $output reply $reply hibernate $hibernate invariant $invariant validate $validate initial $initial dispatch $dispatch;
+            $($rest)*
+        );
+    };
+    (
+        @sections $args:tt transitions $transitions:tt clock $clock:tt trace $trace:tt watchdog $watchdog:tt timers $timers:tt filter $filter:tt interceptors $interceptors:tt rng $rng:tt derive $derive:tt aliases $aliases:tt queue $queue:tt views { } changes $changes:tt output $output:tt reply $reply:tt hibernate $hibernate:tt invariant $invariant:tt validate $validate:tt initial $initial:tt dispatch $dispatch:tt;
+        Views { $($view_state:ident { $($view_field:ident),* $(,)? }),* $(,)? } $($rest:tt)*
+    ) => {
+        rustfsm!(@sections $args
+            transitions $transitions clock $clock trace $trace watchdog $watchdog timers $timers filter $filter interceptors $interceptors rng $rng derive $derive aliases $aliases queue $queue views { $($view_state { $($view_field),* })* } changes $changes output $output reply $reply hibernate $hibernate invariant $invariant validate $validate initial $initial dispatch $dispatch;
+            $($rest)*
+        );
+    };
+    (
+        @sections $args:tt transitions $transitions:tt clock $clock:tt trace $trace:tt watchdog $watchdog:tt timers $timers:tt filter $filter:tt interceptors $interceptors:tt rng $rng:tt derive $derive:tt aliases $aliases:tt queue $queue:tt views $views:tt changes { } output $output:tt reply $reply:tt hibernate $hibernate:tt invariant $invariant:tt validate $validate:tt initial $initial:tt dispatch $dispatch:tt;
+        Changes { $($changed_field:ident),* $(,)? } $($rest:tt)*
+    ) => {
+        rustfsm!(@sections $args
+            transitions $transitions clock $clock trace $trace watchdog $watchdog timers $timers filter $filter interceptors $interceptors rng $rng derive $derive aliases $aliases queue $queue views $views changes { $($changed_field),* } output $output reply $reply hibernate $hibernate invariant $invariant validate $validate initial $initial dispatch $dispatch;
+            $($rest)*
+        );
+    };
+    (
+        @sections $args:tt transitions $transitions:tt clock $clock:tt trace $trace:tt watchdog $watchdog:tt timers $timers:tt filter $filter:tt interceptors $interceptors:tt rng $rng:tt derive $derive:tt aliases $aliases:tt queue $queue:tt views $views:tt changes $changes:tt output { } reply $reply:tt hibernate $hibernate:tt invariant $invariant:tt validate $validate:tt initial $initial:tt dispatch $dispatch:tt;
+        Output { $output_type:ty } $($rest:tt)*
+    ) => {
+        rustfsm!(@sections $args
+            transitions $transitions clock $clock trace $trace watchdog $watchdog timers $timers filter $filter interceptors $interceptors rng $rng derive $derive aliases $aliases queue $queue views $views changes $changes output { $output_type } reply $reply hibernate $hibernate invariant $invariant validate $validate initial $initial dispatch $dispatch;
+            $($rest)*
+        );
+    };
+    (
+        @sections $args:tt transitions $transitions:tt clock $clock:tt trace $trace:tt watchdog $watchdog:tt timers $timers:tt filter $filter:tt interceptors $interceptors:tt rng $rng:tt derive $derive:tt aliases $aliases:tt queue $queue:tt views $views:tt changes $changes:tt output $output:tt reply { } hibernate $hibernate:tt invariant $invariant:tt validate $validate:tt initial $initial:tt dispatch $dispatch:tt;
+        Reply { $reply_type:ty } $($rest:tt)*
+    ) => {
+        rustfsm!(@sections $args
+            transitions $transitions clock $clock trace $trace watchdog $watchdog timers $timers filter $filter interceptors $interceptors rng $rng derive $derive aliases $aliases queue $queue views $views changes $changes output $output reply { $reply_type } hibernate $hibernate invariant $invariant validate $validate initial $initial dispatch $dispatch;
+            $($rest)*
+        );
+    };
+    (
+        @sections $args:tt transitions $transitions:tt clock $clock:tt trace $trace:tt watchdog $watchdog:tt timers $timers:tt filter $filter:tt interceptors $interceptors:tt rng $rng:tt derive $derive:tt aliases $aliases:tt queue $queue:tt views $views:tt changes $changes:tt output $output:tt reply $reply:tt hibernate { } invariant $invariant:tt validate $validate:tt initial $initial:tt dispatch $dispatch:tt;
+        Hibernate { $($hibernate_field:ident),* $(,)? } $($rest:tt)*
+    ) => {
+        rustfsm!(@sections $args
+            transitions $transitions clock $clock trace $trace watchdog $watchdog timers $timers filter $filter interceptors $interceptors rng $rng derive $derive aliases $aliases queue $queue views $views changes $changes output $output reply $reply hibernate { $($hibernate_field),* } invariant $invariant validate $validate initial $initial dispatch $dispatch;
+            $($rest)*
+        );
+    };
+    (
+        @sections $args:tt transitions $transitions:tt clock $clock:tt trace $trace:tt watchdog $watchdog:tt timers $timers:tt filter $filter:tt interceptors $interceptors:tt rng $rng:tt derive $derive:tt aliases $aliases:tt queue $queue:tt views $views:tt changes $changes:tt output $output:tt reply $reply:tt hibernate $hibernate:tt invariant { } validate $validate:tt initial $initial:tt dispatch $dispatch:tt;
+        Invariant { check: $invariant_check:expr $(, on_violation: $invariant_violation:ident)? $(,)? } $($rest:tt)*
+    ) => {
+        rustfsm!(@sections $args
+            transitions $transitions clock $clock trace $trace watchdog $watchdog timers $timers filter $filter interceptors $interceptors rng $rng derive $derive aliases $aliases queue $queue views $views changes $changes output $output reply $reply hibernate $hibernate invariant { $invariant_check; [$($invariant_violation)?] } validate $validate initial $initial dispatch $dispatch;
+            $($rest)*
+        );
+    };
+    (
+        @sections $args:tt transitions $transitions:tt clock $clock:tt trace $trace:tt watchdog $watchdog:tt timers $timers:tt filter $filter:tt interceptors $interceptors:tt rng $rng:tt derive $derive:tt aliases $aliases:tt queue $queue:tt views $views:tt changes $changes:tt output $output:tt reply $reply:tt hibernate $hibernate:tt invariant $invariant:tt validate { } initial $initial:tt dispatch $dispatch:tt;
+        Validate { check: $validate_check:expr $(,)? } $($rest:tt)*
+    ) => {
+        rustfsm!(@sections $args
+            transitions $transitions clock $clock trace $trace watchdog $watchdog timers $timers filter $filter interceptors $interceptors rng $rng derive $derive aliases $aliases queue $queue views $views changes $changes output $output reply $reply hibernate $hibernate invariant $invariant validate { $validate_check } initial $initial dispatch $dispatch;
+            $($rest)*
+        );
+    };
+    (
+        @sections $args:tt transitions $transitions:tt clock $clock:tt trace $trace:tt watchdog $watchdog:tt timers $timers:tt filter $filter:tt interceptors $interceptors:tt rng $rng:tt derive $derive:tt aliases $aliases:tt queue $queue:tt views $views:tt changes $changes:tt output $output:tt reply $reply:tt hibernate $hibernate:tt invariant $invariant:tt validate $validate:tt initial { } dispatch $dispatch:tt;
+        Initial { $initial_state:ident $(($($initial_data:expr),* $(,)?))? } $($rest:tt)*
+    ) => {
+        rustfsm!(@sections $args
+            transitions $transitions clock $clock trace $trace watchdog $watchdog timers $timers filter $filter interceptors $interceptors rng $rng derive $derive aliases $aliases queue $queue views $views changes $changes output $output reply $reply hibernate $hibernate invariant $invariant validate $validate initial { $initial_state $(($($initial_data),*))? } dispatch $dispatch;
+            $($rest)*
+        );
+    };
+    (
+        @sections $args:tt transitions $transitions:tt clock $clock:tt trace $trace:tt watchdog $watchdog:tt timers $timers:tt filter $filter:tt interceptors $interceptors:tt rng $rng:tt derive $derive:tt aliases $aliases:tt queue $queue:tt views $views:tt changes $changes:tt output $output:tt reply $reply:tt hibernate $hibernate:tt invariant $invariant:tt validate $validate:tt initial $initial:tt dispatch { };
+        Dispatch { $($dispatch_options:tt)* } $($rest:tt)*
+    ) => {
+        rustfsm!(@sections $args
+            transitions $transitions clock $clock trace $trace watchdog $watchdog timers $timers filter $filter interceptors $interceptors rng $rng derive $derive aliases $aliases queue $queue views $views changes $changes output $output reply $reply hibernate $hibernate invariant $invariant validate $validate initial $initial dispatch { $($dispatch_options)* };
+            $($rest)*
+        );
+    };
+    (
+        @sections $args:tt transitions $transitions:tt clock $clock:tt trace $trace:tt watchdog $watchdog:tt timers $timers:tt filter $filter:tt interceptors $interceptors:tt rng $rng:tt derive $derive:tt aliases $aliases:tt queue $queue:tt views $views:tt changes $changes:tt output $output:tt reply $reply:tt hibernate $hibernate:tt invariant $invariant:tt validate $validate:tt initial $initial:tt dispatch $dispatch:tt;
+        Config { $($config:tt)* } $($rest:tt)*
+    ) => {
+        rustfsm!(@config [$args transitions $transitions clock $clock trace $trace watchdog $watchdog timers $timers filter $filter interceptors $interceptors rng $rng derive $derive aliases $aliases queue $queue views $views changes $changes output $output reply $reply hibernate $hibernate invariant $invariant validate $validate initial $initial dispatch $dispatch] sections [] dispatch [];
+            { $($config)* } $($rest)*
+        );
+    };
+    (
+        @sections { $($args:tt)* }
+        transitions $transitions:tt clock $clock:tt trace $trace:tt watchdog $watchdog:tt timers $timers:tt filter $filter:tt interceptors $interceptors:tt rng $rng:tt derive $derive:tt aliases $aliases:tt queue $queue:tt views $views:tt changes $changes:tt output $output:tt reply $reply:tt hibernate $hibernate:tt invariant $invariant:tt validate $validate:tt initial $initial:tt dispatch { $($dispatch_options:tt)* };
+    ) => {
+        rustfsm!(@dispatch_options {
+            $($args)*,
+            transitions $transitions,
+            clock $clock,
+            trace $trace,
+            watchdog $watchdog,
+            timers $timers,
+            filter $filter,
+            interceptors $interceptors,
+            rng $rng,
+            derive $derive,
+            aliases $aliases,
+            queue $queue,
+            views $views,
+            changes $changes,
+            output $output,
+            reply $reply,
+            hibernate $hibernate,
+            invariant $invariant,
+            validate $validate,
+            initial $initial
+        } lookup [] inline [] notify [] unhandled [] behavior [] timeouts [] pause [] tagged [];
+            $($dispatch_options)*
+        );
+    };
+    (
+        @sections $args:tt transitions $transitions:tt clock $clock:tt trace $trace:tt watchdog $watchdog:tt timers $timers:tt filter $filter:tt interceptors $interceptors:tt rng $rng:tt derive $derive:tt aliases $aliases:tt queue $queue:tt views $views:tt changes $changes:tt output $output:tt reply $reply:tt hibernate $hibernate:tt invariant $invariant:tt validate $validate:tt initial $initial:tt dispatch $dispatch:tt;
+        $section:ident $($rest:tt)*
+    ) => {
+        compile_error!(concat!("unexpected or repeated section `", stringify!($section), "`"));
+    };
+
+    // Internal normalization of the transition table rules, moving the name
+    // of the named ones after their target. The whole table is matched in
+    // one repetition, the name first moved after the state, so that large
+    // tables don't add to the recursion depth.
+    (
+        @rule_names [$args:tt] [$($slots:tt)*];
+        {
+            $(
+                $head:tt $(: $from_state:tt)? + $event:pat => $target:ident
+                $(if $guard:ident)? $(/ $action:ident)? $(@ min_interval($min_interval:expr))?
+            ),*
+            $(,)?
+        }
+        $($rest:tt)*
+    ) => {
+        rustfsm!(@rule_names [$args] [$($slots)*] moved;
+            { $($($from_state)? $head + $event => $target $(if $guard)? $(/ $action)? [$($min_interval)?],)* }
+            $($rest)*
+        );
+    };
+    (
+        @rule_names [$args:tt] [$($slots:tt)*] moved;
+        {
+            $(
+                $from_state:tt $($name:ident)? + $event:pat => $target:ident
+                $(if $guard:ident)? $(/ $action:ident)? [$($min_interval:expr)?],
+            )*
+        }
+        $($rest:tt)*
+    ) => {
+        rustfsm!(@sections $args
+            transitions { { $($from_state + $event => $target $(if $guard)? $(/ $action)? [$($name)?] [$($min_interval)?],)* } }
+            $($slots)*;
+            $($rest)*
+        );
+    };
+
+    // Internal expansion of the `Config` keys into sections
+    (@config [$($prefix:tt)*] sections [$($sections:tt)*] dispatch []; { } $($rest:tt)*) => {
+        rustfsm!(@sections $($prefix)*; $($sections)* $($rest)*);
+    };
+    (@config [$($prefix:tt)*] sections [$($sections:tt)*] dispatch [$($options:tt)+]; { } $($rest:tt)*) => {
+        rustfsm!(@sections $($prefix)*; $($sections)* Dispatch { $($options)+ } $($rest)*);
+    };
+    (
+        @config $prefix:tt sections [$($sections:tt)*] dispatch $options:tt;
+        { queue_depth = $queue_depth:expr $(, $($config:tt)*)? } $($rest:tt)*
+    ) => {
+        rustfsm!(@config $prefix sections [$($sections)* Queue { depth: $queue_depth }] dispatch $options;
+            { $($($config)*)? } $($rest)*
+        );
+    };
+    (
+        @config $prefix:tt sections [$($sections:tt)*] dispatch $options:tt;
+        { history = $depth:expr $(, $($config:tt)*)? } $($rest:tt)*
+    ) => {
+        rustfsm!(@config $prefix sections [$($sections)* Trace { depth: $depth }] dispatch $options;
+            { $($($config)*)? } $($rest)*
+        );
+    };
+    (
+        @config $prefix:tt sections [$($sections:tt)*] dispatch $options:tt;
+        { timer_slots = $timer_slots:expr $(, $($config:tt)*)? } $($rest:tt)*
+    ) => {
+        rustfsm!(@config $prefix sections [$($sections)* Timers { slots: $timer_slots }] dispatch $options;
+            { $($($config)*)? } $($rest)*
+        );
+    };
+    (
+        @config $prefix:tt sections $sections:tt dispatch [$($options:tt)*];
+        { strict_unhandled = true $(, $($config:tt)*)? } $($rest:tt)*
+    ) => {
+        rustfsm!(@config $prefix sections $sections dispatch [$($options)* unhandled(error),];
+            { $($($config)*)? } $($rest)*
+        );
+    };
+    (
+        @config $prefix:tt sections $sections:tt dispatch $options:tt;
+        { strict_unhandled = false $(, $($config:tt)*)? } $($rest:tt)*
+    ) => {
+        rustfsm!(@config $prefix sections $sections dispatch $options; { $($($config)*)? } $($rest)*);
+    };
+    (
+        @config $prefix:tt sections $sections:tt dispatch [$($options:tt)*];
+        { jump_table = true $(, $($config:tt)*)? } $($rest:tt)*
+    ) => {
+        rustfsm!(@config $prefix sections $sections dispatch [$($options)* jump_table,];
+            { $($($config)*)? } $($rest)*
+        );
+    };
+    (
+        @config $prefix:tt sections $sections:tt dispatch $options:tt;
+        { jump_table = false $(, $($config:tt)*)? } $($rest:tt)*
+    ) => {
+        rustfsm!(@config $prefix sections $sections dispatch $options; { $($($config)*)? } $($rest)*);
+    };
+    (@config $prefix:tt sections $sections:tt dispatch $options:tt; { $key:ident $($config:tt)* } $($rest:tt)*) => {
+        compile_error!(concat!(
+            "unknown or invalid `Config` key `", stringify!($key),
+            "`, expected `queue_depth`, `history`, `timer_slots`, `strict_unhandled` or `jump_table`"
+        ));
+    };
+
+    // Internal normalization of the `Dispatch` options
+    (
+        @dispatch_options $args:tt lookup [] inline $inline:tt notify $notify:tt unhandled $unhandled:tt behavior $behavior:tt timeouts $timeouts:tt pause $pause:tt tagged $tagged:tt;
+        jump_table $(, $($rest:tt)*)?
+    ) => {
+        rustfsm!(@dispatch_options $args lookup [jump_table] inline $inline notify $notify unhandled $unhandled behavior $behavior timeouts $timeouts pause $pause tagged $tagged;
+            $($($rest)*)?
+        );
+    };
+    (
+        @dispatch_options $args:tt lookup $lookup:tt inline [] notify $notify:tt unhandled $unhandled:tt behavior $behavior:tt timeouts $timeouts:tt pause $pause:tt tagged $tagged:tt;
+        inline $(($inline_mode:ident))? $(, $($rest:tt)*)?
+    ) => {
+        rustfsm!(@dispatch_options $args
+            lookup $lookup inline [inline $(($inline_mode))?] notify $notify unhandled $unhandled behavior $behavior timeouts $timeouts pause $pause tagged $tagged;
+            $($($rest)*)?
+        );
+    };
+    (
+        @dispatch_options $args:tt lookup $lookup:tt inline $inline:tt notify [] unhandled $unhandled:tt behavior $behavior:tt timeouts $timeouts:tt pause $pause:tt tagged $tagged:tt;
+        notify($order:ident) $(, $($rest:tt)*)?
+    ) => {
+        rustfsm!(@notify_order $order);
+        rustfsm!(@dispatch_options $args lookup $lookup inline $inline notify [$order] unhandled $unhandled behavior $behavior timeouts $timeouts pause $pause tagged $tagged;
+            $($($rest)*)?
+        );
+    };
+    (
+        @dispatch_options $args:tt lookup $lookup:tt inline $inline:tt notify $notify:tt unhandled [] behavior $behavior:tt timeouts $timeouts:tt pause $pause:tt tagged $tagged:tt;
+        unhandled($policy:ident) $(, $($rest:tt)*)?
+    ) => {
+        rustfsm!(@unhandled_policy $policy);
+        rustfsm!(@dispatch_options $args
+            lookup $lookup inline $inline notify $notify unhandled [$policy] behavior $behavior timeouts $timeouts pause $pause tagged $tagged;
+            $($($rest)*)?
+        );
+    };
+    (
+        @dispatch_options $args:tt
+        lookup $lookup:tt inline $inline:tt notify $notify:tt unhandled $unhandled:tt behavior [] timeouts $timeouts:tt pause $pause:tt tagged $tagged:tt;
+        behavior $(, $($rest:tt)*)?
+    ) => {
+        rustfsm!(@dispatch_options $args
+            lookup $lookup inline $inline notify $notify unhandled $unhandled behavior [behavior] timeouts $timeouts pause $pause tagged $tagged;
+            $($($rest)*)?
+        );
+    };
+    (
+        @dispatch_options $args:tt
+        lookup $lookup:tt inline $inline:tt notify $notify:tt unhandled $unhandled:tt behavior $behavior:tt timeouts [] pause $pause:tt tagged $tagged:tt;
+        timeouts($order:ident) $(, $($rest:tt)*)?
+    ) => {
+        rustfsm!(@timeout_order $order);
+        rustfsm!(@dispatch_options $args
+            lookup $lookup inline $inline notify $notify unhandled $unhandled behavior $behavior timeouts [$order] pause $pause tagged $tagged;
+            $($($rest)*)?
+        );
+    };
+    (
+        @dispatch_options $args:tt
+        lookup $lookup:tt inline $inline:tt notify $notify:tt unhandled $unhandled:tt behavior $behavior:tt timeouts $timeouts:tt pause [] tagged $tagged:tt;
+        pausable $(, $($rest:tt)*)?
+    ) => {
+        rustfsm!(@dispatch_options $args
+            lookup $lookup inline $inline notify $notify unhandled $unhandled behavior $behavior timeouts $timeouts pause [pausable] tagged $tagged;
+            $($($rest)*)?
+        );
+    };
+    (
+        @dispatch_options $args:tt
+        lookup $lookup:tt inline $inline:tt notify $notify:tt unhandled $unhandled:tt behavior $behavior:tt timeouts $timeouts:tt pause $pause:tt tagged [];
+        tagged $(, $($rest:tt)*)?
+    ) => {
+        rustfsm!(@dispatch_options $args
+            lookup $lookup inline $inline notify $notify unhandled $unhandled behavior $behavior timeouts $timeouts pause $pause tagged [tagged];
+            $($($rest)*)?
+        );
+    };
+    (
+        @dispatch_options { $($args:tt)* } lookup $lookup:tt inline $inline:tt notify $notify:tt unhandled $unhandled:tt behavior $behavior:tt timeouts $timeouts:tt pause $pause:tt tagged $tagged:tt;
+    ) => {
+        rustfsm!(@generate $($args)*,
+            dispatch { lookup $lookup inline $inline notify $notify unhandled $unhandled behavior $behavior timeouts $timeouts pause $pause tagged $tagged }
+        );
+    };
+    (
+        @dispatch_options $args:tt lookup $lookup:tt inline $inline:tt notify $notify:tt unhandled $unhandled:tt behavior $behavior:tt timeouts $timeouts:tt pause $pause:tt tagged $tagged:tt;
+        $($option:tt)*
+    ) => {
+        compile_error!(concat!(
+            "unknown or repeated `Dispatch` option in `", stringify!($($option)*), "`"
+        ));
+    };
+
+    // Internal validation of the point of a transition the interceptors are
+    // notified at
+    (@notify_order before_exit) => {};
+    (@notify_order before_enter) => {};
+    (@notify_order after_enter) => {};
+    (@notify_order $order:ident) => {
+        compile_error!(concat!(
+            "unknown `notify` order `", stringify!($order),
+            "`, expected `before_exit`, `before_enter` or `after_enter`"
+        ));
+    };
+
+    // Internal validation of the order of expired timers and queued events
+    (@timeout_order first) => {};
+    (@timeout_order arrival) => {};
+    (@timeout_order priority) => {};
+    (@timeout_order $order:ident) => {
+        compile_error!(concat!(
+            "unknown `timeouts` order `", stringify!($order),
+            "`, expected `first`, `arrival` or `priority`"
+        ));
+    };
+
+    // Internal validation of the policy for events leading to no transition
+    (@unhandled_policy ignore) => {};
+    (@unhandled_policy hook) => {};
+    (@unhandled_policy error) => {};
+    (@unhandled_policy assert) => {};
+    (@unhandled_policy $policy:ident) => {
+        compile_error!(concat!(
+            "unknown `unhandled` policy `", stringify!($policy),
+            "`, expected `ignore`, `hook`, `error` or `assert`"
+        ));
+    };
+
+    // Internal check of the invariants after an event is handled, the
+    // state's own then the `Invariant` section's
+    (@check_invariant $state:expr, $context:ident; $state_type:ident, $context_type:ident;) => {
+        $state.check_invariant($context)
+    };
+    (
+        @check_invariant $state:expr, $context:ident; $state_type:ident, $context_type:ident;
+        $invariant_check:expr
+    ) => {{
+        const CHECK: fn(&$state_type, &$context_type) -> Result<(), $crate::InvariantViolation> =
+            $invariant_check;
+        $state
+            .check_invariant($context)
+            .and_then(|()| CHECK(&$state, $context).map_err(|violation| violation.0))
+    }};
+
+    // Internal check of the payload of an event before it's handled
+    (@validate $event:ident, $context:ident; $event_type:ident, $context_type:ident;) => {
+        Ok::<(), $crate::ValidationError>(())
+    };
+    (
+        @validate $event:ident, $context:ident; $event_type:ident, $context_type:ident;
+        $validate_check:expr
+    ) => {{
+        const CHECK: fn(&$event_type, &$context_type) -> Result<(), $crate::ValidationError> =
+            $validate_check;
+        CHECK($event, $context)
+    }};
+    // Internal reaction to a violated invariant, before escalating to the
+    // `@fault` state, according to the `on_violation` policy
+    (@invariant_violation [$(fault)?]; $state:expr, $reason:ident) => {};
+    (@invariant_violation [panic]; $state:expr, $reason:ident) => {
+        panic!("invariant violated in {:?}: {}", $state, $reason)
+    };
+    (@invariant_violation [assert]; $state:expr, $reason:ident) => {
+        debug_assert!(false, "invariant violated in {:?}: {}", $state, $reason)
+    };
+    (@invariant_violation [$policy:ident]; $state:expr, $reason:ident) => {
+        compile_error!(concat!(
+            "unknown `on_violation` policy `", stringify!($policy),
+            "`, expected `fault`, `panic` or `assert`"
+        ))
+    };
+
+    // Internal handling of an event leading to no transition, according to
+    // the `unhandled` policy, which the `ignore` rules of the transition
+    // table are exempt from
+    (@unhandled [$(ignore)?]; $state:ident, $event:ident, $context:ident) => {};
+    (@unhandled [hook]; $state:ident, $event:ident, $context:ident) => {
+        if !rustfsm!(@ignored $state, $event) {
+            $state.unhandled($event, $context);
+        }
+    };
+    (@unhandled [error]; $state:ident, $event:ident, $context:ident) => {
+        if !rustfsm!(@ignored $state, $event) {
+            return Err($crate::DispatchError::Unhandled);
+        }
+    };
+    (@unhandled [assert]; $state:ident, $event:ident, $context:ident) => {
+        if !rustfsm!(@ignored $state, $event) {
+            debug_assert!(false, "unhandled event {:?} in state {:?}", $event, $state);
+            $state.unhandled($event, $context);
+        }
+    };
+    (@ignored $state:ident, $event:ident) => {
+        $state.table_rule($event).is_some_and(|rule| rule.to.is_none())
+    };
+
+    // Internal notification of the interceptors of a transition, if `$at` is
+    // the chosen point of the transition
+    (@notify $at:ident []; $($args:tt)*) => {
+        rustfsm!(@notify $at [after_enter]; $($args)*)
+    };
+    (
+        @notify $at:ident [$order:ident];
+        $self:ident, $event:ident, $from:ident, $to:ident, $context:ident;
+        $state_type:ty, $event_type:ty, $context_type:ty
+    ) => {
+        rustfsm!(@notify_if $at $order {
+            $crate::interceptor::Interceptor::<$state_type, $event_type, $context_type>::on_transition(
+                &mut $self.interceptors,
+                $event,
+                &$from,
+                &$to,
+                &*$context,
+            );
+        })
+    };
+    (@notify_if before_exit before_exit { $($notify:tt)* }) => { $($notify)* };
+    (@notify_if before_enter before_enter { $($notify:tt)* }) => { $($notify)* };
+    (@notify_if after_enter after_enter { $($notify:tt)* }) => { $($notify)* };
+    (@notify_if $at:ident $order:ident { $($notify:tt)* }) => {};
+
+    // Internal validation of a state marker
+    (@state_marker final) => {};
+    (@state_marker fault) => {};
+    (@state_marker $marker:ident) => {
+        compile_error!(concat!("unknown state marker `@", stringify!($marker), "`"));
+    };
+
+    // Internal sorting of the events marked `@internal` from the commands,
+    // generating the two enums once there are internal events
+    (@commands $state_machine_name:ident, $event_type:ident, $derives:tt; [$($command:tt)*] [$($internal:tt)*];
+        [internal] $event:ident $(($($event_data:ty),*))?; $($rest:tt)*
+    ) => {
+        rustfsm!(@commands $state_machine_name, $event_type, $derives;
+            [$($command)*] [$($internal)* $event $(($($event_data),*))?,]; $($rest)*
+        );
+    };
+    (@commands $state_machine_name:ident, $event_type:ident, $derives:tt; [$($command:tt)*] [$($internal:tt)*];
+        [] $event:ident $(($($event_data:ty),*))?; $($rest:tt)*
+    ) => {
+        rustfsm!(@commands $state_machine_name, $event_type, $derives;
+            [$($command)* $event $(($($event_data),*))?,] [$($internal)*]; $($rest)*
+        );
+    };
+    (@commands $state_machine_name:ident, $event_type:ident, $derives:tt; $commands:tt $internals:tt;
+        [$marker:ident $($markers:ident)*] $($rest:tt)*
+    ) => {
+        compile_error!(concat!("unknown event marker `@", stringify!($marker), "`, expected `@internal`"));
+    };
+    (@commands $state_machine_name:ident, $event_type:ident, $derives:tt; $commands:tt [];) => {};
+    (@commands $state_machine_name:ident, $event_type:ident, [$($derive:ident),+];
+        [$($command:ident $(($($command_data:ty),*))?,)*] [$($internal:ident $(($($internal_data:ty),*))?,)+];
+    ) => {
+        $crate::paste::paste! {
+            /// Commands of the state machine, the events external code
+            /// dispatches with `command`.
+            #[derive($($derive),+)]
+            pub enum [<$state_machine_name Command>] {
+                $($command $(($($command_data),*))?),*
+            }
+
+            /// Internal events of the state machine, marked `@internal` and
+            /// posted by the machine itself.
+            #[allow(dead_code)]
+            #[derive($($derive),+)]
+            pub(crate) enum [<$state_machine_name Internal>] {
+                $($internal $(($($internal_data),*))?),+
+            }
+
+            rustfsm!(@convert [<$state_machine_name Command>], $event_type;
+                $($command $(($($command_data),*))?,)*
+            );
+            rustfsm!(@convert [<$state_machine_name Internal>], $event_type;
+                $($internal $(($($internal_data),*))?,)+
+            );
+
+            impl $state_machine_name {
+                /// Dispatch a command, as external code does.
+                pub fn command(
+                    &mut self,
+                    command: [<$state_machine_name Command>],
+                ) -> Result<(), $crate::DispatchError> {
+                    self.dispatch(command.into())
+                }
+            }
+        }
+    };
+
+    // Internal conversion of the commands or the internal events into the
+    // events, each arm binding the payload fields of its event in turn. The
+    // `field` of every step is a distinct binding, so there's no cap on the
+    // number of fields.
+    (@convert $from:ident, $to:ident; $($event:ident $(($($event_data:ty),*))?,)*) => {
+        impl From<$from> for $to {
+            #[allow(unreachable_patterns)]
+            fn from(event: $from) -> Self {
+                match event {
+                    $(
+                        $from::$event { .. } => rustfsm!(@convert_event event, $from::$event, $to::$event;
+                            [] $($($event_data),*)?
+                        ),
+                    )*
+                }
+            }
+        }
+    };
+    (@convert_event $event:ident, $from:ident::$variant:ident, $to:ident::$to_variant:ident; []) => {
+        $to::$to_variant
+    };
+    (@convert_event $event:ident, $from:ident::$variant:ident, $to:ident::$to_variant:ident;
+        [$($bound:ident)+]
+    ) => {
+        match $event {
+            $from::$variant($($bound),+) => $to::$to_variant($($bound),+),
+            // Taken by the arm of the variant
+            _ => unreachable!(),
+        }
+    };
+    (@convert_event $event:ident, $from:ident::$variant:ident, $to:ident::$to_variant:ident;
+        [$($bound:ident)*] $data:ty $(, $rest:ty)*
+    ) => {
+        rustfsm!(@convert_event $event, $from::$variant, $to::$to_variant; [$($bound)* field] $($rest),*)
+    };
+
+    // Internal check for the `@final` marker in a state marker list
+    (@is_final final $($marker:ident)*) => { true };
+    (@is_final $other:ident $($marker:ident)*) => { rustfsm!(@is_final $($marker)*) };
+    (@is_final) => { false };
+
+    // Internal lookup of the state marked `@fault`
+    (@fault_state $state_type:ident;) => { None };
+    (@fault_state $state_type:ident; [fault $($marker:ident)*] $state:ident $($rest:tt)*) => {
+        Some($state_type::$state)
+    };
+    (@fault_state $state_type:ident; [$other:ident $($marker:ident)*] $state:ident $($rest:tt)*) => {
+        rustfsm!(@fault_state $state_type; [$($marker)*] $state $($rest)*)
+    };
+    (@fault_state $state_type:ident; [] $state:ident $($rest:tt)*) => {
+        rustfsm!(@fault_state $state_type; $($rest)*)
+    };
+
+    // Internal filtering and interception of a dispatched event, only for
+    // machines with a `Filter` or an `Interceptors` section, which need
+    // `Copy` events
+    (
+        @intercept $self:ident, $event:ident, $external_context:ident, $from:ident;
+        $state_type:ty, $event_type:ty, $context_type:ty, $clock_type:ty; [] []
+    ) => {
+        $self.dispatch_step(&$event, $external_context.as_deref_mut())
+    };
+    (
+        @intercept $self:ident, $event:ident, $external_context:ident, $from:ident;
+        $state_type:ty, $event_type:ty, $context_type:ty, $clock_type:ty; $filter:tt $interceptors:tt
+    ) => {{
+        use $crate::interceptor::Interceptor;
+
+        Interceptor::<$state_type, $event_type, $context_type>::on_source(
+            &mut $self.interceptors,
+            $crate::SourceSlot::get(&$self.source),
+        );
+        let result = match $crate::filter::Filter::filter(
+            &mut $self.filter,
+            $event,
+            <$clock_type as $crate::clock::Clock>::now(),
+        ) {
+            Some(event) => match Interceptor::<$state_type, $event_type, $context_type>::before(
+                &mut $self.interceptors,
+                &$from,
+                $external_context.as_deref().unwrap_or(&$self.context),
+                event,
+            ) {
+                Some(event) => $self.dispatch_step(&event, $external_context.as_deref_mut()),
+                None => Err($crate::DispatchError::Vetoed),
+            },
+            None => Err($crate::DispatchError::Filtered),
+        };
+        if result != Err($crate::DispatchError::Filtered) {
+            Interceptor::<$state_type, $event_type, $context_type>::after(
+                &mut $self.interceptors,
+                &$event,
+                &$from,
+                &$self.current_state,
+                &result.map(|_| ()),
+            );
+        }
+        result
+    }};
+
+    // Internal dispatch of an event by reference, the filter and the
+    // interceptors taking a copy
+    (@dispatch_ref $self:ident, $event:ident: $event_type:ty; $trace_depth:tt; $derives:tt; [] []) => {{
+        if $crate::clock::PauseSlot::pause(&$self.paused).is_some() {
+            return Err($crate::DispatchError::Paused);
+        }
+        if !$self.guard.enter() {
+            return Err($crate::DispatchError::Reentrant);
+        }
+        $crate::SourceSlot::set(&mut $self.source, $crate::Source::Host);
+        let from = $self.current_state;
+        let result = $self.dispatch_step($event, None);
+        rustfsm!(@record_ref $self, $event, from, result; $trace_depth; $derives);
+        $self.guard.exit();
+        match result {
+            // Then the events queued meanwhile
+            Ok(_) => $self.process_in(None, None, usize::MAX).map(|_| ()),
+            Err(error) => {
+                $crate::queue::Post::<$event_type>::clear(&mut $self.queue);
+                Err(error)
+            }
+        }
+    }};
+    (@dispatch_ref $self:ident, $event:ident: $event_type:ty; $trace_depth:tt; $derives:tt;
+        $filter:tt $interceptors:tt
+    ) => {
+        $self.dispatch(*$event)
+    };
+    // Tracing a borrowed event needs a clone, and events which aren't
+    // `Clone` go untraced
+    (@record_ref $self:ident, $event:ident, $from:ident, $result:ident; 0; $derives:tt) => {};
+    (@record_ref $self:ident, $event:ident, $from:ident, $result:ident; $trace_depth:tt;
+        [Clone $(, $derive:ident)*]
+    ) => {
+        $self.record($event.clone(), $from, $result)
+    };
+    (@record_ref $self:ident, $event:ident, $from:ident, $result:ident; $trace_depth:tt;
+        [$other:ident $(, $derive:ident)*]
+    ) => {
+        rustfsm!(@record_ref $self, $event, $from, $result; $trace_depth; [$($derive),*])
+    };
+    (@record_ref $self:ident, $event:ident, $from:ident, $result:ident; $trace_depth:tt; []) => {};
+
+    // Internal handling of an event by the current state, through
+    // `try_handle_with` or through `handle_output` for machines with an
+    // `Output` section, keeping the first output
+    (@handle $self:ident, $event:ident, $context:ident, $rng:expr; [] []) => {
+        $self.current_state.try_handle_with(
+            $event,
+            $context,
+            &mut $crate::queue::Outbox::new(
+                &mut $self.queue,
+                $crate::SourceSlot::get(&$self.source),
+                $rng,
+            ),
+        )
+    };
+    (@handle $self:ident, $event:ident, $context:ident, $rng:expr; [$output_type:ty] []) => {{
+        let (next_state, output) =
+            $crate::output::Mealy::handle_output(&$self.current_state, $event, $context);
+        if $self.output.is_none() {
+            $self.output = output;
+        }
+        Ok(next_state)
+    }};
+    (@handle $self:ident, $event:ident, $context:ident, $rng:expr; [] [$reply_type:ty]) => {{
+        let (next_state, reply) =
+            $crate::output::Respond::handle_reply(&$self.current_state, $event, $context);
+        if $self.output.is_none() {
+            $self.output = Some(reply);
+        }
+        Ok(next_state)
+    }};
+    (@handle $self:ident, $event:ident, $context:ident, $rng:expr; [$output_type:ty] [$reply_type:ty]) => {
+        compile_error!("the `Output` and `Reply` sections can't be combined")
+    };
+
+    // Internal output or reply storage, only for machines with an `Output`
+    // or a `Reply` section
+    (@output_type [] []) => { () };
+    (@output_type [$output_type:ty] []) => { Option<$output_type> };
+    (@output_type [] [$reply_type:ty]) => { Option<$reply_type> };
+    (@output_type [$output_type:ty] [$reply_type:ty]) => { () };
+
+    // Internal generation of `dispatch_output()`, for machines with an
+    // `Output` section
+    (@output_methods $event_type:ident;) => {};
+    (@output_methods $event_type:ident; $output_type:ty) => {
+        /// Dispatch event like [`dispatch`](Self::dispatch), returning the
+        /// output of its handling, see the [`output`]($crate::output) module.
+        ///
+        /// With a `Queue` section, the output is the first one produced by
+        /// the event or its follow-up events.
+        pub fn dispatch_output(
+            &mut self,
+            event: $event_type,
+        ) -> Result<Option<$output_type>, $crate::DispatchError> {
+            self.output = None;
+            let result = self.dispatch(event);
+            let output = self.output.take();
+            result.map(|()| output)
+        }
+    };
+
+    // Internal generation of `dispatch_with_reply()`, for machines with a
+    // `Reply` section
+    (@reply_methods $event_type:ident;) => {};
+    (@reply_methods $event_type:ident; $reply_type:ty) => {
+        /// Dispatch event like [`dispatch`](Self::dispatch), returning the
+        /// reply of its handler, see the [`output`]($crate::output) module,
+        /// or `None` if a `min_interval` rule skipped the event.
+        ///
+        /// With a `Queue` section, the reply is the one to the event itself,
+        /// not to its follow-up events.
+        pub fn dispatch_with_reply(
+            &mut self,
+            event: $event_type,
+        ) -> Result<Option<$reply_type>, $crate::DispatchError> {
+            // Queued events would be replied to after the call
+            if $crate::clock::PauseSlot::pause(&self.paused).is_some() {
+                return Err($crate::DispatchError::Paused);
+            }
+            self.output = None;
+            let reached = self.dispatch_in(event, $crate::Source::Host, None)?;
+            Ok(self.output.take().filter(|_| reached))
+        }
+    };
+
+    // Internal event queue, only for machines with a `Queue` section
+    (@queue_type $event_type:ty; 0) => { $crate::queue::NoQueue };
+    (@queue_type $event_type:ty; $depth:expr) => {
+        $crate::queue::EventQueue<$event_type, { $depth }>
+    };
+    (@queue_new $event_type:ty; $clock:tt; 0) => { $crate::queue::NoQueue::new() };
+    (@queue_new $event_type:ty; $clock:tt; $depth:expr) => {
+        rustfsm!(@queue_new $event_type; $clock; $depth; |_| 0)
+    };
+    (@queue_new $event_type:ty; []; $depth:expr; $priority:expr) => {
+        $crate::queue::EventQueue::<$event_type, { $depth }>::with_priority($priority)
+    };
+    (@queue_new $event_type:ty; [$clock_type:ty]; $depth:expr; $priority:expr) => {
+        $crate::queue::EventQueue::<$event_type, { $depth }>::with_priority($priority)
+            .timestamped(<$clock_type as $crate::clock::Clock>::now)
+    };
+
+    // Internal trace storage, only for machines with a `Trace` section
+    (@trace_type $state_type:ty, $event_type:ty; 0) => { $crate::trace::NoTrace };
+    (@trace_type $state_type:ty, $event_type:ty; $depth:expr) => {
+        $crate::trace::Trace<$state_type, $event_type, { $depth }>
+    };
+    (@trace_ref $self:ident, $state_type:ty, $event_type:ty; 0) => {{
+        const EMPTY: &$crate::trace::Trace<$state_type, $event_type, 0> =
+            &$crate::trace::Trace::new();
+        EMPTY
+    }};
+    (@trace_ref $self:ident, $state_type:ty, $event_type:ty; $depth:expr) => {
+        &$self.trace
+    };
+
+    // Internal timestamping of state entries, only for machines with a
+    // `Clock` section
+    (@stay_type) => { () };
+    (@stay_type $clock_type:ty) => { $crate::clock::Stay };
+    (@stay) => { () };
+    (@stay $clock_type:ty) => {
+        $crate::clock::Stay::new(<$clock_type as $crate::clock::Clock>::now())
+    };
+    (@tick_type) => { () };
+    (@tick_type $clock_type:ty) => { u32 };
+    (@now) => { () };
+    (@now $clock_type:ty) => { <$clock_type as $crate::clock::Clock>::now() };
+    // Internal generation of `dispatch_tagged()` and `last_source()`, for
+    // machines with the `tagged` dispatch option
+    (@tagged_methods [] $event_type:ident; $($inline:meta)?) => {};
+    (@tagged_methods [tagged] $event_type:ident; $($inline:meta)?) => {
+        /// Dispatch event like [`dispatch`](Self::dispatch), telling
+        /// where it came from.
+        ///
+        /// The source is given to the handlers by
+        /// [`Outbox::source`]($crate::queue::Outbox::source), to the
+        /// interceptors' `on_source` and recorded in the trace. Events
+        /// given to `dispatch` come from [`Source::Host`]($crate::Source),
+        /// those of expired timers from `Source::Timer`, and queued ones
+        /// from `Source::Queued`.
+        $(#[$inline])?
+        pub fn dispatch_tagged(
+            &mut self,
+            event: $event_type,
+            source: $crate::Source,
+        ) -> Result<(), $crate::DispatchError> {
+            self.dispatch_in(event, source, None).map(|_| ())
+        }
+
+        /// Where the event being dispatched, or the last one, came from
+        pub fn last_source(&self) -> $crate::Source {
+            self.source
+        }
+    };
+    (@source_type []) => { $crate::NoSource };
+    (@source_type [tagged]) => { $crate::Source };
+    // Internal generation of `pause()` and `resume()`, for machines with the
+    // `pausable` dispatch option
+    (@pause_methods [] $($clock_type:ty)?) => {};
+    (@pause_methods [pausable] $($clock_type:ty)?) => {
+        /// Pause the machine, handling the events dispatched meanwhile
+        /// according to `policy`, until [`resume`](Self::resume).
+        ///
+        /// The time spent paused doesn't count towards the watchdog
+        /// limits, the timers and the dwell time metrics, and `tick` does
+        /// nothing. With
+        /// [`PausePolicy::Queue`]($crate::PausePolicy), events are queued
+        /// in the machine's `Queue`, refused with
+        /// [`DispatchError::Paused`]($crate::DispatchError) once it's
+        /// full or without one.
+        pub fn pause(&mut self, policy: $crate::PausePolicy) {
+            let since = match self.paused {
+                Some(pause) => pause.since,
+                None => rustfsm!(@now $($clock_type)?),
+            };
+            self.paused = Some($crate::clock::Pause { policy, since });
+        }
+
+        /// Resume a paused machine, dispatching the events queued
+        /// meanwhile, returning how many were dispatched.
+        pub fn resume(&mut self) -> Result<usize, $crate::DispatchError> {
+            let Some(pause) = self.paused.take() else {
+                return Ok(0);
+            };
+            rustfsm!(@postpone self, pause.since; $($clock_type)?);
+            self.process_all()
+        }
+
+        /// Whether the machine is paused.
+        pub fn is_paused(&self) -> bool {
+            self.paused.is_some()
+        }
+    };
+    (@pause_type [] $($clock_type:ty)?) => { $crate::clock::NoPause };
+    (@pause_type [pausable] $($clock_type:ty)?) => {
+        Option<$crate::clock::Pause<rustfsm!(@tick_type $($clock_type)?)>>
+    };
+    // Leave the time spent paused since `since` out of the time accounting
+    (@postpone $self:ident, $since:expr;) => {};
+    (@postpone $self:ident, $since:expr; $clock_type:ty) => {
+        let paused = $crate::clock::elapsed($since, <$clock_type as $crate::clock::Clock>::now());
+        $self.stay.postpone(paused);
+        $self.timers.postpone(paused);
+        $self.metrics.postpone(paused);
+    };
+    (@too_soon $self:ident, $state_type:ident, $state:ident, $event:ident;) => {{
+        const {
+            assert!(
+                !$crate::table::has_min_interval($state_type::TRANSITION_TABLE),
+                "`min_interval` rules need a `Clock` section"
+            )
+        };
+        false
+    }};
+    (@too_soon $self:ident, $state_type:ident, $state:ident, $event:ident; $clock_type:ty) => {
+        $state.table_rule($event).is_some_and(|rule| {
+            $self.stay.elapsed(<$clock_type as $crate::clock::Clock>::now()) < rule.min_interval
+        })
+    };
+    (@has_clock) => { false };
+    (@has_clock $clock_type:ty) => { true };
+    (@stay_elapsed $self:ident;) => { 0 };
+    (@stay_elapsed $self:ident; $clock_type:ty) => {
+        $self.stay.elapsed(<$clock_type as $crate::clock::Clock>::now())
+    };
+    (@process_budget) => {};
+    (@process_budget $clock_type:ty) => {
+        /// Dispatch queued events until `ticks` have elapsed or the queue is
+        /// drained, reporting how many are left.
+        ///
+        /// The budget is checked between events, an event started before it
+        /// runs out being dispatched in full. An error drops the events still
+        /// queued.
+        pub fn process_budget(
+            &mut self,
+            ticks: u32,
+        ) -> Result<$crate::queue::Progress, $crate::DispatchError> {
+            let start = <$clock_type as $crate::clock::Clock>::now();
+            let mut dispatched = 0;
+            while $crate::clock::elapsed(start, <$clock_type as $crate::clock::Clock>::now()) < ticks
+                && self.process_in(None, None, 1)?.0 == 1
+            {
+                dispatched += 1;
+            }
+            Ok($crate::queue::Progress {
+                dispatched,
+                remaining: self.queued().count(),
+            })
+        }
+    };
+    (@time_in_state) => {};
+    (@time_in_state $clock_type:ty) => {
+        /// Ticks spent in the current state since it was entered, or since
+        /// the machine was created, started or reset.
+        pub fn time_in_current_state(&self) -> u32 {
+            self.stay.elapsed(<$clock_type as $crate::clock::Clock>::now())
+        }
+    };
+
+    // Internal generation of `tick()`, checking the watchdog limits
+    (@tick $state_type:ident, $event_type:ident; []; []; $timeouts:tt; $($watchdog:tt)+) => {
+        compile_error!("a `Watchdog` section needs a `Clock` section");
+    };
+    (@tick $state_type:ident, $event_type:ident; []; [$timer_slots:expr]; $($rest:tt)*) => {
+        compile_error!("a `Timers` section needs a `Clock` section");
+    };
+    (@tick $state_type:ident, $event_type:ident; []; []; $timeouts:tt; ) => {};
+    (
+        @tick $state_type:ident, $event_type:ident; [$clock_type:ty]; [$($timer_slots:expr)?];
+        [$($timeouts:ident)?];
+        $($state:ident: $limit:expr => $action:ident $(($($action_data:expr),*))?),* $(,)?
+    ) => {
+        /// Deliver the events of expired timers, then check the time spent
+        /// in the current state against its watchdog limit, if any.
+        ///
+        /// Once exceeded, the state's watchdog event is dispatched, or a
+        /// [`Fault::Watchdog`]($crate::Fault) is raised for `fault`, a single
+        /// time per stay in the state. Does nothing on a stopped, paused or
+        /// finished machine, and stops at the first event failing to
+        /// dispatch.
+        pub fn tick(&mut self) -> Result<(), $crate::DispatchError> {
+            if self.status == $crate::Status::Stopped
+                || $crate::clock::PauseSlot::pause(&self.paused).is_some()
+                || self.is_finished()
+            {
+                return Ok(());
+            }
+            let now = <$clock_type as $crate::clock::Clock>::now();
+            rustfsm!(@expired_timers self, now; [$($timeouts)?]);
+            match self.current_state {
+                $(
+                    $state_type::$state { .. } if self.stay.watchdog_expired(now, $limit) => {
+                        rustfsm!(@watchdog_action self, $state_type, $event_type, $state,
+                            $action $(($($action_data),*))?)
+                    }
+                )*
+                _ => Ok(()),
+            }
+        }
+    };
+    // Internal choice of the next event to process, between the queue and
+    // the expired timers for a `timeouts` order
+    (@next_event $self:ident, $event_type:ident; []; $clock:tt) => {
+        rustfsm!(@pop $self, $event_type)
+    };
+    (@next_event $self:ident, $event_type:ident; [$order:ident]; []) => {
+        compile_error!("a `timeouts` order needs a `Clock` section")
+    };
+    (@next_event $self:ident, $event_type:ident; [first]; [$clock_type:ty]) => {{
+        let now = <$clock_type as $crate::clock::Clock>::now();
+        rustfsm!(@expired $self, now).or_else(|| rustfsm!(@pop $self, $event_type))
+    }};
+    (@next_event $self:ident, $event_type:ident; [arrival]; [$clock_type:ty]) => {{
+        let now = <$clock_type as $crate::clock::Clock>::now();
+        // The timer wins a tie with the queued event
+        let timer_first = match (
+            $self.timers.peek_expired(now),
+            $crate::queue::Post::<$event_type>::posted_at(&$self.queue),
+        ) {
+            (Some((_, overdue)), Some(posted_at)) => overdue >= $crate::clock::elapsed(posted_at, now),
+            (timer, _) => timer.is_some(),
+        };
+        if timer_first {
+            rustfsm!(@expired $self, now)
+        } else {
+            rustfsm!(@pop $self, $event_type)
+        }
+    }};
+    (@next_event $self:ident, $event_type:ident; [priority]; [$clock_type:ty]) => {{
+        let now = <$clock_type as $crate::clock::Clock>::now();
+        // The timer wins a tie with the queued event
+        let timer_first = match (
+            $self.timers.peek_expired(now),
+            $crate::queue::Post::<$event_type>::peek(&$self.queue),
+        ) {
+            (Some((timer, _)), Some(queued)) => {
+                $crate::queue::Post::<$event_type>::priority(&$self.queue, timer)
+                    >= $crate::queue::Post::<$event_type>::priority(&$self.queue, queued)
+            }
+            (timer, _) => timer.is_some(),
+        };
+        if timer_first {
+            rustfsm!(@expired $self, now)
+        } else {
+            rustfsm!(@pop $self, $event_type)
+        }
+    }};
+
+    (@pop $self:ident, $event_type:ident) => {
+        $crate::queue::Post::<$event_type>::pop(&mut $self.queue)
+            .map(|event| ($crate::Source::Queued, event))
+    };
+    (@expired $self:ident, $now:ident) => {
+        $self.timers.expired($now).map(|event| ($crate::Source::Timer, event))
+    };
+
+    // Internal delivery of the expired timers by `tick()`, through the queue
+    // for a `timeouts` order
+    (@expired_timers $self:ident, $now:ident; []) => {
+        while let Some(event) = $self.timers.expired($now) {
+            $self.dispatch_in(event, $crate::Source::Timer, None)?;
+        }
+    };
+    (@expired_timers $self:ident, $now:ident; [$order:ident]) => {
+        $self.process_all()?;
+    };
+
+    (@watchdog_action $self:ident, $state_type:ident, $event_type:ident, $state:ident, fault) => {
+        Err(Self::raise_fault(
+            &mut $self.current_state,
+            &mut $self.last_fault,
+            &mut $self.metrics,
+            &mut $self.stay,
+            &mut $self.context,
+            $crate::Fault::Watchdog(stringify!($state)),
+        ))
+    };
+    (
+        @watchdog_action $self:ident, $state_type:ident, $event_type:ident, $state:ident,
+        $event:ident $(($($event_data:expr),*))?
+    ) => {
+        $self.dispatch($event_type::$event $(($($event_data),*))?)
+    };
+
+    // Internal generation of `post_after()`, for machines with a `Timers`
+    // section
+    (@post_after $event_type:ident; [$($clock_type:ty)?]; []) => {};
+    (@post_after $event_type:ident; [$clock_type:ty]; [$timer_slots:expr]) => {
+        /// Post `event` to be dispatched by [`tick`](Self::tick) once
+        /// `delay` ticks have elapsed.
+        ///
+        /// Fails if all the timers of the `Timers` section are pending.
+        pub fn post_after(
+            &mut self,
+            delay: u32,
+            event: $event_type,
+        ) -> Result<$crate::timer::TimerToken, $crate::timer::TimersFull> {
+            self.timers.post(<$clock_type as $crate::clock::Clock>::now(), delay, event)
+        }
+
+        /// Cancel the timer of `token`, returning whether it was still
+        /// pending.
+        pub fn cancel_timer(&mut self, token: $crate::timer::TimerToken) -> bool {
+            self.timers.cancel(token)
+        }
+
+        /// Cancel every pending timer.
+        pub fn cancel_timers(&mut self) {
+            self.timers.clear();
+        }
+
+        /// Get the pending timers.
+        pub fn timers(&self) -> &$crate::timer::Timers<$event_type, { $timer_slots }> {
+            &self.timers
+        }
+    };
+    (@post_after $event_type:ident; []; [$timer_slots:expr]) => {};
+    (@filter_type) => { () };
+    (@filter_type $filter_type:ty) => { $filter_type };
+    (@rng_type) => { () };
+    (@rng_type $rng_type:ty) => { $rng_type };
+    (@rng $self:ident;) => { None };
+    (@rng $self:ident; $rng_type:ty) => { Some(&mut $self.rng as &mut dyn $crate::rng::Rng) };
+    (@guard $self:ident, $state:ident, $event:ident, $context:ident;) => {
+        $state.guard($event, $context)
+    };
+    (@guard $self:ident, $state:ident, $event:ident, $context:ident; $rng_type:ty) => {
+        $state.guard_with_rng($event, $context, &mut $self.rng)
+    };
+    (@rng_mut) => {};
+    (@rng_mut $rng_type:ty) => {
+        /// Get a mutable reference to the random number source, to seed it.
+        pub fn rng_mut(&mut self) -> &mut $rng_type {
+            &mut self.rng
+        }
+    };
+    (@timer_slots) => { 0 };
+    (@timer_slots $timer_slots:expr) => { $timer_slots };
+
+    // Value of a variant, if it has no data
+    (@unit_variant $enum_type:ident::$variant:ident) => { Some($enum_type::$variant) };
+    (@unit_variant $enum_type:ident::$variant:ident($($variant_data:ty),*)) => { None };
+
+    // Write the fields of an event variant, binding a name to each of them.
+    // The `field` of every step is a distinct binding, each expansion having
+    // its own hygiene context, so there's no cap on the number of fields.
+    (@encode_fields $event:ident, $writer:ident, $event_type:ident::$event_variant:ident) => {};
+    (
+        @encode_fields $event:ident, $writer:ident,
+        $event_type:ident::$event_variant:ident($($event_variant_data:ty),*)
+    ) => {
+        rustfsm!(@encode_fields $event, $writer, $event_type::$event_variant;
+            [];
+            $($event_variant_data),*
+        )
+    };
+    (
+        @encode_fields $event:ident, $writer:ident, $event_type:ident::$event_variant:ident;
+        [$($bound:ident)*];
+    ) => {
+        if let $event_type::$event_variant($($bound),*) = $event {
+            $($writer.put($bound);)*
+        }
+    };
+    (
+        @encode_fields $event:ident, $writer:ident, $event_type:ident::$event_variant:ident;
+        [$($bound:ident)*];
+        $data:ty $(, $rest:ty)*
+    ) => {
+        rustfsm!(@encode_fields $event, $writer, $event_type::$event_variant;
+            [$($bound)* field];
+            $($rest),*
+        )
+    };
+
+    // Internal checks of the member names, which must leave the machine's
+    // own fields and the context fields alone, failing early with a message
+    // naming the offender rather than deep inside the generated code
+    (@check_members $members:tt; $($member_field:ident)*; $context_type:ident $($fields:tt)?) => {
+        $(
+            const _: () = assert!(
+                !$crate::names_contain($crate::MACHINE_FIELDS, stringify!($member_field)),
+                concat!("member `", stringify!($member_field), "` is reserved by the machine")
+            );
+        )*
+        $(rustfsm!(@check_fields $members; $fields);)?
+    };
+    (
+        @check_fields $members:tt;
+        { $($context_field:ident: $context_field_type:ty = $context_default:expr),* }
+    ) => {
+        $(
+            const _: () = assert!(
+                !$crate::names_contain(&$members, stringify!($context_field)),
+                concat!("context field `", stringify!($context_field), "` shadows a member")
+            );
+        )*
+    };
+
+    // Internal generation of the context struct. A context given by name
+    // only is an existing type, defined elsewhere and possibly shared with
+    // other state machines.
+    (@context []; $context_type:ident) => {};
+    (@context [$($changed_field:ident),+]; $context_type:ident) => {
+        compile_error!("a `Changes` section needs a context declared in the macro");
+    };
+    (@context []; $context_type:ident { $($fields:tt)* }) => {
+        $crate::rustfsm!(@context_items []; []; $context_type { $($fields)* });
+    };
+    (@context [$($changed_field:ident),+]; $context_type:ident { $($fields:tt)* }) => {
+        $crate::rustfsm!(@context_items [__changes]; [$($changed_field),+]; $context_type { $($fields)* });
+    };
+    (
+        @context_items [$($tracking:ident)?]; [$($changed_field:ident),*]; $context_type:ident {
+            $($context_field:ident: $context_field_type:ty = $context_default:expr),*
+        }
+    ) => {
+        /// State machine context data struct.
+        ///
+        /// The Context struct holds all the state's machine data common and
+        /// accessible to every state.
+        pub struct $context_type {
+            $(
+                $context_field: $context_field_type,
+            )*
+            // Fields of the `Changes` section set since the last
+            // `take_changes()`, one bit each
+            $($tracking: u32,)?
+        }
+
+        // Implement Default trait for the Context.
+        impl Default for $context_type {
+            fn default() -> Self {
+                Self {
+                    $(
+                        $context_field: $context_default,
+                    )*
+                    $($tracking: 0,)?
+                }
+            }
+        }
+
+        // Implement Debug by hand, leaving the change tracking out.
+        impl core::fmt::Debug for $context_type {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.debug_struct(stringify!($context_type))
+                    $(.field(stringify!($context_field), &self.$context_field))*
+                    .finish()
+            }
+        }
+
+        impl $context_type {
+            $(
+                #[doc = concat!("Get the `", stringify!($context_field), "` field.")]
+                pub fn $context_field(&self) -> &$context_field_type {
+                    &self.$context_field
+                }
+            )*
+        }
+
+        // Implement Clone for the Context when all of its fields are Clone.
+        // The higher-ranked bounds keep this impl from failing to compile
+        // when they don't hold.
+        impl Clone for $context_type
+        where
+            $(for<'a> $context_field_type: Clone,)*
+        {
+            fn clone(&self) -> Self {
+                Self {
+                    $(
+                        $context_field: self.$context_field.clone(),
+                    )*
+                    $($tracking: self.$tracking,)?
+                }
+            }
+        }
+
+        // Implement PartialEq for the Context when all of its fields are
+        // PartialEq.
+        impl PartialEq for $context_type
+        where
+            $(for<'a> $context_field_type: PartialEq,)*
+        {
+            fn eq(&self, _other: &Self) -> bool {
+                true $(&& self.$context_field == _other.$context_field)*
+            }
+        }
+
+        $crate::__rustfsm_serde!(@struct $context_type {
+            $($context_field: $context_field_type),*
+        });
+        $crate::__rustfsm_diff!(@context $context_type {
+            $($context_field: $context_field_type),*
+        });
+        $crate::rustfsm!(@changes $context_type { $($context_field: $context_field_type),* };
+            [$(stringify!($context_field)),*]; $($changed_field),*
+        );
+    };
+
+    // Internal generation of the setters of the context fields listed in the
+    // `Changes` section, marking them changed
+    (@changes $context_type:ident $fields:tt; $names:tt; ) => {};
+    (
+        @changes $context_type:ident { $($context_field:ident: $context_field_type:ty),* };
+        $names:tt; $($changed_field:ident),+
+    ) => {
+        const _: () = assert!(
+            [$(stringify!($changed_field)),+].len() <= 32,
+            "a `Changes` section tracks at most 32 fields"
+        );
+        $(
+            const _: () = assert!(
+                $crate::names_contain(&$names, stringify!($changed_field)),
+                concat!("`Changes` field `", stringify!($changed_field), "` isn't a context field")
+            );
+        )+
+
+        $crate::paste::paste! {
+            // Types of the context fields, by field name, and bits of the
+            // tracked ones
+            #[doc(hidden)]
+            #[allow(dead_code, non_camel_case_types)]
+            mod [<__ $context_type:snake _changes>] {
+                #[allow(unused_imports)]
+                use super::*;
+                $(pub type $context_field = $context_field_type;)*
+                pub enum Bit {
+                    $($changed_field),+
+                }
+            }
+
+            impl $context_type {
+                $(
+                    #[doc = concat!(
+                        "Set the `", stringify!($changed_field), "` field, marking it changed."
+                    )]
+                    pub fn [<set_ $changed_field>](
+                        &mut self,
+                        value: [<__ $context_type:snake _changes>]::$changed_field,
+                    ) {
+                        self.$changed_field = value;
+                        self.__changes |= 1 << [<__ $context_type:snake _changes>]::Bit::$changed_field as u32;
+                    }
+                )+
+
+                /// Fields set since the last call, see the
+                /// [`changes`]($crate::changes) module.
+                pub fn take_changes(&mut self) -> $crate::changes::Changes {
+                    $crate::changes::Changes::new(
+                        core::mem::take(&mut self.__changes),
+                        &[$(stringify!($changed_field)),+],
+                    )
+                }
+            }
+        }
+    };
+
+    // Internal generation of the per-state context views, for contexts
+    // declared in the macro
+    (@views $state_type:ident; $context_type:ident $({ $($context:tt)* })?;) => {};
+    (
+        @views $state_type:ident; $context_type:ident {
+            $($context_field:ident: $context_field_type:ty = $context_default:expr),*
+        };
+        $($view_state:ident { $($view_field:ident),* })+
+    ) => {
+        $crate::paste::paste! {
+            // Types of the context fields, by field name
+            #[doc(hidden)]
+            #[allow(non_camel_case_types)]
+            mod [<__ $context_type:snake _fields>] {
+                #[allow(unused_imports)]
+                use super::*;
+                $(pub type $context_field = $context_field_type;)*
+            }
+
+            $(
+                #[doc = concat!(
+                    "Fields of the context the `", stringify!($view_state), "` state has access to"
+                )]
+                pub struct [<$view_state View>]<'a> {
+                    $(pub $view_field: &'a mut [<__ $context_type:snake _fields>]::$view_field,)*
+                }
+            )+
+
+            impl $context_type {
+                $(
+                    #[doc = concat!("Get the view of the `", stringify!($view_state), "` state.")]
+                    pub fn [<$view_state:snake _view>](&mut self) -> [<$view_state View>]<'_> {
+                        const _: fn(&$state_type) -> bool =
+                            |state| matches!(state, $state_type::$view_state { .. });
+                        [<$view_state View>] {
+                            $($view_field: &mut self.$view_field,)*
+                        }
+                    }
+                )+
+            }
+        }
+    };
+    (@views $state_type:ident; $context_type:ident; $($views:tt)+) => {
+        compile_error!("a `Views` section needs a context declared in the macro");
+    };
+
+    // Internal generation of the state behavior from the transition table,
+    // with the `behavior` option of the `Dispatch` section
+    (@behavior $state_type:ident, $event_type:ident, $context_type:ident; [] { }) => {};
+    (
+        @behavior $state_type:ident, $event_type:ident, $context_type:ident; [] { {
+            $(
+                $from_state:tt + $event:pat => $target:ident $(if $guard:ident)? $(/ $action:ident)?
+                [$($rule_name:ident)?] [$($min_interval:expr)?]
+            ),*
+            $(,)?
+        } }
+    ) => {
+        $($(rustfsm!(@behavior_needed $guard);)? $(rustfsm!(@behavior_needed $action);)?)*
+    };
+    (@behavior $state_type:ident, $event_type:ident, $context_type:ident; [behavior] { }) => {
+        compile_error!("the `behavior` option needs a `Transitions` section");
+    };
+    (
+        @behavior $state_type:ident, $event_type:ident, $context_type:ident; [behavior] { {
+            $(
+                $from_state:tt + $event:pat => $target:ident $(if $guard:ident)? $(/ $action:ident)?
+                [$($rule_name:ident)?] [$($min_interval:expr)?]
+            ),*
+            $(,)?
+        } }
+    ) => {
+        impl $crate::StateBehavior for $state_type {
+            type State = $state_type;
+            type Event = $event_type;
+            type Context = $context_type;
+
+            fn handle(&self, event: &$event_type, context: &mut $context_type) -> Option<$state_type> {
+                // Actions of the rules, in declaration order
+                const ACTIONS: &[Option<fn(&mut $context_type, &$event_type)>] =
+                    &[$(rustfsm!(@rule_fn fn(&mut $context_type, &$event_type); $($action)?)),*];
+                let rule = self
+                    .table_rule(event)
+                    .and_then(|rule| $crate::table::rule_index($state_type::TRANSITION_TABLE, rule));
+                if let Some(action) = rule.and_then(|index| ACTIONS.get(index).copied().flatten()) {
+                    action(context, event);
+                }
+                self.next_state(event)
+            }
+
+            fn guard(&self, event: &$event_type, context: &$context_type) -> bool {
+                // Guards of the rules, in declaration order
+                const GUARDS: &[Option<fn(&$context_type, &$event_type) -> bool>] =
+                    &[$(rustfsm!(@rule_fn fn(&$context_type, &$event_type) -> bool; $($guard)?)),*];
+                let rule = self
+                    .table_rule(event)
+                    .and_then(|rule| $crate::table::rule_index($state_type::TRANSITION_TABLE, rule));
+                match rule.and_then(|index| GUARDS.get(index).copied().flatten()) {
+                    Some(guard) => guard(context, event),
+                    None => true,
+                }
+            }
+        }
+    };
+    (@behavior_needed $function:ident) => {
+        compile_error!(concat!(
+            "`", stringify!($function),
+            "`: guards and actions of the transition table need `Dispatch { behavior }`"
+        ));
+    };
+
+    // Internal guard or action function of a transition table rule, if any
+    (@rule_fn $fn_type:ty;) => { None };
+    (@rule_fn $fn_type:ty; $function:ident) => { Some($function as $fn_type) };
+
+    // Internal target of a transition table rule
+    (@table_target $state_type:ident, ignore) => { None };
+    (@table_target $state_type:ident, $to_state:ident) => { Some($state_type::$to_state) };
+
+    // Internal initial state, given by the `Initial` section or the first
+    // one listed
+    (@initial $state_type:ident, $first_state:ident;) => { $state_type::$first_state };
+    (@initial $state_type:ident, $first_state:ident; $state:ident $(($($data:expr),*))?) => {
+        $state_type::$state $(($($data),*))?
+    };
+
+    // Internal name of a transition table rule, if given
+    (@rule_name) => { None };
+    (@rule_name $name:ident) => { Some(stringify!($name)) };
+
+    // Internal minimum time in the source state of a transition table rule,
+    // if given
+    (@min_interval) => { 0 };
+    (@min_interval $min_interval:expr) => { $min_interval };
+
+    // Internal rule of the transition table, by state index
+    (@table_rule $index:ident, _, $event:pat, ignore; $($name:ident)?; $($min_interval:expr)?) => {
+        $crate::table::TableRule {
+            from: $crate::table::ANY_STATE,
+            event: stringify!($event),
+            to: None,
+            name: rustfsm!(@rule_name $($name)?),
+            min_interval: rustfsm!(@min_interval $($min_interval)?),
+        }
+    };
+    (@table_rule $index:ident, _, $event:pat, $to_state:ident; $($name:ident)?; $($min_interval:expr)?) => {
+        $crate::table::TableRule {
+            from: $crate::table::ANY_STATE,
+            event: stringify!($event),
+            to: Some($index::$to_state as usize),
+            name: rustfsm!(@rule_name $($name)?),
+            min_interval: rustfsm!(@min_interval $($min_interval)?),
+        }
+    };
+    (@table_rule $index:ident, $from_state:ident, $event:pat, ignore; $($name:ident)?; $($min_interval:expr)?) => {
+        $crate::table::TableRule {
+            from: $index::$from_state as usize,
+            event: stringify!($event),
+            to: None,
+            name: rustfsm!(@rule_name $($name)?),
+            min_interval: rustfsm!(@min_interval $($min_interval)?),
+        }
+    };
+    (@table_rule $index:ident, $from_state:ident, $event:pat, $to_state:ident; $($name:ident)?; $($min_interval:expr)?) => {
+        $crate::table::TableRule {
+            from: $index::$from_state as usize,
+            event: stringify!($event),
+            to: Some($index::$to_state as usize),
+            name: rustfsm!(@rule_name $($name)?),
+            min_interval: rustfsm!(@min_interval $($min_interval)?),
+        }
+    };
+
+    // Internal list of the events without data
+    (@unit_events $event_type:ident [$($unit_event:ident)*];) => {
+        &[$($event_type::$unit_event),*]
+    };
+    (
+        @unit_events $event_type:ident [$($unit_event:ident)*];
+        $event_variant:ident ($($event_variant_data:ty),*), $($rest:tt)*
+    ) => {
+        rustfsm!(@unit_events $event_type [$($unit_event)*]; $($rest)*)
+    };
+    (
+        @unit_events $event_type:ident [$($unit_event:ident)*];
+        $event_variant:ident, $($rest:tt)*
+    ) => {
+        rustfsm!(@unit_events $event_type [$($unit_event)* $event_variant]; $($rest)*)
+    };
+
+    // Internal source state pattern of a transition table rule, `_` for any
+    // state
+    (@from_pattern $state_type:ident, _) => { _ };
+    (@from_pattern $state_type:ident, $from_state:ident) => { $state_type::$from_state { .. } };
+
+    // Internal sorting of the transition table rules, moving the rules for
+    // any state after the others so that per-state rules take precedence.
+    // Rules are moved a run at a time.
+    (@sort_rules { $($args:tt)* } [$($explicit:tt)*] [$($any:tt)*];) => {
+        rustfsm!(@transition_table $($args)*, sorted { $($explicit)* $($any)* });
+    };
+    (
+        @sort_rules $args:tt [$($explicit:tt)*] [$($any:tt)*];
+        $(
+            $from_state:ident + $event:pat => $target:ident
+            [$($name:ident)?] [$($min_interval:expr)?],
+        )+
+        $(_ + $($rest:tt)*)?
+    ) => {
+        rustfsm!(@sort_rules $args
+            [$($explicit)* $(($from_state + $event => $target [$($name)?] [$($min_interval)?]))+]
+            [$($any)*];
+            $(_ + $($rest)*)?
+        );
+    };
+    (
+        @sort_rules $args:tt [$($explicit:tt)*] [$($any:tt)*];
+        $(_ + $event:pat => $target:ident [$($name:ident)?] [$($min_interval:expr)?],)+
+        $($from_state:ident + $($rest:tt)*)?
+    ) => {
+        rustfsm!(@sort_rules $args
+            [$($explicit)*] [$($any)* $((_ + $event => $target [$($name)?] [$($min_interval)?]))+];
+            $($from_state + $($rest)*)?
+        );
+    };
+
+    // Internal generation of the transition table lookup. The match has no
+    // catch-all arm, so the compiler rejects any (state, event) pair that is
+    // neither mapped nor explicitly ignored.
+    (
+        @transition_table $state_type:ident, $event_type:ident,
+        states [$($state_variant:ident)*],
+        initial_state = $initial_state:ident,
+        dispatch [],
+        table { }
+    ) => {
+        impl $state_type {
+            /// Rules of the transition table, empty without a `Transitions`
+            /// section.
+            pub const TRANSITION_TABLE: &'static [$crate::table::TableRule] = &[];
+
+            /// Rule of the transition table matching an event, if any.
+            pub fn table_rule(&self, _event: &$event_type) -> Option<&'static $crate::table::TableRule> {
+                None
+            }
+        }
+    };
+    (
+        @transition_table $state_type:ident, $event_type:ident,
+        states [$($state_variant:ident)*],
+        initial_state = $initial_state:ident,
+        dispatch [$($dispatch:tt)*],
+        table { {
+            $(
+                $from_state:tt + $event:pat => $target:ident $(if $guard:ident)? $(/ $action:ident)?
+                [$($rule_name:ident)?] [$($min_interval:expr)?]
+            ),*
+            $(,)?
+        } }
+    ) => {
+        rustfsm!(@sort_rules {
+                $state_type, $event_type,
+                states [$($state_variant)*],
+                initial_state = $initial_state,
+                dispatch [$($dispatch)*],
+                rules { $($from_state + $event => $target [$($rule_name)?] [$($min_interval)?]),* }
+            } [] [];
+            $($from_state + $event => $target [$($rule_name)?] [$($min_interval)?],)*
+        );
+    };
+    (
+        @transition_table $state_type:ident, $event_type:ident,
+        states [$($state_variant:ident)*],
+        initial_state = $initial_state:ident,
+        dispatch [$($dispatch:tt)*],
+        rules {
+            $(
+                $from_state:tt + $event:pat => $target:ident
+                [$($rule_name:ident)?] [$($min_interval:expr)?]
+            ),*
+        },
+        sorted {
+            $((
+                $sorted_from_state:tt + $sorted_event:pat => $sorted_target:ident
+                [$($sorted_rule_name:ident)?] [$($sorted_min_interval:expr)?]
+            ))*
+        }
+    ) => {
+        impl $state_type {
+            /// Rules of the transition table, in declaration order.
+            pub const TRANSITION_TABLE: &'static [$crate::table::TableRule] = {
+                #[allow(dead_code)]
+                enum Index {
+                    $($state_variant),*
+                }
+                &[$(rustfsm!(@table_rule Index, $from_state, $event, $target;
+                    $($rule_name)?; $($min_interval)?
+                )),*]
+            };
+
+            /// Names of the states that can't be reached from the initial
+            /// state by following the transition table.
+            ///
+            /// Assert it is empty in a constant to turn dead states into a
+            /// compile error:
+            ///
+            /// ```rust,ignore
+            /// const _: () = assert!(FooStates::UNREACHABLE_STATES.is_empty());
+            /// ```
+            pub const UNREACHABLE_STATES: &'static [&'static str] = {
+                const N: usize = $state_type::NAMES.len();
+                const UNREACHABLE: ([&str; N], usize) = $crate::table::unreachable_names::<N>(
+                    $state_type::NAMES,
+                    $crate::table::reachable(
+                        $state_type::INITIAL.index(),
+                        $state_type::TRANSITION_TABLE,
+                    ),
+                );
+                UNREACHABLE.0.split_at(UNREACHABLE.1).0
+            };
+
+            /// Rule of the transition table matching an event, if any.
+            // Event groups are parenthesized or-patterns
+            #[allow(unused_parens)]
+            pub fn table_rule(&self, event: &$event_type) -> Option<&'static $crate::table::TableRule> {
+                #[allow(dead_code)]
+                enum Index {
+                    $($state_variant),*
+                }
+                #[allow(unused_imports)]
+                use $event_type::*;
+                match (self, event) {
+                    $(
+                        (rustfsm!(@from_pattern $state_type, $sorted_from_state), $sorted_event) => {
+                            // In a constant block for the interval to be
+                            // promoted along with the rule
+                            Some(&const {
+                                rustfsm!(@table_rule Index,
+                                    $sorted_from_state, $sorted_event, $sorted_target;
+                                    $($sorted_rule_name)?; $($sorted_min_interval)?
+                                )
+                            })
+                        }
+                    )*
+                }
+            }
+
+            /// Look up the next state for an event in the transition table.
+            ///
+            /// Returns `None` for pairs marked `ignore`.
+            #[allow(unused_parens)]
+            pub fn next_state(&self, event: &$event_type) -> Option<Self> {
+                rustfsm!(@next_state self, event, $state_type, $event_type,
+                    dispatch [$($dispatch)*],
+                    states [$($state_variant)*],
+                    rules { $($sorted_from_state + $sorted_event => $sorted_target),* }
+                )
+            }
+
+            /// Whether the transition table maps the event to a transition in
+            /// this state, rather than to `ignore`.
+            pub fn accepts(&self, event: &$event_type) -> bool {
+                self.next_state(event).is_some()
+            }
+
+            /// Whether the transition table marks the event `ignore` in this
+            /// state, leading nowhere on purpose rather than forgotten.
+            pub fn ignores(&self, event: &$event_type) -> bool {
+                self.table_rule(event).is_some_and(|rule| rule.to.is_none())
+            }
+
+            /// Events without data the transition table maps to a transition
+            /// in this state, for instance to offer only the valid commands.
+            pub fn legal_events(&self) -> impl Iterator<Item = &'static $event_type> {
+                let state = *self;
+                $event_type::UNIT_EVENTS
+                    .iter()
+                    .filter(move |event| state.accepts(event))
+            }
+
+            /// Rules of the transition table leading out of this state, see
+            /// [`transitions_from`]($crate::table::transitions_from).
+            pub fn transitions_from(&self) -> impl Iterator<Item = &'static $crate::table::TableRule> {
+                $crate::table::transitions_from($state_type::TRANSITION_TABLE, self.index())
+            }
+        }
+
+        $crate::__rustfsm_reachability!($state_type);
+    };
+    (
+        @transition_table $state_type:ident, $event_type:ident,
+        states [$($state_variant:ident)*],
+        initial_state = $initial_state:ident,
+        dispatch [$($dispatch:tt)*],
+        table { }
+    ) => {
+        compile_error!("`Dispatch` options need a `Transitions` section");
+    };
+
+    // Internal lookup of the next state in the transition table, through a
+    // nested match by default
+    (
+        @next_state $state:ident, $event_value:ident, $state_type:ident, $event_type:ident,
+        dispatch [],
+        states [$($state_variant:ident)*],
+        rules { $($from_state:tt + $event:pat => $target:ident),* }
+    ) => {{
+        #[allow(unused_imports)]
+        use $event_type::*;
+        match ($state, $event_value) {
+            $(
+                (rustfsm!(@from_pattern $state_type, $from_state), $event) => {
+                    rustfsm!(@table_target $state_type, $target)
+                }
+            )*
+        }
+    }};
+    // or through a table of per-state functions indexed by the state, which
+    // lowers better than large matches on small cores
+    (
+        @next_state $state:ident, $event_value:ident, $state_type:ident, $event_type:ident,
+        dispatch [jump_table],
+        states [$($state_variant:ident)*],
+        rules $rules:tt
+    ) => {{
+        #[allow(dead_code, clippy::enum_variant_names)]
+        enum Tag {
+            $($state_variant),*
+        }
+        rustfsm!(@jump_handlers $state_type, $event_type, $rules; $($state_variant)*);
+        $crate::paste::paste! {
+            const HANDLERS: [fn(&$event_type) -> Option<$state_type>; $state_type::NAMES.len()] =
+                [$([<next_from_ $state_variant:snake>]),*];
+        }
+        match HANDLERS.get($state.index()) {
+            Some(handler) => handler($event_value),
+            None => None,
+        }
+    }};
+    (
+        @next_state $state:ident, $event_value:ident, $state_type:ident, $event_type:ident,
+        dispatch [$($dispatch:tt)*],
+        states [$($state_variant:ident)*],
+        rules $rules:tt
+    ) => {
+        compile_error!(concat!("unknown `Dispatch` option `", stringify!($($dispatch)*), "`"))
+    };
+
+    // Internal generation of the jump table functions, one state at a time
+    (@jump_handlers $state_type:ident, $event_type:ident, $rules:tt;) => {};
+    (
+        @jump_handlers $state_type:ident, $event_type:ident, $rules:tt;
+        $state_variant:ident $($remaining_states:ident)*
+    ) => {
+        rustfsm!(@jump_handler $state_type, $event_type, $state_variant, $rules);
+        rustfsm!(@jump_handlers $state_type, $event_type, $rules; $($remaining_states)*);
+    };
+    (
+        @jump_handler $state_type:ident, $event_type:ident, $state_variant:ident,
+        { $($from_state:tt + $event:pat => $target:ident),* }
+    ) => {
+        $crate::paste::paste! {
+            // The rules of the other states are trimmed at compile time
+            fn [<next_from_ $state_variant:snake>](event: &$event_type) -> Option<$state_type> {
+                #[allow(unused_imports)]
+                use $event_type::*;
+                #[allow(unreachable_patterns)]
+                match (Tag::$state_variant, event) {
+                    $(
+                        (rustfsm!(@from_pattern Tag, $from_state), $event) => {
+                            rustfsm!(@table_target $state_type, $target)
+                        }
+                    )*
+                    _ => None,
+                }
+            }
+        }
+    };
+
+    // Internal implementation for generating the state machine
+    (
+        @generate $state_machine_name:ident, $state_type:ident, $event_type:ident, $context_type:ident,
+        states { $($(@$state_marker:ident)* $state_variant:ident $(($($state_variant_data:ty),*))? ),* },
+        events { $($(@$event_marker:ident)* $event_variant:ident $(($($event_variant_data:ty),*))? ),* },
+        context { $($context:tt)* },
+        members { $($member_field:ident: $member_field_type:ty = $member_default:expr),* },
+        initial_state = $initial_state:ident,
+        transitions { $($transitions:tt)* },
+        clock { $clock_type:ty; $($timed_clock:ty)? },
+        trace { $trace_depth:tt },
+        watchdog { $($watchdog:tt)* },
+        timers { $($timer_slots:expr)? },
+        filter { $($filter_type:ty)? },
+        interceptors { $($interceptor_type:ty),* },
+        rng { $($rng_type:ty)? },
+        derive { $($event_derive:ident),+ },
+        aliases { $($alias:ident = $alias_event:expr),* },
+        queue { $queue_depth:tt $(; $queue_priority:expr)? },
+        views { $($view_state:ident { $($view_field:ident),* })* },
+        changes { $($changed_field:ident),* },
+        output { $($output_type:ty)? },
+        reply { $($reply_type:ty)? },
+        hibernate { $($hibernate_field:ident),* },
+        invariant { $($invariant_check:expr; [$($invariant_violation:ident)?])? },
+        validate { $($validate_check:expr)? },
+        initial { $($initial:tt)* },
+        dispatch {
+            lookup [$($lookup:ident)?] inline [$($inline:meta)?] notify [$($notify:ident)?]
+            unhandled [$($unhandled:ident)?] behavior [$($behavior:ident)?]
+            timeouts [$($timeouts:ident)?] pause [$($pausable:ident)?] tagged [$($tagged:ident)?]
+        }
+    ) => {
+        /// State machine state type.
+        ///
+        /// - The first state in the list is the state machine's initial state.
+        #[derive(Clone, Copy, PartialEq, Debug)]
+        pub enum $state_type {
+            $(
+                $state_variant $(($($state_variant_data),*))?
+            ),*
+        }
+
+        $($(rustfsm!(@state_marker $state_marker);)*)*
+
+        impl $state_type {
+            /// Names of all states, in declaration order.
+            pub const NAMES: &'static [&'static str] = &[$(stringify!($state_variant)),*];
+
+            /// Position of the state in the declaration order.
+            pub const fn index(&self) -> usize {
+                #[allow(dead_code)]
+                enum Index {
+                    $($state_variant),*
+                }
+                match self {
+                    $(
+                        $state_type::$state_variant { .. } => Index::$state_variant as usize,
+                    )*
+                }
+            }
+
+            /// Name of the state.
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(
+                        $state_type::$state_variant { .. } => stringify!($state_variant),
+                    )*
+                }
+            }
+
+            /// Whether this state is a final state, marked with `@final`.
+            pub fn is_final(&self) -> bool {
+                match self {
+                    $(
+                        $state_type::$state_variant { .. } => rustfsm!(@is_final $($state_marker)*),
+                    )*
+                }
+            }
         }
 
         /// State machine event type.
         ///
         /// List of all events handled by the state machine.
-        #[derive(Clone, Copy, PartialEq, Debug)]
+        #[derive($($event_derive),+)]
         pub enum $event_type {
             $(
                 $event_variant $(($($event_variant_data),*))?
             ),*
         }
 
-        /// State machine context data struct.
-        ///
-        /// The Context struct holds all the state's machine data common and
-        /// accessible to every state.
-        #[derive(Debug)]
-        pub struct $context_type {
-            $(
-                $context_field: $context_field_type,
-            )*
-        }
+        rustfsm!(@commands $state_machine_name, $event_type, [$($event_derive),+]; [] [];
+            $([$($event_marker)*] $event_variant $(($($event_variant_data),*))?;)*
+        );
 
-        // Implement Default trait for the Context.
-        impl Default for $context_type {
-            fn default() -> Self {
-                Self {
+        $crate::__rustfsm_arbitrary!($state_type {
+            $($state_variant $(($($state_variant_data),*))?),*
+        });
+        $crate::__rustfsm_arbitrary!($event_type {
+            $($event_variant $(($($event_variant_data),*))?),*
+        });
+        $crate::__rustfsm_serde!(@enum $state_type {
+            $($state_variant $(($($state_variant_data),*))?),*
+        });
+        $crate::__rustfsm_serde!(@enum $event_type {
+            $($event_variant $(($($event_variant_data),*))?),*
+        });
+
+        impl $event_type {
+            /// Names of all events, in declaration order.
+            pub const NAMES: &'static [&'static str] = &[$(stringify!($event_variant)),*];
+
+            /// Events without data, in declaration order.
+            pub const UNIT_EVENTS: &'static [$event_type] = rustfsm!(@unit_events $event_type [];
+                $($event_variant $(($($event_variant_data),*))?,)*
+            );
+
+            /// Name of the event.
+            pub fn name(&self) -> &'static str {
+                match self {
                     $(
-                        $context_field: $context_default,
+                        $event_type::$event_variant { .. } => stringify!($event_variant),
                     )*
                 }
             }
+
+            $(
+                #[doc = concat!("Alias of `", stringify!($alias_event), "`.")]
+                #[allow(non_upper_case_globals)]
+                pub const $alias: $event_type = {
+                    #[allow(unused_imports)]
+                    use $event_type::*;
+                    $alias_event
+                };
+            )*
+
+            /// Encode the event as a wire frame in `buf`, see the
+            /// [`codec`]($crate::codec) module.
+            ///
+            /// Returns the length of the frame, or 0 if it doesn't fit in
+            /// `buf`.
+            #[allow(irrefutable_let_patterns)]
+            pub fn encode(&self, buf: &mut [u8]) -> usize
+            where
+                $($($(for<'a> $event_variant_data: $crate::codec::Wire,)*)?)*
+            {
+                #[allow(dead_code)]
+                enum Index {
+                    $($event_variant),*
+                }
+                let mut writer = $crate::codec::Writer::new(buf);
+                writer.put(&$crate::codec::VERSION);
+                $(
+                    if let $event_type::$event_variant { .. } = self {
+                        writer.put(&(Index::$event_variant as u8));
+                        rustfsm!(@encode_fields self, writer,
+                            $event_type::$event_variant $(($($event_variant_data),*))?
+                        );
+                    }
+                )*
+                writer.finish()
+            }
+
+            /// Decode an event from a wire frame, see the
+            /// [`codec`]($crate::codec) module.
+            pub fn decode(frame: &[u8]) -> Result<Self, $crate::codec::DecodeError>
+            where
+                $($($(for<'a> $event_variant_data: $crate::codec::Wire,)*)?)*
+            {
+                #[allow(dead_code)]
+                enum Index {
+                    $($event_variant),*
+                }
+                let mut reader = $crate::codec::Reader::new(frame);
+                let version: u8 = reader.take()?;
+                if version != $crate::codec::VERSION {
+                    return Err($crate::codec::DecodeError::UnsupportedVersion(version));
+                }
+                let id: u8 = reader.take()?;
+                $(
+                    if id == Index::$event_variant as u8 {
+                        let event = $event_type::$event_variant $(($(
+                            reader.take::<$event_variant_data>()?
+                        ),*))?;
+                        return reader.finish(event);
+                    }
+                )*
+                Err($crate::codec::DecodeError::UnknownEvent(id))
+            }
+        }
+
+        impl $state_type {
+            /// State the machine is created and reset in, the first one
+            /// listed unless an `Initial` section gives it.
+            pub const INITIAL: $state_type = rustfsm!(@initial $state_type, $initial_state; $($initial)*);
         }
 
+        rustfsm!(@context [$($changed_field),*]; $($context)*);
+        rustfsm!(@check_members [$(stringify!($member_field)),*]; $($member_field)*; $($context)*);
+        rustfsm!(@views $state_type; $($context)*; $($view_state { $($view_field),* })*);
+
+        rustfsm!(@transition_table $state_type, $event_type,
+            states [$($state_variant)*],
+            initial_state = $initial_state,
+            dispatch [$($lookup)?],
+            table { $($transitions)* }
+        );
+        rustfsm!(@behavior $state_type, $event_type, $context_type;
+            [$($behavior)?] { $($transitions)* }
+        );
+
         /// State machine struct.
         pub struct $state_machine_name {
             current_state: $state_type,
             context: $context_type,
+            status: $crate::Status,
+            paused: rustfsm!(@pause_type [$($pausable)?] $($timed_clock)?),
+            source: rustfsm!(@source_type [$($tagged)?]),
+            last_fault: Option<$crate::Fault>,
+            coverage: $crate::coverage::Coverage<{ $state_type::TRANSITION_TABLE.len() }>,
+            trace: rustfsm!(@trace_type $state_type, $event_type; $trace_depth),
+            metrics: $crate::metrics::Metrics<{ $state_type::NAMES.len() }>,
+            stay: rustfsm!(@stay_type $($timed_clock)?),
+            queue: rustfsm!(@queue_type $event_type; $queue_depth),
+            output: rustfsm!(@output_type [$($output_type)?] [$($reply_type)?]),
+            guard: $crate::DispatchGuard,
+            timers: $crate::timer::Timers<$event_type, { rustfsm!(@timer_slots $($timer_slots)?) }>,
+            filter: rustfsm!(@filter_type $($filter_type)?),
+            interceptors: ($($interceptor_type,)*),
+            rng: rustfsm!(@rng_type $($rng_type)?),
             $(
                 $member_field: $member_field_type,
             )*
@@ -187,27 +3953,110 @@ macro_rules! rustfsm {
 
         impl $state_machine_name {
             /// Create a new state machine.
+            ///
+            /// The initial state's `enter` function is **not** run. Use
+            /// [`new_started`](Self::new_started) or call
+            /// [`start`](Self::start) if its entry side effects are needed.
             pub fn new() -> Self {
                 Self {
-                    current_state: $state_type::$initial_state,
+                    current_state: $state_type::INITIAL,
                     context: $context_type::default(),
+                    status: $crate::Status::Idle,
+                    paused: Default::default(),
+                    source: Default::default(),
+                    last_fault: None,
+                    coverage: $crate::coverage::Coverage::new(),
+                    trace: <rustfsm!(@trace_type $state_type, $event_type; $trace_depth)>::new(),
+                    metrics: $crate::metrics::Metrics::new(),
+                    stay: rustfsm!(@stay $($timed_clock)?),
+                    timers: $crate::timer::Timers::new(),
+                    filter: Default::default(),
+                    interceptors: Default::default(),
+                    rng: Default::default(),
+                    queue: rustfsm!(@queue_new $event_type; [$($timed_clock)?]; $queue_depth $(; $queue_priority)?),
+                    output: Default::default(),
+                    guard: $crate::DispatchGuard::new(),
                     $(
                         $member_field: $member_default,
                     )*
                 }
             }
 
+            /// Create a new state machine in `state` with `context`, as an
+            /// alternative entry point.
+            ///
+            /// Like [`new`](Self::new), `enter` is not run. Resetting the
+            /// machine still goes back to its initial state.
+            pub fn new_at(state: $state_type, context: $context_type) -> Self {
+                let mut state_machine = Self::new();
+                state_machine.current_state = state;
+                state_machine.context = context;
+                state_machine
+            }
+
+            /// Create a new state machine and start it, running the initial
+            /// state's `enter` function.
+            pub fn new_started() -> Self {
+                let mut state_machine = Self::new();
+                state_machine.start();
+                state_machine
+            }
+
             /// Transition to a new state.
             pub fn transition(&mut self, new_state: $state_type) {
                 self.current_state.exit(&mut self.context);
+                self.current_state.on_any_exit(&mut self.context);
+                self.metrics.transition(
+                    self.current_state.index(),
+                    new_state.index(),
+                    <$clock_type as $crate::clock::Clock>::now,
+                );
                 self.current_state = new_state;
+                self.stay = rustfsm!(@stay $($timed_clock)?);
+                self.current_state.on_any_enter(&mut self.context);
                 self.current_state.enter(&mut self.context);
             }
 
             /// Force transition to a new state without calls to respectives
             /// `enter` and `exit` functions.
+            ///
+            /// Any state is accepted, which makes it the escape hatch of
+            /// recovery code. See
+            /// [`force_state_checked`](Self::force_state_checked) for a
+            /// validated variant. The time in state restarts from the forced
+            /// state.
             pub fn force_state(&mut self, new_state: $state_type) {
                 self.current_state = new_state;
+                self.stay = rustfsm!(@stay $($timed_clock)?);
+            }
+
+            /// Force transition to a new state, running `exit` and `enter`
+            /// or not depending on `mode`.
+            ///
+            /// With a `Transitions` section, the forced state must be
+            /// reachable from the current one by following the table, or
+            /// [`ForceError::Unreachable`]($crate::ForceError::Unreachable)
+            /// is returned and nothing changes.
+            pub fn force_state_checked(
+                &mut self,
+                new_state: $state_type,
+                mode: $crate::ForceMode,
+            ) -> Result<(), $crate::ForceError> {
+                let table = $state_type::TRANSITION_TABLE;
+                if !table.is_empty() && new_state.index() != self.current_state.index() {
+                    let reachable = $crate::table::reachable::<{ $state_type::NAMES.len() }>(
+                        self.current_state.index(),
+                        table,
+                    );
+                    if reachable.get(new_state.index()) != Some(&true) {
+                        return Err($crate::ForceError::Unreachable);
+                    }
+                }
+                match mode {
+                    $crate::ForceMode::Silent => self.force_state(new_state),
+                    $crate::ForceMode::RunHooks => self.transition(new_state),
+                }
+                Ok(())
             }
 
             /// Get a copy of the current state
@@ -215,15 +4064,959 @@ macro_rules! rustfsm {
                 self.current_state
             }
 
+            /// Get a reference to the context
+            pub fn context(&self) -> &$context_type {
+                &self.context
+            }
+
+            /// Get a mutable reference to the context
+            pub fn context_mut(&mut self) -> &mut $context_type {
+                &mut self.context
+            }
+
+            /// Get the lifecycle status
+            pub fn status(&self) -> $crate::Status {
+                self.status
+            }
+
+            /// Health snapshot of the machine for telemetry, keeping up to
+            /// `N` of its last transitions, see the [`stats`]($crate::stats)
+            /// module.
+            pub fn stats<const N: usize>(&self) -> $crate::stats::Stats<N> {
+                const {
+                    assert!(rustfsm!(@has_clock $($timed_clock)?), "`stats` needs a `Clock` section");
+                    assert!($trace_depth > 0, "`stats` needs a `Trace` section");
+                    assert!($crate::metrics::ENABLED, "`stats` needs the `metrics` feature");
+                };
+                $crate::stats::Stats::new(
+                    self.current_state.index() as u8,
+                    rustfsm!(@stay_elapsed self; $($timed_clock)?),
+                    self.metrics.transitions(),
+                    self.trace()
+                        .iter()
+                        .filter(|record| record.from != record.to)
+                        .map(|record| record.to.index() as u8),
+                )
+            }
+
+            /// Read-only view of the machine, for display or telemetry code
+            /// that mustn't dispatch events or mutate the context
+            pub fn view(&self) -> $crate::MachineView<'_, $state_type, $context_type> {
+                $crate::MachineView::new(
+                    self.current_state,
+                    self.status,
+                    self.is_finished(),
+                    &self.context,
+                )
+            }
+
+            /// Start the state machine.
+            ///
+            /// Runs the current state's `enter` function, which is the initial
+            /// state on a freshly created or reset machine. Does nothing if the
+            /// machine is already running.
+            pub fn start(&mut self) {
+                if self.status != $crate::Status::Running {
+                    self.metrics.enter(
+                        self.current_state.index(),
+                        <$clock_type as $crate::clock::Clock>::now,
+                    );
+                    self.stay = rustfsm!(@stay $($timed_clock)?);
+                    self.current_state.on_any_enter(&mut self.context);
+                    self.current_state.enter(&mut self.context);
+                    self.status = $crate::Status::Running;
+                }
+            }
+
+            /// Stop the state machine.
+            ///
+            /// Runs the current state's `exit` function. Further events are
+            /// refused with [`DispatchError::Stopped`]($crate::DispatchError)
+            /// until the machine is started again.
+            pub fn stop(&mut self) {
+                if self.status != $crate::Status::Stopped {
+                    self.current_state.exit(&mut self.context);
+                    self.current_state.on_any_exit(&mut self.context);
+                    self.status = $crate::Status::Stopped;
+                }
+                $crate::clock::PauseSlot::end(&mut self.paused);
+            }
+
+            rustfsm!(@pause_methods [$($pausable)?] $($timed_clock)?);
+
+            /// Reset the state machine to its initial state, restoring or
+            /// preserving the context according to `policy`.
+            ///
+            /// Runs the current state's `exit` function unless the machine is
+            /// stopped. The machine is left idle, as if freshly created.
+            pub fn reset(&mut self, policy: $crate::ResetPolicy) {
+                if self.status != $crate::Status::Stopped {
+                    self.current_state.exit(&mut self.context);
+                    self.current_state.on_any_exit(&mut self.context);
+                }
+                self.current_state = $state_type::INITIAL;
+                self.stay = rustfsm!(@stay $($timed_clock)?);
+                self.timers.clear();
+                $crate::queue::Post::<$event_type>::clear(&mut self.queue);
+                self.filter = Default::default();
+                if policy == $crate::ResetPolicy::RestoreDefaults {
+                    self.context = $context_type::default();
+                }
+                self.status = $crate::Status::Idle;
+                $crate::clock::PauseSlot::end(&mut self.paused);
+                self.last_fault = None;
+            }
+
+            /// Reset the state machine like [`reset`](Self::reset),
+            /// preserving the context, but to `state` rather than the
+            /// initial state.
+            ///
+            /// Machines restored after a warm reboot respawn in the phase
+            /// they were in. As after [`new_at`](Self::new_at), `state`'s
+            /// `enter` is not run until [`start`](Self::start).
+            pub fn respawn_as(&mut self, state: $state_type) {
+                self.reset($crate::ResetPolicy::PreserveContext);
+                self.current_state = state;
+            }
+
+            /// Whether the state machine reached a final state.
+            pub fn is_finished(&self) -> bool {
+                self.current_state.is_final()
+            }
+
+            /// Get the cause of the last fault, if any.
+            pub fn last_fault(&self) -> Option<$crate::Fault> {
+                self.last_fault
+            }
+
+            rustfsm!(@time_in_state $($timed_clock)?);
+            rustfsm!(@rng_mut $($rng_type)?);
+
+            rustfsm!(@tick $state_type, $event_type; [$($timed_clock)?]; [$($timer_slots)?];
+                [$($timeouts)?];
+                $($watchdog)*
+            );
+
+            rustfsm!(@post_after $event_type; [$($timed_clock)?]; [$($timer_slots)?]);
+            rustfsm!(@output_methods $event_type; $($output_type)?);
+            rustfsm!(@reply_methods $event_type; $($reply_type)?);
+
+            /// Get the interceptors of the `Interceptors` section.
+            pub fn interceptors(&self) -> &($($interceptor_type,)*) {
+                &self.interceptors
+            }
+
+            /// Get a mutable reference to the interceptors of the
+            /// `Interceptors` section.
+            pub fn interceptors_mut(&mut self) -> &mut ($($interceptor_type,)*) {
+                &mut self.interceptors
+            }
+
+            /// Get the trace of the last dispatches, empty unless the machine
+            /// has a `Trace` section.
+            pub fn trace(&self) -> &$crate::trace::Trace<$state_type, $event_type, { $trace_depth }> {
+                rustfsm!(@trace_ref self, $state_type, $event_type; $trace_depth)
+            }
+
+            /// Record a fault and escalate to the `@fault` state, if any.
+            fn raise_fault(
+                current_state: &mut $state_type,
+                last_fault: &mut Option<$crate::Fault>,
+                metrics: &mut $crate::metrics::Metrics<{ $state_type::NAMES.len() }>,
+                stay: &mut rustfsm!(@stay_type $($timed_clock)?),
+                context: &mut $context_type,
+                fault: $crate::Fault,
+            ) -> $crate::DispatchError {
+                const FAULT_STATE: Option<$state_type> =
+                    rustfsm!(@fault_state $state_type; $([$($state_marker)*] $state_variant)*);
+
+                *last_fault = Some(fault);
+                if let Some(fault_state) = FAULT_STATE {
+                    if *current_state != fault_state {
+                        current_state.exit(context);
+                        current_state.on_any_exit(context);
+                        metrics.transition(
+                            current_state.index(),
+                            fault_state.index(),
+                            <$clock_type as $crate::clock::Clock>::now,
+                        );
+                        *current_state = fault_state;
+                        *stay = rustfsm!(@stay $($timed_clock)?);
+                        current_state.on_any_enter(context);
+                        current_state.enter(context);
+                    }
+                }
+                $crate::DispatchError::Fault(fault)
+            }
+
+            /// Dispatch event against the owned context, or against an
+            /// external one if given, and trace it, returning whether it
+            /// reached its handler rather than being skipped by a
+            /// `min_interval` rule.
+            $(#[$inline])?
+            fn dispatch_in(
+                &mut self,
+                event: $event_type,
+                source: $crate::Source,
+                mut external_context: Option<&mut $context_type>,
+            ) -> Result<bool, $crate::DispatchError> {
+                self.process_in(Some((source, event)), external_context, usize::MAX)
+                    .map(|(_, reached)| reached)
+            }
+
+            /// Dispatch `event`, if any, then the queued events, up to `budget`
+            /// of them, returning how many were dispatched and whether `event`
+            /// reached its handler.
+            $(#[$inline])?
+            fn process_in(
+                &mut self,
+                event: Option<($crate::Source, $event_type)>,
+                mut external_context: Option<&mut $context_type>,
+                budget: usize,
+            ) -> Result<(usize, bool), $crate::DispatchError> {
+                if let Some(pause) = $crate::clock::PauseSlot::pause(&self.paused) {
+                    return match (event, pause.policy) {
+                        (Some((_, event)), $crate::PausePolicy::Queue) => {
+                            $crate::queue::Post::<$event_type>::post(&mut self.queue, event)
+                                .map(|_| (0, false))
+                                .map_err(|_| $crate::DispatchError::Paused)
+                        }
+                        _ => Err($crate::DispatchError::Paused),
+                    };
+                }
+                if !self.guard.enter() {
+                    return Err($crate::DispatchError::Reentrant);
+                }
+                let mut dispatched = 0;
+                let mut reached = false;
+                let mut result = Ok(());
+                let mut next = match event {
+                    Some(event) => Some(event),
+                    None => rustfsm!(@next_event self, $event_type; [$($timeouts)?]; [$($timed_clock)?]),
+                };
+                // Queued events are dropped after an error
+                while let Some((source, event)) = next {
+                    $crate::SourceSlot::set(&mut self.source, source);
+                    match self.dispatch_traced(event, external_context.as_deref_mut()) {
+                        Ok(handled) => reached |= handled && dispatched == 0,
+                        Err(error) => {
+                            result = Err(error);
+                            $crate::queue::Post::<$event_type>::clear(&mut self.queue);
+                            break;
+                        }
+                    }
+                    dispatched += 1;
+                    if dispatched == budget {
+                        break;
+                    }
+                    next = rustfsm!(@next_event self, $event_type; [$($timeouts)?]; [$($timed_clock)?]);
+                }
+                self.guard.exit();
+                result.map(|()| (dispatched, reached))
+            }
+
+            /// Dispatch a single event and trace it.
+            $(#[$inline])?
+            fn dispatch_traced(
+                &mut self,
+                event: $event_type,
+                mut external_context: Option<&mut $context_type>,
+            ) -> Result<bool, $crate::DispatchError> {
+                let from = self.current_state;
+                let result = rustfsm!(@intercept self, event, external_context, from;
+                    $state_type, $event_type, $context_type, $clock_type;
+                    [$($filter_type)?] [$($interceptor_type),*]
+                );
+                self.record(event, from, result);
+                result
+            }
+
+            /// Record a dispatch in the trace.
+            #[inline(always)]
+            fn record(
+                &mut self,
+                event: $event_type,
+                from: $state_type,
+                result: Result<bool, $crate::DispatchError>,
+            ) {
+                let to = self.current_state;
+                self.trace.record(|| $crate::trace::TraceRecord {
+                    timestamp: <$clock_type as $crate::clock::Clock>::now(),
+                    // No rule is taken by a skipped event
+                    transition: match result {
+                        Ok(true) => from.table_rule(&event).and_then(|rule| rule.name),
+                        _ => None,
+                    },
+                    event,
+                    source: $crate::SourceSlot::get(&self.source),
+                    from,
+                    to,
+                    result: result.map(|_| ()),
+                });
+            }
+
+            /// Run a single dispatch, without tracing it, returning whether
+            /// the event reached its handler.
+            $(#[$inline])?
+            fn dispatch_step(
+                &mut self,
+                event: &$event_type,
+                external_context: Option<&mut $context_type>,
+            ) -> Result<bool, $crate::DispatchError> {
+                if self.status == $crate::Status::Stopped {
+                    return Err($crate::DispatchError::Stopped);
+                }
+                if self.is_finished() {
+                    return Err($crate::DispatchError::Finished);
+                }
+                let context = match external_context {
+                    Some(context) => context,
+                    None => &mut self.context,
+                };
+                rustfsm!(@validate event, context; $event_type, $context_type; $($validate_check)?)
+                    .map_err($crate::DispatchError::Invalid)?;
+                let previous_state = self.current_state;
+                if !rustfsm!(@guard self, previous_state, event, context; $($rng_type)?) {
+                    return Err($crate::DispatchError::GuardRejected {
+                        state: previous_state.name(),
+                        event: event.name(),
+                    });
+                }
+                // A rule taken too soon after entering the state is skipped,
+                // the event dropped without counting as unhandled
+                if rustfsm!(@too_soon self, $state_type, previous_state, event; $($timed_clock)?) {
+                    return Ok(false);
+                }
+                let outcome = rustfsm!(@handle self, event, context, rustfsm!(@rng self; $($rng_type)?);
+                    [$($output_type)?] [$($reply_type)?]
+                );
+                match outcome {
+                    Ok(Some(next_state)) => {
+                        rustfsm!(@notify before_exit [$($notify)?];
+                            self, event, previous_state, next_state, context;
+                            $state_type, $event_type, $context_type
+                        );
+                        self.current_state.exit(context);
+                        self.current_state.on_any_exit(context);
+                        self.metrics.event(previous_state.index(), true);
+                        self.metrics.transition(
+                            previous_state.index(),
+                            next_state.index(),
+                            <$clock_type as $crate::clock::Clock>::now,
+                        );
+                        self.current_state = next_state;
+                        self.stay = rustfsm!(@stay $($timed_clock)?);
+                        rustfsm!(@notify before_enter [$($notify)?];
+                            self, event, previous_state, next_state, context;
+                            $state_type, $event_type, $context_type
+                        );
+                        self.current_state.on_any_enter(context);
+                        self.current_state.enter(context);
+                        rustfsm!(@notify after_enter [$($notify)?];
+                            self, event, previous_state, next_state, context;
+                            $state_type, $event_type, $context_type
+                        );
+                        self.coverage.record(
+                            $state_type::TRANSITION_TABLE,
+                            || previous_state.table_rule(event),
+                            Some(next_state.index()),
+                        );
+                    }
+                    Ok(None) => {
+                        self.metrics.event(previous_state.index(), false);
+                        self.coverage.record(
+                            $state_type::TRANSITION_TABLE,
+                            || previous_state.table_rule(event),
+                            None,
+                        );
+                        rustfsm!(@unhandled [$($unhandled)?]; previous_state, event, context);
+                    }
+                    Err(reason) => {
+                        return Err(Self::raise_fault(
+                            &mut self.current_state,
+                            &mut self.last_fault,
+                            &mut self.metrics,
+                            &mut self.stay,
+                            context,
+                            $crate::Fault::Handler(reason),
+                        ));
+                    }
+                }
+                let invariant = rustfsm!(@check_invariant self.current_state, context;
+                    $state_type, $context_type; $($invariant_check)?
+                );
+                // Nothing is left to raise once `on_violation: panic` panicked
+                #[allow(unreachable_code)]
+                if let Err(reason) = invariant {
+                    rustfsm!(@invariant_violation [$($($invariant_violation)?)?]; self.current_state, reason);
+                    return Err(Self::raise_fault(
+                        &mut self.current_state,
+                        &mut self.last_fault,
+                        &mut self.metrics,
+                        &mut self.stay,
+                        context,
+                        $crate::Fault::Invariant(reason),
+                    ));
+                }
+                Ok(true)
+            }
+
+            /// Dispatch event and transition if necessary.
+            ///
+            /// Events are refused once the machine is stopped or finished. A
+            /// failing handler or invariant check records a fault and
+            /// escalates to the state marked `@fault`, if any.
+            $(#[$inline])?
+            pub fn dispatch(&mut self, event: $event_type) -> Result<(), $crate::DispatchError> {
+                self.dispatch_in(event, $crate::Source::Host, None).map(|_| ())
+            }
+
+            /// Dispatch event like [`dispatch`](Self::dispatch), by reference.
+            ///
+            /// Large events, such as DMA frames, reach the states without
+            /// being copied. Machines with a `Filter` or an `Interceptors`
+            /// section copy the event once for them, and a `Trace` section
+            /// records it only if it's `Clone`, cloning it. A paused machine
+            /// refuses the event, having nowhere to keep it.
+            $(#[$inline])?
+            pub fn dispatch_ref(&mut self, event: &$event_type) -> Result<(), $crate::DispatchError> {
+                rustfsm!(@dispatch_ref self, event: $event_type; $trace_depth; [$($event_derive),+];
+                    [$($filter_type)?] [$($interceptor_type),*]
+                )
+            }
+
+            rustfsm!(@tagged_methods [$($tagged)?] $event_type; $($inline)?);
+
+            /// Dispatch event like [`dispatch`](Self::dispatch), returning
+            /// the name of the transition table rule it was handled by, if
+            /// named as in `t_connect: Idle + Start => Connecting`, and `None`
+            /// if a `min_interval` rule skipped it.
+            pub fn dispatch_named(
+                &mut self,
+                event: $event_type,
+            ) -> Result<Option<&'static str>, $crate::DispatchError> {
+                let rule = self.current_state.table_rule(&event);
+                let reached = self.dispatch_in(event, $crate::Source::Host, None)?;
+                Ok(rule.filter(|_| reached).and_then(|rule| rule.name))
+            }
+
+            /// Dispatch event against an externally owned context instead of
+            /// the machine's own one.
+            ///
+            /// This lets several state machines declared with the same
+            /// existing context type operate on a single shared context. The
+            /// machine's own context is still there, unused by this call, see
+            /// [the macro documentation]($crate::rustfsm) for why.
+            $(#[$inline])?
+            pub fn dispatch_with(
+                &mut self,
+                event: $event_type,
+                context: &mut $context_type,
+            ) -> Result<(), $crate::DispatchError> {
+                self.dispatch_in(event, $crate::Source::Host, Some(context)).map(|_| ())
+            }
+
+            /// Queue event, to be dispatched by [`process_all`](Self::process_all)
+            /// or after the event currently dispatched, giving it back if the
+            /// queue is full or the machine has no `Queue` section.
+            pub fn post(
+                &mut self,
+                event: $event_type,
+            ) -> Result<$crate::queue::PostToken, $event_type> {
+                $crate::queue::Post::<$event_type>::post(&mut self.queue, event)
+            }
+
+            /// Drop the queued event of `token`, returning whether it was
+            /// still queued.
+            pub fn cancel_post(&mut self, token: $crate::queue::PostToken) -> bool {
+                $crate::queue::Post::<$event_type>::cancel(&mut self.queue, token)
+            }
+
+            /// Drop the queued events and cancel the pending timers of the
+            /// same variant as `event`, whatever their payload, returning how
+            /// many were dropped.
+            ///
+            /// Meant for entering a state in which stale retries must not
+            /// fire.
+            pub fn cancel_all_matching(&mut self, event: &$event_type) -> usize {
+                $crate::queue::Post::<$event_type>::cancel_matching(&mut self.queue, event)
+                    + self.timers.cancel_matching(event)
+            }
+
+            /// Dispatch every queued event, including those posted meanwhile,
+            /// by decreasing priority, returning how many were dispatched.
+            ///
+            /// An error drops the events still queued.
+            pub fn process_all(&mut self) -> Result<usize, $crate::DispatchError> {
+                self.process_in(None, None, usize::MAX).map(|(dispatched, _)| dispatched)
+            }
+
+            /// Dispatch the next queued event only, returning whether there
+            /// was one.
+            ///
+            /// Meant for single-stepping the machine in a debugger or a test,
+            /// the pending events being inspected with
+            /// [`queued`](Self::queued) between steps.
+            pub fn step(&mut self) -> Result<bool, $crate::DispatchError> {
+                self.process_in(None, None, 1).map(|(dispatched, _)| dispatched == 1)
+            }
+
+            /// Dispatch at most `max_events` queued events, including those
+            /// posted meanwhile, reporting how many are left.
+            ///
+            /// Bounds the time a deep queue holds up a superloop, the rest
+            /// being processed on the next pass. An error drops the events
+            /// still queued.
+            pub fn process_for(
+                &mut self,
+                max_events: usize,
+            ) -> Result<$crate::queue::Progress, $crate::DispatchError> {
+                let dispatched = match max_events {
+                    0 => 0,
+                    _ => self.process_in(None, None, max_events)?.0,
+                };
+                Ok($crate::queue::Progress {
+                    dispatched,
+                    remaining: self.queued().count(),
+                })
+            }
+
+            rustfsm!(@process_budget $($timed_clock)?);
+
+            /// Queued events, in dispatch order
+            pub fn queued(&self) -> impl Iterator<Item = &$event_type> {
+                (0..).map_while(|index| $crate::queue::Post::<$event_type>::get(&self.queue, index))
+            }
+
+            /// Dispatch event like [`dispatch`](Self::dispatch), returning the
+            /// machine to chain the next one.
+            ///
+            /// Anything convertible into an event is accepted, for scripting
+            /// a sequence concisely: `machine.feed(Start)?.feed(Data(3))?;`.
+            pub fn feed(
+                &mut self,
+                event: impl Into<$event_type>,
+            ) -> Result<&mut Self, $crate::DispatchError> {
+                self.dispatch(event.into()).map(|()| self)
+            }
+
+            /// Drop the queued events of priority lower than `priority`,
+            /// returning how many were dropped.
+            pub fn flush_below(&mut self, priority: u8) -> usize {
+                $crate::queue::Post::<$event_type>::flush_below(&mut self.queue, priority)
+            }
+
             /// Handle event and transition if necessary.
+            ///
+            /// Events refused by [`dispatch`](Self::dispatch) are dropped.
+            $(#[$inline])?
             fn handle(&mut self, event: $event_type) {
-                match self.current_state.handle(&event, &mut self.context) {
-                    Some(next_state) => {
-                        self.current_state.exit(&mut self.context);
-                        self.current_state = next_state;
-                        self.current_state.enter(&mut self.context);
+                let _ = self.dispatch(event);
+            }
+        }
+
+        impl Default for $state_machine_name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        // Queue a sequence of events, as in
+        // `machine.extend([Start, Stop]); machine.process_all()`. When the
+        // queue is full, the events already queued are dispatched to make
+        // room, and machines without a `Queue` section dispatch the events
+        // right away, in order either way. Errors are dropped as by `handle`.
+        impl ::core::iter::Extend<$event_type> for $state_machine_name {
+            fn extend<I: IntoIterator<Item = $event_type>>(&mut self, events: I) {
+                for event in events {
+                    let Err(event) = self.post(event) else {
+                        continue;
+                    };
+                    let _ = self.process_all();
+                    if let Err(event) = self.post(event) {
+                        self.handle(event);
+                    }
+                }
+            }
+        }
+
+        $crate::paste::paste! {
+            /// State machine builder.
+            ///
+            /// Starts from the same initial state, default context and default
+            /// members as `new()`, each of which can be overridden.
+            pub struct [<$state_machine_name Builder>] {
+                state: $state_type,
+                context: $context_type,
+                $(
+                    $member_field: $member_field_type,
+                )*
+            }
+
+            impl $state_machine_name {
+                $(
+                    #[doc = concat!("Get the `", stringify!($member_field), "` member.")]
+                    pub fn $member_field(&self) -> &$member_field_type {
+                        &self.$member_field
+                    }
+
+                    #[doc = concat!("Set the `", stringify!($member_field), "` member.")]
+                    pub fn [<set_ $member_field>](&mut self, value: $member_field_type) {
+                        self.$member_field = value;
+                    }
+                )*
+
+                /// Create a builder for the state machine.
+                pub fn builder() -> [<$state_machine_name Builder>] {
+                    [<$state_machine_name Builder>] {
+                        state: $state_type::INITIAL,
+                        context: $context_type::default(),
+                        $(
+                            $member_field: $member_default,
+                        )*
+                    }
+                }
+            }
+
+            impl [<$state_machine_name Builder>] {
+                /// Set the state the machine starts in.
+                pub fn state(mut self, state: $state_type) -> Self {
+                    self.state = state;
+                    self
+                }
+
+                /// Set the machine's context.
+                pub fn context(mut self, context: $context_type) -> Self {
+                    self.context = context;
+                    self
+                }
+
+                $(
+                    #[doc = concat!("Set the `", stringify!($member_field), "` member.")]
+                    pub fn $member_field(mut self, value: $member_field_type) -> Self {
+                        self.$member_field = value;
+                        self
+                    }
+                )*
+
+                /// Build the state machine, without calling the starting
+                /// state's `enter` function.
+                pub fn build(self) -> $state_machine_name {
+                    $state_machine_name {
+                        current_state: self.state,
+                        context: self.context,
+                        status: $crate::Status::Idle,
+                        paused: Default::default(),
+                        source: Default::default(),
+                        last_fault: None,
+                        coverage: $crate::coverage::Coverage::new(),
+                        trace: <rustfsm!(@trace_type $state_type, $event_type; $trace_depth)>::new(),
+                        metrics: $crate::metrics::Metrics::new(),
+                        stay: rustfsm!(@stay $($timed_clock)?),
+                        timers: $crate::timer::Timers::new(),
+                        filter: Default::default(),
+                        interceptors: Default::default(),
+                        rng: Default::default(),
+                        queue: rustfsm!(@queue_new $event_type; [$($timed_clock)?]; $queue_depth $(; $queue_priority)?),
+                        output: Default::default(),
+                        guard: $crate::DispatchGuard::new(),
+                        $(
+                            $member_field: self.$member_field,
+                        )*
+                    }
+                }
+            }
+        }
+
+        // Implement Clone for the state machine when its context and members
+        // are Clone.
+        impl Clone for $state_machine_name
+        where
+            for<'a> $context_type: Clone,
+            for<'a> rustfsm!(@filter_type $($filter_type)?): Clone,
+            for<'a> ($($interceptor_type,)*): Clone,
+            for<'a> rustfsm!(@rng_type $($rng_type)?): Clone,
+            for<'a> rustfsm!(@trace_type $state_type, $event_type; $trace_depth): Clone,
+            for<'a> $crate::timer::Timers<$event_type, { rustfsm!(@timer_slots $($timer_slots)?) }>: Clone,
+            for<'a> rustfsm!(@queue_type $event_type; $queue_depth): Clone,
+            $(for<'a> $member_field_type: Clone,)*
+        {
+            fn clone(&self) -> Self {
+                Self {
+                    current_state: self.current_state,
+                    context: self.context.clone(),
+                    status: self.status,
+                    paused: self.paused,
+                    source: self.source,
+                    last_fault: self.last_fault,
+                    coverage: self.coverage.clone(),
+                    trace: self.trace.clone(),
+                    metrics: self.metrics.clone(),
+                    stay: self.stay,
+                    timers: self.timers.clone(),
+                    filter: self.filter.clone(),
+                    interceptors: self.interceptors.clone(),
+                    rng: self.rng.clone(),
+                    queue: self.queue.clone(),
+                    output: Default::default(),
+                    guard: $crate::DispatchGuard::new(),
+                    $(
+                        $member_field: self.$member_field.clone(),
+                    )*
+                }
+            }
+        }
+
+        // Implement Debug for the state machine, showing its current state and
+        // context.
+        impl ::core::fmt::Debug for $state_machine_name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                f.debug_struct(stringify!($state_machine_name))
+                    .field("current_state", &self.current_state)
+                    .field("context", &self.context)
+                    .finish_non_exhaustive()
+            }
+        }
+
+        // Implement PartialEq for the state machine when its context and
+        // members are PartialEq.
+        impl PartialEq for $state_machine_name
+        where
+            for<'a> $context_type: PartialEq,
+            $(for<'a> $member_field_type: PartialEq,)*
+        {
+            fn eq(&self, other: &Self) -> bool {
+                self.current_state == other.current_state
+                    && self.context == other.context
+                    && self.status == other.status
+                    && self.last_fault == other.last_fault
+                    $(&& self.$member_field == other.$member_field)*
+            }
+        }
+
+        impl $state_machine_name
+        where
+            for<'a> $state_machine_name: Clone,
+        {
+            /// Take a snapshot of the whole state machine.
+            ///
+            /// The snapshot can be used to fork the machine, try a sequence of
+            /// events and discard it, or be restored with
+            /// [`restore_from`](Self::restore_from).
+            pub fn snapshot(&self) -> Self {
+                self.clone()
+            }
+
+            /// Restore the whole state machine from a snapshot, without calls
+            /// to `enter` and `exit` functions.
+            pub fn restore_from(&mut self, snapshot: &Self) {
+                *self = snapshot.clone();
+            }
+        }
+
+        impl $state_machine_name
+        where
+            for<'a> $context_type: Clone,
+        {
+            /// Take a copy of the context alone, to be restored with
+            /// [`restore_context`](Self::restore_context).
+            pub fn checkpoint_context(&self) -> $context_type {
+                self.context.clone()
+            }
+
+            /// Roll the context back to a checkpoint, leaving the current
+            /// state as it is.
+            pub fn restore_context(&mut self, checkpoint: $context_type) {
+                self.context = checkpoint;
+            }
+        }
+
+        impl $state_machine_name {
+            /// Size of a hibernation image, in bytes: the current state id,
+            /// then the fields of the `Hibernate` section.
+            pub const HIBERNATE_SIZE: usize = 1 $(
+                + $crate::codec::wire_size(|context: &$context_type| &context.$hibernate_field)
+            )*;
+
+            /// Write the state needed to resume after a deep sleep to `buf`,
+            /// returning its length, or 0 if it didn't fit or the current
+            /// state has data, which the image has no room for.
+            pub fn hibernate(&self, buf: &mut [u8]) -> usize {
+                let resumable = match self.current_state {
+                    $(
+                        $state_type::$state_variant { .. } => {
+                            let state: Option<$state_type> = rustfsm!(@unit_variant
+                                $state_type::$state_variant $(($($state_variant_data),*))?
+                            );
+                            state.is_some()
+                        }
+                    )*
+                };
+                if !resumable {
+                    return 0;
+                }
+                let mut writer = $crate::codec::Writer::new(buf);
+                writer.put(&(self.current_state.index() as u8));
+                $(writer.put(&self.context.$hibernate_field);)*
+                writer.finish()
+            }
+
+            /// Create a state machine resuming from a hibernation image, in
+            /// its saved state, the fields left out of the image at their
+            /// defaults.
+            ///
+            /// The state's `enter` function is **not** run. Use
+            /// [`wake_started`](Self::wake_started) to run it again.
+            pub fn wake(image: &[u8]) -> Result<Self, $crate::codec::DecodeError> {
+                #[allow(dead_code)]
+                enum Index {
+                    $($state_variant),*
+                }
+                let mut reader = $crate::codec::Reader::new(image);
+                let id: u8 = reader.take()?;
+                let mut state = None;
+                $(
+                    if id == Index::$state_variant as u8 {
+                        state = rustfsm!(@unit_variant
+                            $state_type::$state_variant $(($($state_variant_data),*))?
+                        );
+                    }
+                )*
+                let mut state_machine = Self::new();
+                state_machine.current_state = state.ok_or($crate::codec::DecodeError::InvalidPayload)?;
+                $(state_machine.context.$hibernate_field = reader.take()?;)*
+                reader.finish(state_machine)
+            }
+
+            /// Create a state machine resuming from a hibernation image and
+            /// start it, running the saved state's `enter` function.
+            pub fn wake_started(image: &[u8]) -> Result<Self, $crate::codec::DecodeError> {
+                let mut state_machine = Self::wake(image)?;
+                state_machine.start();
+                Ok(state_machine)
+            }
+        }
+
+        $crate::__rustfsm_coverage!($state_machine_name, $state_type);
+        $crate::__rustfsm_metrics!($state_machine_name, $state_type);
+        $crate::__rustfsm_ffi!($state_machine_name, $event_type,
+            events { $($event_variant $(($($event_variant_data),*))?),* }
+        );
+        $crate::__rustfsm_wasm!($state_machine_name, $event_type,
+            events { $($event_variant $(($($event_variant_data),*))?),* }
+        );
+        $crate::__rustfsm_serde!(@machine $state_machine_name, $state_type, $context_type);
+        $crate::__rustfsm_diff!(@machine $state_machine_name, $state_type, $context_type);
+
+        impl $crate::remote::Remote for $state_machine_name
+        where
+            $($($(for<'a> $event_variant_data: $crate::codec::Wire,)*)?)*
+        {
+            fn state_id(&self) -> u8 {
+                self.current_state.index() as u8
+            }
+
+            fn dump_context(&self, out: &mut dyn ::core::fmt::Write) -> ::core::fmt::Result {
+                write!(out, "{:?}", self.context)
+            }
+
+            fn inject(
+                &mut self,
+                frame: &[u8],
+            ) -> Result<Result<(), $crate::DispatchError>, $crate::codec::DecodeError> {
+                $event_type::decode(frame).map(|event| self.dispatch(event))
+            }
+
+            fn force_state_id(&mut self, id: u8) -> bool {
+                #[allow(dead_code)]
+                enum Index {
+                    $($state_variant),*
+                }
+                $(
+                    if id == Index::$state_variant as u8 {
+                        let state: Option<$state_type> = rustfsm!(@unit_variant
+                            $state_type::$state_variant $(($($state_variant_data),*))?
+                        );
+                        if let Some(state) = state {
+                            self.force_state(state);
+                            return true;
+                        }
                     }
-                    None => (),
+                )*
+                false
+            }
+        }
+
+        impl $crate::StateMachine for $state_machine_name {
+            type State = $state_type;
+            type Event = $event_type;
+            type Context = $context_type;
+
+            fn new() -> Self {
+                $state_machine_name::new()
+            }
+
+            fn get_current_state(&self) -> $state_type {
+                self.current_state
+            }
+
+            fn context(&self) -> &$context_type {
+                &self.context
+            }
+
+            fn is_finished(&self) -> bool {
+                $state_machine_name::is_finished(self)
+            }
+
+            $(#[$inline])?
+            fn dispatch(&mut self, event: $event_type) -> Result<(), $crate::DispatchError> {
+                $state_machine_name::dispatch(self, event)
+            }
+        }
+
+        impl $crate::FsmCore for $state_machine_name
+        where
+            for<'a> $event_type: Clone,
+        {
+            fn name(&self) -> &'static str {
+                stringify!($state_machine_name)
+            }
+
+            fn current_state_name(&self) -> &'static str {
+                self.current_state.name()
+            }
+
+            fn status(&self) -> $crate::Status {
+                self.status
+            }
+
+            fn is_finished(&self) -> bool {
+                $state_machine_name::is_finished(self)
+            }
+
+            fn last_fault(&self) -> Option<$crate::Fault> {
+                self.last_fault
+            }
+
+            fn start(&mut self) {
+                $state_machine_name::start(self)
+            }
+
+            fn stop(&mut self) {
+                $state_machine_name::stop(self)
+            }
+
+            fn reset(&mut self, policy: $crate::ResetPolicy) {
+                $state_machine_name::reset(self, policy)
+            }
+
+            fn dispatch_erased(
+                &mut self,
+                event: &dyn ::core::any::Any,
+            ) -> Result<(), $crate::DispatchError> {
+                match event.downcast_ref::<$event_type>() {
+                    Some(event) => $state_machine_name::dispatch(self, event.clone()),
+                    None => Err($crate::DispatchError::UnexpectedEvent),
                 }
             }
         }
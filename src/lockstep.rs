@@ -0,0 +1,173 @@
+//! Lockstep orchestration of cooperating machines.
+//!
+//! A [`Lockstep`] advances several machines of possibly different types in a
+//! deterministic order. Each machine is wrapped in a [`Member`] exchanging
+//! messages of a common type `M`. The messages a member sends are routed
+//! to the other members on the next [`cycle`](Lockstep::cycle), so every
+//! cycle sees the same order of deliveries for the same inputs:
+//!
+//! ```rust,ignore
+//! const MOTOR: usize = 0;
+//! const UI: usize = 1;
+//!
+//! impl Member<Message> for Motor {
+//!     fn deliver(&mut self, message: &Message, out: &mut Emitter<'_, Message>) {
+//!         if let Message::Motor(event) = message {
+//!             let _ = self.dispatch(*event);
+//!             if self.get_current_state() == MotorStates::Stalled {
+//!                 let _ = out.send(UI, Message::Ui(UiEvents::ShowAlarm));
+//!             }
+//!         }
+//!     }
+//! }
+//!
+//! let mut system = Lockstep::<_, 2, 8>::new([&mut motor, &mut ui]);
+//! system.post(MOTOR, Message::Motor(MotorEvents::Start))?;
+//! // Once per control loop tick
+//! system.cycle();
+//! ```
+
+use crate::queue::{EventQueue, Post};
+
+/// Message in transit, with the index of its destination, `None` for all
+/// the members but its sender
+#[derive(Clone, Debug)]
+struct Envelope<M> {
+    from: Option<usize>,
+    to: Option<usize>,
+    message: M,
+}
+
+/// Machine taking part in a [`Lockstep`]
+pub trait Member<M> {
+    /// Handle `message`, sending the messages it causes to `out`.
+    fn deliver(&mut self, message: &M, out: &mut Emitter<'_, M>);
+
+    /// Advance the member once per cycle, after the deliveries, for instance
+    /// to `tick()` its timers.
+    fn on_cycle(&mut self, _out: &mut Emitter<'_, M>) {}
+}
+
+/// Messages sent by a member, delivered on the next cycle
+pub struct Emitter<'a, M> {
+    from: Option<usize>,
+    pending: &'a mut dyn Post<Envelope<M>>,
+}
+
+impl<M> Emitter<'_, M> {
+    /// Send `message` to the member at index `to`, giving it back if the
+    /// orchestrator's queue is full.
+    pub fn send(&mut self, to: usize, message: M) -> Result<(), M> {
+        self.post(Some(to), message)
+    }
+
+    /// Send `message` to every other member, giving it back if the
+    /// orchestrator's queue is full.
+    pub fn broadcast(&mut self, message: M) -> Result<(), M> {
+        self.post(None, message)
+    }
+
+    fn post(&mut self, to: Option<usize>, message: M) -> Result<(), M> {
+        let envelope = Envelope {
+            from: self.from,
+            to,
+            message,
+        };
+        self.pending
+            .post(envelope)
+            .map(|_| ())
+            .map_err(|envelope| envelope.message)
+    }
+}
+
+/// Orchestrator of `N` members exchanging messages of type `M`, up to `Q` of
+/// them in transit
+pub struct Lockstep<'a, M, const N: usize, const Q: usize> {
+    members: [&'a mut dyn Member<M>; N],
+    pending: EventQueue<Envelope<M>, Q>,
+    cycles: u32,
+}
+
+impl<'a, M, const N: usize, const Q: usize> Lockstep<'a, M, N, Q> {
+    /// Create an orchestrator of `members`, advanced in that order.
+    pub fn new(members: [&'a mut dyn Member<M>; N]) -> Self {
+        Self {
+            members,
+            pending: EventQueue::new(),
+            cycles: 0,
+        }
+    }
+
+    /// Post `message` from outside to the member at index `to`, delivered on
+    /// the next cycle, giving it back if the queue is full.
+    pub fn post(&mut self, to: usize, message: M) -> Result<(), M> {
+        self.emitter(None).send(to, message)
+    }
+
+    /// Run one cycle, returning how many messages were delivered.
+    ///
+    /// The messages pending when the cycle starts are delivered in the order
+    /// they were sent, broadcasts to the members in order. Then every member
+    /// runs its [`on_cycle`](Member::on_cycle). Messages sent meanwhile wait
+    /// for the next cycle.
+    pub fn cycle(&mut self) -> usize {
+        let mut delivered = 0;
+        for _ in 0..self.pending.len() {
+            let Some(envelope) = EventQueue::pop(&mut self.pending) else {
+                break;
+            };
+            for (index, member) in self.members.iter_mut().enumerate() {
+                let addressed = match envelope.to {
+                    Some(to) => to == index,
+                    None => envelope.from != Some(index),
+                };
+                if addressed {
+                    let mut out = Emitter {
+                        from: Some(index),
+                        pending: &mut self.pending,
+                    };
+                    member.deliver(&envelope.message, &mut out);
+                    delivered += 1;
+                }
+            }
+        }
+        for (index, member) in self.members.iter_mut().enumerate() {
+            let mut out = Emitter {
+                from: Some(index),
+                pending: &mut self.pending,
+            };
+            member.on_cycle(&mut out);
+        }
+        self.cycles = self.cycles.wrapping_add(1);
+        delivered
+    }
+
+    /// Run cycles until no message is in transit, at most `max_cycles` of
+    /// them, returning how many ran, or `None` if the system didn't quiesce.
+    pub fn run_until_quiescent(&mut self, max_cycles: u32) -> Option<u32> {
+        for cycles in 0..max_cycles {
+            if self.is_quiescent() {
+                return Some(cycles);
+            }
+            self.cycle();
+        }
+        self.is_quiescent().then_some(max_cycles)
+    }
+
+    /// Whether no message is in transit
+    pub fn is_quiescent(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Number of cycles run so far
+    pub fn cycles(&self) -> u32 {
+        self.cycles
+    }
+
+    fn emitter(&mut self, from: Option<usize>) -> Emitter<'_, M> {
+        Emitter {
+            from,
+            pending: &mut self.pending,
+        }
+    }
+}
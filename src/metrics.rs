@@ -0,0 +1,150 @@
+//! Per-state metrics.
+
+/// Counters of a single state
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct StateMetrics {
+    /// Number of times the state was entered
+    pub entered: u32,
+    /// Number of events handled in the state, leading to a transition
+    pub handled: u32,
+    /// Number of events ignored in the state, leading to no transition
+    pub ignored: u32,
+    /// Cumulative ticks spent in the state, the ongoing stay excluded
+    pub dwell: u32,
+}
+
+#[cfg(feature = "metrics")]
+impl StateMetrics {
+    const ZERO: Self = Self {
+        entered: 0,
+        handled: 0,
+        ignored: 0,
+        dwell: 0,
+    };
+}
+
+/// Whether the `metrics` feature is enabled, for the checks of the generated
+/// code
+#[doc(hidden)]
+pub const ENABLED: bool = cfg!(feature = "metrics");
+
+/// Per-state metrics of a state machine with `N` states
+///
+/// Without the `metrics` feature it holds nothing and records nothing.
+#[derive(Clone, Debug)]
+pub struct Metrics<const N: usize> {
+    #[cfg(feature = "metrics")]
+    states: [StateMetrics; N],
+    #[cfg(feature = "metrics")]
+    entered_at: u32,
+}
+
+impl<const N: usize> Metrics<N> {
+    /// Create empty metrics.
+    #[doc(hidden)]
+    pub const fn new() -> Self {
+        Self {
+            #[cfg(feature = "metrics")]
+            states: [StateMetrics::ZERO; N],
+            #[cfg(feature = "metrics")]
+            entered_at: 0,
+        }
+    }
+
+    /// Record the state at `index` being entered at tick `now`.
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn enter(&mut self, _index: usize, _now: impl FnOnce() -> u32) {
+        #[cfg(feature = "metrics")]
+        {
+            if let Some(state) = self.states.get_mut(_index) {
+                state.entered = state.entered.saturating_add(1);
+            }
+            self.entered_at = _now();
+        }
+    }
+
+    /// Record a transition from the state at `from` to the one at `to`, at
+    /// tick `now`.
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn transition(&mut self, _from: usize, _to: usize, _now: impl FnOnce() -> u32) {
+        #[cfg(feature = "metrics")]
+        {
+            let now = _now();
+            if let Some(from) = self.states.get_mut(_from) {
+                from.dwell = from
+                    .dwell
+                    .saturating_add(crate::clock::elapsed(self.entered_at, now));
+            }
+            self.enter(_to, || now);
+        }
+    }
+
+    /// Leave `ticks` out of the dwell time of the current state, spent
+    /// paused.
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn postpone(&mut self, _ticks: u32) {
+        #[cfg(feature = "metrics")]
+        {
+            self.entered_at = self.entered_at.wrapping_add(_ticks);
+        }
+    }
+
+    /// Number of events which led to a transition, 0 without the `metrics`
+    /// feature.
+    #[doc(hidden)]
+    pub fn transitions(&self) -> u32 {
+        #[cfg(feature = "metrics")]
+        {
+            self.states
+                .iter()
+                .fold(0, |total: u32, state| total.saturating_add(state.handled))
+        }
+        #[cfg(not(feature = "metrics"))]
+        0
+    }
+
+    /// Record an event handled in the state at `index`, which led to a
+    /// transition or not.
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn event(&mut self, _index: usize, _transitioned: bool) {
+        #[cfg(feature = "metrics")]
+        {
+            if let Some(state) = self.states.get_mut(_index) {
+                if _transitioned {
+                    state.handled = state.handled.saturating_add(1);
+                } else {
+                    state.ignored = state.ignored.saturating_add(1);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl<const N: usize> Metrics<N> {
+    /// Metrics of the state at `index`, as given by the state's `index()`,
+    /// all zero for an index out of range.
+    pub fn get(&self, index: usize) -> &StateMetrics {
+        self.states.get(index).unwrap_or(&StateMetrics::ZERO)
+    }
+
+    /// Metrics of every state, in declaration order.
+    pub fn iter(&self) -> impl Iterator<Item = &StateMetrics> {
+        self.states.iter()
+    }
+
+    /// Reset every counter.
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+}
+
+impl<const N: usize> Default for Metrics<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
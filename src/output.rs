@@ -0,0 +1,68 @@
+//! Mealy-style outputs and typed replies.
+//!
+//! A machine with an `Output { Frame }` section hands every event to
+//! [`Mealy::handle_output`], which returns an output along with the next
+//! state. `dispatch_output(event)` then gives the output back to the caller,
+//! for protocol machines emitting a frame as the direct consequence of an
+//! event:
+//!
+//! ```rust,ignore
+//! impl Mealy for LinkStates {
+//!     type Output = Frame;
+//!
+//!     fn handle_output(
+//!         &self,
+//!         event: &Self::Event,
+//!         _context: &mut Self::Context,
+//!     ) -> (Option<Self::State>, Option<Frame>) {
+//!         match (self, event) {
+//!             (Self::Idle, Events::Connect) => (Some(Self::Connecting), Some(Frame::Syn)),
+//!             _ => (None, None),
+//!         }
+//!     }
+//! }
+//!
+//! if let Some(frame) = link.dispatch_output(Events::Connect)? {
+//!     uart.send(frame);
+//! }
+//! ```
+//!
+//! A `Reply { Ack }` section is the request/response counterpart: every
+//! event gets a reply from [`Respond::handle_reply`], returned by
+//! `dispatch_with_reply(event)`, so command-style machines answer with an
+//! ACK, a NACK or a computed value rather than having the caller sniff the
+//! context afterwards. A refused event gets the dispatch error instead.
+//!
+//! Either way, the machine still needs a [`StateBehavior`] impl, whose
+//! `handle` isn't called when dispatching. It can forward to the method
+//! above.
+
+use crate::StateBehavior;
+
+/// Event handling producing an output
+pub trait Mealy: StateBehavior {
+    /// Output type of the machine
+    type Output;
+
+    /// Handle an event, returning the next state, if a transition occurs,
+    /// and the output, if any.
+    fn handle_output(
+        &self,
+        event: &Self::Event,
+        context: &mut Self::Context,
+    ) -> (Option<Self::State>, Option<Self::Output>);
+}
+
+/// Event handling producing a reply
+pub trait Respond: StateBehavior {
+    /// Reply type of the machine
+    type Reply;
+
+    /// Handle an event, returning the next state, if a transition occurs,
+    /// and the reply to the caller.
+    fn handle_reply(
+        &self,
+        event: &Self::Event,
+        context: &mut Self::Context,
+    ) -> (Option<Self::State>, Self::Reply);
+}
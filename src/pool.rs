@@ -0,0 +1,105 @@
+use crate::{DispatchError, StateMachine};
+
+/// Fixed-size pool of identical state machines.
+///
+/// The pool owns `N` instances of the same machine in a plain array, without
+/// any allocation. Machines are accessed by index, and events can be
+/// dispatched either to a single machine or to all of them at once.
+///
+/// The machines are built at run time, their constructors not being
+/// `const`, so the pool is made in `main` and owned by the main loop, or
+/// moved once made into whatever static cell the application uses:
+///
+/// ```rust,ignore
+/// let mut sensors: Pool<Sensor, 32> = Pool::new();
+///
+/// loop {
+///     if let Some(result) = sensors.dispatch(3, Events::Sample(42)) {
+///         result?;
+///     }
+///     sensors.dispatch_to_all(Events::Reset)?;
+/// }
+/// ```
+pub struct Pool<M, const N: usize> {
+    machines: [M; N],
+}
+
+impl<M: StateMachine, const N: usize> Pool<M, N> {
+    /// Create a new pool with `N` freshly constructed machines.
+    pub fn new() -> Self {
+        Self {
+            machines: core::array::from_fn(|_| M::new()),
+        }
+    }
+
+    /// Create a new pool with the machine at each index made by `make`, such
+    /// as machines starting in different states with
+    /// `new_at(state, context)`.
+    pub fn from_fn(make: impl FnMut(usize) -> M) -> Self {
+        Self {
+            machines: core::array::from_fn(make),
+        }
+    }
+
+    /// Number of machines in the pool.
+    pub const fn len(&self) -> usize {
+        N
+    }
+
+    /// Whether the pool holds no machines at all.
+    pub const fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    /// Get a reference to the machine at `index`.
+    pub fn get(&self, index: usize) -> Option<&M> {
+        self.machines.get(index)
+    }
+
+    /// Get a mutable reference to the machine at `index`.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut M> {
+        self.machines.get_mut(index)
+    }
+
+    /// Dispatch event to the machine at `index`.
+    ///
+    /// Returns `None` if `index` is out of bounds, and the result of the
+    /// dispatch otherwise.
+    pub fn dispatch(&mut self, index: usize, event: M::Event) -> Option<Result<(), DispatchError>> {
+        self.machines
+            .get_mut(index)
+            .map(|machine| machine.dispatch(event))
+    }
+
+    /// Dispatch the same event to every machine of the pool, in index order.
+    ///
+    /// A machine refusing the event doesn't keep it from the next ones, the
+    /// first error being returned once all of them had it.
+    pub fn dispatch_to_all(&mut self, event: M::Event) -> Result<(), DispatchError>
+    where
+        M::Event: Clone,
+    {
+        let mut result = Ok(());
+        for machine in self.machines.iter_mut() {
+            let dispatched = machine.dispatch(event.clone());
+            result = result.and(dispatched);
+        }
+        result
+    }
+
+    /// Iterate over the machines of the pool.
+    pub fn iter(&self) -> core::slice::Iter<'_, M> {
+        self.machines.iter()
+    }
+
+    /// Iterate mutably over the machines of the pool.
+    pub fn iter_mut(&mut self) -> core::slice::IterMut<'_, M> {
+        self.machines.iter_mut()
+    }
+}
+
+impl<M: StateMachine, const N: usize> Default for Pool<M, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,431 @@
+//! Internal event queue.
+//!
+//! A machine with a `Queue { depth: N }` section owns a queue of `N` events
+//! its states post follow-up events to from
+//! [`try_handle_with`](crate::StateBehavior::try_handle_with). They are
+//! dispatched in order, each one like an event given to `dispatch`, before
+//! the `dispatch` call that caused them returns, so multi-step reactions
+//! don't rely on the caller sending a second event.
+//!
+//! External code can queue events as well with the machine's `post`, then
+//! dispatch them all with `process_all`.
+//!
+//! Events are dispatched by decreasing priority, then in the order they
+//! were posted. The priority of an event is given by the `priority` function
+//! of the section, 0 for every event by default:
+//!
+//! ```rust,ignore
+//! Queue {
+//!     depth: 8,
+//!     priority: |event| match event {
+//!         Events::EmergencyStop => 1,
+//!         _ => 0,
+//!     },
+//! }
+//! ```
+//!
+//! so an emergency stop posted while `process_all` runs is dispatched before
+//! the events queued ahead of it. Once in a safety state,
+//! [`flush_below`](Outbox::flush_below) drops the stale lower priority
+//! events.
+//!
+//! In a superloop, a machine with a deep queue would hold up the other tasks
+//! until `process_all` returns. `process_for(n)` dispatches at most `n` queued
+//! events, and with a `Clock` section `process_budget(ticks)` those
+//! dispatched within `ticks`, both reporting with a [`Progress`] how many are
+//! left for the next pass:
+//!
+//! ```rust,ignore
+//! loop {
+//!     let progress = conveyor.process_for(4)?;
+//!     display.refresh();
+//!     if progress.is_done() {
+//!         cpu::wait_for_interrupt();
+//!     }
+//! }
+//! ```
+//!
+//! Posting returns a [`PostToken`], cancelling the event with `cancel_post`
+//! while it's still queued. `cancel_all_matching` drops the queued events and
+//! the pending timers of the same variant, so that retries scheduled in one
+//! state don't fire in the next one.
+//!
+//! ```rust,ignore
+//! fn try_handle_with(
+//!     &self,
+//!     event: &Self::Event,
+//!     context: &mut Self::Context,
+//!     outbox: &mut Outbox<'_, Self::Event>,
+//! ) -> Result<Option<Self::State>, &'static str> {
+//!     match (self, event) {
+//!         (Self::Filling, Events::Full) => {
+//!             let _ = outbox.post(Events::StartHeating);
+//!             Ok(Some(Self::Full))
+//!         }
+//!         _ => Ok(self.handle(event, context)),
+//!     }
+//! }
+//! ```
+
+use crate::rng::Rng;
+use crate::Source;
+
+/// Queue of the follow-up events of a machine
+#[doc(hidden)]
+pub trait Post<E> {
+    /// Take the next event to dispatch, if any.
+    fn pop(&mut self) -> Option<E>;
+
+    /// Drop all events.
+    fn clear(&mut self);
+
+    /// Queue `event`, giving it back if there is no room for it.
+    fn post(&mut self, event: E) -> Result<PostToken, E>;
+
+    /// Drop the event of `token`, returning whether it was still queued.
+    fn cancel(&mut self, token: PostToken) -> bool;
+
+    /// Drop the events of the same variant as `event`, returning how many
+    /// were dropped.
+    fn cancel_matching(&mut self, event: &E) -> usize;
+
+    /// Drop the events of priority lower than `priority`, returning how many
+    /// were dropped.
+    fn flush_below(&mut self, priority: u8) -> usize;
+
+    /// Next event to dispatch, if any
+    fn peek(&self) -> Option<&E> {
+        self.get(0)
+    }
+
+    /// Event queued at `index` in dispatch order, if any
+    fn get(&self, index: usize) -> Option<&E>;
+
+    /// Tick the next event to dispatch was posted at, if any
+    fn posted_at(&self) -> Option<u32>;
+
+    /// Priority of `event` in the queue
+    fn priority(&self, event: &E) -> u8;
+}
+
+/// Follow-up events posted while handling an event
+pub struct Outbox<'a, E> {
+    queue: &'a mut dyn Post<E>,
+    source: Source,
+    rng: Option<&'a mut dyn Rng>,
+}
+
+impl<'a, E> Outbox<'a, E> {
+    #[doc(hidden)]
+    pub fn new(queue: &'a mut dyn Post<E>, source: Source, rng: Option<&'a mut dyn Rng>) -> Self {
+        Self { queue, source, rng }
+    }
+
+    /// Where the event being handled came from, always `Source::Host`
+    /// without the `tagged` dispatch option
+    pub fn source(&self) -> Source {
+        self.source
+    }
+
+    /// Random number source of the machine, if it has an `Rng` section. See
+    /// the [`rng`](crate::rng) module.
+    pub fn rng(&mut self) -> Option<&mut (dyn Rng + 'a)> {
+        self.rng.as_deref_mut()
+    }
+
+    /// Post `event`, to be dispatched once the current one is handled,
+    /// giving it back if the queue is full or the machine has no `Queue`
+    /// section.
+    pub fn post(&mut self, event: E) -> Result<PostToken, E> {
+        self.queue.post(event)
+    }
+
+    /// Drop the queued event of `token`, returning whether it was still
+    /// queued.
+    pub fn cancel(&mut self, token: PostToken) -> bool {
+        self.queue.cancel(token)
+    }
+
+    /// Drop the queued events of priority lower than `priority`, returning
+    /// how many were dropped.
+    pub fn flush_below(&mut self, priority: u8) -> usize {
+        self.queue.flush_below(priority)
+    }
+}
+
+/// Handle to a queued event, cancelling it with the machine's `cancel_post`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PostToken(u32);
+
+/// Work done by a budgeted processing of the queue, `process_for` or
+/// `process_budget`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Progress {
+    /// Events dispatched within the budget
+    pub dispatched: usize,
+    /// Events still queued, for the next time slice
+    pub remaining: usize,
+}
+
+impl Progress {
+    /// Whether the queue was drained
+    pub fn is_done(&self) -> bool {
+        self.remaining == 0
+    }
+}
+
+/// Queued event
+#[derive(Clone, Debug)]
+struct Queued<E> {
+    token: u32,
+    posted_at: u32,
+    event: E,
+}
+
+/// Priority queue of up to `N` events, first in, first out among events of
+/// the same priority
+#[derive(Clone, Debug)]
+pub struct EventQueue<E, const N: usize> {
+    /// Queued events, in dispatch order
+    events: [Option<Queued<E>>; N],
+    len: usize,
+    next_token: u32,
+    priority: fn(&E) -> u8,
+    clock: fn() -> u32,
+}
+
+impl<E, const N: usize> EventQueue<E, N> {
+    /// Create an empty queue.
+    #[doc(hidden)]
+    pub const fn new() -> Self {
+        Self::with_priority(|_| 0)
+    }
+
+    /// Create an empty queue ordering events by `priority`.
+    #[doc(hidden)]
+    pub const fn with_priority(priority: fn(&E) -> u8) -> Self {
+        Self {
+            events: [const { None }; N],
+            len: 0,
+            next_token: 0,
+            priority,
+            clock: || 0,
+        }
+    }
+
+    /// Timestamp the events with `clock` as they're posted.
+    #[doc(hidden)]
+    pub const fn timestamped(mut self, clock: fn() -> u32) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Take the oldest event of the highest priority, if any.
+    #[doc(hidden)]
+    pub fn pop(&mut self) -> Option<E> {
+        if self.len == 0 {
+            return None;
+        }
+        self.remove(0)
+    }
+
+    /// Take the event at `index`, keeping the following ones in order
+    fn remove(&mut self, index: usize) -> Option<E> {
+        let following = self.events.get_mut(index..self.len)?;
+        let queued = following.first_mut()?.take();
+        following.rotate_left(1);
+        self.len -= 1;
+        queued.map(|queued| queued.event)
+    }
+
+    /// Slots of the queued events
+    fn queued(&self) -> &[Option<Queued<E>>] {
+        self.events.get(..self.len).unwrap_or_default()
+    }
+
+    /// Index of the first queued event of priority lower than `priority`
+    fn first_below(&self, priority: u8) -> usize {
+        self.queued()
+            .iter()
+            .position(|queued| {
+                queued
+                    .as_ref()
+                    .is_some_and(|queued| (self.priority)(&queued.event) < priority)
+            })
+            .unwrap_or(self.len)
+    }
+
+    /// Maximum number of events held
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Number of events held
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no event is queued
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Queued events, in dispatch order
+    pub fn iter(&self) -> impl Iterator<Item = &E> {
+        self.queued().iter().flatten().map(|queued| &queued.event)
+    }
+
+    /// Drop all events
+    pub fn clear(&mut self) {
+        self.events = [const { None }; N];
+        self.len = 0;
+    }
+}
+
+impl<E, const N: usize> Post<E> for EventQueue<E, N> {
+    fn pop(&mut self) -> Option<E> {
+        EventQueue::pop(self)
+    }
+
+    fn clear(&mut self) {
+        EventQueue::clear(self)
+    }
+
+    fn post(&mut self, event: E) -> Result<PostToken, E> {
+        if self.len == N {
+            return Err(event);
+        }
+        let token = self.next_token;
+        self.next_token = token.wrapping_add(1);
+        // Behind the events of the same priority
+        let index = self.first_below((self.priority)(&event));
+        let Some(following) = self.events.get_mut(index..=self.len) else {
+            return Err(event);
+        };
+        following.rotate_right(1);
+        if let Some(slot) = following.first_mut() {
+            *slot = Some(Queued {
+                token,
+                posted_at: (self.clock)(),
+                event,
+            });
+        }
+        self.len += 1;
+        Ok(PostToken(token))
+    }
+
+    fn cancel(&mut self, token: PostToken) -> bool {
+        let index = self.queued().iter().position(|queued| {
+            queued
+                .as_ref()
+                .is_some_and(|queued| queued.token == token.0)
+        });
+        index.and_then(|index| self.remove(index)).is_some()
+    }
+
+    fn cancel_matching(&mut self, event: &E) -> usize {
+        let variant = core::mem::discriminant(event);
+        let mut cancelled = 0;
+        let mut index = 0;
+        while index < self.len {
+            match self.events.get(index) {
+                Some(Some(queued)) if core::mem::discriminant(&queued.event) == variant => {
+                    self.remove(index);
+                    cancelled += 1;
+                }
+                _ => index += 1,
+            }
+        }
+        cancelled
+    }
+
+    fn flush_below(&mut self, priority: u8) -> usize {
+        let index = self.first_below(priority);
+        let flushed = self.len - index;
+        if let Some(flushed) = self.events.get_mut(index..self.len) {
+            flushed.fill_with(|| None);
+        }
+        self.len = index;
+        flushed
+    }
+
+    fn get(&self, index: usize) -> Option<&E> {
+        self.iter().nth(index)
+    }
+
+    fn posted_at(&self) -> Option<u32> {
+        self.queued()
+            .iter()
+            .flatten()
+            .next()
+            .map(|queued| queued.posted_at)
+    }
+
+    fn priority(&self, event: &E) -> u8 {
+        (self.priority)(event)
+    }
+}
+
+impl<E, const N: usize> Default for EventQueue<E, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Stand-in for the queue of machines without a `Queue` section, refusing
+/// every event
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug)]
+pub struct NoQueue;
+
+impl NoQueue {
+    #[doc(hidden)]
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+impl<E> Post<E> for NoQueue {
+    #[inline(always)]
+    fn pop(&mut self) -> Option<E> {
+        None
+    }
+
+    #[inline(always)]
+    fn clear(&mut self) {}
+
+    #[inline(always)]
+    fn post(&mut self, event: E) -> Result<PostToken, E> {
+        Err(event)
+    }
+
+    #[inline(always)]
+    fn cancel(&mut self, _token: PostToken) -> bool {
+        false
+    }
+
+    #[inline(always)]
+    fn cancel_matching(&mut self, _event: &E) -> usize {
+        0
+    }
+
+    #[inline(always)]
+    fn flush_below(&mut self, _priority: u8) -> usize {
+        0
+    }
+
+    #[inline(always)]
+    fn get(&self, _index: usize) -> Option<&E> {
+        None
+    }
+
+    #[inline(always)]
+    fn posted_at(&self) -> Option<u32> {
+        None
+    }
+
+    #[inline(always)]
+    fn priority(&self, _event: &E) -> u8 {
+        0
+    }
+}
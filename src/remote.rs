@@ -0,0 +1,261 @@
+//! Remote debug and control protocol.
+//!
+//! A [`Server`] answers requests read from a byte [`Transport`], a UART or a
+//! USB CDC link for instance, on behalf of any generated machine, which all
+//! implement [`Remote`]. This gives every product the same debug console.
+//!
+//! Requests and responses are frames made of a length byte followed by that
+//! many bytes. A request starts with a command:
+//!
+//! | command | arguments | response data |
+//! |---------|-----------|---------------|
+//! | [`QUERY_STATE`] | | current state id |
+//! | [`DUMP_CONTEXT`] | | context `Debug` text, truncated to the frame |
+//! | [`INJECT_EVENT`] | event wire frame, see [`codec`](crate::codec) | |
+//! | [`FORCE_STATE`] | state id | |
+//!
+//! A response starts with [`OK`] or one of the error codes below. State ids
+//! are their declaration positions, starting at 0, and only data-less states
+//! can be forced. Forcing a state doesn't run any `exit` or `enter`
+//! function.
+//!
+//! ```rust,ignore
+//! let mut console = Server::<_, 64>::new(uart);
+//! loop {
+//!     console.poll(&mut machine);
+//! }
+//! ```
+
+use core::fmt;
+
+use crate::codec::DecodeError;
+use crate::DispatchError;
+
+/// Query the current state
+pub const QUERY_STATE: u8 = 0x01;
+/// Dump the context
+pub const DUMP_CONTEXT: u8 = 0x02;
+/// Dispatch an event
+pub const INJECT_EVENT: u8 = 0x03;
+/// Force the current state
+pub const FORCE_STATE: u8 = 0x04;
+
+/// The request was served
+pub const OK: u8 = 0x00;
+/// The state machine is stopped
+pub const STOPPED: u8 = 0x01;
+/// The state machine reached a final state
+pub const FINISHED: u8 = 0x02;
+/// A fault was raised
+pub const FAULT: u8 = 0x03;
+/// The event was dropped by the machine's filter
+pub const FILTERED: u8 = 0x04;
+/// The event was vetoed by one of the machine's interceptors
+pub const VETOED: u8 = 0x05;
+/// The machine was already handling an event
+pub const REENTRANT: u8 = 0x06;
+/// The event led to no transition, and the machine refuses those
+pub const UNHANDLED: u8 = 0x07;
+/// The current state's guard refused the event
+pub const GUARD_REJECTED: u8 = 0x08;
+/// The state machine is paused
+pub const PAUSED: u8 = 0x09;
+/// The event's payload was refused by the machine's `Validate` section
+pub const INVALID: u8 = 0x0A;
+/// Unknown command
+pub const UNKNOWN_COMMAND: u8 = 0x80;
+/// Invalid arguments: undecodable event, unknown state or state with data
+pub const INVALID_ARGUMENT: u8 = 0x81;
+
+/// Byte link the requests are read from and the responses written to
+pub trait Transport {
+    /// Read the bytes available without blocking into `buf`, returning how
+    /// many were read.
+    fn read(&mut self, buf: &mut [u8]) -> usize;
+
+    /// Write all of `bytes`.
+    fn write(&mut self, bytes: &[u8]);
+}
+
+/// Server side of the protocol, implemented by every generated machine
+pub trait Remote {
+    /// Position of the current state in its declaration order.
+    fn state_id(&self) -> u8;
+
+    /// Write the context's `Debug` text to `out`.
+    fn dump_context(&self, out: &mut dyn fmt::Write) -> fmt::Result;
+
+    /// Decode an event from a wire frame and dispatch it.
+    fn inject(&mut self, frame: &[u8]) -> Result<Result<(), DispatchError>, DecodeError>;
+
+    /// Force the data-less state at position `id`, returning whether it
+    /// exists.
+    fn force_state_id(&mut self, id: u8) -> bool;
+}
+
+/// Protocol server over a transport, buffering requests of up to `N - 1`
+/// bytes
+///
+/// Requests longer than that can't be served and are dropped without a
+/// response. `N` must be at least 2.
+pub struct Server<T, const N: usize = 64> {
+    transport: T,
+    pending: [u8; N],
+    len: usize,
+    // Bytes of a dropped request still to come
+    skip: usize,
+}
+
+impl<T: Transport, const N: usize> Server<T, N> {
+    /// Create a server reading requests from `transport`.
+    pub const fn new(transport: T) -> Self {
+        Self {
+            transport,
+            pending: [0; N],
+            len: 0,
+            skip: 0,
+        }
+    }
+
+    /// Get a reference to the transport
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Get a mutable reference to the transport
+    pub fn transport_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    /// Give the transport back.
+    pub fn into_inner(self) -> T {
+        self.transport
+    }
+
+    /// Read the available bytes and serve every complete request on
+    /// `machine`, returning how many were served.
+    pub fn poll<M: Remote>(&mut self, machine: &mut M) -> usize {
+        if let Some(free) = self.pending.get_mut(self.len..) {
+            self.len += self.transport.read(free);
+        }
+
+        let mut served = 0;
+        loop {
+            if self.skip > 0 {
+                let skipped = self.skip.min(self.len);
+                self.consume(skipped);
+                self.skip -= skipped;
+            }
+            let Some(&request_len) = self.pending.get(..self.len).and_then(<[u8]>::first) else {
+                break;
+            };
+            let frame_len = 1 + request_len as usize;
+            if frame_len > N {
+                self.skip = frame_len;
+                continue;
+            }
+            if frame_len > self.len {
+                break;
+            }
+
+            let mut response = [0; N];
+            let (Some(request), Some((length, body))) =
+                (self.pending.get(1..frame_len), response.split_first_mut())
+            else {
+                break;
+            };
+            let response_len = serve(machine, request, body);
+            *length = response_len as u8;
+            if let Some(frame) = response.get(..1 + response_len) {
+                self.transport.write(frame);
+            }
+            served += 1;
+            self.consume(frame_len);
+        }
+        served
+    }
+
+    fn consume(&mut self, len: usize) {
+        self.pending.copy_within(len..self.len, 0);
+        self.len -= len;
+    }
+}
+
+/// Serve a request, writing the response to `response` and returning its
+/// length.
+fn serve<M: Remote>(machine: &mut M, request: &[u8], response: &mut [u8]) -> usize {
+    let Some((code, data)) = response.split_first_mut() else {
+        return 0;
+    };
+    // The length byte caps the responses
+    let data_len = data.len().min(u8::MAX as usize - 1);
+    let data = data.get_mut(..data_len).unwrap_or_default();
+
+    let (status, len) = match request {
+        [QUERY_STATE] => match data.first_mut() {
+            Some(byte) => {
+                *byte = machine.state_id();
+                (OK, 1)
+            }
+            None => (OK, 0),
+        },
+        [DUMP_CONTEXT] => {
+            let mut out = Truncate { buf: data, len: 0 };
+            // Running out of space truncates the dump
+            let _ = machine.dump_context(&mut out);
+            (OK, out.len)
+        }
+        [INJECT_EVENT, frame @ ..] => match machine.inject(frame) {
+            Ok(result) => (status(result), 0),
+            Err(_) => (INVALID_ARGUMENT, 0),
+        },
+        [FORCE_STATE, id] => match machine.force_state_id(*id) {
+            true => (OK, 0),
+            false => (INVALID_ARGUMENT, 0),
+        },
+        [FORCE_STATE | QUERY_STATE | DUMP_CONTEXT, ..] => (INVALID_ARGUMENT, 0),
+        _ => (UNKNOWN_COMMAND, 0),
+    };
+    *code = status;
+    1 + len
+}
+
+/// Response code of a dispatch result
+pub const fn status(result: Result<(), DispatchError>) -> u8 {
+    match result {
+        Ok(()) => OK,
+        Err(DispatchError::Stopped) => STOPPED,
+        Err(DispatchError::Finished) => FINISHED,
+        Err(DispatchError::Fault(_)) => FAULT,
+        Err(DispatchError::Filtered) => FILTERED,
+        Err(DispatchError::Vetoed) => VETOED,
+        Err(DispatchError::Reentrant) => REENTRANT,
+        Err(DispatchError::Unhandled) => UNHANDLED,
+        Err(DispatchError::GuardRejected { .. }) => GUARD_REJECTED,
+        Err(DispatchError::Paused) => PAUSED,
+        Err(DispatchError::Invalid(_)) => INVALID,
+        Err(DispatchError::UnexpectedEvent) => INVALID_ARGUMENT,
+    }
+}
+
+/// Writer into a byte slice, dropping what doesn't fit
+struct Truncate<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl fmt::Write for Truncate<'_> {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        let free = self.buf.get_mut(self.len..).unwrap_or_default();
+        let len = text.len().min(free.len());
+        for (byte, text_byte) in free.iter_mut().zip(text.bytes()) {
+            *byte = text_byte;
+        }
+        self.len += len;
+        if len < text.len() {
+            Err(fmt::Error)
+        } else {
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,84 @@
+//! Randomness for stochastic transitions.
+//!
+//! A machine with an `Rng { Source }` section owns a random number source,
+//! outside of its context, that guards and handlers draw from as they're
+//! run: guards through
+//! [`guard_with_rng`](crate::StateBehavior::guard_with_rng), handlers
+//! through the [`Outbox`](crate::queue::Outbox) of
+//! [`try_handle_with`](crate::StateBehavior::try_handle_with). Randomized
+//! backoff delays or chaos injection then don't depend on a platform RNG:
+//!
+//! ```rust,ignore
+//! fn try_handle_with(
+//!     &self,
+//!     event: &Self::Event,
+//!     context: &mut Self::Context,
+//!     outbox: &mut Outbox<'_, Self::Event>,
+//! ) -> Result<Option<Self::State>, &'static str> {
+//!     match (self, event) {
+//!         (Self::Connecting, Events::Failed) => {
+//!             let jitter = outbox.rng().map_or(0, |rng| rng.below(100));
+//!             context.backoff = context.backoff * 2 + jitter;
+//!             Ok(Some(Self::Waiting))
+//!         }
+//!         _ => Ok(self.handle(event, context)),
+//!     }
+//! }
+//! ```
+//!
+//! The source is any [`Rng`] implementing `Default`, such as a driver of the
+//! MCU's hardware RNG. [`Seeded`] is a deterministic one, drawing the same
+//! sequence from the same seed, for reproducible tests: seed it with
+//! `machine.rng_mut()`.
+
+/// Source of random numbers
+pub trait Rng {
+    /// Next random number, uniformly distributed
+    fn next_u32(&mut self) -> u32;
+
+    /// Random number uniformly distributed below `bound`, 0 for a bound of 0
+    fn below(&mut self, bound: u32) -> u32 {
+        // Multiply-shift, avoiding a division on cores without one
+        ((u64::from(self.next_u32()) * u64::from(bound)) >> 32) as u32
+    }
+
+    /// Whether an event of probability `numerator / denominator` happens
+    fn chance(&mut self, numerator: u32, denominator: u32) -> bool {
+        self.below(denominator) < numerator
+    }
+}
+
+/// Deterministic xorshift generator, drawing the same sequence from the same
+/// seed
+///
+/// It isn't suitable for cryptography.
+#[derive(Clone, Debug)]
+pub struct Seeded(u32);
+
+impl Seeded {
+    /// Generator seeded with `seed`, a seed of 0 being replaced by a fixed
+    /// non-zero one.
+    pub const fn new(seed: u32) -> Self {
+        match seed {
+            0 => Self(0x9E37_79B9),
+            seed => Self(seed),
+        }
+    }
+}
+
+impl Default for Seeded {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl Rng for Seeded {
+    fn next_u32(&mut self) -> u32 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.0 = x;
+        x
+    }
+}
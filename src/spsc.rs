@@ -0,0 +1,162 @@
+//! Event queue between an interrupt handler and a state machine.
+//!
+//! With the `heapless` feature, a [`heapless::spsc::Queue`] is split into an
+//! [`EventProducer`], moved into the interrupt handler, and an
+//! [`EventConsumer`] feeding the machine from the main loop. The queue
+//! handles the memory ordering: an event is only seen by the consumer once
+//! it's completely written.
+//!
+//! The queue has a single producer, so the producer handle can't be cloned.
+//! Give each interrupt handler posting events its own queue.
+//!
+//! An [`EventChannel`] is a queue to put in a plain `static`, split once
+//! into `'static` handles without any `unsafe` on the caller's side. The
+//! producer is `Send` for events that are, so on a dual-core MCU it can be
+//! moved to the second core, which injects events while the first one owns
+//! the machine:
+//!
+//! ```rust,ignore
+//! static CHANNEL: EventChannel<Events, 8> = EventChannel::new();
+//!
+//! let (producer, mut consumer) = CHANNEL.split().unwrap();
+//! core1.spawn(move || radio_task(producer));
+//! loop {
+//!     consumer.process_all(&mut machine);
+//! }
+//! ```
+//!
+//! Splitting takes an atomic swap, so the channel isn't available on targets
+//! without atomic read-modify-write operations, such as the Cortex-M0, nor
+//! with the `audit` feature, which forbids its `unsafe` internals.
+//!
+//! ```rust,ignore
+//! static mut QUEUE: Queue<Events, 8> = Queue::new();
+//!
+//! // Before enabling the interrupt
+//! let (producer, mut consumer) = rustfsm::spsc::split(unsafe { &mut QUEUE });
+//! BUTTON.init(producer);
+//!
+//! #[interrupt]
+//! fn EXTI0() {
+//!     let _ = BUTTON.get().post(Events::Pressed);
+//! }
+//!
+//! loop {
+//!     consumer.process_all(&mut machine);
+//! }
+//! ```
+
+#[cfg(all(target_has_atomic = "8", not(feature = "audit")))]
+use core::cell::UnsafeCell;
+#[cfg(all(target_has_atomic = "8", not(feature = "audit")))]
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use heapless::spsc::{Consumer, Producer, Queue};
+
+use crate::StateMachine;
+
+/// Split `queue` into its producer and consumer handles.
+pub fn split<E, const N: usize>(
+    queue: &mut Queue<E, N>,
+) -> (EventProducer<'_, E, N>, EventConsumer<'_, E, N>) {
+    let (producer, consumer) = queue.split();
+    (EventProducer(producer), EventConsumer(consumer))
+}
+
+/// Event queue meant for a `static`, split once into `'static` handles
+#[cfg(all(target_has_atomic = "8", not(feature = "audit")))]
+pub struct EventChannel<E, const N: usize> {
+    queue: UnsafeCell<Queue<E, N>>,
+    split: AtomicBool,
+}
+
+// SAFETY: the queue is only reached through the handles given out by
+// `split`, once, which synchronize with each other and are `Send` for `Send`
+// events.
+#[cfg(all(target_has_atomic = "8", not(feature = "audit")))]
+unsafe impl<E: Send, const N: usize> Sync for EventChannel<E, N> {}
+
+#[cfg(all(target_has_atomic = "8", not(feature = "audit")))]
+impl<E, const N: usize> EventChannel<E, N> {
+    /// Create an empty channel holding up to `N - 1` events.
+    pub const fn new() -> Self {
+        Self {
+            queue: UnsafeCell::new(Queue::new()),
+            split: AtomicBool::new(false),
+        }
+    }
+
+    /// Split the channel into its producer and consumer handles, `None` if
+    /// it was already split.
+    pub fn split(
+        &'static self,
+    ) -> Option<(EventProducer<'static, E, N>, EventConsumer<'static, E, N>)> {
+        if self.split.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+        // SAFETY: the flag makes this the only reference to the queue ever
+        // created.
+        Some(split(unsafe { &mut *self.queue.get() }))
+    }
+}
+
+#[cfg(all(target_has_atomic = "8", not(feature = "audit")))]
+impl<E, const N: usize> Default for EventChannel<E, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Producer side of an event queue, for the interrupt handler
+pub struct EventProducer<'a, E, const N: usize>(Producer<'a, E, N>);
+
+impl<E, const N: usize> EventProducer<'_, E, N> {
+    /// Post an event, giving it back if the queue is full.
+    pub fn post(&mut self, event: E) -> Result<(), E> {
+        self.0.enqueue(event)
+    }
+
+    /// Whether the queue has room for another event.
+    pub fn ready(&self) -> bool {
+        self.0.ready()
+    }
+}
+
+/// Consumer side of an event queue, feeding a state machine
+pub struct EventConsumer<'a, E, const N: usize>(Consumer<'a, E, N>);
+
+impl<E, const N: usize> EventConsumer<'_, E, N> {
+    /// Dispatch every queued event to `machine`, returning how many were
+    /// dispatched.
+    ///
+    /// Events posted while processing are dispatched as well.
+    pub fn process_all<M: StateMachine<Event = E>>(&mut self, machine: &mut M) -> usize {
+        let mut processed = 0;
+        while let Some(event) = self.0.dequeue() {
+            machine.handle(event);
+            processed += 1;
+        }
+        processed
+    }
+
+    /// Dispatch the oldest queued event to `machine`, if any.
+    pub fn process_one<M: StateMachine<Event = E>>(&mut self, machine: &mut M) -> bool {
+        match self.0.dequeue() {
+            Some(event) => {
+                machine.handle(event);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Number of queued events.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether no event is queued.
+    pub fn is_empty(&self) -> bool {
+        !self.0.ready()
+    }
+}
@@ -0,0 +1,108 @@
+//! Health snapshot of a machine for telemetry.
+//!
+//! Every generated machine assembles a [`Stats`] with `stats()`, its current
+//! state, how long it has been there, how many transitions it took and the
+//! last ones. It encodes with [`Wire`] into `Stats::<N>::SIZE` bytes, 18 for
+//! the last 8 transitions, small enough for a single LoRa or BLE payload:
+//!
+//! ```rust,ignore
+//! let mut payload = [0; Stats::<8>::SIZE];
+//! pump.stats::<8>().write(&mut payload);
+//! radio.send(&payload);
+//! ```
+//!
+//! The counts come from the machine's instrumentation: the time in state
+//! from its `Clock` section, the transition count from the `metrics` feature
+//! and the last transitions from its `Trace` section. `stats()` fails to
+//! compile without any of them, as does a `Stats` keeping more than 255
+//! transitions.
+
+use crate::codec::Wire;
+
+/// Snapshot of a machine, keeping up to `N` of its last transitions
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Stats<const N: usize> {
+    /// Id of the current state, its declaration position
+    pub state: u8,
+    /// Ticks spent in the current state
+    pub time_in_state: u32,
+    /// Number of transitions taken
+    pub transitions: u32,
+    recent: [u8; N],
+    len: u8,
+}
+
+impl<const N: usize> Stats<N> {
+    // The count of recent transitions is encoded as a byte
+    const FITS: () = assert!(N <= u8::MAX as usize, "`Stats` keeps up to 255 transitions");
+
+    /// Assemble a snapshot from the ids of the states entered by the last
+    /// transitions, oldest first, keeping the last `N` of them.
+    #[doc(hidden)]
+    pub fn new(
+        state: u8,
+        time_in_state: u32,
+        transitions: u32,
+        entered: impl Iterator<Item = u8>,
+    ) -> Self {
+        let () = Self::FITS;
+        let mut recent = [0; N];
+        let mut len = 0;
+        for id in entered.filter(|_| N > 0) {
+            if len == N {
+                recent.rotate_left(1);
+                len -= 1;
+            }
+            if let Some(slot) = recent.get_mut(len) {
+                *slot = id;
+                len += 1;
+            }
+        }
+        Self {
+            state,
+            time_in_state,
+            transitions,
+            recent,
+            len: len as u8,
+        }
+    }
+
+    /// Ids of the states entered by the last transitions, oldest first
+    pub fn recent(&self) -> &[u8] {
+        self.recent.get(..self.len as usize).unwrap_or_default()
+    }
+}
+
+impl<const N: usize> Wire for Stats<N> {
+    const SIZE: usize = 1 + 4 + 4 + 1 + N;
+
+    fn write(&self, buf: &mut [u8]) {
+        if buf.len() < Self::SIZE {
+            return;
+        }
+        let (state, buf) = buf.split_at_mut(1);
+        let (time_in_state, buf) = buf.split_at_mut(4);
+        let (transitions, buf) = buf.split_at_mut(4);
+        let (len, recent) = buf.split_at_mut(1);
+        self.state.write(state);
+        self.time_in_state.write(time_in_state);
+        self.transitions.write(transitions);
+        self.len.write(len);
+        self.recent.write(recent);
+    }
+
+    fn read(buf: &[u8]) -> Option<Self> {
+        let () = Self::FITS;
+        let len = u8::read(buf.get(9..10)?)?;
+        if len as usize > N {
+            return None;
+        }
+        Some(Self {
+            state: u8::read(buf.get(..1)?)?,
+            time_in_state: u32::read(buf.get(1..5)?)?,
+            transitions: u32::read(buf.get(5..9)?)?,
+            recent: <[u8; N]>::read(buf.get(10..)?)?,
+            len,
+        })
+    }
+}
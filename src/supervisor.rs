@@ -0,0 +1,158 @@
+//! Supervision of state machines.
+
+use crate::{DispatchError, Fault, FsmCore, ResetPolicy, StateMachine};
+
+/// What a [`Supervisor`] does about a failure of its child
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Recovery {
+    /// Report the failure to the caller, the child carrying on as it is
+    Ignore,
+    /// Reset and start the child again, up to the strategy's restart limit
+    Restart,
+    /// Stop the child and refuse events until the supervisor is restarted
+    Escalate,
+}
+
+/// Recovery of a [`Supervisor`] for each kind of failure of its child
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Strategy {
+    /// On a fault raised by a handler or an invariant check
+    pub on_fault: Recovery,
+    /// On a state outliving its watchdog limit
+    pub on_watchdog: Recovery,
+    /// On an event refused by the current state's guard
+    pub on_guard_rejected: Recovery,
+    /// Restarts allowed before escalating instead
+    pub max_restarts: u32,
+    /// Context handling of the restarts
+    pub reset: ResetPolicy,
+}
+
+impl Default for Strategy {
+    /// Restart on faults and watchdog expiries, at most 3 times, ignoring
+    /// guard rejections.
+    fn default() -> Self {
+        Self {
+            on_fault: Recovery::Restart,
+            on_watchdog: Recovery::Restart,
+            on_guard_rejected: Recovery::Ignore,
+            max_restarts: 3,
+            reset: ResetPolicy::RestoreDefaults,
+        }
+    }
+}
+
+/// State machine restarted or escalated on failure, Erlang style
+///
+/// The supervisor watches the outcome of everything run on its child. A
+/// failure is handled according to its [`Strategy`]: reported, recovered by
+/// restarting the child through its lifecycle API, `reset` then `start`, or
+/// escalated to the caller, the child being stopped until
+/// [`restart`](Self::restart) is called. Once the child has used up its
+/// restarts, the next failure to restart escalates.
+///
+/// ```rust,ignore
+/// let mut pump = Supervisor::new(Pump::new(), Strategy::default());
+///
+/// pump.dispatch(Events::Prime)?;
+/// pump.run(|pump| pump.tick())?;
+/// if let Some(failure) = pump.escalation() {
+///     log::error!("pump gave up: {failure:?}");
+/// }
+/// ```
+pub struct Supervisor<M> {
+    child: M,
+    strategy: Strategy,
+    restarts: u32,
+    escalation: Option<DispatchError>,
+}
+
+impl<M: StateMachine + FsmCore> Supervisor<M> {
+    /// Supervise `child` according to `strategy`.
+    pub const fn new(child: M, strategy: Strategy) -> Self {
+        Self {
+            child,
+            strategy,
+            restarts: 0,
+            escalation: None,
+        }
+    }
+
+    /// Dispatch event to the child, handling its failure.
+    ///
+    /// Returns `Ok` for a failure recovered by a restart, and the failure
+    /// itself when it's ignored or escalated. Once escalated, events are
+    /// refused with the failure escalated.
+    pub fn dispatch(&mut self, event: M::Event) -> Result<(), DispatchError> {
+        self.run(|child| child.dispatch(event))
+    }
+
+    /// Run `f` on the child, such as its `tick()`, handling its failure like
+    /// [`dispatch`](Self::dispatch).
+    pub fn run(
+        &mut self,
+        f: impl FnOnce(&mut M) -> Result<(), DispatchError>,
+    ) -> Result<(), DispatchError> {
+        if let Some(failure) = self.escalation {
+            return Err(failure);
+        }
+        let Err(error) = f(&mut self.child) else {
+            return Ok(());
+        };
+        let recovery = match error {
+            DispatchError::Fault(Fault::Watchdog(_)) => self.strategy.on_watchdog,
+            DispatchError::Fault(_) => self.strategy.on_fault,
+            DispatchError::GuardRejected { .. } => self.strategy.on_guard_rejected,
+            _ => Recovery::Ignore,
+        };
+        match recovery {
+            Recovery::Ignore => Err(error),
+            Recovery::Restart if self.restarts < self.strategy.max_restarts => {
+                self.restarts += 1;
+                self.child.reset(self.strategy.reset);
+                self.child.start();
+                Ok(())
+            }
+            Recovery::Restart | Recovery::Escalate => {
+                self.child.stop();
+                self.escalation = Some(error);
+                Err(error)
+            }
+        }
+    }
+
+    /// Get a reference to the child.
+    pub fn child(&self) -> &M {
+        &self.child
+    }
+
+    /// Get a mutable reference to the child, its failures going unnoticed.
+    pub fn child_mut(&mut self) -> &mut M {
+        &mut self.child
+    }
+
+    /// Number of restarts of the child since the supervisor was created or
+    /// restarted
+    pub fn restarts(&self) -> u32 {
+        self.restarts
+    }
+
+    /// Failure escalated, if any
+    pub fn escalation(&self) -> Option<DispatchError> {
+        self.escalation
+    }
+
+    /// Reset and start the child, clearing the escalation and the restart
+    /// count.
+    pub fn restart(&mut self) {
+        self.child.reset(self.strategy.reset);
+        self.child.start();
+        self.restarts = 0;
+        self.escalation = None;
+    }
+
+    /// Unwrap the child.
+    pub fn into_inner(self) -> M {
+        self.child
+    }
+}
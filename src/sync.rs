@@ -0,0 +1,97 @@
+//! Thread-safe state machine wrapper.
+
+use std::sync::{Mutex, MutexGuard};
+
+use crate::{DispatchError, StateMachine};
+
+/// What to do with a machine whose lock was poisoned by a thread panicking
+/// while holding it
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PoisonPolicy {
+    /// Panic as well
+    Panic,
+    /// Keep using the machine as the panicking thread left it
+    Recover,
+    /// Replace the machine with a new one
+    Reset,
+}
+
+/// State machine shared between threads behind a mutex
+///
+/// ```rust,ignore
+/// let sensor = Arc::new(SyncFsm::new(Sensor::new()));
+///
+/// let worker = Arc::clone(&sensor);
+/// thread::spawn(move || worker.dispatch(Events::Sample(42)));
+/// println!("{:?}", sensor.state());
+/// ```
+pub struct SyncFsm<M> {
+    machine: Mutex<M>,
+    policy: PoisonPolicy,
+}
+
+impl<M: StateMachine> SyncFsm<M> {
+    /// Wrap `machine`, panicking on poisoned locks.
+    pub const fn new(machine: M) -> Self {
+        Self::with_policy(machine, PoisonPolicy::Panic)
+    }
+
+    /// Wrap `machine`, handling poisoned locks according to `policy`.
+    pub const fn with_policy(machine: M, policy: PoisonPolicy) -> Self {
+        Self {
+            machine: Mutex::new(machine),
+            policy,
+        }
+    }
+
+    /// Dispatch event and transition if necessary.
+    pub fn dispatch(&self, event: M::Event) -> Result<(), DispatchError> {
+        self.lock().dispatch(event)
+    }
+
+    /// Get a copy of the current state
+    pub fn state(&self) -> M::State {
+        self.lock().get_current_state()
+    }
+
+    /// Run `f` with exclusive access to the machine.
+    pub fn with<R>(&self, f: impl FnOnce(&mut M) -> R) -> R {
+        f(&mut self.lock())
+    }
+
+    /// Lock the machine, holding other threads off until the guard is
+    /// dropped.
+    // Panicking is what `PoisonPolicy::Panic` asks for
+    #[allow(clippy::panic)]
+    pub fn lock(&self) -> MutexGuard<'_, M> {
+        match self.machine.lock() {
+            Ok(machine) => machine,
+            Err(poisoned) => {
+                let machine = match self.policy {
+                    PoisonPolicy::Panic => panic!("state machine lock poisoned"),
+                    PoisonPolicy::Recover => poisoned.into_inner(),
+                    PoisonPolicy::Reset => {
+                        let mut machine = poisoned.into_inner();
+                        *machine = M::new();
+                        machine
+                    }
+                };
+                self.machine.clear_poison();
+                machine
+            }
+        }
+    }
+
+    /// Unwrap the machine, handling a poisoned lock like [`lock`](Self::lock).
+    #[allow(clippy::panic)]
+    pub fn into_inner(self) -> M {
+        match self.machine.into_inner() {
+            Ok(machine) => machine,
+            Err(poisoned) => match self.policy {
+                PoisonPolicy::Panic => panic!("state machine lock poisoned"),
+                PoisonPolicy::Recover => poisoned.into_inner(),
+                PoisonPolicy::Reset => M::new(),
+            },
+        }
+    }
+}
@@ -0,0 +1,212 @@
+//! Transition table introspection.
+
+/// Source state index of the rules written `_ + Event`, applying to every
+/// state without a rule of its own for the event
+pub const ANY_STATE: usize = usize::MAX;
+
+/// Rule of a transition table, with states given by their index
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TableRule {
+    /// Index of the source state, [`ANY_STATE`] for rules applying to every
+    /// state
+    pub from: usize,
+    /// Event pattern, as written in the table
+    pub event: &'static str,
+    /// Index of the target state, `None` for `ignore` rules
+    pub to: Option<usize>,
+    /// Name of the rule, as written in `name: From + Event => To`
+    pub name: Option<&'static str>,
+    /// Ticks to spend in the source state before the rule applies, as
+    /// written in `From + Event => To @ min_interval(ticks)`, 0 if not given
+    pub min_interval: u32,
+}
+
+impl TableRule {
+    /// Whether the rule may apply in the state at index `state`.
+    ///
+    /// Rules for any state are assumed to apply everywhere, even though the
+    /// rules of a state take precedence over them.
+    pub const fn applies_to(&self, state: usize) -> bool {
+        self.from == ANY_STATE || self.from == state
+    }
+}
+
+/// Position of `rule` in `table`.
+#[doc(hidden)]
+pub fn rule_index(table: &[TableRule], rule: &TableRule) -> Option<usize> {
+    table.iter().position(|entry| entry == rule)
+}
+
+/// Whether a rule of `table` has a minimum interval.
+#[doc(hidden)]
+pub const fn has_min_interval(mut table: &[TableRule]) -> bool {
+    while let [rule, rest @ ..] = table {
+        if rule.min_interval > 0 {
+            return true;
+        }
+        table = rest;
+    }
+    false
+}
+
+/// Rule of `table` named `name`, and its position in the table.
+pub fn rule_named(table: &'static [TableRule], name: &str) -> Option<(usize, &'static TableRule)> {
+    table
+        .iter()
+        .enumerate()
+        .find(|(_, rule)| rule.name == Some(name))
+}
+
+/// Rules of `table` leading out of the state at index `state`.
+///
+/// Rules for any state are included unless the state has a rule of its own
+/// for the same event pattern, as written. `ignore` rules are left out.
+pub fn transitions_from(
+    table: &'static [TableRule],
+    state: usize,
+) -> impl Iterator<Item = &'static TableRule> {
+    table.iter().filter(move |rule| {
+        rule.to.is_some()
+            && (rule.from == state
+                || rule.from == ANY_STATE
+                    && !table
+                        .iter()
+                        .any(|own| own.from == state && own.event == rule.event))
+    })
+}
+
+/// Compute which states are reachable from `initial` by following the
+/// transition table.
+#[doc(hidden)]
+// Const, so indexing rather than `get`, every index being checked against `N`
+#[allow(clippy::indexing_slicing)]
+pub const fn reachable<const N: usize>(initial: usize, table: &[TableRule]) -> [bool; N] {
+    let mut reachable = [false; N];
+    if initial < N {
+        reachable[initial] = true;
+    }
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        let mut rules = table;
+        while let [rule, rest @ ..] = rules {
+            if let Some(to) = rule.to {
+                let from = rule.from;
+                let from_reached = from == ANY_STATE || from < N && reachable[from];
+                if from_reached && to < N && !reachable[to] {
+                    reachable[to] = true;
+                    changed = true;
+                }
+            }
+            rules = rest;
+        }
+    }
+    reachable
+}
+
+/// Collect the names of the states not flagged in `reachable`.
+///
+/// Returns the names packed at the start of the array, and how many there
+/// are.
+#[doc(hidden)]
+// Const, so indexing rather than `get`, every index being below `N`
+#[allow(clippy::indexing_slicing)]
+pub const fn unreachable_names<const N: usize>(
+    names: &[&'static str],
+    reachable: [bool; N],
+) -> ([&'static str; N], usize) {
+    let mut unreachable = [""; N];
+    let mut count = 0;
+    let mut i = 0;
+    while i < N && i < names.len() {
+        if !reachable[i] {
+            unreachable[count] = names[i];
+            count += 1;
+        }
+        i += 1;
+    }
+    (unreachable, count)
+}
+
+/// Sequence of events leading from one state to another, for a machine with
+/// `N` states
+#[cfg(feature = "reachability")]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct EventPath<const N: usize> {
+    events: [&'static str; N],
+    len: usize,
+}
+
+#[cfg(feature = "reachability")]
+impl<const N: usize> EventPath<N> {
+    /// Event patterns of the path, as written in the transition table
+    pub fn events(&self) -> &[&'static str] {
+        self.events.get(..self.len).unwrap_or_default()
+    }
+
+    /// Number of events in the path
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the path is empty, going from a state to itself
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// Breadth-first search of the shortest path from `from` to `to` through the
+/// transition table of a machine with `N` states.
+#[cfg(feature = "reachability")]
+#[doc(hidden)]
+pub fn shortest_path<const N: usize>(
+    table: &[TableRule],
+    from: usize,
+    to: usize,
+) -> Option<EventPath<N>> {
+    // Rule used to first reach each state, and the state it was taken from
+    let mut reached_by: [Option<(usize, usize)>; N] = [None; N];
+    let mut visited = [false; N];
+    let mut queue = [0; N];
+    let (mut head, mut tail) = (0, 0);
+
+    *visited.get_mut(from)? = true;
+    *queue.get_mut(tail)? = from;
+    tail += 1;
+    while head < tail && !*visited.get(to)? {
+        let state = *queue.get(head)?;
+        head += 1;
+        for (index, rule) in table.iter().enumerate() {
+            let Some(next) = rule.to.filter(|_| rule.applies_to(state)) else {
+                continue;
+            };
+            match visited.get_mut(next) {
+                Some(seen) if !*seen => {
+                    *seen = true;
+                    *reached_by.get_mut(next)? = Some((index, state));
+                    *queue.get_mut(tail)? = next;
+                    tail += 1;
+                }
+                _ => (),
+            }
+        }
+    }
+    if !*visited.get(to)? {
+        return None;
+    }
+
+    // Walk back from the target, then put the events in order
+    let mut path = EventPath {
+        events: [""; N],
+        len: 0,
+    };
+    let mut state = to;
+    while let Some((index, previous)) = *reached_by.get(state)? {
+        *path.events.get_mut(path.len)? = table.get(index)?.event;
+        path.len += 1;
+        state = previous;
+    }
+    path.events.get_mut(..path.len)?.reverse();
+    Some(path)
+}
@@ -0,0 +1,379 @@
+//! Testing helpers for state machines.
+
+use crate::{DispatchError, StateMachine};
+
+/// Small seeded pseudo-random number generator (xorshift64*)
+///
+/// Good enough to drive tests reproducibly, not meant for anything else.
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Create a generator from a seed. The same seed always yields the same
+    /// sequence.
+    pub fn new(seed: u64) -> Self {
+        // xorshift gets stuck on a zero state
+        Self {
+            state: seed ^ 0x9E37_79B9_7F4A_7C15,
+        }
+    }
+
+    /// Next pseudo-random number
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Next pseudo-random number in `0..bound`
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+}
+
+/// Invariant violation found during a random walk
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct WalkFailure<S, E> {
+    /// Step at which the invariant failed, starting at 0
+    pub step: usize,
+    /// Event dispatched at that step
+    pub event: E,
+    /// State the machine ended up in
+    pub state: S,
+    /// Reason given by the invariant
+    pub reason: &'static str,
+}
+
+/// Seeded random walk feeding events to a state machine
+///
+/// At every step an event is picked at random among `events` and dispatched,
+/// then the user-supplied invariant is checked against the resulting state and
+/// context. The walk ends early once the machine stops accepting events.
+///
+/// ```rust,ignore
+/// let mut mario = Mario::new();
+/// RandomWalk::new(42, &[Events::Hit, Events::GetConsumable(Mushroom)])
+///     .steps(1000)
+///     .run(&mut mario, |state, context| match state {
+///         MarioStates::DeadMario if context.alive => Err("dead but alive"),
+///         _ => Ok(()),
+///     })
+///     .unwrap();
+/// ```
+pub struct RandomWalk<'a, E> {
+    seed: u64,
+    steps: usize,
+    events: &'a [E],
+}
+
+impl<'a, E: Clone> RandomWalk<'a, E> {
+    /// Create a walk of 100 steps picking among `events`.
+    pub fn new(seed: u64, events: &'a [E]) -> Self {
+        Self {
+            seed,
+            steps: 100,
+            events,
+        }
+    }
+
+    /// Set the number of steps.
+    pub fn steps(mut self, steps: usize) -> Self {
+        self.steps = steps;
+        self
+    }
+
+    /// Run the walk on `machine`, checking `invariant` after every step.
+    ///
+    /// Returns the number of steps taken, or the first invariant violation.
+    pub fn run<M, F>(
+        &self,
+        machine: &mut M,
+        mut invariant: F,
+    ) -> Result<usize, WalkFailure<M::State, E>>
+    where
+        M: StateMachine<Event = E>,
+        F: FnMut(&M::State, &M::Context) -> Result<(), &'static str>,
+    {
+        if self.events.is_empty() {
+            return Ok(0);
+        }
+
+        let mut rng = Rng::new(self.seed);
+        for step in 0..self.steps {
+            let Some(event) = self.events.get(rng.below(self.events.len())).cloned() else {
+                continue;
+            };
+            match machine.dispatch(event.clone()) {
+                Err(DispatchError::Stopped) | Err(DispatchError::Finished) => return Ok(step),
+                _ => (),
+            }
+            let state = machine.get_current_state();
+            if let Err(reason) = invariant(&state, machine.context()) {
+                return Err(WalkFailure {
+                    step,
+                    event,
+                    state,
+                    reason,
+                });
+            }
+        }
+        Ok(self.steps)
+    }
+}
+
+/// One step of a replayed trajectory
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Step<S, E> {
+    /// Event dispatched
+    pub event: E,
+    /// State before the event
+    pub from: S,
+    /// State after the event
+    pub to: S,
+    /// Outcome of the dispatch
+    pub result: Result<(), DispatchError>,
+}
+
+/// Iterator replaying a recorded event log on a state machine
+///
+/// Created by [`replay`], yields one [`Step`] per event.
+pub struct Replay<'a, M: StateMachine> {
+    machine: &'a mut M,
+    events: core::slice::Iter<'a, M::Event>,
+}
+
+impl<M> Iterator for Replay<'_, M>
+where
+    M: StateMachine,
+    M::Event: Clone,
+{
+    type Item = Step<M::State, M::Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let event = self.events.next()?.clone();
+        let from = self.machine.get_current_state();
+        let result = self.machine.dispatch(event.clone());
+        Some(Step {
+            event,
+            from,
+            to: self.machine.get_current_state(),
+            result,
+        })
+    }
+}
+
+/// Replay `events` on `machine`, one dispatch per step of the returned
+/// iterator.
+///
+/// ```rust,ignore
+/// let log = [Events::GetConsumable(Flower), Events::Hit];
+/// for step in replay(&mut mario, &log) {
+///     println!("{:?}: {:?} -> {:?}", step.event, step.from, step.to);
+/// }
+/// ```
+pub fn replay<'a, M: StateMachine>(machine: &'a mut M, events: &'a [M::Event]) -> Replay<'a, M> {
+    Replay {
+        machine,
+        events: events.iter(),
+    }
+}
+
+/// Replay `events` on `machine` and assert the state reached after each of
+/// them.
+///
+/// Panics on the first step that doesn't match, or if `events` and
+/// `expected_states` differ in length.
+#[track_caller]
+pub fn assert_trajectory<M>(machine: &mut M, events: &[M::Event], expected_states: &[M::State])
+where
+    M: StateMachine,
+    M::State: PartialEq + core::fmt::Debug,
+    M::Event: Clone + core::fmt::Debug,
+{
+    assert_eq!(
+        events.len(),
+        expected_states.len(),
+        "trajectory has {} events but {} expected states",
+        events.len(),
+        expected_states.len()
+    );
+    for (index, (step, expected)) in replay(machine, events).zip(expected_states).enumerate() {
+        assert!(
+            step.to == *expected,
+            "step {}: {:?} from {:?} led to {:?}, expected {:?}",
+            index,
+            step.event,
+            step.from,
+            step.to,
+            expected
+        );
+    }
+}
+
+/// Start an expectation on `machine`, reading as a specification of its
+/// behavior.
+///
+/// Every assertion panics with the event, the states before and after it,
+/// and the outcome of the dispatch:
+///
+/// ```rust,ignore
+/// expect(&mut mario)
+///     .on(Events::GetConsumable(Mushroom))
+///     .transitions_to(SuperMario)
+///     .with(|context| context.size == Large)
+///     .on(Events::Hit)
+///     .transitions_to(SmallMario)
+///     .on_sequence(&[Events::Hit, Events::Hit])
+///     .fails_with(DispatchError::Finished);
+/// ```
+pub fn expect<M: StateMachine>(machine: &mut M) -> Expect<'_, M> {
+    Expect { machine }
+}
+
+/// Machine events are dispatched to, created by [`expect`]
+pub struct Expect<'a, M> {
+    machine: &'a mut M,
+}
+
+impl<'a, M> Expect<'a, M>
+where
+    M: StateMachine,
+    M::State: PartialEq + core::fmt::Debug,
+    M::Event: Clone + core::fmt::Debug,
+{
+    /// Assert the machine is in `state`.
+    #[track_caller]
+    pub fn in_state(self, state: M::State) -> Self {
+        let current = self.machine.get_current_state();
+        assert!(current == state, "in {:?}, expected {:?}", current, state);
+        self
+    }
+
+    /// Dispatch `event`, to assert what it led to.
+    pub fn on(self, event: M::Event) -> Expectation<'a, M> {
+        let from = self.machine.get_current_state();
+        let result = self.machine.dispatch(event.clone());
+        Expectation {
+            machine: self.machine,
+            from,
+            last: Some(event),
+            result,
+        }
+    }
+
+    /// Dispatch `events` in order, to assert what they led to together.
+    ///
+    /// The outcome is the first failed dispatch, if any, every event being
+    /// dispatched whatever the outcome of the previous ones.
+    pub fn on_sequence(self, events: &[M::Event]) -> Expectation<'a, M> {
+        let from = self.machine.get_current_state();
+        let mut result = Ok(());
+        for event in events {
+            let outcome = self.machine.dispatch(event.clone());
+            result = result.and(outcome);
+        }
+        Expectation {
+            machine: self.machine,
+            from,
+            last: events.last().cloned(),
+            result,
+        }
+    }
+}
+
+/// Outcome of the events dispatched by [`Expect::on`] or
+/// [`Expect::on_sequence`], to assert
+pub struct Expectation<'a, M: StateMachine> {
+    machine: &'a mut M,
+    /// State before the events
+    from: M::State,
+    /// Last event dispatched
+    last: Option<M::Event>,
+    result: Result<(), DispatchError>,
+}
+
+impl<'a, M> Expectation<'a, M>
+where
+    M: StateMachine,
+    M::State: PartialEq + core::fmt::Debug,
+    M::Event: Clone + core::fmt::Debug,
+{
+    /// Assert the events were accepted and led to `state`.
+    #[track_caller]
+    pub fn transitions_to(self, state: M::State) -> Self {
+        let to = self.machine.get_current_state();
+        assert!(
+            self.result.is_ok() && to == state,
+            "{:?} from {:?} led to {:?} with {:?}, expected {:?}",
+            self.event(),
+            self.from,
+            to,
+            self.result,
+            state
+        );
+        self
+    }
+
+    /// Assert the events were accepted and left the machine in the state it
+    /// was in.
+    #[track_caller]
+    pub fn stays(self) -> Self {
+        let from = self.machine.get_current_state();
+        self.transitions_to(from)
+    }
+
+    /// Assert a dispatch failed with `error`.
+    #[track_caller]
+    pub fn fails_with(self, error: DispatchError) -> Self {
+        assert!(
+            self.result == Err(error),
+            "{:?} from {:?} gave {:?}, expected {:?}",
+            self.event(),
+            self.from,
+            self.result,
+            Err::<(), _>(error)
+        );
+        self
+    }
+
+    /// Assert `check` holds on the context.
+    #[track_caller]
+    pub fn with(self, check: impl FnOnce(&M::Context) -> bool) -> Self {
+        assert!(
+            check(self.machine.context()),
+            "context check failed after {:?} from {:?} to {:?}",
+            self.event(),
+            self.from,
+            self.machine.get_current_state()
+        );
+        self
+    }
+
+    /// Dispatch the next event.
+    pub fn on(self, event: M::Event) -> Expectation<'a, M> {
+        self.then().on(event)
+    }
+
+    /// Dispatch the next events in order.
+    pub fn on_sequence(self, events: &[M::Event]) -> Expectation<'a, M> {
+        self.then().on_sequence(events)
+    }
+
+    /// Last event dispatched, for the assertion messages
+    fn event(&self) -> &dyn core::fmt::Debug {
+        match &self.last {
+            Some(event) => event,
+            None => &"no event",
+        }
+    }
+
+    /// Go back to the machine, to assert its state.
+    pub fn then(self) -> Expect<'a, M> {
+        Expect {
+            machine: self.machine,
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//! Delayed event posting.
+
+/// Error returned when every timer slot is taken
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TimersFull;
+
+/// Handle to a pending timer, cancelling it with the machine's
+/// `cancel_timer`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TimerToken {
+    slot: usize,
+    generation: u32,
+}
+
+/// Pending timer
+#[derive(Clone, Copy, Debug)]
+struct Timer<E> {
+    posted_at: u32,
+    delay: u32,
+    event: E,
+}
+
+impl<E> Timer<E> {
+    /// Ticks elapsed since the expiry, if expired at `now`
+    fn overdue(&self, now: u32) -> Option<u32> {
+        crate::clock::elapsed(self.posted_at, now).checked_sub(self.delay)
+    }
+}
+
+/// Fixed set of `N` timers, each delivering an event once expired
+#[derive(Clone, Debug)]
+pub struct Timers<E, const N: usize> {
+    slots: [Option<Timer<E>>; N],
+    /// Number of timers posted to each slot, telling their tokens apart
+    generations: [u32; N],
+}
+
+impl<E, const N: usize> Timers<E, N> {
+    /// Create a set of idle timers.
+    #[doc(hidden)]
+    pub const fn new() -> Self {
+        Self {
+            slots: [const { None }; N],
+            generations: [0; N],
+        }
+    }
+
+    /// Post `event` to be delivered `delay` ticks after `now`.
+    #[doc(hidden)]
+    pub fn post(&mut self, now: u32, delay: u32, event: E) -> Result<TimerToken, TimersFull> {
+        let (slot, (timer, generation)) = self
+            .slots
+            .iter_mut()
+            .zip(self.generations.iter_mut())
+            .enumerate()
+            .find(|(_, (timer, _))| timer.is_none())
+            .ok_or(TimersFull)?;
+        *timer = Some(Timer {
+            posted_at: now,
+            delay,
+            event,
+        });
+        *generation = generation.wrapping_add(1);
+        Ok(TimerToken {
+            slot,
+            generation: *generation,
+        })
+    }
+
+    /// Cancel the timer of `token`, returning whether it was still pending.
+    pub fn cancel(&mut self, token: TimerToken) -> bool {
+        if self.generations.get(token.slot) != Some(&token.generation) {
+            return false;
+        }
+        self.slots
+            .get_mut(token.slot)
+            .and_then(Option::take)
+            .is_some()
+    }
+
+    /// Cancel the timers of events of the same variant as `event`, whatever
+    /// their payload, returning how many were cancelled.
+    pub fn cancel_matching(&mut self, event: &E) -> usize {
+        let variant = core::mem::discriminant(event);
+        self.slots
+            .iter_mut()
+            .filter(|slot| {
+                slot.as_ref()
+                    .is_some_and(|timer| core::mem::discriminant(&timer.event) == variant)
+            })
+            .map(|slot| *slot = None)
+            .count()
+    }
+
+    /// Slot of the expired timer that expired first, with the ticks elapsed
+    /// since, if any
+    fn first_expired(&self, now: u32) -> Option<(usize, u32)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(index, slot)| Some((index, slot.as_ref()?.overdue(now)?)))
+            .max_by_key(|(_, overdue)| *overdue)
+    }
+
+    /// Take the event of the expired timer that expired first, if any.
+    #[doc(hidden)]
+    pub fn expired(&mut self, now: u32) -> Option<E> {
+        let (slot, _) = self.first_expired(now)?;
+        self.slots.get_mut(slot)?.take().map(|timer| timer.event)
+    }
+
+    /// Event of the expired timer that expired first, with the ticks elapsed
+    /// since, if any
+    #[doc(hidden)]
+    pub fn peek_expired(&self, now: u32) -> Option<(&E, u32)> {
+        let (slot, overdue) = self.first_expired(now)?;
+        self.slots
+            .get(slot)?
+            .as_ref()
+            .map(|timer| (&timer.event, overdue))
+    }
+
+    /// Delay every pending timer by `ticks`, spent paused.
+    #[doc(hidden)]
+    pub fn postpone(&mut self, ticks: u32) {
+        for timer in self.slots.iter_mut().flatten() {
+            timer.posted_at = timer.posted_at.wrapping_add(ticks);
+        }
+    }
+
+    /// Number of pending timers
+    pub fn len(&self) -> usize {
+        self.slots.iter().filter(|slot| slot.is_some()).count()
+    }
+
+    /// Whether no timer is pending
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Cancel every pending timer.
+    pub fn clear(&mut self) {
+        self.slots = [const { None }; N];
+    }
+}
+
+impl<E, const N: usize> Default for Timers<E, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,138 @@
+//! Dispatch trace recording.
+
+use crate::{DispatchError, Source};
+
+/// One dispatch recorded in a [`Trace`]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TraceRecord<S, E> {
+    /// Tick at which the event was dispatched
+    pub timestamp: u32,
+    /// Event dispatched
+    pub event: E,
+    /// Where the event came from, always `Source::Host` without the `tagged`
+    /// dispatch option
+    pub source: Source,
+    /// State before the event
+    pub from: S,
+    /// State after the event
+    pub to: S,
+    /// Outcome of the dispatch
+    pub result: Result<(), DispatchError>,
+    /// Name of the transition table rule applied, if named
+    pub transition: Option<&'static str>,
+}
+
+/// Ring buffer of the last `N` dispatches of a state machine
+///
+/// Once full, every new record overwrites the oldest one. Machines without a
+/// `Trace` section don't store one, and their `trace()` is always empty.
+#[derive(Clone, Debug)]
+pub struct Trace<S, E, const N: usize> {
+    records: [Option<TraceRecord<S, E>>; N],
+    next: usize,
+    len: usize,
+}
+
+impl<S, E, const N: usize> Trace<S, E, N> {
+    /// Create an empty trace.
+    #[doc(hidden)]
+    pub const fn new() -> Self {
+        Self {
+            records: [const { None }; N],
+            next: 0,
+            len: 0,
+        }
+    }
+
+    /// Record a dispatch, built by `record` only if there is room for it.
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn record(&mut self, record: impl FnOnce() -> TraceRecord<S, E>) {
+        if N == 0 {
+            return;
+        }
+        if let Some(slot) = self.records.get_mut(self.next) {
+            *slot = Some(record());
+        }
+        self.next = (self.next + 1) % N;
+        self.len = (self.len + 1).min(N);
+    }
+
+    /// Maximum number of records held
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Number of records held
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether nothing was recorded
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Most recent record
+    pub fn last(&self) -> Option<&TraceRecord<S, E>> {
+        self.iter().last()
+    }
+
+    /// Records, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &TraceRecord<S, E>> {
+        let start = (self.next + N - self.len) % N.max(1);
+        (0..self.len).filter_map(move |offset| {
+            self.records
+                .get((start + offset) % N)
+                .and_then(Option::as_ref)
+        })
+    }
+
+    /// Drop all records
+    pub fn clear(&mut self) {
+        self.records = [const { None }; N];
+        self.next = 0;
+        self.len = 0;
+    }
+}
+
+/// Stand-in for the trace of machines without a `Trace` section, holding
+/// and recording nothing
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug)]
+pub struct NoTrace;
+
+impl NoTrace {
+    #[doc(hidden)]
+    pub const fn new() -> Self {
+        Self
+    }
+
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn record<S, E>(&mut self, _record: impl FnOnce() -> TraceRecord<S, E>) {}
+}
+
+#[cfg(feature = "log")]
+impl<S: core::fmt::Debug, E: core::fmt::Debug, const N: usize> Trace<S, E, N> {
+    /// Dump the records, oldest first, through the `log` crate.
+    pub fn log_dump(&self) {
+        for record in self.iter() {
+            log::info!(
+                "[{}] {:?} from {:?}: {:?} -> {:?} ({:?})",
+                record.timestamp,
+                record.event,
+                record.source,
+                record.from,
+                record.to,
+                record.result
+            );
+        }
+    }
+}
+
+impl<S, E, const N: usize> Default for Trace<S, E, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,57 @@
+use crate::{DispatchError, StateMachine};
+
+/// State machine driven by the events of another event type.
+///
+/// Each outer event is mapped onto the machine's own events by a function,
+/// which lets bridged or composed machines be wired without hand-written
+/// match-and-forward code. Outer events mapped to `None` are refused with
+/// [`DispatchError::UnexpectedEvent`].
+///
+/// ```rust,ignore
+/// let mut motor = Translate::new(Motor::new(), |event: PanelEvents| match event {
+///     PanelEvents::StartPressed => Some(MotorEvents::Start),
+///     PanelEvents::StopPressed => Some(MotorEvents::Stop),
+///     _ => None,
+/// });
+/// motor.dispatch(PanelEvents::StartPressed);
+/// ```
+pub struct Translate<M, F> {
+    machine: M,
+    map: F,
+}
+
+impl<M: StateMachine, F> Translate<M, F> {
+    /// Wrap `machine`, translating outer events with `map`.
+    pub fn new<O>(machine: M, map: F) -> Self
+    where
+        F: FnMut(O) -> Option<M::Event>,
+    {
+        Self { machine, map }
+    }
+
+    /// Get a reference to the wrapped machine.
+    pub fn machine(&self) -> &M {
+        &self.machine
+    }
+
+    /// Get a mutable reference to the wrapped machine.
+    pub fn machine_mut(&mut self) -> &mut M {
+        &mut self.machine
+    }
+
+    /// Unwrap the machine.
+    pub fn into_inner(self) -> M {
+        self.machine
+    }
+
+    /// Translate `event` and dispatch it to the machine.
+    pub fn dispatch<O>(&mut self, event: O) -> Result<(), DispatchError>
+    where
+        F: FnMut(O) -> Option<M::Event>,
+    {
+        match (self.map)(event) {
+            Some(event) => self.machine.dispatch(event),
+            None => Err(DispatchError::UnexpectedEvent),
+        }
+    }
+}
@@ -0,0 +1,155 @@
+/// Trait implemented by every state type generated with
+/// [`rustfsm_typestate`](crate::rustfsm_typestate)
+pub trait TypeState {
+    /// Name of the state
+    const NAME: &'static str;
+}
+
+/// # RustFSM typestate
+///
+/// Compile-time checked variant of [`rustfsm`](crate::rustfsm).
+///
+/// Each state is a distinct type and each transition is a method consuming
+/// the machine in its source state and returning it in its target state, so
+/// illegal transitions fail to compile.
+///
+/// The `rustfsm_typestate` macro takes as input the state machine's name, list
+/// of states, list of named transitions and context. The first state in the
+/// list is the state machine's initial state.
+///
+/// ```rust,ignore
+/// use rustfsm::rustfsm_typestate;
+///
+/// rustfsm_typestate!(
+///     FooName,
+///     States {
+///         FooStateA,
+///         FooStateB,
+///     },
+///     Transitions {
+///         go: FooStateA => FooStateB,
+///         back: FooStateB => FooStateA,
+///     },
+///     Context {
+///         context_foo_data: u8 = 0,
+///     }
+/// );
+///
+/// let foo = FooName::new();
+/// let foo: FooName<FooStateB> = foo.go();
+/// ```
+///
+/// Calling a transition from the wrong state is rejected by the compiler:
+///
+/// ```rust,compile_fail
+/// use rustfsm::rustfsm_typestate;
+///
+/// rustfsm_typestate!(
+///     Door,
+///     States { Closed, Open },
+///     Transitions { open: Closed => Open },
+///     Context {}
+/// );
+///
+/// let door = Door::new();
+/// let door = door.open();
+/// let door = door.open();
+/// ```
+#[macro_export]
+macro_rules! rustfsm_typestate {
+    (
+        $state_machine_name:ident,
+        States {
+            $first_state:ident,
+            $($remaining_states:ident),* $(,)?
+        },
+        Transitions {
+            $($transition:ident: $from_state:ident => $to_state:ident),* $(,)?
+        },
+        $context_type:ident {
+            $($context_field:ident: $context_field_type:ty = $context_default:expr),* $(,)?
+        }
+    ) => {
+        /// State machine state type.
+        #[derive(Clone, Copy, PartialEq, Debug)]
+        pub struct $first_state;
+        $(
+            /// State machine state type.
+            #[derive(Clone, Copy, PartialEq, Debug)]
+            pub struct $remaining_states;
+        )*
+
+        impl $crate::TypeState for $first_state {
+            const NAME: &'static str = stringify!($first_state);
+        }
+        $(
+            impl $crate::TypeState for $remaining_states {
+                const NAME: &'static str = stringify!($remaining_states);
+            }
+        )*
+
+        $crate::rustfsm!(@context []; $context_type {
+            $($context_field: $context_field_type = $context_default),*
+        });
+
+        /// State machine struct, parameterized by its current state type.
+        #[derive(Debug)]
+        pub struct $state_machine_name<S: $crate::TypeState> {
+            state: S,
+            context: $context_type,
+        }
+
+        impl $state_machine_name<$first_state> {
+            /// Create a new state machine in its initial state.
+            pub fn new() -> Self {
+                Self {
+                    state: $first_state,
+                    context: $context_type::default(),
+                }
+            }
+        }
+
+        impl Default for $state_machine_name<$first_state> {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl<S: $crate::TypeState> $state_machine_name<S> {
+            /// Get the current state
+            pub fn state(&self) -> &S {
+                &self.state
+            }
+
+            /// Name of the current state
+            pub fn state_name(&self) -> &'static str {
+                S::NAME
+            }
+
+            /// Get a reference to the context
+            pub fn context(&self) -> &$context_type {
+                &self.context
+            }
+
+            /// Get a mutable reference to the context
+            pub fn context_mut(&mut self) -> &mut $context_type {
+                &mut self.context
+            }
+        }
+
+        $(
+            impl $state_machine_name<$from_state> {
+                #[doc = concat!(
+                    "Transition from `", stringify!($from_state),
+                    "` to `", stringify!($to_state), "`."
+                )]
+                pub fn $transition(self) -> $state_machine_name<$to_state> {
+                    $state_machine_name {
+                        state: $to_state,
+                        context: self.context,
+                    }
+                }
+            }
+        )*
+    };
+}
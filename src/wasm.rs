@@ -0,0 +1,36 @@
+//! WASM bindings support.
+//!
+//! With the `wasm` feature, every generated machine `FooName` gets a
+//! `FooNameWasm` wrapper exported to JavaScript with `wasm_bindgen`, which
+//! dispatches data-less events by name and queries the current state.
+
+use alloc::format;
+use alloc::string::String;
+
+use wasm_bindgen::JsValue;
+
+use crate::DispatchError;
+
+#[doc(hidden)]
+pub use wasm_bindgen;
+
+#[doc(hidden)]
+pub use alloc::string::String as JsString;
+
+/// JavaScript error of a refused event.
+#[doc(hidden)]
+pub fn dispatch_error(error: DispatchError) -> JsValue {
+    JsValue::from_str(&format!("{:?}", error))
+}
+
+/// JavaScript error of an event name that isn't a data-less event.
+#[doc(hidden)]
+pub fn unknown_event(name: &str) -> JsValue {
+    JsValue::from_str(&format!("unknown event `{}`", name))
+}
+
+/// Owned name of a state, as returned to JavaScript.
+#[doc(hidden)]
+pub fn name(name: &str) -> String {
+    String::from(name)
+}
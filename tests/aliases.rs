@@ -0,0 +1,56 @@
+use rustfsm::*;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Press {
+    Short,
+    Long,
+}
+
+rustfsm!(
+    Button,
+    ButtonStates {
+        Released,
+        Menu,
+        Off,
+    },
+    Events {
+        ButtonPress(Press),
+        Reset,
+    },
+    Context {},
+    Aliases {
+        ShortPress = ButtonPress(Press::Short),
+        LongPress = ButtonPress(Press::Long),
+    }
+);
+
+impl StateBehavior for ButtonStates {
+    type State = ButtonStates;
+    type Event = Events;
+    type Context = Context;
+
+    fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+        match event {
+            Events::ButtonPress(Press::Short) => Some(ButtonStates::Menu),
+            Events::ButtonPress(Press::Long) => Some(ButtonStates::Off),
+            Events::Reset => Some(ButtonStates::Released),
+        }
+    }
+}
+
+#[test]
+fn aliases_test() {
+    assert_eq!(Events::ShortPress, Events::ButtonPress(Press::Short));
+
+    let mut button = Button::new();
+    button.dispatch(Events::ShortPress).unwrap();
+    assert_eq!(button.get_current_state(), ButtonStates::Menu);
+    button.dispatch(Events::LongPress).unwrap();
+    assert_eq!(button.get_current_state(), ButtonStates::Off);
+
+    // Aliases are constants, usable as patterns
+    assert!(matches!(
+        Events::ButtonPress(Press::Long),
+        Events::LongPress
+    ));
+}
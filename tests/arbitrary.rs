@@ -0,0 +1,58 @@
+#![cfg(feature = "arbitrary")]
+
+use rustfsm::arbitrary::{Arbitrary, Unstructured};
+use rustfsm::testing::Rng;
+use rustfsm::*;
+
+rustfsm!(
+    Dimmer,
+    DimmerStates {
+        Off,
+        On(u8),
+    },
+    Events {
+        SetLevel(u8),
+        Toggle,
+    },
+    Context {}
+);
+
+impl StateBehavior for DimmerStates {
+    type State = DimmerStates;
+    type Event = Events;
+    type Context = Context;
+
+    fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+        match (self, event) {
+            (DimmerStates::Off, Events::Toggle) => Some(DimmerStates::On(u8::MAX)),
+            (DimmerStates::On(_), Events::Toggle) => Some(DimmerStates::Off),
+            (DimmerStates::On(_), Events::SetLevel(level)) => Some(DimmerStates::On(*level)),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn arbitrary_events_test() {
+    let mut rng = Rng::new(7);
+    let bytes: Vec<u8> = (0..1024).map(|_| rng.next_u64() as u8).collect();
+    let mut u = Unstructured::new(&bytes);
+
+    let mut dimmer = Dimmer::new();
+    let mut seen = [false; 2];
+    while !u.is_empty() {
+        let event = Events::arbitrary(&mut u).unwrap();
+        seen[matches!(event, Events::Toggle) as usize] = true;
+        dimmer.dispatch(event).unwrap();
+    }
+    assert_eq!(seen, [true, true]);
+
+    let mut u = Unstructured::new(&bytes);
+    let states: Vec<DimmerStates> = (0..64)
+        .map(|_| DimmerStates::arbitrary(&mut u).unwrap())
+        .collect();
+    assert!(states.contains(&DimmerStates::Off));
+    assert!(states
+        .iter()
+        .any(|state| matches!(state, DimmerStates::On(_))));
+}
@@ -0,0 +1,146 @@
+use rustfsm::*;
+
+rustfsm!(
+    Link {
+        retries: u8 = 3,
+        address: u16 = 0,
+    },
+    LinkStates { Down, Up },
+    Events {
+        Connect,
+        Disconnect,
+    },
+    Context {
+        connections: u32 = 0
+    }
+);
+
+impl StateBehavior for LinkStates {
+    type State = LinkStates;
+    type Event = Events;
+    type Context = Context;
+
+    fn enter(&self, context: &mut Self::Context) {
+        if let LinkStates::Up = self {
+            context.connections += 1;
+        }
+    }
+
+    fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+        match (self, event) {
+            (LinkStates::Down, Events::Connect) => Some(LinkStates::Up),
+            (LinkStates::Up, Events::Disconnect) => Some(LinkStates::Down),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn default_test() {
+    let link = Link::default();
+    assert_eq!(link.current_state, LinkStates::Down);
+    assert_eq!(link.retries, 3);
+    assert_eq!(link.address, 0);
+}
+
+#[test]
+fn builder_test() {
+    let mut link = Link::builder()
+        .state(LinkStates::Up)
+        .context(Context { connections: 10 })
+        .address(0x42)
+        .build();
+    assert_eq!(link.current_state, LinkStates::Up);
+    assert_eq!(link.context.connections, 10);
+    assert_eq!(link.retries, 3);
+    assert_eq!(link.address, 0x42);
+
+    link.dispatch(Events::Disconnect).unwrap();
+    link.dispatch(Events::Connect).unwrap();
+    assert_eq!(link.context.connections, 11);
+}
+
+#[test]
+fn member_accessors_test() {
+    let mut link = Link::new();
+    assert_eq!(*link.retries(), 3);
+
+    link.set_retries(5);
+    link.set_address(0x10);
+    assert_eq!(*link.retries(), 5);
+    assert_eq!(*link.address(), 0x10);
+}
+
+#[test]
+fn new_at_test() {
+    let mut link = Link::new_at(LinkStates::Up, Context { connections: 7 });
+    assert_eq!(link.current_state, LinkStates::Up);
+    assert_eq!(link.context.connections, 7);
+    assert_eq!(link.retries, 3);
+
+    // Resetting goes back to the initial state
+    link.reset(ResetPolicy::PreserveContext);
+    assert_eq!(link.current_state, LinkStates::INITIAL);
+    assert_eq!(link.current_state, LinkStates::Down);
+}
+
+#[test]
+fn respawn_as_test() {
+    let mut link = Link::new_started();
+    link.dispatch(Events::Connect).unwrap();
+    link.dispatch(Events::Disconnect).unwrap();
+
+    // Back up without running `enter`, the context kept
+    link.respawn_as(LinkStates::Up);
+    assert_eq!(link.current_state, LinkStates::Up);
+    assert_eq!(link.status(), Status::Idle);
+    assert_eq!(link.context.connections, 1);
+
+    link.dispatch(Events::Disconnect).unwrap();
+    assert_eq!(link.current_state, LinkStates::Down);
+}
+
+mod initial {
+    use rustfsm::*;
+
+    rustfsm!(
+        Dimmer,
+        DimmerStates {
+            Lit(u8),
+            Off,
+        },
+        Events { Dim, Switch },
+        Context {},
+        Initial { Lit(100) }
+    );
+
+    impl StateBehavior for DimmerStates {
+        type State = DimmerStates;
+        type Event = Events;
+        type Context = Context;
+
+        fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+            match (self, event) {
+                (DimmerStates::Lit(level), Events::Dim) => Some(DimmerStates::Lit(level / 2)),
+                (DimmerStates::Lit(_), Events::Switch) => Some(DimmerStates::Off),
+                (DimmerStates::Off, Events::Switch) => Some(DimmerStates::INITIAL),
+                (DimmerStates::Off, Events::Dim) => None,
+            }
+        }
+    }
+
+    #[test]
+    fn initial_section_test() {
+        let mut dimmer = Dimmer::new();
+        assert_eq!(dimmer.get_current_state(), DimmerStates::Lit(100));
+        dimmer.dispatch(Events::Dim).unwrap();
+        assert_eq!(dimmer.get_current_state(), DimmerStates::Lit(50));
+
+        dimmer.reset(ResetPolicy::RestoreDefaults);
+        assert_eq!(dimmer.get_current_state(), DimmerStates::Lit(100));
+        assert_eq!(
+            Dimmer::builder().build().get_current_state(),
+            DimmerStates::Lit(100)
+        );
+    }
+}
@@ -0,0 +1,59 @@
+use rustfsm::*;
+
+rustfsm!(
+    Boiler,
+    BoilerStates { Idle, Heating },
+    Events {
+        Sample(u16, u16),
+        Heat,
+    },
+    Context {
+        temperature: u16 = 0,
+        pressure: u16 = 0,
+        samples: u32 = 0,
+    },
+    Changes { temperature, pressure }
+);
+
+impl StateBehavior for BoilerStates {
+    type State = BoilerStates;
+    type Event = Events;
+    type Context = Context;
+
+    fn handle(&self, event: &Self::Event, context: &mut Self::Context) -> Option<Self::State> {
+        match event {
+            Events::Sample(temperature, pressure) => {
+                context.set_temperature(*temperature);
+                if *pressure != context.pressure {
+                    context.set_pressure(*pressure);
+                }
+                context.samples += 1;
+                None
+            }
+            Events::Heat => Some(BoilerStates::Heating),
+        }
+    }
+}
+
+#[test]
+fn take_changes_test() {
+    let mut boiler = Boiler::new();
+    assert!(boiler.context_mut().take_changes().is_empty());
+
+    boiler.dispatch(Events::Sample(20, 3)).unwrap();
+    let changes = boiler.context_mut().take_changes();
+    assert!(changes.iter().eq(["temperature", "pressure"]));
+    assert_eq!(changes.bits(), 0b11);
+    assert!(!changes.contains("samples"));
+
+    // Taking the changes clears them
+    boiler.dispatch(Events::Sample(21, 3)).unwrap();
+    let changes = boiler.context_mut().take_changes();
+    assert!(changes.contains("temperature"));
+    assert!(!changes.contains("pressure"));
+    assert!(boiler.context_mut().take_changes().is_empty());
+
+    boiler.dispatch(Events::Heat).unwrap();
+    assert!(boiler.context_mut().take_changes().is_empty());
+    assert_eq!(*boiler.context().samples(), 2);
+}
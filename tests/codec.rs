@@ -0,0 +1,261 @@
+use rustfsm::codec::{DecodeError, Wire, VERSION};
+use rustfsm::*;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Direction {
+    Forward,
+    Backward,
+}
+
+impl Wire for Direction {
+    const SIZE: usize = 1;
+
+    fn write(&self, buf: &mut [u8]) {
+        buf[0] = *self as u8;
+    }
+
+    fn read(buf: &[u8]) -> Option<Self> {
+        match buf[0] {
+            0 => Some(Direction::Forward),
+            1 => Some(Direction::Backward),
+            _ => None,
+        }
+    }
+}
+
+rustfsm!(
+    Motor,
+    MotorStates {
+        Stopped,
+        Running,
+    },
+    Events {
+        Stop,
+        Run(Direction, u16),
+        Calibrate([i8; 3]),
+    },
+    Context {
+        speed: u16 = 0
+    }
+);
+
+impl StateBehavior for MotorStates {
+    type State = MotorStates;
+    type Event = Events;
+    type Context = Context;
+
+    fn handle(&self, event: &Self::Event, context: &mut Self::Context) -> Option<Self::State> {
+        match event {
+            Events::Stop => Some(MotorStates::Stopped),
+            Events::Run(_, speed) => {
+                context.speed = *speed;
+                Some(MotorStates::Running)
+            }
+            Events::Calibrate(_) => None,
+        }
+    }
+}
+
+#[test]
+fn encode_decode_test() {
+    let mut frame = [0; 8];
+
+    let len = Events::Stop.encode(&mut frame);
+    assert_eq!(&frame[..len], &[VERSION, 0]);
+    assert_eq!(Events::decode(&frame[..len]), Ok(Events::Stop));
+
+    let len = Events::Run(Direction::Backward, 0x1234).encode(&mut frame);
+    assert_eq!(&frame[..len], &[VERSION, 1, 1, 0x34, 0x12]);
+
+    // Drive a machine from the decoded frame
+    let mut motor = Motor::new();
+    motor
+        .dispatch(Events::decode(&frame[..len]).unwrap())
+        .unwrap();
+    assert_eq!(motor.get_current_state(), MotorStates::Running);
+    assert_eq!(motor.context().speed, 0x1234);
+
+    let event = Events::Calibrate([-1, 0, 1]);
+    let len = event.encode(&mut frame);
+    assert_eq!(len, 5);
+    assert_eq!(Events::decode(&frame[..len]), Ok(event));
+
+    // Frames that don't fit aren't written
+    assert_eq!(event.encode(&mut frame[..4]), 0);
+}
+
+#[test]
+fn decode_error_test() {
+    use DecodeError::*;
+
+    assert_eq!(Events::decode(&[]), Err(Truncated));
+    assert_eq!(Events::decode(&[VERSION, 1, 0, 0x34]), Err(Truncated));
+    assert_eq!(
+        Events::decode(&[VERSION + 1, 0]),
+        Err(UnsupportedVersion(VERSION + 1))
+    );
+    assert_eq!(Events::decode(&[VERSION, 3]), Err(UnknownEvent(3)));
+    assert_eq!(Events::decode(&[VERSION, 1, 2, 0, 0]), Err(InvalidPayload));
+    assert_eq!(Events::decode(&[VERSION, 0, 0]), Err(TrailingBytes));
+}
+
+/// Value encoded in no bytes at all
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct Marker;
+
+impl Wire for Marker {
+    const SIZE: usize = 0;
+
+    fn write(&self, _buf: &mut [u8]) {}
+
+    fn read(_buf: &[u8]) -> Option<Self> {
+        Some(Marker)
+    }
+}
+
+#[test]
+fn array_test() {
+    let mut buf = [0; 2];
+    [Direction::Backward, Direction::Forward].write(&mut buf);
+    assert_eq!(buf, [1, 0]);
+    assert_eq!(
+        <[Direction; 2]>::read(&buf),
+        Some([Direction::Backward, Direction::Forward])
+    );
+    assert_eq!(<[Direction; 2]>::read(&[0, 2]), None);
+
+    // Empty arrays and zero-sized values read from no bytes
+    assert_eq!(<[u8; 0]>::read(&[]), Some([]));
+    assert_eq!(<[Marker; 3]>::SIZE, 0);
+    assert_eq!(<[Marker; 3]>::read(&[]), Some([Marker; 3]));
+    [Marker; 3].write(&mut []);
+}
+
+mod wide {
+    use rustfsm::codec::VERSION;
+    use rustfsm::*;
+
+    rustfsm!(
+        Mixer,
+        MixerStates { Idle, Mixing },
+        Events {
+            Stop,
+            Levels(u8, u8, u8, u8, u8, u8, u8, u8, u8, u8),
+        },
+        Context {}
+    );
+
+    impl StateBehavior for MixerStates {
+        type State = MixerStates;
+        type Event = Events;
+        type Context = Context;
+
+        fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+            match event {
+                Events::Stop => Some(MixerStates::Idle),
+                Events::Levels(..) => Some(MixerStates::Mixing),
+            }
+        }
+    }
+
+    #[test]
+    fn many_fields_test() {
+        let event = Events::Levels(0, 1, 2, 3, 4, 5, 6, 7, 8, 9);
+        let mut frame = [0; 12];
+        assert_eq!(event.encode(&mut frame), 12);
+        assert_eq!(frame, [VERSION, 1, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(Events::decode(&frame), Ok(event));
+    }
+}
+
+mod hibernation {
+    use rustfsm::codec::DecodeError;
+    use rustfsm::*;
+
+    rustfsm!(
+        Node,
+        NodeStates { Idle, Measuring, Sending(u8) },
+        Events { Measure, Send(u8), Done },
+        Context {
+            samples: u16 = 0,
+            entries: u8 = 0,
+            scratch: [u8; 16] = [0; 16]
+        },
+        Hibernate { samples, entries }
+    );
+
+    impl StateBehavior for NodeStates {
+        type State = NodeStates;
+        type Event = Events;
+        type Context = Context;
+
+        fn enter(&self, context: &mut Self::Context) {
+            context.entries += 1;
+        }
+
+        fn handle(&self, event: &Self::Event, context: &mut Self::Context) -> Option<Self::State> {
+            match (self, event) {
+                (NodeStates::Idle, Events::Measure) => {
+                    context.samples += 1;
+                    context.scratch[0] = 0xAA;
+                    Some(NodeStates::Measuring)
+                }
+                (NodeStates::Measuring, Events::Send(count)) => Some(NodeStates::Sending(*count)),
+                (NodeStates::Measuring | NodeStates::Sending(_), Events::Done) => {
+                    Some(NodeStates::Idle)
+                }
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn hibernate_test() {
+        assert_eq!(Node::HIBERNATE_SIZE, 4);
+
+        let mut node = Node::new();
+        node.dispatch(Events::Measure).unwrap();
+        let mut image = [0; Node::HIBERNATE_SIZE];
+        assert_eq!(node.hibernate(&mut image), Node::HIBERNATE_SIZE);
+        assert_eq!(image, [1, 1, 0, 1]);
+        // Too small a buffer
+        assert_eq!(node.hibernate(&mut image[..3]), 0);
+
+        // Only the listed fields are kept
+        let woken = Node::wake(&image).unwrap();
+        assert_eq!(woken.get_current_state(), NodeStates::Measuring);
+        assert_eq!(woken.context().samples, 1);
+        assert_eq!(woken.context().entries, 1);
+        assert_eq!(woken.context().scratch[0], 0);
+
+        // Replaying enter
+        let mut woken = Node::wake_started(&image).unwrap();
+        assert_eq!(woken.context().entries, 2);
+        woken.dispatch(Events::Done).unwrap();
+        assert_eq!(woken.get_current_state(), NodeStates::Idle);
+
+        // A state with data can't be saved
+        woken.dispatch(Events::Measure).unwrap();
+        woken.dispatch(Events::Send(3)).unwrap();
+        assert_eq!(woken.get_current_state(), NodeStates::Sending(3));
+        assert_eq!(woken.hibernate(&mut image), 0);
+    }
+
+    #[test]
+    fn wake_error_test() {
+        assert_eq!(Node::wake(&[]).err(), Some(DecodeError::Truncated));
+        assert_eq!(Node::wake(&[1, 1]).err(), Some(DecodeError::Truncated));
+        assert_eq!(
+            Node::wake(&[2, 0, 0, 0]).err(),
+            Some(DecodeError::InvalidPayload)
+        );
+        assert_eq!(
+            Node::wake(&[3, 0, 0, 0]).err(),
+            Some(DecodeError::InvalidPayload)
+        );
+        assert_eq!(
+            Node::wake(&[0, 0, 0, 0, 0]).err(),
+            Some(DecodeError::TrailingBytes)
+        );
+    }
+}
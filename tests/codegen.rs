@@ -0,0 +1,200 @@
+#![cfg(feature = "codegen")]
+
+use rustfsm::codegen::{Definition, Error};
+
+const TURNSTILE: &str = r#"
+# Maintained by the systems team
+name = "Turnstile"
+states = ["Locked", "Unlocked"]
+events = [
+    "Coin",
+    "Push",
+    "SetFare(u32)", # In cents
+]
+dispatch = ["behavior"]
+
+[context]
+fare = { type = "u32", default = "100" }
+paid = { type = "u32", default = "0" }
+
+[[transition]]
+from = "Locked"
+event = "Coin"
+to = "Unlocked"
+guard = "paid_enough"
+
+[[transition]]
+from = "Unlocked"
+event = "Push | Coin"
+to = "Locked"
+
+[[transition]]
+from = "_"
+event = "SetFare(_)"
+to = "ignore"
+action = "set_fare"
+
+[[transition]]
+from = "Locked"
+event = "Push"
+to = "ignore"
+"#;
+
+#[test]
+fn codegen_test() {
+    let definition = Definition::parse(TURNSTILE).unwrap();
+    assert_eq!(definition.state_type, "TurnstileStates");
+    assert_eq!(definition.events[2], "SetFare(u32)");
+
+    assert_eq!(
+        definition.to_rust(),
+        "rustfsm!(
+    Turnstile,
+    TurnstileStates { Locked, Unlocked },
+    Events { Coin, Push, SetFare(u32) },
+    Context { fare: u32 = 100, paid: u32 = 0 },
+    Transitions {
+        Locked + Coin => Unlocked if paid_enough,
+        Unlocked + Push | Coin => Locked,
+        _ + SetFare(_) => ignore / set_fare,
+        Locked + Push => ignore,
+    },
+    Dispatch { behavior }
+);
+"
+    );
+}
+
+#[test]
+fn codegen_error_test() {
+    let error = |text: &str| Definition::parse(text).unwrap_err().to_string();
+
+    assert_eq!(error("states = []\nevents = []"), "missing `name`");
+    assert_eq!(error("name = Turnstile"), "line 1: invalid value");
+    assert_eq!(error("[machine]"), "line 1: unknown table");
+    assert_eq!(
+        error("name = \"T\"\nstates = [\"A\"]\nevents = [\"Go\"]\n\n[[transition]]\nfrom = \"A\"\nevent = \"Stop\"\nto = \"A\""),
+        "line 5: undeclared `Stop`"
+    );
+    assert_eq!(
+        error("name = \"T\"\nstates = [\"A\"]\nevents = [\"Go\"]\n[[transition]]\nfrom = \"A\""),
+        "line 4: a transition needs `from`, `event` and `to`"
+    );
+    assert!(matches!(
+        Definition::parse("states = [\"A\""),
+        Err(Error::Syntax { line: 1, .. })
+    ));
+}
+
+#[test]
+fn generate_test() {
+    let dir = std::env::temp_dir().join("rustfsm-codegen-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let definition = dir.join("turnstile.toml");
+    std::fs::write(&definition, TURNSTILE).unwrap();
+    std::env::set_var("OUT_DIR", &dir);
+
+    rustfsm::codegen::generate(&definition, "turnstile.rs").unwrap();
+    let code = std::fs::read_to_string(dir.join("turnstile.rs")).unwrap();
+    assert!(code.starts_with("// Generated by rustfsm::codegen"));
+    assert!(code.ends_with(&Definition::parse(TURNSTILE).unwrap().to_rust()));
+
+    assert!(matches!(
+        rustfsm::codegen::generate(dir.join("missing.toml"), "missing.rs"),
+        Err(Error::Io(_))
+    ));
+}
+
+const TURNSTILE_PLANTUML: &str = "
+@startuml
+title Turnstile
+' Coin-operated gate
+[*] --> Locked
+Locked --> Unlocked : Coin [paid_enough] / count
+Unlocked -up-> Locked : Push
+Locked : waits for a coin
+note left of Locked
+  Push does nothing here
+end note
+Unlocked --> [*]
+@enduml
+";
+
+#[test]
+fn plantuml_test() {
+    let definition = Definition::from_plantuml("Gate", TURNSTILE_PLANTUML).unwrap();
+    assert_eq!(
+        definition.to_rust(),
+        "rustfsm!(
+    Gate,
+    GateStates { Locked, Unlocked },
+    Events { Coin, Push },
+    Context {},
+    Transitions {
+        Locked + Coin => Unlocked if paid_enough / count,
+        Unlocked + Push => Locked,
+        _ + _ => ignore,
+    },
+    Dispatch { behavior }
+);
+"
+    );
+
+    let error = |text| Definition::from_plantuml("Gate", text).unwrap_err().to_string();
+    assert_eq!(error("A --> B"), "line 1: a transition needs an event");
+    assert_eq!(
+        error("state Active {\n}"),
+        "line 1: composite states aren't supported"
+    );
+    assert_eq!(
+        error("A --> B : error.io"),
+        "line 1: states and events must be valid Rust names"
+    );
+}
+
+const TURNSTILE_SCXML: &str = r#"<?xml version="1.0"?>
+<scxml xmlns="http://www.w3.org/2005/07/scxml" version="1.0" name="Turnstile" initial="Locked">
+  <!-- Coin-operated gate -->
+  <state id="Unlocked">
+    <transition event="Push Timeout" target="Locked"/>
+  </state>
+  <state id="Locked">
+    <onentry><log expr="'locked'"/></onentry>
+    <transition event="Coin" target="Unlocked" cond="paid_enough"/>
+    <transition event="Push"/>
+  </state>
+  <final id="Broken"/>
+</scxml>
+"#;
+
+#[test]
+fn scxml_test() {
+    let definition = Definition::from_scxml("Gate", TURNSTILE_SCXML).unwrap();
+    assert_eq!(
+        definition.to_rust(),
+        "rustfsm!(
+    Turnstile,
+    TurnstileStates { Locked, Unlocked, Broken },
+    Events { Push, Timeout, Coin },
+    Context {},
+    Transitions {
+        Unlocked + Push | Timeout => Locked,
+        Locked + Coin => Unlocked if paid_enough,
+        Locked + Push => ignore,
+        _ + _ => ignore,
+    },
+    Dispatch { behavior }
+);
+"
+    );
+
+    let error = |text| Definition::from_scxml("Gate", text).unwrap_err().to_string();
+    assert_eq!(
+        error("<scxml>\n<state id=\"A\">\n<state id=\"B\"/>"),
+        "line 3: nested states aren't supported"
+    );
+    assert_eq!(
+        error("<scxml>\n<transition event=\"Go\"/>"),
+        "line 2: transition outside a state"
+    );
+}
@@ -0,0 +1,54 @@
+use rustfsm::*;
+
+rustfsm!(
+    Gate,
+    GateStates { Locked, Unlocked },
+    Events { Coin, Push },
+    Context {
+        coins: u8 = 0
+    },
+    Config {
+        queue_depth = 2,
+        history = 4,
+        strict_unhandled = true,
+        jump_table = false,
+    }
+);
+
+impl StateBehavior for GateStates {
+    type State = GateStates;
+    type Event = Events;
+    type Context = Context;
+
+    fn handle(&self, event: &Self::Event, context: &mut Self::Context) -> Option<Self::State> {
+        match (self, event) {
+            (GateStates::Locked, Events::Coin) => {
+                context.coins += 1;
+                Some(GateStates::Unlocked)
+            }
+            (GateStates::Unlocked, Events::Push) => Some(GateStates::Locked),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn config_test() {
+    let mut gate = Gate::new();
+
+    // `queue_depth` gives the machine a queue
+    gate.post(Events::Coin).unwrap();
+    gate.post(Events::Push).unwrap();
+    assert_eq!(gate.post(Events::Coin), Err(Events::Coin));
+    assert_eq!(gate.process_all(), Ok(2));
+    assert_eq!(gate.get_current_state(), GateStates::Locked);
+
+    // `strict_unhandled` refuses the events leading nowhere
+    assert_eq!(gate.dispatch(Events::Push), Err(DispatchError::Unhandled));
+
+    // `history` keeps the last dispatches
+    gate.dispatch(Events::Coin).unwrap();
+    gate.dispatch(Events::Push).unwrap();
+    assert_eq!(gate.trace().len(), 4);
+    assert_eq!(gate.context().coins, 2);
+}
@@ -0,0 +1,69 @@
+use rustfsm::*;
+
+/// Large payload, deliberately neither `Clone` nor `PartialEq`
+#[derive(Debug)]
+pub struct Frame {
+    bytes: [u8; 64],
+}
+
+rustfsm!(
+    Dma,
+    DmaStates {
+        Idle,
+        Received(u8),
+    },
+    Events {
+        Frame(Frame),
+        Flush,
+    },
+    Context {
+        checksum: u32 = 0
+    },
+    Trace { depth: 2 },
+    Derive { events: Debug },
+    Dispatch { pausable }
+);
+
+impl StateBehavior for DmaStates {
+    type State = DmaStates;
+    type Event = Events;
+    type Context = Context;
+
+    fn handle(&self, event: &Self::Event, context: &mut Self::Context) -> Option<Self::State> {
+        match event {
+            Events::Frame(frame) => {
+                context.checksum += frame.bytes.iter().map(|&byte| byte as u32).sum::<u32>();
+                Some(DmaStates::Received(frame.bytes[0]))
+            }
+            Events::Flush => Some(DmaStates::Idle),
+        }
+    }
+}
+
+#[test]
+fn derive_test() {
+    let mut dma = Dma::new();
+    dma.dispatch(Events::Frame(Frame { bytes: [2; 64] }))
+        .unwrap();
+    assert_eq!(dma.get_current_state(), DmaStates::Received(2));
+    assert_eq!(dma.context().checksum, 128);
+
+    dma.dispatch(Events::Flush).unwrap();
+    assert_eq!(dma.get_current_state(), DmaStates::Idle);
+    assert_eq!(dma.trace().len(), 2);
+}
+
+#[test]
+fn dispatch_ref_test() {
+    let mut dma = Dma::new();
+    let frame = Events::Frame(Frame { bytes: [3; 64] });
+    dma.dispatch_ref(&frame).unwrap();
+    dma.dispatch_ref(&frame).unwrap();
+    assert_eq!(dma.get_current_state(), DmaStates::Received(3));
+    assert_eq!(dma.context().checksum, 384);
+    // Not `Clone`, the frames go untraced
+    assert_eq!(dma.trace().len(), 0);
+
+    dma.pause(PausePolicy::Queue);
+    assert_eq!(dma.dispatch_ref(&frame), Err(DispatchError::Paused));
+}
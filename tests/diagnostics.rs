@@ -0,0 +1,62 @@
+//! Errors reported on mistaken machine definitions and uses.
+//!
+//! Every file of `tests/ui` is built as a binary of a scratch crate
+//! depending on this one, so that the checks of generic code run too. It
+//! must fail to compile with an error containing the message given by its
+//! first line, `// error: <message>`, which names the offender. Definitions
+//! go through `rustfsm!` or through the `fsm!` front end.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn diagnostics_test() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let scratch = Path::new(env!("CARGO_TARGET_TMPDIR")).join("ui");
+    fs::create_dir_all(scratch.join("src/bin")).unwrap();
+    fs::write(
+        scratch.join("Cargo.toml"),
+        format!(
+            "[package]\nname = \"ui\"\nversion = \"0.0.0\"\nedition = \"2021\"\n\n\
+             [workspace]\n\n[dependencies]\n\
+             rustfsm = {{ path = {:?}, features = [\"macros\"] }}\n",
+            root
+        ),
+    )
+    .unwrap();
+    // Same dependency versions as the crate itself, resolved offline
+    fs::copy(root.join("Cargo.lock"), scratch.join("Cargo.lock")).unwrap();
+
+    let mut cases: Vec<_> = fs::read_dir(root.join("tests/ui"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    cases.sort();
+    assert!(!cases.is_empty());
+
+    for case in cases {
+        let name = case.file_stem().unwrap().to_str().unwrap();
+        let source = fs::read_to_string(&case).unwrap();
+        let expected = source
+            .lines()
+            .next()
+            .and_then(|line| line.strip_prefix("// error: "))
+            .unwrap_or_else(|| panic!("{name}: no `// error:` line"));
+        fs::write(scratch.join("src/bin").join(format!("{name}.rs")), &source).unwrap();
+
+        let output = Command::new(env!("CARGO"))
+            .args(["build", "--offline", "--quiet", "--message-format", "short"])
+            .args(["--bin", name])
+            .env("CARGO_TARGET_DIR", scratch.join("target"))
+            .current_dir(&scratch)
+            .output()
+            .unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(!output.status.success(), "{name}: compiled");
+        assert!(
+            stderr.contains(expected),
+            "{name}: expected `{expected}`, got:\n{stderr}"
+        );
+    }
+}
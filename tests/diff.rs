@@ -0,0 +1,67 @@
+#![cfg(feature = "diff")]
+
+use rustfsm::*;
+
+rustfsm!(
+    Heater,
+    HeaterStates { Off, Heating },
+    Events { Heat(i16), Stop },
+    Context {
+        setpoint: i16 = 0,
+        cycles: u32 = 0,
+    }
+);
+
+impl StateBehavior for HeaterStates {
+    type State = HeaterStates;
+    type Event = Events;
+    type Context = Context;
+
+    fn handle(&self, event: &Self::Event, context: &mut Self::Context) -> Option<Self::State> {
+        match event {
+            Events::Heat(setpoint) => {
+                context.setpoint = *setpoint;
+                context.cycles += 1;
+                Some(HeaterStates::Heating)
+            }
+            Events::Stop => Some(HeaterStates::Off),
+        }
+    }
+}
+
+#[test]
+fn diff_test() {
+    let mut reference = Heater::new();
+    let mut device = Heater::new();
+    assert!(reference.diff(&device).is_empty());
+
+    reference.dispatch(Events::Heat(21)).unwrap();
+    device.dispatch(Events::Heat(21)).unwrap();
+    device.dispatch(Events::Stop).unwrap();
+    device.dispatch(Events::Heat(19)).unwrap();
+
+    let diff = reference.diff(&device);
+    assert_eq!(diff.state, None);
+    let changes: Vec<_> = diff
+        .fields()
+        .map(|change| {
+            (
+                change.name,
+                format!("{:?}", change.left),
+                format!("{:?}", change.right),
+            )
+        })
+        .collect();
+    assert_eq!(
+        changes,
+        [
+            ("setpoint", "21".into(), "19".into()),
+            ("cycles", "1".into(), "2".into()),
+        ]
+    );
+
+    device.dispatch(Events::Stop).unwrap();
+    let diff = reference.diff(&device);
+    assert_eq!(diff.state, Some((HeaterStates::Heating, HeaterStates::Off)));
+    assert!(!diff.is_empty());
+}
@@ -0,0 +1,128 @@
+#![cfg(feature = "alloc")]
+
+use rustfsm::dynamic::{DefinitionError, DynFsm};
+use rustfsm::{DispatchError, Status};
+
+#[derive(Default)]
+struct Approvals {
+    count: u32,
+    reviews: u32,
+    log: Vec<&'static str>,
+}
+
+fn workflow() -> DynFsm<Approvals> {
+    DynFsm::builder(Approvals::default())
+        .state("Draft")
+        .state("Review")
+        .final_state("Published")
+        .event("Submit")
+        .event("Approve")
+        .event("Reject")
+        .event("Withdraw")
+        .transition("Review", "Approve", "Published")
+        .guard(|approvals| approvals.count >= 1)
+        .internal("Review", "Approve")
+        .action(|approvals| approvals.count += 1)
+        .transition("Draft", "Submit", "Review")
+        .transition("Review", "Reject", "Draft")
+        .transition_from_any("Withdraw", "Draft")
+        .on_enter("Review", |approvals| {
+            approvals.count = 0;
+            approvals.reviews += 1;
+        })
+        .on_exit("Draft", |approvals| approvals.log.push("Draft exited"))
+        .on_enter("Draft", |approvals| approvals.log.push("Draft entered"))
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn dyn_fsm_test() {
+    let mut workflow = workflow();
+    assert_eq!(workflow.current_state(), "Draft");
+    assert_eq!(
+        workflow.states().collect::<Vec<_>>(),
+        ["Draft", "Review", "Published"]
+    );
+    assert_eq!(workflow.events().count(), 4);
+
+    workflow.start();
+    assert_eq!(workflow.status(), Status::Running);
+    assert_eq!(workflow.context().log, ["Draft entered"]);
+
+    workflow.dispatch("Submit").unwrap();
+    assert_eq!(workflow.current_state(), "Review");
+    assert_eq!(workflow.context().reviews, 1);
+
+    // The guard refuses the first approval, taken by the internal rule
+    workflow.dispatch("Approve").unwrap();
+    assert_eq!(workflow.current_state(), "Review");
+    assert_eq!(workflow.context().count, 1);
+
+    // Rules of any state
+    workflow.dispatch("Withdraw").unwrap();
+    assert_eq!(workflow.current_state(), "Draft");
+    // Ignored
+    workflow.dispatch("Approve").unwrap();
+    assert_eq!(workflow.current_state(), "Draft");
+    assert_eq!(
+        workflow.dispatch("Publish"),
+        Err(DispatchError::UnexpectedEvent)
+    );
+
+    let submit = workflow.event_id("Submit").unwrap();
+    workflow.dispatch_id(submit).unwrap();
+    assert_eq!(workflow.context().reviews, 2);
+    workflow.context_mut().count = 1;
+    workflow.dispatch("Approve").unwrap();
+    assert_eq!(workflow.current_state(), "Published");
+    assert!(workflow.is_finished());
+    assert_eq!(workflow.dispatch("Withdraw"), Err(DispatchError::Finished));
+    assert_eq!(
+        workflow.context().log,
+        ["Draft entered", "Draft exited", "Draft entered", "Draft exited"]
+    );
+
+    workflow.stop();
+    assert_eq!(workflow.dispatch("Withdraw"), Err(DispatchError::Stopped));
+}
+
+#[test]
+fn dyn_fsm_definition_error_test() {
+    let error = |builder: rustfsm::dynamic::DynFsmBuilder<()>| builder.build().err();
+
+    assert_eq!(
+        error(DynFsm::builder(()).event("Go")),
+        Some(DefinitionError::NoStates)
+    );
+    assert_eq!(
+        error(DynFsm::builder(()).state("A").state("A")),
+        Some(DefinitionError::DuplicateState("A".into()))
+    );
+    assert_eq!(
+        error(DynFsm::builder(()).state("A").event("Go").event("Go")),
+        Some(DefinitionError::DuplicateEvent("Go".into()))
+    );
+    // The first mistake is reported
+    assert_eq!(
+        error(
+            DynFsm::builder(())
+                .state("A")
+                .transition("A", "Go", "B")
+                .transition("C", "Go", "A")
+        ),
+        Some(DefinitionError::UnknownEvent("Go".into()))
+    );
+    assert_eq!(
+        error(DynFsm::builder(()).state("A").event("Go").transition("A", "Go", "B")),
+        Some(DefinitionError::UnknownState("B".into()))
+    );
+    assert_eq!(
+        error(DynFsm::builder(()).on_enter("A", |_| ())),
+        Some(DefinitionError::UnknownState("A".into()))
+    );
+    assert_eq!(
+        error(DynFsm::builder(()).state("A").guard(|_| true)),
+        Some(DefinitionError::NoTransition)
+    );
+}
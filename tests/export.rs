@@ -0,0 +1,155 @@
+#![cfg(feature = "serde")]
+
+use rustfsm::export::{Export, Snapshot};
+use rustfsm::*;
+
+rustfsm!(
+    Heater,
+    HeaterStates {
+        Off,
+        Heating(u8),
+        @final Broken,
+    },
+    Events {
+        SetPoint(u8),
+        TurnOff,
+        Break,
+    },
+    Context {
+        cycles: u32 = 0,
+        eco: bool = false
+    }
+);
+
+impl StateBehavior for HeaterStates {
+    type State = HeaterStates;
+    type Event = Events;
+    type Context = Context;
+
+    fn enter(&self, context: &mut Self::Context) {
+        if let HeaterStates::Heating(_) = self {
+            context.cycles += 1;
+        }
+    }
+
+    fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+        match event {
+            Events::SetPoint(degrees) => Some(HeaterStates::Heating(*degrees)),
+            Events::TurnOff => Some(HeaterStates::Off),
+            Events::Break => Some(HeaterStates::Broken),
+        }
+    }
+}
+
+#[test]
+fn export_test() {
+    let mut heater = Heater::new();
+    heater.dispatch(Events::SetPoint(21)).unwrap();
+
+    assert_eq!(
+        heater.export(),
+        Snapshot {
+            state: &HeaterStates::Heating(21),
+            context: &Context {
+                cycles: 1,
+                eco: false
+            },
+        }
+    );
+
+    let mirror = Heater::import(Snapshot {
+        state: HeaterStates::Heating(19),
+        context: Context {
+            cycles: 4,
+            eco: true,
+        },
+    });
+    assert_eq!(mirror.get_current_state(), HeaterStates::Heating(19));
+    assert_eq!(mirror.context().cycles, 4);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_test() {
+    let mut heater = Heater::new();
+    heater.dispatch(Events::SetPoint(21)).unwrap();
+
+    let json = heater.to_json().unwrap();
+    assert_eq!(
+        json,
+        r#"{"state":{"Heating":[21]},"context":{"cycles":1,"eco":false}}"#
+    );
+    let mirror = Heater::from_json(&json).unwrap();
+    assert_eq!(mirror, heater);
+
+    // Missing context fields keep their default values
+    let mirror = Heater::from_json(r#"{"state":"Broken","context":{"eco":true}}"#).unwrap();
+    assert!(mirror.is_finished());
+    assert_eq!(mirror.context().cycles, 0);
+    assert!(mirror.context().eco);
+
+    assert!(Heater::from_json(r#"{"state":"Melted","context":{}}"#).is_err());
+    assert!(Heater::from_json(r#"{"state":{"Heating":[]},"context":{}}"#).is_err());
+}
+
+#[cfg(feature = "cbor")]
+#[test]
+fn cbor_test() {
+    let mut heater = Heater::new();
+    heater.dispatch(Events::SetPoint(18)).unwrap();
+    heater.dispatch(Events::TurnOff).unwrap();
+
+    let cbor = heater.to_cbor().unwrap();
+    let mirror = Heater::from_cbor(&cbor).unwrap();
+    assert_eq!(mirror, heater);
+    assert_eq!(mirror.get_current_state(), HeaterStates::Off);
+}
+
+#[cfg(feature = "json")]
+mod wide {
+    use rustfsm::export::Export;
+    use rustfsm::*;
+
+    rustfsm!(
+        Mixer,
+        MixerStates {
+            Idle,
+            Mixing(u8, u8, u8, u8, u8, u8, u8, u8, u8),
+        },
+        Events {
+            Stop,
+            Levels(u8, u8, u8, u8, u8, u8, u8, u8, u8),
+        },
+        Context {}
+    );
+
+    impl StateBehavior for MixerStates {
+        type State = MixerStates;
+        type Event = Events;
+        type Context = Context;
+
+        fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+            match *event {
+                Events::Stop => Some(MixerStates::Idle),
+                Events::Levels(a, b, c, d, e, f, g, h, i) => {
+                    Some(MixerStates::Mixing(a, b, c, d, e, f, g, h, i))
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn many_fields_test() {
+        let mut mixer = Mixer::new();
+        mixer
+            .dispatch(Events::Levels(1, 2, 3, 4, 5, 6, 7, 8, 9))
+            .unwrap();
+
+        let json = mixer.to_json().unwrap();
+        assert_eq!(
+            json,
+            r#"{"state":{"Mixing":[1,2,3,4,5,6,7,8,9]},"context":{}}"#
+        );
+        assert_eq!(Mixer::from_json(&json).unwrap(), mixer);
+    }
+}
@@ -0,0 +1,221 @@
+use rustfsm::*;
+
+rustfsm!(
+    Motor,
+    MotorStates {
+        Idle,
+        Running,
+        @fault Faulted,
+    },
+    Events {
+        Start,
+        SetSpeed(u16),
+        Overload,
+    },
+    Context {
+        speed: u16 = 0
+    }
+);
+
+impl StateBehavior for MotorStates {
+    type State = MotorStates;
+    type Event = Events;
+    type Context = Context;
+
+    fn enter(&self, context: &mut Self::Context) {
+        if let MotorStates::Faulted = self {
+            context.speed = 0;
+        }
+    }
+
+    fn handle(&self, _event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+        None
+    }
+
+    fn try_handle(
+        &self,
+        event: &Self::Event,
+        context: &mut Self::Context,
+    ) -> Result<Option<Self::State>, &'static str> {
+        use Events::*;
+        use MotorStates::*;
+        match (self, event) {
+            (Idle, Start) => Ok(Some(Running)),
+            (Running, SetSpeed(speed)) => {
+                context.speed = *speed;
+                Ok(None)
+            }
+            (Running, Overload) => Err("overload"),
+            _ => Ok(None),
+        }
+    }
+
+    fn check_invariant(&self, context: &Self::Context) -> Result<(), &'static str> {
+        match (self, context.speed) {
+            (MotorStates::Running, speed) if speed > 3000 => Err("overspeed"),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[test]
+fn handler_fault_test() {
+    let mut motor = Motor::new();
+    assert_eq!(motor.dispatch(Events::Start), Ok(()));
+    assert_eq!(motor.dispatch(Events::SetSpeed(1200)), Ok(()));
+    assert_eq!(motor.last_fault(), None);
+
+    assert_eq!(
+        motor.dispatch(Events::Overload),
+        Err(DispatchError::Fault(Fault::Handler("overload")))
+    );
+    assert_eq!(motor.current_state, MotorStates::Faulted);
+    assert_eq!(motor.last_fault(), Some(Fault::Handler("overload")));
+    assert_eq!(motor.context.speed, 0);
+
+    // Reset clears the fault
+    motor.reset(ResetPolicy::RestoreDefaults);
+    assert_eq!(motor.last_fault(), None);
+    assert_eq!(motor.current_state, MotorStates::Idle);
+}
+
+#[test]
+fn invariant_fault_test() {
+    let mut motor = Motor::new();
+    motor.dispatch(Events::Start).unwrap();
+
+    assert_eq!(
+        motor.dispatch(Events::SetSpeed(5000)),
+        Err(DispatchError::Fault(Fault::Invariant("overspeed")))
+    );
+    assert_eq!(motor.current_state, MotorStates::Faulted);
+    assert_eq!(motor.last_fault(), Some(Fault::Invariant("overspeed")));
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn reentrancy_guard_test() {
+    // A nested dispatch finds the guard taken
+    let guard = DispatchGuard::new();
+    assert!(guard.enter());
+    assert!(!guard.enter());
+    guard.exit();
+    assert!(guard.enter());
+
+    // and the guard is released after every dispatch
+    let mut motor = Motor::new();
+    motor.dispatch(Events::Start).unwrap();
+    assert_eq!(motor.dispatch(Events::SetSpeed(1200)), Ok(()));
+    assert_eq!(
+        rustfsm::remote::status(Err(DispatchError::Reentrant)),
+        rustfsm::remote::REENTRANT
+    );
+}
+
+mod invariants {
+    use rustfsm::*;
+
+    rustfsm!(
+        Heater,
+        HeaterStates {
+            Off,
+            Heating,
+            @fault Tripped,
+        },
+        Events {
+            Heat(u8),
+            Cool,
+        },
+        Context {
+            power: u8 = 0
+        },
+        Invariant {
+            check: |state, context| match state {
+                HeaterStates::Off if context.power > 0 => Err(InvariantViolation("powered off")),
+                _ => Ok(()),
+            },
+        }
+    );
+
+    impl StateBehavior for HeaterStates {
+        type State = HeaterStates;
+        type Event = Events;
+        type Context = Context;
+
+        fn handle(&self, event: &Self::Event, context: &mut Self::Context) -> Option<Self::State> {
+            match (self, event) {
+                (HeaterStates::Tripped, _) => None,
+                (_, Events::Heat(power)) => {
+                    context.power = *power;
+                    Some(HeaterStates::Heating)
+                }
+                // Forgets to cut the power
+                (_, Events::Cool) => Some(HeaterStates::Off),
+            }
+        }
+    }
+
+    #[test]
+    fn invariant_section_test() {
+        let mut heater = Heater::new();
+        heater.dispatch(Events::Heat(3)).unwrap();
+        assert_eq!(
+            heater.dispatch(Events::Cool),
+            Err(DispatchError::Fault(Fault::Invariant("powered off")))
+        );
+        assert_eq!(heater.get_current_state(), HeaterStates::Tripped);
+    }
+
+    mod strict {
+        use rustfsm::*;
+
+        rustfsm!(
+            StrictHeater,
+            StrictHeaterStates { Off, Heating },
+            Events {
+                Heat(u8),
+                Cool,
+            },
+            Context {
+                power: u8 = 0
+            },
+            Invariant {
+                check: |state, context| match state {
+                    StrictHeaterStates::Off if context.power > 0 => {
+                        Err(InvariantViolation("powered off"))
+                    }
+                    _ => Ok(()),
+                },
+                on_violation: panic,
+            }
+        );
+
+        impl StateBehavior for StrictHeaterStates {
+            type State = StrictHeaterStates;
+            type Event = Events;
+            type Context = Context;
+
+            fn handle(
+                &self,
+                event: &Self::Event,
+                context: &mut Self::Context,
+            ) -> Option<Self::State> {
+                match event {
+                    Events::Heat(power) => {
+                        context.power = *power;
+                        Some(StrictHeaterStates::Heating)
+                    }
+                    Events::Cool => Some(StrictHeaterStates::Off),
+                }
+            }
+        }
+
+        #[test]
+        #[should_panic(expected = "invariant violated in Off: powered off")]
+        fn invariant_panic_test() {
+            let mut heater = StrictHeater::new();
+            heater.dispatch(Events::Heat(3)).unwrap();
+            let _ = heater.dispatch(Events::Cool);
+        }
+    }
+}
@@ -0,0 +1,137 @@
+#![cfg(feature = "ffi")]
+
+use core::ffi::c_void;
+use core::mem::MaybeUninit;
+
+use rustfsm::*;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[repr(C)]
+struct Color {
+    red: u8,
+    green: u8,
+}
+
+rustfsm!(
+    RgbLed,
+    LedStates {
+        Off,
+        On(u8, Color),
+    },
+    Events {
+        TurnOff,
+        TurnOn(u8, Color),
+    },
+    Context {}
+);
+
+impl StateBehavior for LedStates {
+    type State = LedStates;
+    type Event = Events;
+    type Context = Context;
+
+    fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+        match event {
+            Events::TurnOff => Some(LedStates::Off),
+            Events::TurnOn(level, color) => Some(LedStates::On(*level, *color)),
+        }
+    }
+}
+
+#[repr(C)]
+struct TurnOnPayload {
+    level: u8,
+    color: Color,
+}
+
+#[test]
+fn ffi_test() {
+    assert_eq!(rgb_led_size(), core::mem::size_of::<RgbLed>());
+    assert_eq!(rgb_led_align(), core::mem::align_of::<RgbLed>());
+
+    let mut storage = MaybeUninit::<RgbLed>::uninit();
+    let led = storage.as_mut_ptr();
+    unsafe {
+        rgb_led_new(led);
+        assert_eq!(rgb_led_current_state(led), 0);
+
+        let payload = TurnOnPayload {
+            level: 7,
+            color: Color { red: 1, green: 2 },
+        };
+        let payload = &payload as *const TurnOnPayload as *const c_void;
+        assert_eq!(rgb_led_dispatch(led, 1, payload), ffi::OK);
+        assert_eq!(rgb_led_current_state(led), 1);
+        assert_eq!(
+            (*led).get_current_state(),
+            LedStates::On(7, Color { red: 1, green: 2 })
+        );
+
+        assert_eq!(
+            rgb_led_dispatch(led, 1, core::ptr::null()),
+            ffi::UNEXPECTED_EVENT
+        );
+        assert_eq!(
+            rgb_led_dispatch(led, 2, core::ptr::null()),
+            ffi::UNEXPECTED_EVENT
+        );
+        assert_eq!(rgb_led_dispatch(led, 0, core::ptr::null()), ffi::OK);
+        assert_eq!(rgb_led_current_state(led), 0);
+        storage.assume_init_drop();
+    }
+}
+
+mod wide {
+    use core::ffi::c_void;
+    use core::mem::MaybeUninit;
+
+    use rustfsm::*;
+
+    rustfsm!(
+        Equalizer,
+        EqualizerStates {
+            Flat,
+            Shaped(u16),
+        },
+        Events {
+            Reset,
+            Bands(u8, u8, u8, u8, u8, u8, u8, u8, u8),
+        },
+        Context {}
+    );
+
+    impl StateBehavior for EqualizerStates {
+        type State = EqualizerStates;
+        type Event = Events;
+        type Context = Context;
+
+        fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+            match *event {
+                Events::Reset => Some(EqualizerStates::Flat),
+                Events::Bands(a, b, c, d, e, f, g, h, i) => Some(EqualizerStates::Shaped(
+                    [a, b, c, d, e, f, g, h, i]
+                        .iter()
+                        .map(|&band| u16::from(band))
+                        .sum(),
+                )),
+            }
+        }
+    }
+
+    #[test]
+    fn many_fields_test() {
+        let mut storage = MaybeUninit::<Equalizer>::uninit();
+        let equalizer = storage.as_mut_ptr();
+        let bands: [u8; 9] = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+        unsafe {
+            equalizer_new(equalizer);
+            let payload = &bands as *const [u8; 9] as *const c_void;
+            assert_eq!(equalizer_dispatch(equalizer, 1, payload), ffi::OK);
+            assert_eq!(
+                (*equalizer).get_current_state(),
+                EqualizerStates::Shaped(45)
+            );
+            storage.assume_init_drop();
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use rustfsm::*;
+
+mod door {
+    use rustfsm::*;
+
+    rustfsm!(
+        Door,
+        DoorStates { Closed, Open },
+        Events { Push },
+        Context {}
+    );
+
+    impl StateBehavior for DoorStates {
+        type State = DoorStates;
+        type Event = Events;
+        type Context = Context;
+
+        fn handle(
+            &self,
+            _event: &Self::Event,
+            _context: &mut Self::Context,
+        ) -> Option<Self::State> {
+            match self {
+                DoorStates::Closed => Some(DoorStates::Open),
+                DoorStates::Open => Some(DoorStates::Closed),
+            }
+        }
+    }
+}
+
+mod lamp {
+    use rustfsm::*;
+
+    rustfsm!(
+        Lamp,
+        LampStates {
+            Off,
+            @final Broken,
+        },
+        Events {
+            Smash,
+        },
+        Context {}
+    );
+
+    impl StateBehavior for LampStates {
+        type State = LampStates;
+        type Event = Events;
+        type Context = Context;
+
+        fn handle(
+            &self,
+            _event: &Self::Event,
+            _context: &mut Self::Context,
+        ) -> Option<Self::State> {
+            Some(LampStates::Broken)
+        }
+    }
+}
+
+#[test]
+fn fsm_core_test() {
+    let mut door = door::Door::new();
+    let mut lamp = lamp::Lamp::new();
+    let mut machines: [&mut dyn FsmCore; 2] = [&mut door, &mut lamp];
+
+    assert_eq!(machines[0].name(), "Door");
+    assert_eq!(machines[0].current_state_name(), "Closed");
+    assert_eq!(machines[1].name(), "Lamp");
+    assert_eq!(machines[1].current_state_name(), "Off");
+
+    // Events are routed to the machine that understands them
+    assert_eq!(machines[0].dispatch_erased(&door::Events::Push), Ok(()));
+    assert_eq!(
+        machines[1].dispatch_erased(&door::Events::Push),
+        Err(DispatchError::UnexpectedEvent)
+    );
+    assert_eq!(machines[1].dispatch_erased(&lamp::Events::Smash), Ok(()));
+
+    assert_eq!(machines[0].current_state_name(), "Open");
+    assert!(!machines[0].is_finished());
+    assert!(machines[1].is_finished());
+
+    for machine in machines.iter_mut() {
+        machine.stop();
+        assert_eq!(machine.status(), Status::Stopped);
+    }
+}
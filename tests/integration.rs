@@ -1,3 +1,4 @@
+use rustfsm::testing::{assert_trajectory, expect, replay, RandomWalk};
 use rustfsm::*;
 
 #[allow(dead_code)]
@@ -21,7 +22,7 @@ rustfsm!(
         SuperMario,
         FireMario,
         CapeMario,
-        DeadMario,
+        @final DeadMario,
     },
     Events {
         GetConsumable(MarioConsumables),
@@ -110,3 +111,295 @@ fn integration_test() {
     assert_eq!(mario.current_state, MarioStates::DeadMario);
     assert!(!mario.context.alive);
 }
+
+#[test]
+fn lifecycle_test() {
+    let mut mario = Mario::new();
+    assert_eq!(mario.status(), Status::Idle);
+
+    // Starting runs the initial state's entry
+    mario.context.size = MarioSize::Large;
+    mario.start();
+    assert_eq!(mario.status(), Status::Running);
+    assert_eq!(mario.context.size, MarioSize::Small);
+
+    assert_eq!(
+        mario.dispatch(Events::GetConsumable(MarioConsumables::Mushroom)),
+        Ok(())
+    );
+    assert_eq!(mario.current_state, MarioStates::SuperMario);
+
+    // Stopped machines refuse events
+    mario.stop();
+    assert_eq!(mario.status(), Status::Stopped);
+    assert_eq!(mario.dispatch(Events::Hit), Err(DispatchError::Stopped));
+    assert_eq!(mario.current_state, MarioStates::SuperMario);
+
+    // Reset goes back to the initial state and default context
+    mario.reset(ResetPolicy::RestoreDefaults);
+    assert_eq!(mario.status(), Status::Idle);
+    assert_eq!(mario.current_state, MarioStates::SmallMario);
+    assert_eq!(mario.dispatch(Events::Hit), Ok(()));
+    assert_eq!(mario.current_state, MarioStates::DeadMario);
+}
+
+#[test]
+fn new_started_test() {
+    // A started machine runs its initial state's entry
+    let mario = Mario::new_started();
+    assert_eq!(mario.status(), Status::Running);
+    assert_eq!(mario.current_state, MarioStates::SmallMario);
+    assert_eq!(mario.context.size, MarioSize::Small);
+}
+
+#[test]
+fn final_state_test() {
+    let mut mario = Mario::new();
+    assert!(!mario.is_finished());
+
+    assert_eq!(mario.dispatch(Events::Hit), Ok(()));
+    assert!(mario.is_finished());
+    assert!(MarioStates::DeadMario.is_final());
+
+    // Finished machines refuse events
+    assert_eq!(
+        mario.dispatch(Events::GetConsumable(MarioConsumables::Mushroom)),
+        Err(DispatchError::Finished)
+    );
+    assert_eq!(mario.current_state, MarioStates::DeadMario);
+}
+
+#[test]
+fn reset_policy_test() {
+    let mut mario = Mario::new();
+    mario.dispatch(Events::Hit).unwrap();
+    assert!(!mario.context.alive);
+
+    // Preserve the context across the reset
+    mario.reset(ResetPolicy::PreserveContext);
+    assert_eq!(mario.current_state, MarioStates::SmallMario);
+    assert!(!mario.context.alive);
+
+    // Restore the context defaults
+    mario.dispatch(Events::Hit).unwrap();
+    mario.reset(ResetPolicy::RestoreDefaults);
+    assert_eq!(mario.current_state, MarioStates::SmallMario);
+    assert!(mario.context.alive);
+}
+
+#[test]
+fn force_state_checked_test() {
+    let mut mario = Mario::new();
+
+    // Without a transition table any state can be forced
+    mario
+        .force_state_checked(MarioStates::SuperMario, ForceMode::Silent)
+        .unwrap();
+    assert_eq!(mario.current_state, MarioStates::SuperMario);
+    assert_eq!(mario.context.size, MarioSize::Small);
+
+    mario
+        .force_state_checked(MarioStates::DeadMario, ForceMode::RunHooks)
+        .unwrap();
+    assert!(!mario.context.alive);
+}
+
+#[test]
+fn snapshot_test() {
+    let mut mario = Mario::new();
+    mario
+        .dispatch(Events::GetConsumable(MarioConsumables::Flower))
+        .unwrap();
+    let snapshot = mario.snapshot();
+
+    // Try a sequence of events on a fork
+    let mut fork = mario.clone();
+    fork.dispatch(Events::Hit).unwrap();
+    fork.dispatch(Events::Hit).unwrap();
+    assert_eq!(fork.current_state, MarioStates::DeadMario);
+    assert_eq!(mario.current_state, MarioStates::FireMario);
+
+    // Restore a snapshot
+    mario.dispatch(Events::Hit).unwrap();
+    mario.restore_from(&snapshot);
+    assert_eq!(mario.current_state, MarioStates::FireMario);
+    assert_eq!(mario.context.size, MarioSize::Large);
+}
+
+#[test]
+fn context_checkpoint_test() {
+    let mut mario = Mario::new();
+    let checkpoint = mario.checkpoint_context();
+    mario
+        .dispatch(Events::GetConsumable(MarioConsumables::Mushroom))
+        .unwrap();
+
+    // Only the context is rolled back
+    mario.restore_context(checkpoint);
+    assert_eq!(mario.current_state, MarioStates::SuperMario);
+    assert_eq!(mario.context.size, MarioSize::Small);
+
+    let result = transaction(&mut mario.context, |context| {
+        context.size = MarioSize::Large;
+        context.alive = false;
+        Err::<(), _>("lost a life")
+    });
+    assert_eq!(result, Err("lost a life"));
+    assert_eq!(mario.context.size, MarioSize::Small);
+    assert!(mario.context.alive);
+}
+
+#[test]
+fn debug_and_eq_test() {
+    let mut mario = Mario::new();
+    let mut luigi = Mario::new();
+    assert_eq!(mario, luigi);
+
+    mario.dispatch(Events::Hit).unwrap();
+    assert_ne!(mario, luigi);
+    luigi.dispatch(Events::Hit).unwrap();
+    assert_eq!(mario, luigi);
+
+    assert_eq!(
+        format!("{:?}", mario),
+        "Mario { current_state: DeadMario, context: Context { size: Small, alive: false }, .. }"
+    );
+}
+
+#[test]
+fn context_accessors_test() {
+    let mut mario = Mario::new();
+    mario
+        .dispatch(Events::GetConsumable(MarioConsumables::Mushroom))
+        .unwrap();
+    assert_eq!(*mario.context().size(), MarioSize::Large);
+    assert!(*mario.context().alive());
+
+    *mario.context_mut() = Context::default();
+    assert_eq!(*mario.context().size(), MarioSize::Small);
+}
+
+#[test]
+fn random_walk_test() {
+    use MarioConsumables::*;
+
+    let events = [
+        Events::GetConsumable(Mushroom),
+        Events::GetConsumable(Flower),
+        Events::GetConsumable(Feather),
+        Events::Hit,
+    ];
+    let invariant = |state: &MarioStates, context: &Context| match state {
+        MarioStates::DeadMario if context.alive => Err("dead Mario is alive"),
+        MarioStates::SmallMario if context.size != MarioSize::Small => Err("small Mario is large"),
+        _ => Ok(()),
+    };
+
+    for seed in 0..20 {
+        let mut mario = Mario::new();
+        let steps = RandomWalk::new(seed, &events)
+            .steps(50)
+            .run(&mut mario, invariant)
+            .unwrap();
+        assert!(steps == 50 || mario.is_finished());
+    }
+
+    // A broken invariant is reported with the offending step
+    let mut mario = Mario::new();
+    let failure = RandomWalk::new(7, &events)
+        .run(&mut mario, |state: &MarioStates, _: &Context| match state {
+            MarioStates::FireMario => Err("no fire allowed"),
+            _ => Ok(()),
+        })
+        .unwrap_err();
+    assert_eq!(failure.state, MarioStates::FireMario);
+    assert_eq!(failure.reason, "no fire allowed");
+}
+
+#[test]
+fn replay_test() {
+    use MarioConsumables::*;
+    use MarioStates::*;
+
+    let log = [
+        Events::GetConsumable(Flower),
+        Events::Hit,
+        Events::Hit,
+        Events::Hit,
+    ];
+    let mut mario = Mario::new();
+    let steps: Vec<_> = replay(&mut mario, &log).collect();
+    assert_eq!(steps.len(), 4);
+    assert_eq!((steps[0].from, steps[0].to), (SmallMario, FireMario));
+    assert_eq!((steps[2].from, steps[2].to), (SmallMario, DeadMario));
+    assert_eq!(steps[3].result, Err(DispatchError::Finished));
+
+    assert_trajectory(
+        &mut Mario::new(),
+        &log,
+        &[FireMario, SmallMario, DeadMario, DeadMario],
+    );
+}
+
+#[test]
+#[should_panic(expected = "step 1")]
+fn assert_trajectory_test() {
+    use MarioStates::*;
+
+    assert_trajectory(
+        &mut Mario::new(),
+        &[
+            Events::GetConsumable(MarioConsumables::Mushroom),
+            Events::Hit,
+        ],
+        &[SuperMario, SuperMario],
+    );
+}
+
+#[test]
+fn expect_test() {
+    use MarioConsumables::*;
+    use MarioStates::*;
+
+    let mut mario = Mario::new();
+    expect(&mut mario)
+        .in_state(SmallMario)
+        .on(Events::GetConsumable(Mushroom))
+        .transitions_to(SuperMario)
+        .with(|context| context.size == MarioSize::Large)
+        .on(Events::GetConsumable(Mushroom))
+        .stays()
+        .on_sequence(&[Events::GetConsumable(Feather), Events::Hit])
+        .transitions_to(SmallMario)
+        .with(|context| context.alive)
+        .on_sequence(&[Events::Hit, Events::Hit])
+        .fails_with(DispatchError::Finished)
+        .then()
+        .in_state(DeadMario);
+}
+
+#[test]
+#[should_panic(expected = "Hit from SuperMario led to SmallMario with Ok(()), expected DeadMario")]
+fn expect_failure_test() {
+    expect(&mut Mario::new())
+        .on(Events::GetConsumable(MarioConsumables::Mushroom))
+        .on(Events::Hit)
+        .transitions_to(MarioStates::DeadMario);
+}
+
+#[test]
+fn view_test() {
+    fn render(view: MachineView<'_, MarioStates, Context>) -> (MarioStates, bool) {
+        (view.state(), view.context().alive)
+    }
+
+    let mut mario = Mario::new_started();
+    mario
+        .dispatch(Events::GetConsumable(MarioConsumables::Feather))
+        .unwrap();
+    let view = mario.view();
+    assert_eq!(render(view), (MarioStates::CapeMario, true));
+    assert_eq!(view.status(), Status::Running);
+    assert!(!view.is_finished());
+    assert_eq!(view.context().size, MarioSize::Large);
+}
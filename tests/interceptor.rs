@@ -0,0 +1,311 @@
+use rustfsm::interceptor::Interceptor;
+use rustfsm::*;
+
+rustfsm!(
+    Safe,
+    SafeStates { Closed, Opened },
+    Events {
+        Unlock,
+        Open,
+        Close,
+        Nudge,
+    },
+    Context {
+        unlocked: bool = false
+    },
+    Interceptors {
+        Authorization,
+        Outcomes
+    }
+);
+
+impl StateBehavior for SafeStates {
+    type State = SafeStates;
+    type Event = Events;
+    type Context = Context;
+
+    fn handle(&self, event: &Self::Event, context: &mut Self::Context) -> Option<Self::State> {
+        match (self, event) {
+            (_, Events::Unlock) => {
+                context.unlocked = true;
+                None
+            }
+            (SafeStates::Closed, Events::Open) => Some(SafeStates::Opened),
+            (SafeStates::Opened, Events::Close) => Some(SafeStates::Closed),
+            _ => None,
+        }
+    }
+}
+
+/// Vetoes opening a locked safe, and turns nudges into closing it
+#[derive(Clone, Default)]
+struct Authorization;
+
+impl Interceptor<SafeStates, Events, Context> for Authorization {
+    fn before(&mut self, _state: &SafeStates, context: &Context, event: Events) -> Option<Events> {
+        match event {
+            Events::Open if !context.unlocked => None,
+            Events::Nudge => Some(Events::Close),
+            event => Some(event),
+        }
+    }
+}
+
+/// Counts the outcomes of the dispatches
+#[derive(Clone, Default)]
+struct Outcomes {
+    vetoed: usize,
+    transitions: usize,
+}
+
+impl Interceptor<SafeStates, Events, Context> for Outcomes {
+    fn after(
+        &mut self,
+        _event: &Events,
+        from: &SafeStates,
+        to: &SafeStates,
+        result: &Result<(), DispatchError>,
+    ) {
+        match result {
+            Err(DispatchError::Vetoed) => self.vetoed += 1,
+            Ok(()) if from != to => self.transitions += 1,
+            _ => (),
+        }
+    }
+}
+
+#[test]
+fn interceptor_test() {
+    let mut safe = Safe::new();
+    assert_eq!(safe.dispatch(Events::Open), Err(DispatchError::Vetoed));
+    assert_eq!(safe.get_current_state(), SafeStates::Closed);
+
+    safe.dispatch(Events::Unlock).unwrap();
+    safe.dispatch(Events::Open).unwrap();
+    assert_eq!(safe.get_current_state(), SafeStates::Opened);
+
+    // Transformed event
+    safe.dispatch(Events::Nudge).unwrap();
+    assert_eq!(safe.get_current_state(), SafeStates::Closed);
+
+    let outcomes = &safe.interceptors().1;
+    assert_eq!(outcomes.vetoed, 1);
+    assert_eq!(outcomes.transitions, 2);
+}
+
+mod notify_order {
+    use rustfsm::interceptor::Interceptor;
+    use rustfsm::*;
+
+    rustfsm!(
+        Valve,
+        ValveStates {
+            Shut,
+            Open,
+        },
+        Events {
+            Toggle,
+        },
+        Context {
+            entries: u32 = 0
+        },
+        Interceptors { Logger },
+        Dispatch { notify(before_enter) }
+    );
+
+    impl StateBehavior for ValveStates {
+        type State = ValveStates;
+        type Event = Events;
+        type Context = Context;
+
+        fn enter(&self, context: &mut Self::Context) {
+            context.entries += 1;
+        }
+
+        fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+            match (self, event) {
+                (ValveStates::Shut, Events::Toggle) => Some(ValveStates::Open),
+                (ValveStates::Open, Events::Toggle) => Some(ValveStates::Shut),
+            }
+        }
+    }
+
+    /// Logs the transitions along with the entries seen so far
+    #[derive(Clone, Default)]
+    struct Logger {
+        log: Vec<(ValveStates, ValveStates, u32)>,
+    }
+
+    impl Interceptor<ValveStates, Events, Context> for Logger {
+        fn on_transition(
+            &mut self,
+            _event: &Events,
+            from: &ValveStates,
+            to: &ValveStates,
+            context: &Context,
+        ) {
+            self.log.push((*from, *to, context.entries));
+        }
+    }
+
+    #[test]
+    fn notify_order_test() {
+        let mut valve = Valve::new();
+        valve.dispatch(Events::Toggle).unwrap();
+        valve.dispatch(Events::Toggle).unwrap();
+
+        // Notified before the new state's `enter` ran
+        assert_eq!(
+            valve.interceptors().0.log,
+            [
+                (ValveStates::Shut, ValveStates::Open, 0),
+                (ValveStates::Open, ValveStates::Shut, 1),
+            ]
+        );
+        assert_eq!(valve.context().entries, 2);
+    }
+}
+
+mod guard {
+    use rustfsm::interceptor::Interceptor;
+    use rustfsm::*;
+
+    rustfsm!(
+        Airlock,
+        AirlockStates { Sealed, Cycling },
+        Events { Cycle, Vent },
+        Context {
+            pressurized: bool = false
+        },
+        Interceptors { Refusals }
+    );
+
+    impl StateBehavior for AirlockStates {
+        type State = AirlockStates;
+        type Event = Events;
+        type Context = Context;
+
+        fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+            match (self, event) {
+                (AirlockStates::Sealed, Events::Cycle) => Some(AirlockStates::Cycling),
+                _ => None,
+            }
+        }
+
+        fn guard(&self, event: &Self::Event, context: &Self::Context) -> bool {
+            !matches!((self, event), (AirlockStates::Sealed, Events::Cycle)) || context.pressurized
+        }
+    }
+
+    /// Counts the refused events
+    #[derive(Clone, Default)]
+    struct Refusals(usize);
+
+    impl Interceptor<AirlockStates, Events, Context> for Refusals {
+        fn after(
+            &mut self,
+            _event: &Events,
+            _from: &AirlockStates,
+            _to: &AirlockStates,
+            result: &Result<(), DispatchError>,
+        ) {
+            if let Err(DispatchError::GuardRejected { .. }) = result {
+                self.0 += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn guard_rejected_test() {
+        let mut airlock = Airlock::new();
+        assert_eq!(
+            airlock.dispatch(Events::Cycle),
+            Err(DispatchError::GuardRejected {
+                state: "Sealed",
+                event: "Cycle",
+            })
+        );
+        // Unknown to the state, but not refused
+        assert_eq!(airlock.dispatch(Events::Vent), Ok(()));
+
+        airlock.context_mut().pressurized = true;
+        airlock.dispatch(Events::Cycle).unwrap();
+        assert_eq!(airlock.get_current_state(), AirlockStates::Cycling);
+        assert_eq!(airlock.interceptors().0 .0, 1);
+    }
+}
+
+mod provenance {
+    use rustfsm::interceptor::Interceptor;
+    use rustfsm::queue::Outbox;
+    use rustfsm::*;
+
+    rustfsm!(
+        Sensor,
+        SensorStates { Idle, Sampling },
+        Events { Trigger, Sample },
+        Context {
+            handled: Option<Source> = None
+        },
+        Trace { depth: 4 },
+        Queue { depth: 2 },
+        Interceptors { Sources },
+        Dispatch { tagged }
+    );
+
+    impl StateBehavior for SensorStates {
+        type State = SensorStates;
+        type Event = Events;
+        type Context = Context;
+
+        fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+            match event {
+                Events::Trigger => Some(SensorStates::Sampling),
+                Events::Sample => Some(SensorStates::Idle),
+            }
+        }
+
+        fn try_handle_with(
+            &self,
+            event: &Self::Event,
+            context: &mut Self::Context,
+            outbox: &mut Outbox<'_, Self::Event>,
+        ) -> Result<Option<Self::State>, &'static str> {
+            context.handled = Some(outbox.source());
+            if *event == Events::Trigger {
+                outbox.post(Events::Sample).unwrap();
+            }
+            self.try_handle(event, context)
+        }
+    }
+
+    /// Sources seen, latest first
+    #[derive(Clone, Default)]
+    struct Sources {
+        seen: [Option<Source>; 2],
+    }
+
+    impl Interceptor<SensorStates, Events, Context> for Sources {
+        fn on_source(&mut self, source: Source) {
+            self.seen = [Some(source), self.seen[0]];
+        }
+    }
+
+    #[test]
+    fn provenance_test() {
+        let mut sensor = Sensor::new();
+        sensor
+            .dispatch_tagged(Events::Trigger, Source::Isr)
+            .unwrap();
+        assert_eq!(
+            sensor.interceptors().0.seen,
+            [Some(Source::Queued), Some(Source::Isr)]
+        );
+        assert_eq!(sensor.context().handled, Some(Source::Queued));
+        assert_eq!(sensor.last_source(), Source::Queued);
+
+        sensor.dispatch(Events::Sample).unwrap();
+        let sources: Vec<_> = sensor.trace().iter().map(|record| record.source).collect();
+        assert_eq!(sources, [Source::Isr, Source::Queued, Source::Host]);
+    }
+}
@@ -0,0 +1,158 @@
+use rustfsm::lockstep::{Emitter, Lockstep, Member};
+use rustfsm::*;
+
+const MOTOR: usize = 0;
+const CHARGER: usize = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Message {
+    Motor(MotorEvents),
+    Charger(ChargerEvents),
+    LowBattery,
+}
+
+rustfsm!(
+    Motor,
+    MotorStates { Stopped, Running },
+    MotorEvents { Start, Stop },
+    MotorContext {}
+);
+
+impl StateBehavior for MotorStates {
+    type State = MotorStates;
+    type Event = MotorEvents;
+    type Context = MotorContext;
+
+    fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+        use MotorEvents::*;
+        use MotorStates::*;
+        match (self, event) {
+            (Stopped, Start) => Some(Running),
+            (Running, Stop) => Some(Stopped),
+            _ => None,
+        }
+    }
+}
+
+impl Member<Message> for Motor {
+    fn deliver(&mut self, message: &Message, out: &mut Emitter<'_, Message>) {
+        match message {
+            Message::Motor(event) => {
+                let _ = self.dispatch(*event);
+                if *event == MotorEvents::Start {
+                    let _ = out.send(CHARGER, Message::Charger(ChargerEvents::Drain));
+                }
+            }
+            Message::LowBattery => {
+                let _ = self.dispatch(MotorEvents::Stop);
+            }
+            _ => {}
+        }
+    }
+}
+
+rustfsm!(
+    Charger,
+    ChargerStates { Full, Low },
+    ChargerEvents { Drain, Charge },
+    ChargerContext { cycles: u32 = 0 }
+);
+
+impl StateBehavior for ChargerStates {
+    type State = ChargerStates;
+    type Event = ChargerEvents;
+    type Context = ChargerContext;
+
+    fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+        use ChargerEvents::*;
+        use ChargerStates::*;
+        match (self, event) {
+            (Full, Drain) => Some(Low),
+            (Low, Charge) => Some(Full),
+            _ => None,
+        }
+    }
+}
+
+impl Member<Message> for Charger {
+    fn deliver(&mut self, message: &Message, out: &mut Emitter<'_, Message>) {
+        if let Message::Charger(event) = message {
+            let _ = self.dispatch(*event);
+            if self.get_current_state() == ChargerStates::Low {
+                let _ = out.broadcast(Message::LowBattery);
+            }
+        }
+    }
+
+    fn on_cycle(&mut self, _out: &mut Emitter<'_, Message>) {
+        self.context_mut().cycles += 1;
+    }
+}
+
+/// Records the messages it receives
+#[derive(Default)]
+struct Ui {
+    alarms: u32,
+}
+
+impl Member<Message> for Ui {
+    fn deliver(&mut self, message: &Message, _out: &mut Emitter<'_, Message>) {
+        if *message == Message::LowBattery {
+            self.alarms += 1;
+        }
+    }
+}
+
+#[test]
+fn lockstep_test() {
+    let mut motor = Motor::new();
+    let mut charger = Charger::new();
+    let mut ui = Ui::default();
+
+    {
+        let mut system = Lockstep::<_, 3, 4>::new([&mut motor, &mut charger, &mut ui]);
+        assert!(system.is_quiescent());
+        assert_eq!(system.post(MOTOR, Message::Motor(MotorEvents::Start)), Ok(()));
+
+        // Each hop takes a cycle: start, drain, then the broadcast to the
+        // motor and the UI
+        assert_eq!(system.cycle(), 1);
+        assert!(!system.is_quiescent());
+        assert_eq!(system.cycle(), 1);
+        assert_eq!(system.run_until_quiescent(10), Some(1));
+        assert_eq!(system.cycles(), 3);
+
+        // Nothing in transit
+        assert_eq!(system.cycle(), 0);
+        assert_eq!(system.run_until_quiescent(10), Some(0));
+    }
+
+    assert_eq!(motor.get_current_state(), MotorStates::Stopped);
+    assert_eq!(charger.get_current_state(), ChargerStates::Low);
+    assert_eq!(charger.context().cycles, 4);
+    // The broadcast skipped its sender
+    assert_eq!(ui.alarms, 1);
+}
+
+/// Sends itself a message every time it receives one
+struct Echo;
+
+impl Member<Message> for Echo {
+    fn deliver(&mut self, message: &Message, out: &mut Emitter<'_, Message>) {
+        let _ = out.send(0, *message);
+    }
+}
+
+#[test]
+fn lockstep_not_quiescent_test() {
+    let mut echo = Echo;
+    let mut system = Lockstep::<_, 1, 1>::new([&mut echo]);
+    assert_eq!(system.post(0, Message::LowBattery), Ok(()));
+    // Full queue
+    assert_eq!(
+        system.post(0, Message::LowBattery),
+        Err(Message::LowBattery)
+    );
+    assert_eq!(system.run_until_quiescent(5), None);
+    assert_eq!(system.cycles(), 5);
+}
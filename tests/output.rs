@@ -0,0 +1,71 @@
+use rustfsm::output::Mealy;
+use rustfsm::*;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Frame {
+    Syn,
+    Ack,
+    Fin,
+}
+
+rustfsm!(
+    Link,
+    LinkStates {
+        Closed,
+        Connecting,
+        Open,
+    },
+    Events {
+        Connect,
+        Received(Frame),
+        Close,
+    },
+    Context {},
+    Output { Frame }
+);
+
+impl StateBehavior for LinkStates {
+    type State = LinkStates;
+    type Event = Events;
+    type Context = Context;
+
+    fn handle(&self, event: &Self::Event, context: &mut Self::Context) -> Option<Self::State> {
+        self.handle_output(event, context).0
+    }
+}
+
+impl Mealy for LinkStates {
+    type Output = Frame;
+
+    fn handle_output(
+        &self,
+        event: &Self::Event,
+        _context: &mut Self::Context,
+    ) -> (Option<Self::State>, Option<Frame>) {
+        use LinkStates::*;
+
+        match (self, event) {
+            (Closed, Events::Connect) => (Some(Connecting), Some(Frame::Syn)),
+            (Connecting, Events::Received(Frame::Syn)) => (Some(Open), Some(Frame::Ack)),
+            (Open, Events::Close) => (Some(Closed), Some(Frame::Fin)),
+            _ => (None, None),
+        }
+    }
+}
+
+#[test]
+fn output_test() {
+    let mut link = Link::new();
+    assert_eq!(link.dispatch_output(Events::Connect), Ok(Some(Frame::Syn)));
+    assert_eq!(link.dispatch_output(Events::Connect), Ok(None));
+    assert_eq!(
+        link.dispatch_output(Events::Received(Frame::Syn)),
+        Ok(Some(Frame::Ack))
+    );
+    assert_eq!(link.get_current_state(), LinkStates::Open);
+
+    // Plain dispatches drop the output
+    link.dispatch(Events::Close).unwrap();
+    assert_eq!(link.get_current_state(), LinkStates::Closed);
+    assert_eq!(link.dispatch_output(Events::Close), Ok(None));
+}
@@ -0,0 +1,102 @@
+use rustfsm::*;
+
+rustfsm!(
+    Channel,
+    ChannelStates { Idle, Sampling },
+    Events { Start, Stop },
+    Context { samples: u32 = 0 }
+);
+
+impl StateBehavior for ChannelStates {
+    type State = ChannelStates;
+    type Event = Events;
+    type Context = Context;
+
+    fn enter(&self, context: &mut Self::Context) {
+        if let ChannelStates::Sampling = self {
+            context.samples += 1;
+        }
+    }
+
+    fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+        use ChannelStates::*;
+        use Events::*;
+        match (self, event) {
+            (Idle, Start) => Some(Sampling),
+            (Sampling, Stop) => Some(Idle),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn pool_test() {
+    let mut pool: Pool<Channel, 4> = Pool::new();
+    assert_eq!(pool.len(), 4);
+
+    // Single machine dispatch
+    assert_eq!(pool.dispatch(1, Events::Start), Some(Ok(())));
+    assert_eq!(pool.get(0).unwrap().current_state, ChannelStates::Idle);
+    assert_eq!(pool.get(1).unwrap().current_state, ChannelStates::Sampling);
+
+    // Out of bounds
+    assert_eq!(pool.dispatch(4, Events::Start), None);
+    assert!(pool.get(4).is_none());
+
+    // Bulk dispatch
+    assert_eq!(pool.dispatch_to_all(Events::Start), Ok(()));
+    assert!(pool
+        .iter()
+        .all(|channel| channel.current_state == ChannelStates::Sampling));
+    assert_eq!(pool.get(1).unwrap().context.samples, 1);
+
+    assert_eq!(pool.dispatch_to_all(Events::Stop), Ok(()));
+    assert!(pool
+        .iter()
+        .all(|channel| channel.current_state == ChannelStates::Idle));
+}
+
+#[test]
+fn dispatch_error_test() {
+    let mut pool: Pool<Channel, 3> = Pool::new();
+    pool.get_mut(1).unwrap().stop();
+    assert_eq!(
+        pool.dispatch(1, Events::Start),
+        Some(Err(DispatchError::Stopped))
+    );
+
+    // The machines after the one refusing the event still get it
+    assert_eq!(
+        pool.dispatch_to_all(Events::Start),
+        Err(DispatchError::Stopped)
+    );
+    let states: Vec<_> = pool.iter().map(|channel| channel.current_state).collect();
+    assert_eq!(
+        states,
+        [
+            ChannelStates::Sampling,
+            ChannelStates::Idle,
+            ChannelStates::Sampling
+        ]
+    );
+}
+
+#[test]
+fn from_fn_test() {
+    // Every other channel restored while sampling
+    let pool: Pool<Channel, 4> = Pool::from_fn(|index| match index % 2 {
+        0 => Channel::new(),
+        _ => Channel::new_at(ChannelStates::Sampling, Context { samples: 9 }),
+    });
+    let states: Vec<_> = pool.iter().map(|channel| channel.current_state).collect();
+    assert_eq!(
+        states,
+        [
+            ChannelStates::Idle,
+            ChannelStates::Sampling,
+            ChannelStates::Idle,
+            ChannelStates::Sampling
+        ]
+    );
+    assert_eq!(pool.get(3).unwrap().context.samples, 9);
+}
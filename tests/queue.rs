@@ -0,0 +1,357 @@
+use rustfsm::queue::Outbox;
+use rustfsm::*;
+
+rustfsm!(
+    Boiler,
+    BoilerStates {
+        Idle,
+        Filling,
+        Heating,
+        Ready,
+    },
+    Events {
+        Start,
+        Full,
+        StartHeating,
+        Hot,
+        Drain,
+    },
+    Context {
+        posted: usize = 0,
+        refused: usize = 0,
+    },
+    Queue { depth: 1 }
+);
+
+impl StateBehavior for BoilerStates {
+    type State = BoilerStates;
+    type Event = Events;
+    type Context = Context;
+
+    fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+        use BoilerStates::*;
+        use Events::*;
+
+        match (self, event) {
+            (Idle, Start) => Some(Filling),
+            (Filling, Full) => Some(Filling),
+            (Filling, StartHeating) => Some(Heating),
+            (Heating, Hot) => Some(Ready),
+            (_, Drain) => Some(Idle),
+            _ => None,
+        }
+    }
+
+    fn try_handle_with(
+        &self,
+        event: &Self::Event,
+        context: &mut Self::Context,
+        outbox: &mut Outbox<'_, Self::Event>,
+    ) -> Result<Option<Self::State>, &'static str> {
+        if let (BoilerStates::Filling, Events::Full) = (self, event) {
+            for event in [Events::StartHeating, Events::Hot] {
+                match outbox.post(event) {
+                    Ok(_) => context.posted += 1,
+                    Err(_) => context.refused += 1,
+                }
+            }
+        }
+        self.try_handle(event, context)
+    }
+}
+
+#[test]
+fn follow_up_events_test() {
+    let mut boiler = Boiler::new();
+    boiler.dispatch(Events::Start).unwrap();
+    boiler.dispatch(Events::Full).unwrap();
+
+    // The follow-up event is handled before `dispatch` returns
+    assert_eq!(boiler.get_current_state(), BoilerStates::Heating);
+    assert_eq!(boiler.context().posted, 1);
+    assert_eq!(boiler.context().refused, 1);
+}
+
+mod unqueued {
+    use rustfsm::queue::Outbox;
+    use rustfsm::*;
+
+    rustfsm!(
+        Kettle,
+        KettleStates { Cold, Hot },
+        Events { Heat, Boil },
+        Context {
+            refused: bool = false,
+        }
+    );
+
+    impl StateBehavior for KettleStates {
+        type State = KettleStates;
+        type Event = Events;
+        type Context = Context;
+
+        fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+            match event {
+                Events::Boil => Some(KettleStates::Hot),
+                Events::Heat => None,
+            }
+        }
+
+        fn try_handle_with(
+            &self,
+            event: &Self::Event,
+            context: &mut Self::Context,
+            outbox: &mut Outbox<'_, Self::Event>,
+        ) -> Result<Option<Self::State>, &'static str> {
+            if let Events::Heat = event {
+                context.refused = outbox.post(Events::Boil) == Err(Events::Boil);
+            }
+            self.try_handle(event, context)
+        }
+    }
+
+    #[test]
+    fn missing_queue_test() {
+        let mut kettle = Kettle::new();
+        kettle.dispatch(Events::Heat).unwrap();
+
+        // Without a `Queue` section, every follow-up event is given back
+        assert!(kettle.context().refused);
+        assert_eq!(kettle.get_current_state(), KettleStates::Cold);
+    }
+}
+
+mod priority {
+    use rustfsm::queue::Outbox;
+    use rustfsm::*;
+
+    rustfsm!(
+        Press,
+        PressStates {
+            Idle,
+            Moving(u8),
+            Stopped,
+        },
+        Events {
+            Jog(u8),
+            Overload,
+            EmergencyStop,
+        },
+        Context {
+            flushed: usize = 0,
+        },
+        Queue {
+            depth: 4,
+            priority: |event| match event {
+                Events::EmergencyStop => 1,
+                _ => 0,
+            },
+        },
+        Dispatch { pausable }
+    );
+
+    impl StateBehavior for PressStates {
+        type State = PressStates;
+        type Event = Events;
+        type Context = Context;
+
+        fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+            match (self, event) {
+                (PressStates::Stopped, _) => None,
+                (_, Events::Jog(position)) => Some(PressStates::Moving(*position)),
+                (_, Events::EmergencyStop) => Some(PressStates::Stopped),
+                (_, Events::Overload) => None,
+            }
+        }
+
+        fn try_handle_with(
+            &self,
+            event: &Self::Event,
+            context: &mut Self::Context,
+            outbox: &mut Outbox<'_, Self::Event>,
+        ) -> Result<Option<Self::State>, &'static str> {
+            match event {
+                Events::Overload => {
+                    outbox.post(Events::EmergencyStop).unwrap();
+                }
+                Events::EmergencyStop => context.flushed = outbox.flush_below(1),
+                Events::Jog(_) => {}
+            }
+            self.try_handle(event, context)
+        }
+    }
+
+    #[test]
+    fn priority_test() {
+        let mut press = Press::new();
+        press.post(Events::Jog(1)).unwrap();
+        press.post(Events::Overload).unwrap();
+        press.post(Events::Jog(2)).unwrap();
+        press.post(Events::Jog(3)).unwrap();
+        assert_eq!(press.post(Events::Jog(4)), Err(Events::Jog(4)));
+
+        // The emergency stop posted on overload skips ahead of the jogs,
+        // which it then drops
+        assert_eq!(press.process_all(), Ok(3));
+        assert_eq!(press.get_current_state(), PressStates::Stopped);
+        assert_eq!(press.context().flushed, 2);
+        assert_eq!(press.process_all(), Ok(0));
+    }
+
+    #[test]
+    fn step_test() {
+        let mut press = Press::new();
+        press.post(Events::Jog(1)).unwrap();
+        press.post(Events::Overload).unwrap();
+        press.post(Events::Jog(2)).unwrap();
+        assert!(press.queued().eq(&[Events::Jog(1), Events::Overload, Events::Jog(2)]));
+
+        assert_eq!(press.step(), Ok(true));
+        assert_eq!(press.get_current_state(), PressStates::Moving(1));
+        assert!(press.queued().eq(&[Events::Overload, Events::Jog(2)]));
+
+        // The emergency stop posted on overload is next
+        assert_eq!(press.step(), Ok(true));
+        assert!(press.queued().eq(&[Events::EmergencyStop, Events::Jog(2)]));
+        assert_eq!(press.step(), Ok(true));
+        assert_eq!(press.get_current_state(), PressStates::Stopped);
+        assert_eq!(press.queued().count(), 0);
+        assert_eq!(press.step(), Ok(false));
+    }
+
+    #[test]
+    fn process_for_test() {
+        let mut press = Press::new();
+        press.post(Events::Jog(1)).unwrap();
+        press.post(Events::Jog(2)).unwrap();
+        press.post(Events::Jog(3)).unwrap();
+
+        let progress = press.process_for(2).unwrap();
+        assert_eq!(progress.dispatched, 2);
+        assert_eq!(progress.remaining, 1);
+        assert_eq!(press.get_current_state(), PressStates::Moving(2));
+        assert_eq!(press.process_for(0).unwrap().dispatched, 0);
+
+        // Events posted meanwhile count towards the budget
+        press.post(Events::Overload).unwrap();
+        let progress = press.process_for(8).unwrap();
+        assert_eq!(progress.dispatched, 3);
+        assert!(progress.is_done());
+        assert_eq!(press.get_current_state(), PressStates::Stopped);
+    }
+
+    #[test]
+    fn pause_queue_test() {
+        let mut press = Press::new();
+        press.pause(PausePolicy::Queue);
+        press.dispatch(Events::Jog(1)).unwrap();
+        press.dispatch(Events::Jog(2)).unwrap();
+        assert_eq!(press.get_current_state(), PressStates::Idle);
+        assert_eq!(press.process_all(), Err(DispatchError::Paused));
+
+        // The queued work survives the pause
+        assert_eq!(press.resume(), Ok(2));
+        assert_eq!(press.get_current_state(), PressStates::Moving(2));
+        assert_eq!(press.resume(), Ok(0));
+    }
+
+    #[test]
+    fn cancel_test() {
+        let mut press = Press::new();
+        let first = press.post(Events::Jog(1)).unwrap();
+        press.post(Events::Jog(2)).unwrap();
+        press.post(Events::EmergencyStop).unwrap();
+        assert!(press.cancel_post(first));
+        assert!(!press.cancel_post(first));
+
+        // Jogs are cancelled whatever their position
+        assert_eq!(press.cancel_all_matching(&Events::Jog(0)), 1);
+        assert_eq!(press.process_all(), Ok(1));
+        assert_eq!(press.get_current_state(), PressStates::Stopped);
+    }
+}
+
+mod scripted {
+    use rustfsm::*;
+
+    rustfsm!(
+        Winch,
+        WinchStates { Idle, Reeling(u8) },
+        Events { Reel(u8), Halt },
+        Context {
+            reeled: u32 = 0,
+        },
+        Queue { depth: 2 }
+    );
+
+    impl From<u8> for Events {
+        fn from(length: u8) -> Self {
+            Events::Reel(length)
+        }
+    }
+
+    impl StateBehavior for WinchStates {
+        type State = WinchStates;
+        type Event = Events;
+        type Context = Context;
+
+        fn handle(&self, event: &Self::Event, context: &mut Self::Context) -> Option<Self::State> {
+            match event {
+                Events::Reel(length) => {
+                    context.reeled += u32::from(*length);
+                    Some(WinchStates::Reeling(*length))
+                }
+                Events::Halt => Some(WinchStates::Idle),
+            }
+        }
+    }
+
+    #[test]
+    fn extend_test() {
+        let mut winch = Winch::new();
+        winch.extend([Events::Reel(1), Events::Reel(2)]);
+        assert_eq!(winch.queued().count(), 2);
+        assert_eq!(winch.get_current_state(), WinchStates::Idle);
+
+        // A full queue is dispatched to make room, keeping the order
+        winch.extend([Events::Reel(3), Events::Halt, Events::Reel(4)]);
+        assert!(winch.queued().eq(&[Events::Reel(4)]));
+        assert_eq!(winch.get_current_state(), WinchStates::Idle);
+        assert_eq!(winch.process_all(), Ok(1));
+        assert_eq!(winch.get_current_state(), WinchStates::Reeling(4));
+        assert_eq!(winch.context().reeled, 10);
+    }
+
+    #[test]
+    fn feed_test() {
+        let mut winch = Winch::new();
+        winch
+            .feed(5)
+            .unwrap()
+            .feed(Events::Halt)
+            .unwrap()
+            .feed(7)
+            .unwrap();
+        assert_eq!(winch.get_current_state(), WinchStates::Reeling(7));
+        assert_eq!(winch.context().reeled, 12);
+    }
+
+    #[test]
+    fn snapshot_queue_test() {
+        let mut winch = Winch::new();
+        winch.post(Events::Reel(3)).unwrap();
+
+        // A snapshot keeps the queued events
+        let snapshot = winch.snapshot();
+        assert!(snapshot.queued().eq(&[Events::Reel(3)]));
+        assert_eq!(winch.process_all(), Ok(1));
+        winch.restore_from(&snapshot);
+        assert!(winch.queued().eq(&[Events::Reel(3)]));
+
+        // A reset drops them
+        winch.reset(ResetPolicy::PreserveContext);
+        assert_eq!(winch.queued().count(), 0);
+        assert_eq!(winch.process_all(), Ok(0));
+        assert_eq!(winch.get_current_state(), WinchStates::Idle);
+    }
+}
@@ -0,0 +1,139 @@
+use rustfsm::remote::*;
+use rustfsm::*;
+
+rustfsm!(
+    Pump,
+    PumpStates {
+        Idle,
+        Pumping(u8),
+        Priming,
+    },
+    Events {
+        Start(u8),
+        Stop,
+    },
+    Context {
+        runs: u32 = 0
+    }
+);
+
+impl StateBehavior for PumpStates {
+    type State = PumpStates;
+    type Event = Events;
+    type Context = Context;
+
+    fn handle(&self, event: &Self::Event, context: &mut Self::Context) -> Option<Self::State> {
+        match event {
+            Events::Start(rate) => {
+                context.runs += 1;
+                Some(PumpStates::Pumping(*rate))
+            }
+            Events::Stop => Some(PumpStates::Idle),
+        }
+    }
+}
+
+/// Loopback transport, handing out the received bytes a few at a time
+#[derive(Default)]
+struct Link {
+    rx: Vec<u8>,
+    tx: Vec<u8>,
+}
+
+impl Transport for Link {
+    fn read(&mut self, buf: &mut [u8]) -> usize {
+        let len = self.rx.len().min(buf.len()).min(3);
+        buf[..len].copy_from_slice(&self.rx[..len]);
+        self.rx.drain(..len);
+        len
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.tx.extend_from_slice(bytes);
+    }
+}
+
+fn request(server: &mut Server<Link, 16>, pump: &mut Pump, request: &[u8]) -> Vec<u8> {
+    server.transport_mut().rx.push(request.len() as u8);
+    server.transport_mut().rx.extend_from_slice(request);
+    while !server.transport().rx.is_empty() {
+        server.poll(pump);
+    }
+    core::mem::take(&mut server.transport_mut().tx)
+}
+
+#[test]
+fn remote_test() {
+    let mut pump = Pump::new();
+    let mut server = Server::<_, 16>::new(Link::default());
+
+    assert_eq!(request(&mut server, &mut pump, &[QUERY_STATE]), [2, OK, 0]);
+
+    // Inject an event as a wire frame
+    let mut frame = [INJECT_EVENT, 0, 0, 0];
+    let len = Events::Start(5).encode(&mut frame[1..]);
+    assert_eq!(request(&mut server, &mut pump, &frame[..1 + len]), [1, OK]);
+    assert_eq!(pump.get_current_state(), PumpStates::Pumping(5));
+
+    // The context dump is truncated to the frame
+    assert_eq!(
+        request(&mut server, &mut pump, &[DUMP_CONTEXT]),
+        [&[15, OK][..], b"Context { runs"].concat()
+    );
+
+    // Only data-less states can be forced
+    assert_eq!(request(&mut server, &mut pump, &[FORCE_STATE, 2]), [1, OK]);
+    assert_eq!(pump.get_current_state(), PumpStates::Priming);
+    assert_eq!(
+        request(&mut server, &mut pump, &[FORCE_STATE, 1]),
+        [1, INVALID_ARGUMENT]
+    );
+    assert_eq!(
+        request(&mut server, &mut pump, &[FORCE_STATE, 7]),
+        [1, INVALID_ARGUMENT]
+    );
+
+    pump.stop();
+    assert_eq!(
+        request(&mut server, &mut pump, &frame[..1 + len]),
+        [1, STOPPED]
+    );
+    assert_eq!(
+        request(&mut server, &mut pump, &[INJECT_EVENT, 1]),
+        [1, INVALID_ARGUMENT]
+    );
+    assert_eq!(
+        request(&mut server, &mut pump, &[0x42]),
+        [1, UNKNOWN_COMMAND]
+    );
+}
+
+#[test]
+fn pipelined_requests_test() {
+    let mut pump = Pump::new();
+    let mut server = Server::<_, 16>::new(Link::default());
+
+    // Requests split across reads and back to back are all served
+    server.transport_mut().rx.extend_from_slice(&[
+        1,
+        QUERY_STATE,
+        2,
+        FORCE_STATE,
+        2,
+        1,
+        QUERY_STATE,
+    ]);
+    let mut served = 0;
+    while !server.transport().rx.is_empty() {
+        served += server.poll(&mut pump);
+    }
+    assert_eq!(served, 3);
+    assert_eq!(
+        core::mem::take(&mut server.transport_mut().tx),
+        [2, OK, 0, 1, OK, 2, OK, 2]
+    );
+
+    // Oversized requests are dropped
+    assert!(request(&mut server, &mut pump, &[QUERY_STATE; 40]).is_empty());
+    assert_eq!(request(&mut server, &mut pump, &[QUERY_STATE]), [2, OK, 2]);
+}
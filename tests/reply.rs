@@ -0,0 +1,63 @@
+use rustfsm::output::Respond;
+use rustfsm::*;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Ack {
+    Ack,
+    Nack,
+    Value(u16),
+}
+
+rustfsm!(
+    Sensor,
+    SensorStates { Asleep, Awake },
+    Events { Wake, Read },
+    Context { reading: u16 = 42 },
+    Reply { Ack }
+);
+
+impl StateBehavior for SensorStates {
+    type State = SensorStates;
+    type Event = Events;
+    type Context = Context;
+
+    fn handle(&self, event: &Self::Event, context: &mut Self::Context) -> Option<Self::State> {
+        self.handle_reply(event, context).0
+    }
+}
+
+impl Respond for SensorStates {
+    type Reply = Ack;
+
+    fn handle_reply(
+        &self,
+        event: &Self::Event,
+        context: &mut Self::Context,
+    ) -> (Option<Self::State>, Ack) {
+        match (self, event) {
+            (SensorStates::Asleep, Events::Wake) => (Some(SensorStates::Awake), Ack::Ack),
+            (SensorStates::Awake, Events::Read) => (None, Ack::Value(context.reading)),
+            _ => (None, Ack::Nack),
+        }
+    }
+}
+
+#[test]
+fn reply_test() {
+    let mut sensor = Sensor::new();
+    assert_eq!(
+        sensor.dispatch_with_reply(Events::Read),
+        Ok(Some(Ack::Nack))
+    );
+    assert_eq!(sensor.dispatch_with_reply(Events::Wake), Ok(Some(Ack::Ack)));
+    assert_eq!(
+        sensor.dispatch_with_reply(Events::Read),
+        Ok(Some(Ack::Value(42)))
+    );
+
+    sensor.stop();
+    assert_eq!(
+        sensor.dispatch_with_reply(Events::Read),
+        Err(DispatchError::Stopped)
+    );
+}
@@ -0,0 +1,104 @@
+use rustfsm::queue::Outbox;
+use rustfsm::rng::{Rng, Seeded};
+use rustfsm::*;
+
+rustfsm!(
+    Radio,
+    RadioStates {
+        Connecting,
+        Waiting,
+        Connected,
+    },
+    Events { Failed, Retry, Ack },
+    Context {
+        backoff: u32 = 10,
+        retries: u32 = 0,
+    },
+    Rng { Seeded }
+);
+
+impl StateBehavior for RadioStates {
+    type State = RadioStates;
+    type Event = Events;
+    type Context = Context;
+
+    fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+        match (self, event) {
+            (RadioStates::Waiting, Events::Retry) => Some(RadioStates::Connecting),
+            (RadioStates::Connecting, Events::Ack) => Some(RadioStates::Connected),
+            _ => None,
+        }
+    }
+
+    fn try_handle_with(
+        &self,
+        event: &Self::Event,
+        context: &mut Self::Context,
+        outbox: &mut Outbox<'_, Self::Event>,
+    ) -> Result<Option<Self::State>, &'static str> {
+        match (self, event) {
+            (RadioStates::Connecting, Events::Failed) => {
+                let jitter = outbox.rng().map_or(0, |rng| rng.below(10));
+                context.backoff = context.backoff * 2 + jitter;
+                context.retries += 1;
+                Ok(Some(RadioStates::Waiting))
+            }
+            _ => Ok(self.handle(event, context)),
+        }
+    }
+
+    fn guard_with_rng(
+        &self,
+        event: &Self::Event,
+        _context: &Self::Context,
+        rng: &mut dyn Rng,
+    ) -> bool {
+        // Chaos injection: one ack out of two is lost
+        !matches!(event, Events::Ack) || rng.chance(1, 2)
+    }
+}
+
+/// Backoffs of 4 failed attempts, and how many acks it took to connect
+fn run(radio: &mut Radio) -> ([u32; 4], u32) {
+    let mut backoffs = [0; 4];
+    for backoff in &mut backoffs {
+        radio.dispatch(Events::Failed).unwrap();
+        *backoff = *radio.context().backoff();
+        radio.dispatch(Events::Retry).unwrap();
+    }
+    let mut acks = 1;
+    while radio.dispatch(Events::Ack).is_err() {
+        acks += 1;
+    }
+    (backoffs, acks)
+}
+
+#[test]
+fn seeded_test() {
+    let mut radio = Radio::new();
+    *radio.rng_mut() = Seeded::new(42);
+    let (backoffs, acks) = run(&mut radio);
+    assert_eq!(radio.get_current_state(), RadioStates::Connected);
+    assert_eq!(*radio.context().retries(), 4);
+    // The jitter adds up to 9 to each doubling
+    let mut previous = 10;
+    for backoff in backoffs {
+        assert!((previous * 2..previous * 2 + 10).contains(&backoff));
+        previous = backoff;
+    }
+
+    // The same seed draws the same run
+    let mut replay = Radio::new();
+    *replay.rng_mut() = Seeded::new(42);
+    assert_eq!(run(&mut replay), (backoffs, acks));
+}
+
+#[test]
+fn chance_test() {
+    let mut rng = Seeded::new(7);
+    assert!((0..1000).all(|_| rng.below(6) < 6));
+    assert_eq!(rng.below(0), 0);
+    assert!((0..100).all(|_| !rng.chance(0, 3) && rng.chance(3, 3)));
+    let heads = (0..1000).filter(|_| rng.chance(1, 2)).count();
+    assert!((400..600).contains(&heads));
+}
@@ -0,0 +1,95 @@
+use rustfsm::*;
+
+mod charger {
+    use rustfsm::*;
+
+    rustfsm!(
+        Charger,
+        ChargerStates { Idle, Charging },
+        ChargerEvents { Plug, Unplug },
+        Context {
+            charging: bool = false,
+            displayed: bool = false,
+        }
+    );
+
+    impl StateBehavior for ChargerStates {
+        type State = ChargerStates;
+        type Event = ChargerEvents;
+        type Context = Context;
+
+        fn enter(&self, context: &mut Self::Context) {
+            context.charging = *self == ChargerStates::Charging;
+        }
+
+        fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+            match (self, event) {
+                (ChargerStates::Idle, ChargerEvents::Plug) => Some(ChargerStates::Charging),
+                (ChargerStates::Charging, ChargerEvents::Unplug) => Some(ChargerStates::Idle),
+                _ => None,
+            }
+        }
+    }
+
+    impl Context {
+        pub fn set_displayed(&mut self, displayed: bool) {
+            self.displayed = displayed;
+        }
+    }
+}
+
+use charger::Context;
+
+rustfsm!(
+    Display,
+    DisplayStates { Off, On },
+    DisplayEvents { Refresh },
+    Context
+);
+
+impl StateBehavior for DisplayStates {
+    type State = DisplayStates;
+    type Event = DisplayEvents;
+    type Context = Context;
+
+    fn enter(&self, context: &mut Self::Context) {
+        context.set_displayed(*self == DisplayStates::On);
+    }
+
+    fn handle(&self, _event: &Self::Event, context: &mut Self::Context) -> Option<Self::State> {
+        match (self, context.charging()) {
+            (DisplayStates::Off, true) => Some(DisplayStates::On),
+            (DisplayStates::On, false) => Some(DisplayStates::Off),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn shared_context_test() {
+    let mut context = Context::default();
+    let mut charger = charger::Charger::new();
+    let mut display = Display::new();
+
+    charger
+        .dispatch_with(charger::ChargerEvents::Plug, &mut context)
+        .unwrap();
+    display
+        .dispatch_with(DisplayEvents::Refresh, &mut context)
+        .unwrap();
+    assert_eq!(display.get_current_state(), DisplayStates::On);
+    assert!(*context.displayed());
+
+    charger
+        .dispatch_with(charger::ChargerEvents::Unplug, &mut context)
+        .unwrap();
+    display
+        .dispatch_with(DisplayEvents::Refresh, &mut context)
+        .unwrap();
+    assert_eq!(display.get_current_state(), DisplayStates::Off);
+    assert!(!*context.displayed());
+
+    // The machines' own contexts were left untouched
+    assert!(!*charger.context().charging());
+    assert!(!*display.context().displayed());
+}
@@ -0,0 +1,155 @@
+//! Memory footprint of the generated machines, for every feature set.
+//!
+//! Instrumentation left out must not take any room, so a machine without
+//! any is exactly as large as its state, context, status and last fault, plus
+//! the reentrancy flag of debug builds.
+//! The expected sizes follow the enabled features, run the feature matrix
+//! with:
+//!
+//! ```sh
+//! for features in "" coverage metrics coverage,metrics; do
+//!     cargo test --test size --features "$features"
+//! done
+//! ```
+
+use core::mem::size_of;
+
+use rustfsm::coverage::Coverage;
+use rustfsm::metrics::Metrics;
+use rustfsm::timer::Timers;
+use rustfsm::trace::{NoTrace, Trace};
+use rustfsm::*;
+
+rustfsm!(
+    Lamp,
+    LampStates { Off, On },
+    Events { Toggle },
+    Context { switches: u16 = 0 }
+);
+
+impl StateBehavior for LampStates {
+    type State = LampStates;
+    type Event = Events;
+    type Context = Context;
+
+    fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+        match (self, event) {
+            (LampStates::Off, Events::Toggle) => Some(LampStates::On),
+            (LampStates::On, Events::Toggle) => Some(LampStates::Off),
+        }
+    }
+}
+
+mod traced {
+    use rustfsm::*;
+
+    rustfsm!(
+        TracedLamp,
+        TracedStates { Off, On },
+        TracedEvents { Toggle },
+        Context { switches: u16 = 0 },
+        Trace { depth: 4 }
+    );
+
+    impl StateBehavior for TracedStates {
+        type State = TracedStates;
+        type Event = TracedEvents;
+        type Context = Context;
+
+        fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+            match (self, event) {
+                (TracedStates::Off, TracedEvents::Toggle) => Some(TracedStates::On),
+                (TracedStates::On, TracedEvents::Toggle) => Some(TracedStates::Off),
+            }
+        }
+    }
+}
+
+use traced::{TracedEvents, TracedLamp, TracedStates};
+
+mod tight {
+    use rustfsm::*;
+
+    // A context leaving no padding for stray fields to hide in
+    rustfsm!(
+        Strip,
+        StripStates { Off, On },
+        StripEvents { Toggle },
+        Context {
+            pixels: [u8; 6] = [0; 6]
+        }
+    );
+
+    impl StateBehavior for StripStates {
+        type State = StripStates;
+        type Event = StripEvents;
+        type Context = Context;
+
+        fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+            match (self, event) {
+                (StripStates::Off, StripEvents::Toggle) => Some(StripStates::On),
+                (StripStates::On, StripEvents::Toggle) => Some(StripStates::Off),
+            }
+        }
+    }
+}
+
+use tight::{Strip, StripStates};
+
+/// Hand-written equivalent of the machines, holding only the enabled
+/// instrumentation
+#[allow(dead_code)]
+struct Bare<S, C, T> {
+    state: S,
+    context: C,
+    status: Status,
+    last_fault: Option<Fault>,
+    guard: DispatchGuard,
+    trace: T,
+    #[cfg(feature = "metrics")]
+    metrics: Metrics<2>,
+}
+
+#[test]
+fn instrumentation_size_test() {
+    #[cfg(not(debug_assertions))]
+    assert_eq!(size_of::<DispatchGuard>(), 0);
+    assert_eq!(size_of::<NoTrace>(), 0);
+    assert_eq!(size_of::<Timers<Events, 0>>(), 0);
+    assert_eq!(
+        size_of::<Trace<LampStates, Events, 0>>(),
+        2 * size_of::<usize>()
+    );
+
+    #[cfg(not(feature = "coverage"))]
+    assert_eq!(size_of::<Coverage<16>>(), 0);
+    #[cfg(feature = "coverage")]
+    assert_eq!(size_of::<Coverage<16>>(), 16);
+
+    #[cfg(not(feature = "metrics"))]
+    assert_eq!(size_of::<Metrics<16>>(), 0);
+    #[cfg(feature = "metrics")]
+    assert!(size_of::<Metrics<16>>() > 0);
+}
+
+#[test]
+fn machine_size_test() {
+    assert_eq!(
+        size_of::<Lamp>(),
+        size_of::<Bare<LampStates, Context, ()>>()
+    );
+    assert_eq!(
+        size_of::<TracedLamp>(),
+        size_of::<Bare<TracedStates, traced::Context, Trace<TracedStates, TracedEvents, 4>>>()
+    );
+    assert_eq!(
+        size_of::<Strip>(),
+        size_of::<Bare<StripStates, tight::Context, ()>>()
+    );
+
+    // Without a trace, none is recorded or reported
+    let mut lamp = Lamp::new();
+    lamp.dispatch(Events::Toggle).unwrap();
+    assert!(lamp.trace().is_empty());
+    assert_eq!(lamp.trace().capacity(), 0);
+}
@@ -0,0 +1,93 @@
+#![cfg(feature = "heapless")]
+
+use heapless::spsc::Queue;
+use rustfsm::*;
+
+rustfsm!(
+    Counter,
+    CounterStates {
+        Idle,
+        Counting,
+    },
+    Events { Add(u32) },
+    Context {
+        total: u32 = 0
+    }
+);
+
+impl StateBehavior for CounterStates {
+    type State = CounterStates;
+    type Event = Events;
+    type Context = Context;
+
+    fn handle(&self, event: &Self::Event, context: &mut Self::Context) -> Option<Self::State> {
+        let Events::Add(value) = event;
+        context.total += value;
+        Some(CounterStates::Counting)
+    }
+}
+
+#[test]
+fn spsc_test() {
+    let mut queue = Queue::<Events, 4>::new();
+    let (mut producer, mut consumer) = spsc::split(&mut queue);
+    let mut counter = Counter::new();
+
+    assert!(consumer.is_empty());
+    producer.post(Events::Add(1)).unwrap();
+    producer.post(Events::Add(2)).unwrap();
+    producer.post(Events::Add(3)).unwrap();
+    // One slot is kept free
+    assert!(!producer.ready());
+    assert_eq!(producer.post(Events::Add(4)), Err(Events::Add(4)));
+
+    assert_eq!(consumer.len(), 3);
+    assert!(consumer.process_one(&mut counter));
+    assert_eq!(counter.context().total, 1);
+    assert_eq!(consumer.process_all(&mut counter), 2);
+    assert_eq!(counter.context().total, 6);
+    assert!(!consumer.process_one(&mut counter));
+}
+
+#[cfg(not(feature = "audit"))]
+#[test]
+fn channel_test() {
+    static CHANNEL: spsc::EventChannel<Events, 8> = spsc::EventChannel::new();
+
+    let (mut producer, mut consumer) = CHANNEL.split().unwrap();
+    assert!(CHANNEL.split().is_none());
+    let mut counter = Counter::new();
+
+    // The producer is moved to another thread, as to the second core of a
+    // dual-core MCU
+    let core1 = std::thread::spawn(move || {
+        for value in 1..=100 {
+            while producer.post(Events::Add(value)).is_err() {}
+        }
+    });
+    while counter.context().total < 5050 {
+        consumer.process_all(&mut counter);
+    }
+    core1.join().unwrap();
+    assert_eq!(counter.context().total, 5050);
+}
+
+#[test]
+fn producer_thread_test() {
+    let mut queue = Queue::<Events, 8>::new();
+    let (mut producer, mut consumer) = spsc::split(&mut queue);
+    let mut counter = Counter::new();
+
+    // The producer runs concurrently, as an interrupt handler would
+    std::thread::scope(|scope| {
+        scope.spawn(move || {
+            for value in 1..=1000 {
+                while producer.post(Events::Add(value)).is_err() {}
+            }
+        });
+        while counter.context().total < 500_500 {
+            consumer.process_all(&mut counter);
+        }
+    });
+    assert_eq!(counter.context().total, 500_500);
+}
@@ -0,0 +1,119 @@
+use rustfsm::*;
+
+rustfsm!(
+    Spindle,
+    SpindleStates {
+        Idle,
+        Running,
+        @fault Faulted,
+    },
+    Events {
+        Start,
+        Overload,
+    },
+    Context {
+        starts: u8 = 0,
+        locked: bool = false
+    }
+);
+
+impl StateBehavior for SpindleStates {
+    type State = SpindleStates;
+    type Event = Events;
+    type Context = Context;
+
+    fn enter(&self, context: &mut Self::Context) {
+        if let SpindleStates::Running = self {
+            context.starts += 1;
+        }
+    }
+
+    fn guard(&self, event: &Self::Event, context: &Self::Context) -> bool {
+        !matches!(event, Events::Start) || !context.locked
+    }
+
+    fn handle(&self, _event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+        None
+    }
+
+    fn try_handle(
+        &self,
+        event: &Self::Event,
+        _context: &mut Self::Context,
+    ) -> Result<Option<Self::State>, &'static str> {
+        match (self, event) {
+            (SpindleStates::Idle, Events::Start) => Ok(Some(SpindleStates::Running)),
+            (SpindleStates::Running, Events::Overload) => Err("overload"),
+            _ => Ok(None),
+        }
+    }
+}
+
+#[test]
+fn restart_test() {
+    let strategy = Strategy {
+        max_restarts: 1,
+        reset: ResetPolicy::PreserveContext,
+        ..Strategy::default()
+    };
+    let mut spindle = Supervisor::new(Spindle::new(), strategy);
+    spindle.dispatch(Events::Start).unwrap();
+
+    // Recovered by a restart
+    assert_eq!(spindle.dispatch(Events::Overload), Ok(()));
+    assert_eq!(spindle.restarts(), 1);
+    assert_eq!(spindle.child().get_current_state(), SpindleStates::Idle);
+    assert_eq!(spindle.child().status(), Status::Running);
+
+    // Out of restarts, escalated
+    spindle.dispatch(Events::Start).unwrap();
+    let overload = DispatchError::Fault(Fault::Handler("overload"));
+    assert_eq!(spindle.dispatch(Events::Overload), Err(overload));
+    assert_eq!(spindle.escalation(), Some(overload));
+    assert_eq!(spindle.child().status(), Status::Stopped);
+    assert_eq!(spindle.dispatch(Events::Start), Err(overload));
+
+    spindle.restart();
+    assert_eq!(spindle.escalation(), None);
+    assert_eq!(spindle.restarts(), 0);
+    spindle.dispatch(Events::Start).unwrap();
+    assert_eq!(spindle.child().context().starts, 3);
+}
+
+#[test]
+fn escalate_test() {
+    let strategy = Strategy {
+        on_guard_rejected: Recovery::Escalate,
+        ..Strategy::default()
+    };
+    let mut spindle = Supervisor::new(Spindle::new(), strategy);
+    spindle.child_mut().context_mut().locked = true;
+
+    let rejected = DispatchError::GuardRejected {
+        state: "Idle",
+        event: "Start",
+    };
+    assert_eq!(spindle.dispatch(Events::Start), Err(rejected));
+    assert_eq!(spindle.escalation(), Some(rejected));
+    assert_eq!(spindle.restarts(), 0);
+}
+
+#[test]
+fn ignore_test() {
+    let strategy = Strategy {
+        on_fault: Recovery::Ignore,
+        ..Strategy::default()
+    };
+    let mut spindle = Supervisor::new(Spindle::new(), strategy);
+    spindle.dispatch(Events::Start).unwrap();
+
+    assert!(spindle
+        .run(|spindle| spindle.dispatch(Events::Overload))
+        .is_err());
+    assert_eq!(spindle.child().get_current_state(), SpindleStates::Faulted);
+    assert_eq!(spindle.escalation(), None);
+    assert_eq!(
+        spindle.into_inner().last_fault(),
+        Some(Fault::Handler("overload"))
+    );
+}
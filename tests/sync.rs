@@ -0,0 +1,85 @@
+#![cfg(feature = "std")]
+
+use std::sync::Arc;
+use std::thread;
+
+use rustfsm::*;
+
+rustfsm!(
+    Turnstile,
+    TurnstileStates { Locked, Unlocked },
+    Events { Coin, Push },
+    Context { coins: u32 = 0 }
+);
+
+impl StateBehavior for TurnstileStates {
+    type State = TurnstileStates;
+    type Event = Events;
+    type Context = Context;
+
+    fn handle(&self, event: &Self::Event, context: &mut Self::Context) -> Option<Self::State> {
+        match event {
+            Events::Coin => {
+                context.coins += 1;
+                Some(TurnstileStates::Unlocked)
+            }
+            Events::Push => Some(TurnstileStates::Locked),
+        }
+    }
+}
+
+#[test]
+fn sync_fsm_test() {
+    let turnstile = Arc::new(SyncFsm::new(Turnstile::new()));
+
+    let workers: Vec<_> = (0..4)
+        .map(|_| {
+            let turnstile = Arc::clone(&turnstile);
+            thread::spawn(move || {
+                for _ in 0..100 {
+                    turnstile.dispatch(Events::Coin).unwrap();
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+
+    assert_eq!(turnstile.state(), TurnstileStates::Unlocked);
+    assert_eq!(turnstile.with(|turnstile| turnstile.context().coins), 400);
+}
+
+fn poison(turnstile: &SyncFsm<Turnstile>) {
+    thread::scope(|scope| {
+        let result = scope
+            .spawn(|| {
+                turnstile.with(|turnstile| {
+                    turnstile.dispatch(Events::Coin).unwrap();
+                    panic!("worker crashed");
+                })
+            })
+            .join();
+        assert!(result.is_err());
+    });
+}
+
+#[test]
+fn poison_policy_test() {
+    let turnstile = SyncFsm::with_policy(Turnstile::new(), PoisonPolicy::Recover);
+    poison(&turnstile);
+    assert_eq!(turnstile.state(), TurnstileStates::Unlocked);
+
+    let turnstile = SyncFsm::with_policy(Turnstile::new(), PoisonPolicy::Reset);
+    poison(&turnstile);
+    assert_eq!(turnstile.state(), TurnstileStates::Locked);
+    assert_eq!(turnstile.into_inner().context().coins, 0);
+}
+
+#[test]
+#[should_panic(expected = "poisoned")]
+fn poison_panic_test() {
+    let turnstile = SyncFsm::new(Turnstile::new());
+    poison(&turnstile);
+    turnstile.state();
+}
@@ -0,0 +1,966 @@
+use std::cell::Cell;
+
+use rustfsm::clock::Clock;
+use rustfsm::*;
+
+thread_local! {
+    static TICKS: Cell<u32> = const { Cell::new(0) };
+}
+
+struct TestClock;
+
+impl Clock for TestClock {
+    fn now() -> u32 {
+        TICKS.with(Cell::get)
+    }
+}
+
+fn advance(ticks: u32) {
+    TICKS.with(|now| now.set(now.get().wrapping_add(ticks)));
+}
+
+rustfsm!(
+    Valve,
+    ValveStates {
+        Closed,
+        Open,
+        Draining,
+        @fault Stuck,
+        @final Broken,
+    },
+    Events {
+        Open,
+        Close,
+        Drain,
+        Break,
+    },
+    Context {},
+    Clock { TestClock },
+    Trace { depth: 3 },
+    Watchdog {
+        Open: 20 => Close,
+        Draining: 50 => fault,
+    },
+    Timers { slots: 2 },
+    Dispatch { pausable }
+);
+
+impl StateBehavior for ValveStates {
+    type State = ValveStates;
+    type Event = Events;
+    type Context = Context;
+
+    fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+        match (self, event) {
+            (ValveStates::Closed, Events::Open) => Some(ValveStates::Open),
+            (ValveStates::Open, Events::Close) => Some(ValveStates::Closed),
+            (ValveStates::Open, Events::Drain) => Some(ValveStates::Draining),
+            (_, Events::Break) => Some(ValveStates::Broken),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn trace_test() {
+    let mut valve = Valve::new();
+    assert!(valve.trace().is_empty());
+    assert_eq!(valve.trace().capacity(), 3);
+
+    advance(10);
+    valve.dispatch(Events::Open).unwrap();
+    advance(5);
+    valve.dispatch(Events::Open).unwrap();
+    let record = valve.trace().last().unwrap();
+    assert_eq!(record.timestamp, 15);
+    assert_eq!(
+        (record.from, record.to),
+        (ValveStates::Open, ValveStates::Open)
+    );
+
+    // Oldest records are overwritten, refused events are recorded too
+    advance(5);
+    valve.dispatch(Events::Break).unwrap();
+    advance(5);
+    assert!(valve.dispatch(Events::Close).is_err());
+    let records: Vec<_> = valve.trace().iter().collect();
+    assert_eq!(records.len(), 3);
+    assert_eq!(
+        records
+            .iter()
+            .map(|record| record.timestamp)
+            .collect::<Vec<_>>(),
+        [15, 20, 25]
+    );
+    assert_eq!(records[2].event, Events::Close);
+    assert_eq!(records[2].result, Err(DispatchError::Finished));
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn metrics_test() {
+    use ValveStates::*;
+
+    let mut valve = Valve::new_started();
+    advance(10);
+    valve.dispatch(Events::Close).unwrap();
+    valve.dispatch(Events::Open).unwrap();
+    advance(30);
+    valve.dispatch(Events::Close).unwrap();
+    advance(5);
+    valve.dispatch(Events::Open).unwrap();
+
+    let closed = valve.metrics().get(Closed.index());
+    assert_eq!(closed.entered, 2);
+    assert_eq!(closed.handled, 2);
+    assert_eq!(closed.ignored, 1);
+    assert_eq!(closed.dwell, 15);
+
+    let open = valve.metrics().get(Open.index());
+    assert_eq!(open.entered, 2);
+    assert_eq!(open.handled, 1);
+    assert_eq!(open.dwell, 30);
+
+    // No state there, all zero rather than a panic
+    assert_eq!(valve.metrics().get(2).entered, 0);
+}
+
+#[test]
+fn time_in_current_state_test() {
+    let mut valve = Valve::new_started();
+    advance(7);
+    assert_eq!(valve.time_in_current_state(), 7);
+
+    // Ignored events don't restart the count
+    valve.dispatch(Events::Close).unwrap();
+    advance(3);
+    assert_eq!(valve.time_in_current_state(), 10);
+
+    valve.dispatch(Events::Open).unwrap();
+    assert_eq!(valve.time_in_current_state(), 0);
+    advance(4);
+    assert_eq!(valve.time_in_current_state(), 4);
+}
+
+#[test]
+fn watchdog_test() {
+    use ValveStates::*;
+
+    // An open valve closes by itself
+    let mut valve = Valve::new_started();
+    valve.dispatch(Events::Open).unwrap();
+    advance(20);
+    valve.tick().unwrap();
+    assert_eq!(valve.get_current_state(), Open);
+    advance(1);
+    valve.tick().unwrap();
+    assert_eq!(valve.get_current_state(), Closed);
+
+    // A valve draining for too long is stuck
+    valve.dispatch(Events::Open).unwrap();
+    valve.dispatch(Events::Drain).unwrap();
+    advance(51);
+    assert_eq!(
+        valve.tick(),
+        Err(DispatchError::Fault(Fault::Watchdog("Draining")))
+    );
+    assert_eq!(valve.get_current_state(), Stuck);
+    assert_eq!(valve.last_fault(), Some(Fault::Watchdog("Draining")));
+}
+
+#[test]
+fn force_state_test() {
+    use rustfsm::remote::Remote;
+    use ValveStates::*;
+
+    // A forced state is timed from when it was forced
+    let mut valve = Valve::new_started();
+    valve.dispatch(Events::Open).unwrap();
+    advance(15);
+    valve.force_state(Closed);
+    assert_eq!(valve.time_in_current_state(), 0);
+    valve.force_state(Open);
+    advance(10);
+    valve.tick().unwrap();
+    assert_eq!(valve.get_current_state(), Open);
+
+    advance(5);
+    valve.force_state_checked(Open, ForceMode::Silent).unwrap();
+    advance(10);
+    valve.tick().unwrap();
+    assert_eq!(valve.get_current_state(), Open);
+
+    // Remotely too
+    advance(5);
+    assert!(valve.force_state_id(1));
+    advance(10);
+    valve.tick().unwrap();
+    assert_eq!(valve.get_current_state(), Open);
+    advance(11);
+    valve.tick().unwrap();
+    assert_eq!(valve.get_current_state(), Closed);
+}
+
+#[test]
+fn post_after_test() {
+    use ValveStates::*;
+
+    let mut valve = Valve::new_started();
+    valve.post_after(10, Events::Open).unwrap();
+    valve.post_after(15, Events::Drain).unwrap();
+    assert_eq!(valve.post_after(1, Events::Break), Err(timer::TimersFull));
+
+    advance(9);
+    valve.tick().unwrap();
+    assert_eq!(valve.get_current_state(), Closed);
+
+    // Expired timers are delivered in expiry order
+    advance(10);
+    valve.tick().unwrap();
+    assert_eq!(valve.get_current_state(), Draining);
+    assert!(valve.timers().is_empty());
+
+    // Reset cancels pending timers
+    valve.post_after(5, Events::Break).unwrap();
+    valve.reset(ResetPolicy::RestoreDefaults);
+    valve.start();
+    advance(5);
+    valve.tick().unwrap();
+    assert_eq!(valve.get_current_state(), Closed);
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn stats_test() {
+    use rustfsm::codec::Wire;
+    use rustfsm::stats::Stats;
+    use ValveStates::*;
+
+    let mut valve = Valve::new_started();
+    valve.dispatch(Events::Open).unwrap();
+    valve.dispatch(Events::Drain).unwrap();
+    valve.dispatch(Events::Close).unwrap();
+    advance(7);
+    let stats = valve.stats::<4>();
+    assert_eq!(stats.state, Draining.index() as u8);
+    assert_eq!(stats.time_in_state, 7);
+    assert_eq!(stats.transitions, 2);
+    assert_eq!(stats.recent(), [Open.index() as u8, Draining.index() as u8]);
+
+    // Only the last transitions are kept
+    assert_eq!(valve.stats::<1>().recent(), [Draining.index() as u8]);
+
+    // One telemetry payload
+    let mut payload = [0; Stats::<4>::SIZE];
+    stats.write(&mut payload);
+    assert_eq!(payload.len(), 14);
+    assert_eq!(Stats::<4>::read(&payload), Some(stats));
+}
+
+#[test]
+fn wraparound_test() {
+    use ValveStates::*;
+
+    TICKS.with(|now| now.set(u32::MAX - 5));
+    let mut valve = Valve::new_started();
+    valve.post_after(10, Events::Open).unwrap();
+    advance(9);
+    valve.tick().unwrap();
+    assert_eq!(valve.get_current_state(), Closed);
+
+    // The timer and the watchdog fire across the wraparound of the clock
+    advance(1);
+    valve.tick().unwrap();
+    assert_eq!(valve.get_current_state(), Open);
+    advance(20);
+    valve.tick().unwrap();
+    assert_eq!(valve.time_in_current_state(), 20);
+    assert_eq!(valve.get_current_state(), Open);
+    advance(1);
+    valve.tick().unwrap();
+    assert_eq!(valve.get_current_state(), Closed);
+}
+
+#[test]
+fn is_reached_test() {
+    use rustfsm::clock::{elapsed, is_reached};
+
+    assert_eq!(elapsed(u32::MAX - 1, 3), 5);
+    assert!(is_reached(3, u32::MAX - 1));
+    assert!(!is_reached(u32::MAX - 1, 3));
+    assert!(is_reached(7, 7));
+}
+
+#[test]
+fn cancel_timer_test() {
+    use ValveStates::*;
+
+    let mut valve = Valve::new_started();
+    let open = valve.post_after(10, Events::Open).unwrap();
+    valve.post_after(5, Events::Drain).unwrap();
+    assert!(valve.cancel_timer(open));
+    assert!(!valve.cancel_timer(open));
+    assert_eq!(valve.cancel_all_matching(&Events::Drain), 1);
+    assert!(valve.timers().is_empty());
+
+    advance(10);
+    valve.tick().unwrap();
+    assert_eq!(valve.get_current_state(), Closed);
+}
+
+#[test]
+fn pause_test() {
+    use ValveStates::*;
+
+    let mut valve = Valve::new_started();
+    valve.dispatch(Events::Open).unwrap();
+    valve.post_after(15, Events::Drain).unwrap();
+    advance(10);
+
+    // Time stands still while paused
+    valve.pause(PausePolicy::Reject);
+    assert!(valve.is_paused());
+    assert_eq!(valve.dispatch(Events::Close), Err(DispatchError::Paused));
+    // No queue to keep the events in
+    valve.pause(PausePolicy::Queue);
+    assert_eq!(valve.dispatch(Events::Close), Err(DispatchError::Paused));
+    advance(100);
+    valve.tick().unwrap();
+    assert_eq!(valve.get_current_state(), Open);
+
+    assert_eq!(valve.resume(), Ok(0));
+    assert!(!valve.is_paused());
+    assert_eq!(valve.time_in_current_state(), 10);
+    advance(5);
+    valve.tick().unwrap();
+    assert_eq!(valve.get_current_state(), Draining);
+
+    // Stopping ends the pause
+    valve.pause(PausePolicy::Reject);
+    valve.stop();
+    assert!(!valve.is_paused());
+    assert_eq!(valve.dispatch(Events::Close), Err(DispatchError::Stopped));
+}
+
+#[cfg(feature = "fugit")]
+#[test]
+fn fugit_clock_test() {
+    use fugit::ExtU32;
+    use rustfsm::clock::{ticks, FugitClock, InstantSource};
+
+    struct Monotonic;
+
+    impl InstantSource<1_000> for Monotonic {
+        fn now() -> fugit::TimerInstantU32<1_000> {
+            fugit::TimerInstantU32::from_ticks(TestClock::now())
+        }
+    }
+
+    advance(42);
+    assert_eq!(FugitClock::<Monotonic, 1_000>::now(), TestClock::now());
+    assert_eq!(ticks::<1_000, 1, 1>(3.secs()), 3_000);
+    assert_eq!(ticks::<100, 1, 1_000>(250.millis()), 25);
+}
+
+mod button {
+    use super::TestClock;
+    use rustfsm::filter::Debounce;
+    use rustfsm::*;
+
+    rustfsm!(
+        Button,
+        ButtonStates {
+            Released,
+            Pressed,
+        },
+        Events {
+            Press,
+            Release,
+        },
+        Context {
+            presses: u8 = 0
+        },
+        Clock { TestClock },
+        Filter { Debounce<Events, 10> }
+    );
+
+    impl StateBehavior for ButtonStates {
+        type State = ButtonStates;
+        type Event = Events;
+        type Context = Context;
+
+        fn handle(&self, event: &Self::Event, context: &mut Self::Context) -> Option<Self::State> {
+            match event {
+                Events::Press => {
+                    context.presses += 1;
+                    Some(ButtonStates::Pressed)
+                }
+                Events::Release => Some(ButtonStates::Released),
+            }
+        }
+    }
+}
+
+#[test]
+fn filter_test() {
+    use button::*;
+
+    let mut button = Button::new();
+    assert_eq!(button.dispatch(Events::Press), Ok(()));
+    advance(3);
+    assert_eq!(button.dispatch(Events::Press), Err(DispatchError::Filtered));
+    assert_eq!(button.dispatch(Events::Release), Ok(()));
+    assert_eq!(button.dispatch(Events::Press), Ok(()));
+    advance(12);
+    assert_eq!(button.dispatch(Events::Press), Ok(()));
+    assert_eq!(*button.context().presses(), 3);
+}
+
+mod ordering {
+    use super::{advance, TestClock};
+    use rustfsm::*;
+
+    rustfsm!(
+        Logger,
+        LoggerStates {
+            Logging,
+            Idle,
+        },
+        Events {
+            Timeout,
+            Sample,
+            Alarm,
+        },
+        Context {
+            log: [Option<Events>; 4] = [None; 4],
+            len: usize = 0
+        },
+        Clock { TestClock },
+        Timers { slots: 2 },
+        Queue {
+            depth: 2,
+            priority: |event| match event {
+                Events::Alarm => 2,
+                Events::Timeout => 1,
+                Events::Sample => 0,
+            },
+        },
+        Dispatch { timeouts(arrival) }
+    );
+
+    impl StateBehavior for LoggerStates {
+        type State = LoggerStates;
+        type Event = Events;
+        type Context = Context;
+
+        fn handle(&self, event: &Self::Event, context: &mut Self::Context) -> Option<Self::State> {
+            context.log[context.len] = Some(*event);
+            context.len += 1;
+            None
+        }
+    }
+
+    #[test]
+    fn arrival_order_test() {
+        use Events::*;
+
+        // The sample was posted before the timer expired
+        let mut logger = Logger::new_started();
+        logger.post_after(5, Timeout).unwrap();
+        advance(3);
+        logger.post(Sample).unwrap();
+        advance(3);
+        assert_eq!(logger.process_all(), Ok(2));
+        assert_eq!(logger.context().log, [Some(Sample), Some(Timeout), None, None]);
+
+        // The timer expired before the sample was posted
+        logger.post_after(1, Timeout).unwrap();
+        advance(4);
+        logger.post(Sample).unwrap();
+        logger.tick().unwrap();
+        assert_eq!(logger.context().log[2..], [Some(Timeout), Some(Sample)]);
+    }
+}
+
+mod ranking {
+    use super::{advance, TestClock};
+    use rustfsm::*;
+
+    rustfsm!(
+        Ranker,
+        RankerStates {
+            Ranking,
+            Idle,
+        },
+        Events {
+            Timeout,
+            Sample,
+            Alarm,
+        },
+        Context {
+            log: [Option<Events>; 4] = [None; 4],
+            len: usize = 0
+        },
+        Clock { TestClock },
+        Timers { slots: 2 },
+        Queue {
+            depth: 2,
+            priority: |event| match event {
+                Events::Alarm => 2,
+                Events::Timeout => 1,
+                Events::Sample => 0,
+            },
+        },
+        Dispatch { timeouts(priority) }
+    );
+
+    impl StateBehavior for RankerStates {
+        type State = RankerStates;
+        type Event = Events;
+        type Context = Context;
+
+        fn handle(&self, event: &Self::Event, context: &mut Self::Context) -> Option<Self::State> {
+            context.log[context.len] = Some(*event);
+            context.len += 1;
+            None
+        }
+    }
+
+    #[test]
+    fn priority_order_test() {
+        use Events::*;
+
+        // The timeout ranks between the alarm and the sample, whatever the
+        // order they arrived in
+        let mut ranker = Ranker::new_started();
+        ranker.post(Sample).unwrap();
+        ranker.post_after(1, Timeout).unwrap();
+        ranker.post(Alarm).unwrap();
+        advance(2);
+        assert_eq!(ranker.process_all(), Ok(3));
+        assert_eq!(ranker.context().log, [Some(Alarm), Some(Timeout), Some(Sample), None]);
+    }
+}
+
+mod budget {
+    use super::{advance, TestClock};
+    use rustfsm::*;
+
+    rustfsm!(
+        Sorter,
+        SorterStates { Sorting, Jammed },
+        Events { Parcel, Jam },
+        Context { sorted: u32 = 0 },
+        Clock { TestClock },
+        Queue { depth: 4 }
+    );
+
+    impl StateBehavior for SorterStates {
+        type State = SorterStates;
+        type Event = Events;
+        type Context = Context;
+
+        fn handle(&self, event: &Self::Event, context: &mut Self::Context) -> Option<Self::State> {
+            // Every parcel takes 2 ticks to sort
+            advance(2);
+            match event {
+                Events::Parcel => {
+                    context.sorted += 1;
+                    None
+                }
+                Events::Jam => Some(SorterStates::Jammed),
+            }
+        }
+    }
+
+    #[test]
+    fn process_budget_test() {
+        let mut sorter = Sorter::new_started();
+        for _ in 0..4 {
+            sorter.post(Events::Parcel).unwrap();
+        }
+
+        // The second parcel starts within the budget and runs past it
+        let progress = sorter.process_budget(3).unwrap();
+        assert_eq!(progress.dispatched, 2);
+        assert_eq!(progress.remaining, 2);
+        assert!(!progress.is_done());
+
+        let progress = sorter.process_budget(10).unwrap();
+        assert_eq!(progress.dispatched, 2);
+        assert!(progress.is_done());
+        assert_eq!(sorter.context().sorted, 4);
+        assert_eq!(sorter.process_budget(0).unwrap().dispatched, 0);
+    }
+}
+
+mod hysteresis {
+    use super::{advance, TestClock};
+    use rustfsm::*;
+
+    rustfsm!(
+        Uplink,
+        UplinkStates {
+            Disconnected,
+            Connected,
+        },
+        Events {
+            LinkUp,
+            LinkDown,
+        },
+        Context {},
+        Clock { TestClock },
+        Trace { depth: 4 },
+        Transitions {
+            Disconnected + LinkUp => Connected @ min_interval(50),
+            t_lost: Connected + LinkDown => Disconnected @ min_interval(200),
+            _ + _ => ignore,
+        }
+    );
+
+    impl StateBehavior for UplinkStates {
+        type State = UplinkStates;
+        type Event = Events;
+        type Context = Context;
+
+        fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+            self.next_state(event)
+        }
+    }
+
+    #[test]
+    fn min_interval_test() {
+        use UplinkStates::*;
+
+        let mut uplink = Uplink::new();
+        advance(50);
+        uplink.dispatch(Events::LinkUp).unwrap();
+        assert_eq!(uplink.get_current_state(), Connected);
+
+        // A flapping link stays connected for the interval
+        advance(199);
+        uplink.dispatch(Events::LinkDown).unwrap();
+        assert_eq!(uplink.get_current_state(), Connected);
+        advance(1);
+        uplink.dispatch(Events::LinkDown).unwrap();
+        assert_eq!(uplink.get_current_state(), Disconnected);
+        uplink.dispatch(Events::LinkUp).unwrap();
+        assert_eq!(uplink.get_current_state(), Disconnected);
+
+        let (_, rule) = table::rule_named(UplinkStates::TRANSITION_TABLE, "t_lost").unwrap();
+        assert_eq!(rule.min_interval, 200);
+        assert_eq!(UplinkStates::TRANSITION_TABLE[2].min_interval, 0);
+    }
+
+    #[test]
+    fn too_soon_named_test() {
+        let mut uplink = Uplink::new();
+        advance(50);
+        uplink.dispatch(Events::LinkUp).unwrap();
+
+        // A skipped event takes no rule
+        assert_eq!(uplink.dispatch_named(Events::LinkDown), Ok(None));
+        advance(200);
+        assert_eq!(uplink.dispatch_named(Events::LinkDown), Ok(Some("t_lost")));
+
+        let names: Vec<_> = uplink
+            .trace()
+            .iter()
+            .map(|record| record.transition)
+            .collect();
+        assert_eq!(names, [None, None, Some("t_lost")]);
+    }
+
+    mod replied {
+        use super::{advance, TestClock};
+        use rustfsm::output::Respond;
+        use rustfsm::*;
+
+        #[derive(Clone, Copy, PartialEq, Debug)]
+        pub enum Ack {
+            Ack,
+            Nack,
+        }
+
+        rustfsm!(
+            Pager,
+            PagerStates {
+                Idle,
+                Armed,
+            },
+            Events {
+                Arm,
+                Disarm,
+            },
+            Context {},
+            Clock { TestClock },
+            Reply { Ack },
+            Transitions {
+                Idle + Arm => Armed @ min_interval(10),
+                Armed + Disarm => Idle,
+                _ + _ => ignore,
+            }
+        );
+
+        impl StateBehavior for PagerStates {
+            type State = PagerStates;
+            type Event = Events;
+            type Context = Context;
+
+            fn handle(
+                &self,
+                event: &Self::Event,
+                context: &mut Self::Context,
+            ) -> Option<Self::State> {
+                self.handle_reply(event, context).0
+            }
+        }
+
+        impl Respond for PagerStates {
+            type Reply = Ack;
+
+            fn handle_reply(
+                &self,
+                event: &Self::Event,
+                _context: &mut Self::Context,
+            ) -> (Option<Self::State>, Ack) {
+                match self.next_state(event) {
+                    Some(state) => (Some(state), Ack::Ack),
+                    None => (None, Ack::Nack),
+                }
+            }
+        }
+
+        #[test]
+        fn too_soon_reply_test() {
+            // A skipped event gets no reply, without being unhandled
+            let mut pager = Pager::new();
+            assert_eq!(pager.dispatch_with_reply(Events::Arm), Ok(None));
+            assert_eq!(pager.get_current_state(), PagerStates::Idle);
+
+            advance(10);
+            assert_eq!(pager.dispatch_with_reply(Events::Arm), Ok(Some(Ack::Ack)));
+            assert_eq!(pager.dispatch_with_reply(Events::Arm), Ok(Some(Ack::Nack)));
+        }
+    }
+
+    mod strict {
+        use super::{advance, TestClock};
+        use rustfsm::*;
+
+        rustfsm!(
+            Alarm,
+            AlarmStates {
+                Idle,
+                Armed,
+            },
+            Events {
+                Arm,
+                Disarm,
+            },
+            Context {},
+            Clock { TestClock },
+            Queue { depth: 2 },
+            Transitions {
+                Idle + Arm => Armed @ min_interval(10),
+                Armed + Disarm => Idle,
+                _ + _ => ignore,
+            },
+            Dispatch { unhandled(error) }
+        );
+
+        impl StateBehavior for AlarmStates {
+            type State = AlarmStates;
+            type Event = Events;
+            type Context = Context;
+
+            fn handle(
+                &self,
+                event: &Self::Event,
+                _context: &mut Self::Context,
+            ) -> Option<Self::State> {
+                self.next_state(event)
+            }
+        }
+
+        #[test]
+        fn too_soon_strict_test() {
+            // Skipped rules aren't unhandled events, nor drop the queue
+            let mut alarm = Alarm::new();
+            alarm.post(Events::Arm).unwrap();
+            alarm.post(Events::Arm).unwrap();
+            assert_eq!(alarm.process_all(), Ok(2));
+            assert_eq!(alarm.get_current_state(), AlarmStates::Idle);
+
+            advance(10);
+            assert_eq!(alarm.dispatch(Events::Arm), Ok(()));
+            assert_eq!(alarm.get_current_state(), AlarmStates::Armed);
+        }
+    }
+
+    mod asserted {
+        use super::{advance, TestClock};
+        use rustfsm::*;
+
+        rustfsm!(
+            Siren,
+            SirenStates {
+                Idle,
+                Armed,
+            },
+            Events {
+                Arm,
+                Disarm,
+            },
+            Context {},
+            Clock { TestClock },
+            Transitions {
+                Idle + Arm => Armed @ min_interval(10),
+                Armed + Disarm => Idle,
+                _ + _ => ignore,
+            },
+            Dispatch { unhandled(assert) }
+        );
+
+        impl StateBehavior for SirenStates {
+            type State = SirenStates;
+            type Event = Events;
+            type Context = Context;
+
+            fn handle(
+                &self,
+                event: &Self::Event,
+                _context: &mut Self::Context,
+            ) -> Option<Self::State> {
+                self.next_state(event)
+            }
+        }
+
+        #[test]
+        fn too_soon_assert_test() {
+            let mut siren = Siren::new();
+            assert_eq!(siren.dispatch(Events::Arm), Ok(()));
+            assert_eq!(siren.get_current_state(), SirenStates::Idle);
+
+            advance(10);
+            siren.dispatch(Events::Arm).unwrap();
+            assert_eq!(siren.get_current_state(), SirenStates::Armed);
+        }
+    }
+
+    mod hooked {
+        use super::{advance, TestClock};
+        use rustfsm::*;
+
+        rustfsm!(
+            Beacon,
+            BeaconStates {
+                Idle,
+                Armed,
+            },
+            Events {
+                Arm,
+                Disarm,
+            },
+            Context { unhandled: u8 = 0 },
+            Clock { TestClock },
+            Transitions {
+                Idle + Arm => Armed @ min_interval(10),
+                Armed + Disarm => Idle,
+                _ + _ => ignore,
+            },
+            Dispatch { unhandled(hook) }
+        );
+
+        impl StateBehavior for BeaconStates {
+            type State = BeaconStates;
+            type Event = Events;
+            type Context = Context;
+
+            fn handle(
+                &self,
+                event: &Self::Event,
+                _context: &mut Self::Context,
+            ) -> Option<Self::State> {
+                self.next_state(event)
+            }
+
+            fn unhandled(&self, _event: &Self::Event, context: &mut Self::Context) {
+                context.unhandled += 1;
+            }
+        }
+
+        #[test]
+        fn too_soon_hook_test() {
+            let mut beacon = Beacon::new();
+            beacon.dispatch(Events::Arm).unwrap();
+            assert_eq!(beacon.get_current_state(), BeaconStates::Idle);
+            assert_eq!(beacon.context().unhandled, 0);
+
+            advance(10);
+            beacon.dispatch(Events::Arm).unwrap();
+            assert_eq!(beacon.get_current_state(), BeaconStates::Armed);
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+mod mock {
+    use rustfsm::clock::MockClock;
+    use rustfsm::*;
+
+    rustfsm!(
+        Door,
+        DoorStates {
+            Closed,
+            Open,
+            Alarm,
+        },
+        Events {
+            Open,
+            Close,
+            Timeout,
+        },
+        Context {},
+        Clock { MockClock },
+        Watchdog { Open: 300 => Timeout },
+        Timers { slots: 1 }
+    );
+
+    impl StateBehavior for DoorStates {
+        type State = DoorStates;
+        type Event = Events;
+        type Context = Context;
+
+        fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+            match (self, event) {
+                (DoorStates::Closed, Events::Open) => Some(DoorStates::Open),
+                (_, Events::Close) => Some(DoorStates::Closed),
+                (DoorStates::Open, Events::Timeout) => Some(DoorStates::Alarm),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn mock_clock_test() {
+        let mut door = Door::new_started();
+        door.post_after(1_000, Events::Open).unwrap();
+        MockClock::advance(999);
+        door.tick().unwrap();
+        assert_eq!(door.get_current_state(), DoorStates::Closed);
+
+        // Time moves only when told to
+        MockClock::advance(1);
+        door.tick().unwrap();
+        assert_eq!(door.get_current_state(), DoorStates::Open);
+        MockClock::advance(301);
+        door.tick().unwrap();
+        assert_eq!(door.get_current_state(), DoorStates::Alarm);
+
+        MockClock::set(u32::MAX);
+        door.dispatch(Events::Close).unwrap();
+        MockClock::advance(2);
+        assert_eq!(door.time_in_current_state(), 2);
+    }
+}
@@ -0,0 +1,1282 @@
+use rustfsm::*;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum MarioConsumables {
+    Mushroom,
+    Flower,
+    Feather,
+}
+
+use MarioConsumables::*;
+
+rustfsm!(
+    Mario,
+    MarioStates {
+        SmallMario,
+        SuperMario,
+        FireMario,
+        CapeMario,
+        @final DeadMario,
+    },
+    Events {
+        GetConsumable(MarioConsumables),
+        Hit,
+    },
+    Context {},
+    Transitions {
+        SmallMario + GetConsumable(Mushroom) => SuperMario,
+        SmallMario + GetConsumable(Flower) => FireMario,
+        SmallMario + GetConsumable(Feather) => CapeMario,
+        SuperMario + GetConsumable(Mushroom) => ignore,
+        SuperMario + GetConsumable(Flower) => FireMario,
+        SuperMario + GetConsumable(Feather) => CapeMario,
+        FireMario + GetConsumable(Feather) => CapeMario,
+        FireMario + GetConsumable(_) => ignore,
+        CapeMario + GetConsumable(Flower) => FireMario,
+        CapeMario + GetConsumable(_) => ignore,
+        SmallMario + Hit => DeadMario,
+        SuperMario + Hit => SmallMario,
+        FireMario + Hit => SmallMario,
+        CapeMario + Hit => SmallMario,
+        DeadMario + _ => ignore,
+    }
+);
+
+impl StateBehavior for MarioStates {
+    type State = MarioStates;
+    type Event = Events;
+    type Context = Context;
+
+    fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+        self.next_state(event)
+    }
+}
+
+#[test]
+fn transition_table_test() {
+    assert_eq!(
+        MarioStates::SmallMario.next_state(&Events::GetConsumable(Flower)),
+        Some(MarioStates::FireMario)
+    );
+    assert_eq!(
+        MarioStates::FireMario.next_state(&Events::GetConsumable(Mushroom)),
+        None
+    );
+
+    let mut mario = Mario::new();
+    mario.dispatch(Events::GetConsumable(Mushroom)).unwrap();
+    assert_eq!(mario.current_state, MarioStates::SuperMario);
+    mario.dispatch(Events::GetConsumable(Mushroom)).unwrap();
+    assert_eq!(mario.current_state, MarioStates::SuperMario);
+    mario.dispatch(Events::GetConsumable(Feather)).unwrap();
+    assert_eq!(mario.current_state, MarioStates::CapeMario);
+    mario.dispatch(Events::Hit).unwrap();
+    mario.dispatch(Events::Hit).unwrap();
+    assert!(mario.is_finished());
+}
+
+#[test]
+fn unreachable_states_test() {
+    assert!(MarioStates::UNREACHABLE_STATES.is_empty());
+    assert_eq!(Door::UNREACHABLE_STATES, &["Jammed"]);
+}
+
+rustfsm!(
+    DoorMachine,
+    Door {
+        Closed,
+        Open,
+        Jammed,
+    },
+    DoorEvents {
+        Push,
+    },
+    DoorContext {},
+    Transitions {
+        Closed + Push => Open,
+        Open + Push => Closed,
+        Jammed + Push => Closed,
+    }
+);
+
+impl StateBehavior for Door {
+    type State = Door;
+    type Event = DoorEvents;
+    type Context = DoorContext;
+
+    fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+        self.next_state(event)
+    }
+}
+
+#[cfg(feature = "reachability")]
+#[test]
+fn reachability_test() {
+    use MarioStates::*;
+
+    assert!(SmallMario.is_reachable(&CapeMario));
+    assert!(CapeMario.is_reachable(&DeadMario));
+    assert!(!DeadMario.is_reachable(&SmallMario));
+    assert!(!Door::Closed.is_reachable(&Door::Jammed));
+
+    let path = SuperMario.shortest_event_path(&DeadMario).unwrap();
+    assert_eq!(path.events(), &["Hit", "Hit"]);
+    let path = FireMario.shortest_event_path(&SuperMario).unwrap();
+    assert_eq!(path.events(), &["Hit", "GetConsumable(Mushroom)"]);
+    assert!(SmallMario
+        .shortest_event_path(&SmallMario)
+        .unwrap()
+        .is_empty());
+    assert!(DeadMario.shortest_event_path(&SmallMario).is_none());
+}
+
+#[cfg(feature = "coverage")]
+#[test]
+fn coverage_test() {
+    let mut door = DoorMachine::new();
+    let report = door.coverage_report();
+    assert_eq!((report.covered, report.total), (0, 3));
+
+    door.dispatch(DoorEvents::Push).unwrap();
+    door.dispatch(DoorEvents::Push).unwrap();
+    let report = door.coverage_report();
+    assert_eq!((report.covered, report.total), (2, 3));
+    assert!(!report.is_complete());
+
+    let uncovered: Vec<_> = door.uncovered_transitions().collect();
+    assert_eq!(uncovered.len(), 1);
+    assert_eq!(Door::NAMES[uncovered[0].from], "Jammed");
+
+    // Forcing into the dead state lets the last rule fire
+    door.force_state(Door::Jammed);
+    door.dispatch(DoorEvents::Push).unwrap();
+    assert!(door.coverage_report().is_complete());
+}
+
+#[test]
+fn force_state_checked_test() {
+    let mut mario = Mario::new();
+    assert_eq!(
+        mario.force_state_checked(MarioStates::DeadMario, ForceMode::Silent),
+        Ok(())
+    );
+    assert!(mario.is_finished());
+
+    // Nothing leaves the final state
+    assert_eq!(
+        mario.force_state_checked(MarioStates::SmallMario, ForceMode::Silent),
+        Err(ForceError::Unreachable)
+    );
+    assert_eq!(mario.current_state, MarioStates::DeadMario);
+
+    // The raw escape hatch still works
+    mario.force_state(MarioStates::SmallMario);
+    assert_eq!(mario.current_state, MarioStates::SmallMario);
+}
+
+#[allow(clippy::enum_variant_names)]
+mod jump_table {
+    use super::MarioConsumables::{self, *};
+    use rustfsm::*;
+
+    // Distinct name, the C FFI symbols are global
+    rustfsm!(
+        JumpMario,
+        MarioStates {
+            SmallMario,
+            SuperMario,
+            FireMario,
+            CapeMario,
+            @final DeadMario,
+        },
+        Events {
+            GetConsumable(MarioConsumables),
+            Hit,
+        },
+        Context {},
+        Transitions {
+            SmallMario + GetConsumable(Mushroom) => SuperMario,
+            SmallMario + GetConsumable(Flower) => FireMario,
+            SmallMario + GetConsumable(Feather) => CapeMario,
+            SuperMario + GetConsumable(Mushroom) => ignore,
+            SuperMario + GetConsumable(Flower) => FireMario,
+            SuperMario + GetConsumable(Feather) => CapeMario,
+            FireMario + GetConsumable(Feather) => CapeMario,
+            FireMario + GetConsumable(_) => ignore,
+            CapeMario + GetConsumable(Flower) => FireMario,
+            CapeMario + GetConsumable(_) => ignore,
+            SmallMario + Hit => DeadMario,
+            SuperMario + Hit => SmallMario,
+            FireMario + Hit => SmallMario,
+            CapeMario + Hit => SmallMario,
+            DeadMario + _ => ignore,
+        },
+        Dispatch { jump_table, inline }
+    );
+
+    impl StateBehavior for MarioStates {
+        type State = MarioStates;
+        type Event = Events;
+        type Context = Context;
+
+        fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+            self.next_state(event)
+        }
+    }
+
+    #[test]
+    fn jump_table_test() {
+        use super::{Events as MatchEvents, MarioStates as MatchStates};
+
+        let states = [
+            (MarioStates::SmallMario, MatchStates::SmallMario),
+            (MarioStates::SuperMario, MatchStates::SuperMario),
+            (MarioStates::FireMario, MatchStates::FireMario),
+            (MarioStates::CapeMario, MatchStates::CapeMario),
+            (MarioStates::DeadMario, MatchStates::DeadMario),
+        ];
+        let events = [
+            (
+                Events::GetConsumable(Mushroom),
+                MatchEvents::GetConsumable(Mushroom),
+            ),
+            (
+                Events::GetConsumable(Flower),
+                MatchEvents::GetConsumable(Flower),
+            ),
+            (
+                Events::GetConsumable(Feather),
+                MatchEvents::GetConsumable(Feather),
+            ),
+            (Events::Hit, MatchEvents::Hit),
+        ];
+
+        // Both lookups agree on every pair
+        for (state, match_state) in states {
+            for (event, match_event) in events {
+                assert_eq!(
+                    state.next_state(&event).map(|to| to.index()),
+                    match_state.next_state(&match_event).map(|to| to.index())
+                );
+            }
+        }
+
+        let mut mario = JumpMario::new();
+        mario.dispatch(Events::GetConsumable(Flower)).unwrap();
+        assert_eq!(mario.get_current_state(), MarioStates::FireMario);
+    }
+}
+
+mod inline_never {
+    use rustfsm::*;
+
+    rustfsm!(
+        ColdPath,
+        ColdStates {
+            Armed,
+            Tripped,
+        },
+        Events {
+            Trip,
+        },
+        Context {},
+        Dispatch { inline(never) }
+    );
+
+    impl StateBehavior for ColdStates {
+        type State = ColdStates;
+        type Event = Events;
+        type Context = Context;
+
+        fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+            match event {
+                Events::Trip => Some(ColdStates::Tripped),
+            }
+        }
+    }
+
+    #[test]
+    fn inline_never_test() {
+        let mut breaker = ColdPath::new();
+        breaker.dispatch(Events::Trip).unwrap();
+        assert_eq!(breaker.get_current_state(), ColdStates::Tripped);
+    }
+}
+
+mod any_state {
+    use rustfsm::table::ANY_STATE;
+    use rustfsm::*;
+
+    rustfsm!(
+        Conveyor,
+        ConveyorStates {
+            Idle,
+            Running,
+            Jammed,
+            Halted,
+        },
+        Events {
+            Start,
+            Jam,
+            Clear,
+            EmergencyStop,
+        },
+        Context {},
+        Transitions {
+            _ + EmergencyStop => Halted,
+            Idle + Start => Running,
+            Running + Jam => Jammed,
+            Jammed + Clear => Idle,
+            Halted + EmergencyStop => ignore,
+            Halted + Clear => Idle,
+            _ + _ => ignore,
+        }
+    );
+
+    impl StateBehavior for ConveyorStates {
+        type State = ConveyorStates;
+        type Event = Events;
+        type Context = Context;
+
+        fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+            self.next_state(event)
+        }
+    }
+
+    mod jump_table {
+        use rustfsm::*;
+
+        rustfsm!(
+            JumpConveyor,
+            ConveyorStates {
+                Idle,
+                Running,
+                Jammed,
+                Halted,
+            },
+            Events {
+                Start,
+                Jam,
+                Clear,
+                EmergencyStop,
+            },
+            Context {},
+            Transitions {
+                _ + EmergencyStop => Halted,
+                Idle + Start => Running,
+                Running + Jam => Jammed,
+                Jammed + Clear => Idle,
+                Halted + EmergencyStop => ignore,
+                Halted + Clear => Idle,
+                _ + _ => ignore,
+            },
+            Dispatch { jump_table }
+        );
+
+        impl StateBehavior for ConveyorStates {
+            type State = ConveyorStates;
+            type Event = Events;
+            type Context = Context;
+
+            fn handle(
+                &self,
+                event: &Self::Event,
+                _context: &mut Self::Context,
+            ) -> Option<Self::State> {
+                self.next_state(event)
+            }
+        }
+    }
+
+    #[test]
+    fn any_state_test() {
+        use ConveyorStates::*;
+
+        // Any state stops, but per-state rules come first
+        for state in [Idle, Running, Jammed] {
+            assert_eq!(state.next_state(&Events::EmergencyStop), Some(Halted));
+        }
+        assert_eq!(Halted.next_state(&Events::EmergencyStop), None);
+        assert_eq!(Idle.next_state(&Events::Jam), None);
+        assert_eq!(Halted.next_state(&Events::Clear), Some(Idle));
+
+        assert_eq!(ConveyorStates::TRANSITION_TABLE[0].from, ANY_STATE);
+        assert_eq!(
+            Halted
+                .table_rule(&Events::EmergencyStop)
+                .map(|rule| rule.from),
+            Some(Halted.index())
+        );
+        assert_eq!(
+            Running
+                .table_rule(&Events::EmergencyStop)
+                .map(|rule| rule.from),
+            Some(ANY_STATE)
+        );
+        assert!(ConveyorStates::UNREACHABLE_STATES.is_empty());
+
+        // The jump table agrees
+        let states = [
+            jump_table::ConveyorStates::Idle,
+            jump_table::ConveyorStates::Running,
+            jump_table::ConveyorStates::Jammed,
+            jump_table::ConveyorStates::Halted,
+        ];
+        let events = [
+            (Events::Start, jump_table::Events::Start),
+            (Events::Jam, jump_table::Events::Jam),
+            (Events::Clear, jump_table::Events::Clear),
+            (Events::EmergencyStop, jump_table::Events::EmergencyStop),
+        ];
+        for (index, jump_state) in states.into_iter().enumerate() {
+            let state = [Idle, Running, Jammed, Halted][index];
+            for (event, jump_event) in events {
+                assert_eq!(
+                    state.next_state(&event).map(|to| to.index()),
+                    jump_state.next_state(&jump_event).map(|to| to.index())
+                );
+            }
+        }
+
+        let mut conveyor = Conveyor::new();
+        conveyor.dispatch(Events::Start).unwrap();
+        conveyor.dispatch(Events::EmergencyStop).unwrap();
+        assert_eq!(conveyor.get_current_state(), Halted);
+    }
+
+    #[test]
+    fn transitions_from_test() {
+        use ConveyorStates::*;
+
+        let outgoing = |state: ConveyorStates| -> Vec<_> {
+            state
+                .transitions_from()
+                .map(|rule| (rule.event, ConveyorStates::NAMES[rule.to.unwrap()]))
+                .collect()
+        };
+        assert_eq!(
+            outgoing(Idle),
+            [("EmergencyStop", "Halted"), ("Start", "Running")]
+        );
+        // The rule of the state overrides the one for any state
+        assert_eq!(outgoing(Halted), [("Clear", "Idle")]);
+    }
+}
+
+mod event_groups {
+    use rustfsm::*;
+
+    rustfsm!(
+        Player,
+        PlayerStates {
+            Stopped,
+            Playing,
+            Paused,
+        },
+        Events {
+            Play,
+            Resume,
+            Pause,
+            Stop,
+        },
+        Context {},
+        Transitions {
+            Stopped + (Play | Resume) => Playing,
+            Paused + (Play | Resume) => Playing,
+            Playing + Pause => Paused,
+            _ + Stop => Stopped,
+            _ + (Play | Resume | Pause) => ignore,
+        },
+        Dispatch { jump_table }
+    );
+
+    impl StateBehavior for PlayerStates {
+        type State = PlayerStates;
+        type Event = Events;
+        type Context = Context;
+
+        fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+            self.next_state(event)
+        }
+    }
+
+    #[test]
+    fn event_groups_test() {
+        use PlayerStates::*;
+
+        for event in [Events::Play, Events::Resume] {
+            assert_eq!(Stopped.next_state(&event), Some(Playing));
+            assert_eq!(Paused.next_state(&event), Some(Playing));
+            assert_eq!(Playing.next_state(&event), None);
+        }
+
+        // A group is a single rule
+        assert_eq!(PlayerStates::TRANSITION_TABLE.len(), 5);
+        assert_eq!(PlayerStates::TRANSITION_TABLE[0].event, "(Play | Resume)");
+    }
+
+    #[test]
+    fn legal_events_test() {
+        use PlayerStates::*;
+
+        assert!(Playing.accepts(&Events::Pause));
+        assert!(!Playing.accepts(&Events::Play));
+
+        let legal: Vec<_> = Stopped.legal_events().collect();
+        assert_eq!(legal, [&Events::Play, &Events::Resume, &Events::Stop]);
+        let legal: Vec<_> = Playing.legal_events().collect();
+        assert_eq!(legal, [&Events::Pause, &Events::Stop]);
+    }
+}
+
+mod unhandled {
+    use rustfsm::*;
+
+    rustfsm!(
+        Door,
+        DoorStates { Closed, Open },
+        Events { Push, Pull },
+        Context { refused: u8 = 0 },
+        Dispatch { unhandled(hook) }
+    );
+
+    impl StateBehavior for DoorStates {
+        type State = DoorStates;
+        type Event = Events;
+        type Context = Context;
+
+        fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+            match (self, event) {
+                (DoorStates::Closed, Events::Push) => Some(DoorStates::Open),
+                (DoorStates::Open, Events::Pull) => Some(DoorStates::Closed),
+                _ => None,
+            }
+        }
+
+        fn unhandled(&self, _event: &Self::Event, context: &mut Self::Context) {
+            context.refused += 1;
+        }
+    }
+
+    #[test]
+    fn unhandled_hook_test() {
+        let mut door = Door::new();
+        door.dispatch(Events::Pull).unwrap();
+        door.dispatch(Events::Push).unwrap();
+        door.dispatch(Events::Push).unwrap();
+        assert_eq!(door.get_current_state(), DoorStates::Open);
+        assert_eq!(door.context().refused, 2);
+    }
+
+    mod strict {
+        use rustfsm::*;
+
+        rustfsm!(
+            Hatch,
+            HatchStates { Shut, Ajar },
+            Events { Lift, Drop },
+            Context {},
+            Dispatch { unhandled(error) }
+        );
+
+        impl StateBehavior for HatchStates {
+            type State = HatchStates;
+            type Event = Events;
+            type Context = Context;
+
+            fn handle(
+                &self,
+                event: &Self::Event,
+                _context: &mut Self::Context,
+            ) -> Option<Self::State> {
+                match (self, event) {
+                    (HatchStates::Shut, Events::Lift) => Some(HatchStates::Ajar),
+                    (HatchStates::Ajar, Events::Drop) => Some(HatchStates::Shut),
+                    _ => None,
+                }
+            }
+        }
+
+        #[test]
+        fn unhandled_error_test() {
+            let mut hatch = Hatch::new();
+            assert_eq!(hatch.dispatch(Events::Drop), Err(DispatchError::Unhandled));
+            assert_eq!(hatch.dispatch(Events::Lift), Ok(()));
+            assert_eq!(hatch.get_current_state(), HatchStates::Ajar);
+        }
+    }
+
+    mod asserted {
+        use rustfsm::*;
+
+        rustfsm!(
+            Latch,
+            LatchStates { Locked, Unlocked },
+            Events { Unlock },
+            Context {},
+            Dispatch { unhandled(assert) }
+        );
+
+        impl StateBehavior for LatchStates {
+            type State = LatchStates;
+            type Event = Events;
+            type Context = Context;
+
+            fn handle(
+                &self,
+                _event: &Self::Event,
+                _context: &mut Self::Context,
+            ) -> Option<Self::State> {
+                match self {
+                    LatchStates::Locked => Some(LatchStates::Unlocked),
+                    LatchStates::Unlocked => None,
+                }
+            }
+        }
+
+        #[cfg(debug_assertions)]
+        #[test]
+        #[should_panic(expected = "unhandled event Unlock in state Unlocked")]
+        fn unhandled_assert_test() {
+            let mut latch = Latch::new();
+            latch.dispatch(Events::Unlock).unwrap();
+            let _ = latch.dispatch(Events::Unlock);
+        }
+    }
+
+    mod ignored {
+        use rustfsm::*;
+
+        rustfsm!(
+            Valve,
+            ValveStates { Shut, Flowing },
+            Events { Open, Close },
+            Context { powered: bool = false },
+            Transitions {
+                Shut + Open => Flowing,
+                Shut + Close => ignore,
+                Flowing + Close => Shut,
+                Flowing + Open => ignore,
+            },
+            Dispatch { unhandled(error) }
+        );
+
+        impl StateBehavior for ValveStates {
+            type State = ValveStates;
+            type Event = Events;
+            type Context = Context;
+
+            fn handle(
+                &self,
+                event: &Self::Event,
+                context: &mut Self::Context,
+            ) -> Option<Self::State> {
+                self.next_state(event).filter(|_| context.powered)
+            }
+        }
+
+        #[test]
+        fn ignore_rules_test() {
+            let mut valve = Valve::new();
+            assert!(ValveStates::Shut.ignores(&Events::Close));
+            assert!(!ValveStates::Shut.ignores(&Events::Open));
+
+            // Ignored on purpose, not an error
+            assert_eq!(valve.dispatch(Events::Close), Ok(()));
+            // Leading nowhere despite its rule
+            assert_eq!(valve.dispatch(Events::Open), Err(DispatchError::Unhandled));
+
+            valve.context_mut().powered = true;
+            valve.dispatch(Events::Open).unwrap();
+            assert_eq!(valve.get_current_state(), ValveStates::Flowing);
+        }
+
+        #[cfg(feature = "coverage")]
+        #[test]
+        fn ignore_coverage_test() {
+            let mut valve = Valve::new();
+            valve.context_mut().powered = true;
+            valve.dispatch(Events::Open).unwrap();
+            valve.dispatch(Events::Close).unwrap();
+
+            let report = valve.coverage_report();
+            assert_eq!((report.covered, report.total), (2, 4));
+            assert_eq!((report.ignores_covered, report.ignores), (0, 2));
+            assert!(report.transitions_complete());
+            assert!(!report.is_complete());
+        }
+    }
+}
+
+mod behavior {
+    use rustfsm::*;
+
+    rustfsm!(
+        Pump,
+        PumpStates {
+            Idle,
+            Priming,
+            Running,
+        },
+        Events {
+            Start,
+            Primed,
+            Stop,
+            Tick,
+        },
+        Context {
+            primed: bool = false,
+            ticks: u32 = 0,
+            starts: u32 = 0,
+        },
+        Transitions {
+            Idle + Start => Priming / count_start,
+            Priming + Primed => Running if is_primed,
+            Running + Tick => ignore / count_tick,
+            _ + Stop => Idle,
+            _ + _ => ignore,
+        },
+        Dispatch { behavior }
+    );
+
+    fn count_start(context: &mut Context, _event: &Events) {
+        context.starts += 1;
+    }
+
+    fn count_tick(context: &mut Context, _event: &Events) {
+        context.ticks += 1;
+    }
+
+    fn is_primed(context: &Context, _event: &Events) -> bool {
+        context.primed
+    }
+
+    #[test]
+    fn behavior_test() {
+        use PumpStates::*;
+
+        let mut pump = Pump::new();
+        pump.dispatch(Events::Start).unwrap();
+        assert_eq!(pump.get_current_state(), Priming);
+        assert_eq!(pump.context().starts, 1);
+
+        assert!(matches!(
+            pump.dispatch(Events::Primed),
+            Err(DispatchError::GuardRejected {
+                state: "Priming",
+                event: "Primed"
+            })
+        ));
+        pump.context_mut().primed = true;
+        pump.dispatch(Events::Primed).unwrap();
+        assert_eq!(pump.get_current_state(), Running);
+
+        pump.dispatch(Events::Tick).unwrap();
+        pump.dispatch(Events::Tick).unwrap();
+        assert_eq!(pump.context().ticks, 2);
+        assert_eq!(pump.get_current_state(), Running);
+
+        pump.dispatch(Events::Stop).unwrap();
+        assert_eq!(pump.get_current_state(), Idle);
+    }
+}
+
+mod any_hooks {
+    use rustfsm::*;
+
+    rustfsm!(
+        Lamp,
+        LampStates { Off, On, Blinking },
+        Events { Toggle, Blink },
+        Context {
+            kicks: u8 = 0,
+            log: [Option<(bool, LampStates)>; 6] = [None; 6],
+            len: usize = 0
+        }
+    );
+
+    impl Context {
+        fn record(&mut self, entered: bool, state: LampStates) {
+            self.log[self.len] = Some((entered, state));
+            self.len += 1;
+        }
+    }
+
+    impl StateBehavior for LampStates {
+        type State = LampStates;
+        type Event = Events;
+        type Context = Context;
+
+        fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+            match (self, event) {
+                (LampStates::Off, Events::Toggle) => Some(LampStates::On),
+                (LampStates::On | LampStates::Blinking, Events::Toggle) => Some(LampStates::Off),
+                (_, Events::Blink) => Some(LampStates::Blinking),
+            }
+        }
+
+        fn enter(&self, context: &mut Self::Context) {
+            if *self == LampStates::Blinking {
+                context.kicks = 0;
+            }
+        }
+
+        fn on_any_enter(&self, context: &mut Self::Context) {
+            context.kicks += 1;
+            context.record(true, *self);
+        }
+
+        fn on_any_exit(&self, context: &mut Self::Context) {
+            context.record(false, *self);
+        }
+    }
+
+    #[test]
+    fn any_hooks_test() {
+        use LampStates::*;
+
+        let mut lamp = Lamp::new_started();
+        lamp.dispatch(Events::Toggle).unwrap();
+        assert_eq!(lamp.context().kicks, 2);
+
+        // The common entry hook runs before the state's own
+        lamp.dispatch(Events::Blink).unwrap();
+        assert_eq!(lamp.context().kicks, 0);
+        assert_eq!(
+            lamp.context().log,
+            [
+                Some((true, Off)),
+                Some((false, Off)),
+                Some((true, On)),
+                Some((false, On)),
+                Some((true, Blinking)),
+                None,
+            ]
+        );
+    }
+}
+
+mod named {
+    use rustfsm::table::rule_named;
+    use rustfsm::*;
+
+    rustfsm!(
+        Link,
+        LinkStates {
+            Idle,
+            Connecting,
+            Connected,
+        },
+        Events {
+            Start,
+            Ack,
+            Drop,
+        },
+        Context {},
+        Trace { depth: 4 },
+        Transitions {
+            t_connect: Idle + Start => Connecting,
+            t_established: Connecting + Ack => Connected,
+            Connected + Ack => ignore,
+            t_drop: _ + Drop => Idle,
+            _ + _ => ignore,
+        }
+    );
+
+    impl StateBehavior for LinkStates {
+        type State = LinkStates;
+        type Event = Events;
+        type Context = Context;
+
+        fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+            self.next_state(event)
+        }
+    }
+
+    #[test]
+    fn named_transitions_test() {
+        let mut link = Link::new();
+        assert_eq!(link.dispatch_named(Events::Start), Ok(Some("t_connect")));
+        assert_eq!(link.dispatch_named(Events::Ack), Ok(Some("t_established")));
+        assert_eq!(link.dispatch_named(Events::Ack), Ok(None));
+        link.dispatch(Events::Drop).unwrap();
+
+        let names: Vec<_> = link
+            .trace()
+            .iter()
+            .map(|record| record.transition)
+            .collect();
+        assert_eq!(
+            names,
+            [
+                Some("t_connect"),
+                Some("t_established"),
+                None,
+                Some("t_drop")
+            ]
+        );
+
+        // Names are stable identifiers of the rules
+        let (id, rule) = rule_named(LinkStates::TRANSITION_TABLE, "t_drop").unwrap();
+        assert_eq!(id, 3);
+        assert_eq!(rule.to, Some(LinkStates::Idle.index()));
+        assert!(rule_named(LinkStates::TRANSITION_TABLE, "t_lost").is_none());
+    }
+}
+
+mod large {
+    use rustfsm::*;
+
+    // Large enough for anything recursing per state or per rule to exceed
+    // the default recursion limit
+    rustfsm!(
+        Ring,
+        RingStates {
+            S0,
+            S1,
+            S2,
+            S3,
+            S4,
+            S5,
+            S6,
+            S7,
+            S8,
+            S9,
+            S10,
+            S11,
+            S12,
+            S13,
+            S14,
+            S15,
+            S16,
+            S17,
+            S18,
+            S19,
+            S20,
+            S21,
+            S22,
+            S23,
+            S24,
+            S25,
+            S26,
+            S27,
+            S28,
+            S29,
+            S30,
+            S31,
+            S32,
+            S33,
+            S34,
+            S35,
+            S36,
+            S37,
+            S38,
+            S39,
+            S40,
+            S41,
+            S42,
+            S43,
+            S44,
+            S45,
+            S46,
+            S47,
+            S48,
+            S49,
+            S50,
+            S51,
+            S52,
+            S53,
+            S54,
+            S55,
+            S56,
+            S57,
+            S58,
+            S59,
+            S60,
+            S61,
+            S62,
+            S63,
+        },
+        Events {
+            Next,
+            Back,
+        },
+        Context {},
+        Transitions {
+            next_0: S0 + Next => S1,
+            S1 + Next => S2,
+            next_2: S2 + Next => S3,
+            S3 + Next => S4,
+            next_4: S4 + Next => S5,
+            S5 + Next => S6,
+            next_6: S6 + Next => S7,
+            S7 + Next => S8,
+            next_8: S8 + Next => S9,
+            S9 + Next => S10,
+            next_10: S10 + Next => S11,
+            S11 + Next => S12,
+            next_12: S12 + Next => S13,
+            S13 + Next => S14,
+            next_14: S14 + Next => S15,
+            S15 + Next => S16,
+            next_16: S16 + Next => S17,
+            S17 + Next => S18,
+            next_18: S18 + Next => S19,
+            S19 + Next => S20,
+            next_20: S20 + Next => S21,
+            S21 + Next => S22,
+            next_22: S22 + Next => S23,
+            S23 + Next => S24,
+            next_24: S24 + Next => S25,
+            S25 + Next => S26,
+            next_26: S26 + Next => S27,
+            S27 + Next => S28,
+            next_28: S28 + Next => S29,
+            S29 + Next => S30,
+            next_30: S30 + Next => S31,
+            S31 + Next => S32,
+            next_32: S32 + Next => S33,
+            S33 + Next => S34,
+            next_34: S34 + Next => S35,
+            S35 + Next => S36,
+            next_36: S36 + Next => S37,
+            S37 + Next => S38,
+            next_38: S38 + Next => S39,
+            S39 + Next => S40,
+            next_40: S40 + Next => S41,
+            S41 + Next => S42,
+            next_42: S42 + Next => S43,
+            S43 + Next => S44,
+            next_44: S44 + Next => S45,
+            S45 + Next => S46,
+            next_46: S46 + Next => S47,
+            S47 + Next => S48,
+            next_48: S48 + Next => S49,
+            S49 + Next => S50,
+            next_50: S50 + Next => S51,
+            S51 + Next => S52,
+            next_52: S52 + Next => S53,
+            S53 + Next => S54,
+            next_54: S54 + Next => S55,
+            S55 + Next => S56,
+            next_56: S56 + Next => S57,
+            S57 + Next => S58,
+            next_58: S58 + Next => S59,
+            S59 + Next => S60,
+            next_60: S60 + Next => S61,
+            S61 + Next => S62,
+            next_62: S62 + Next => S63,
+            S63 + Next => S0,
+            S0 + Back => S63,
+            S1 + Back => S0,
+            S2 + Back => S1,
+            S3 + Back => S2,
+            S4 + Back => S3,
+            S5 + Back => S4,
+            S6 + Back => S5,
+            S7 + Back => S6,
+            S8 + Back => S7,
+            S9 + Back => S8,
+            S10 + Back => S9,
+            S11 + Back => S10,
+            S12 + Back => S11,
+            S13 + Back => S12,
+            S14 + Back => S13,
+            S15 + Back => S14,
+            S16 + Back => S15,
+            S17 + Back => S16,
+            S18 + Back => S17,
+            S19 + Back => S18,
+            S20 + Back => S19,
+            S21 + Back => S20,
+            S22 + Back => S21,
+            S23 + Back => S22,
+            S24 + Back => S23,
+            S25 + Back => S24,
+            S26 + Back => S25,
+            S27 + Back => S26,
+            S28 + Back => S27,
+            S29 + Back => S28,
+            S30 + Back => S29,
+            S31 + Back => S30,
+            S32 + Back => S31,
+            S33 + Back => S32,
+            S34 + Back => S33,
+            S35 + Back => S34,
+            S36 + Back => S35,
+            S37 + Back => S36,
+            S38 + Back => S37,
+            S39 + Back => S38,
+            S40 + Back => S39,
+            S41 + Back => S40,
+            S42 + Back => S41,
+            S43 + Back => S42,
+            S44 + Back => S43,
+            S45 + Back => S44,
+            S46 + Back => S45,
+            S47 + Back => S46,
+            S48 + Back => S47,
+            S49 + Back => S48,
+            S50 + Back => S49,
+            S51 + Back => S50,
+            S52 + Back => S51,
+            S53 + Back => S52,
+            S54 + Back => S53,
+            S55 + Back => S54,
+            S56 + Back => S55,
+            S57 + Back => S56,
+            S58 + Back => S57,
+            S59 + Back => S58,
+            S60 + Back => S59,
+            S61 + Back => S60,
+            S62 + Back => S61,
+            S63 + Back => S62,
+        },
+        Dispatch { behavior }
+    );
+
+    #[test]
+    fn large_table_test() {
+        assert_eq!(RingStates::NAMES.len(), 64);
+        assert_eq!(RingStates::TRANSITION_TABLE.len(), 128);
+
+        let mut ring = Ring::new();
+        assert_eq!(ring.dispatch_named(Events::Next), Ok(Some("next_0")));
+        assert_eq!(ring.dispatch_named(Events::Next), Ok(None));
+        for _ in 2..64 {
+            ring.dispatch(Events::Next).unwrap();
+        }
+        assert_eq!(ring.get_current_state(), RingStates::S0);
+        ring.dispatch(Events::Back).unwrap();
+        assert_eq!(ring.get_current_state(), RingStates::S63);
+    }
+}
+
+mod validation {
+    use rustfsm::*;
+
+    rustfsm!(
+        Thermostat,
+        ThermostatStates { Idle, Regulating(u8) },
+        Events {
+            Setpoint(u8),
+            Off,
+        },
+        Context {
+            max_setpoint: u8 = 30,
+            handled: u32 = 0,
+        },
+        Validate {
+            check: |event, context| match event {
+                Events::Setpoint(setpoint) if *setpoint > context.max_setpoint => {
+                    Err(ValidationError("setpoint out of range"))
+                }
+                _ => Ok(()),
+            },
+        }
+    );
+
+    impl StateBehavior for ThermostatStates {
+        type State = ThermostatStates;
+        type Event = Events;
+        type Context = Context;
+
+        fn handle(&self, event: &Self::Event, context: &mut Self::Context) -> Option<Self::State> {
+            context.handled += 1;
+            match event {
+                Events::Setpoint(setpoint) => Some(ThermostatStates::Regulating(*setpoint)),
+                Events::Off => Some(ThermostatStates::Idle),
+            }
+        }
+    }
+
+    #[test]
+    fn validate_test() {
+        let mut thermostat = Thermostat::new();
+        thermostat.dispatch(Events::Setpoint(21)).unwrap();
+
+        // Malformed payloads never reach the handlers
+        assert_eq!(
+            thermostat.dispatch(Events::Setpoint(80)),
+            Err(DispatchError::Invalid(ValidationError(
+                "setpoint out of range"
+            )))
+        );
+        assert_eq!(
+            thermostat.get_current_state(),
+            ThermostatStates::Regulating(21)
+        );
+        assert_eq!(thermostat.context().handled, 1);
+
+        // The check sees the context
+        thermostat.context_mut().max_setpoint = 90;
+        thermostat.dispatch(Events::Setpoint(80)).unwrap();
+        assert_eq!(
+            thermostat.get_current_state(),
+            ThermostatStates::Regulating(80)
+        );
+    }
+}
+
+mod commands {
+    use rustfsm::*;
+
+    rustfsm!(
+        Kettle,
+        KettleStates { Idle, Heating(u8) },
+        Events {
+            Boil(u8),
+            Cancel,
+            @internal Reached(u8, bool),
+            @internal Timeout,
+            @internal Probe(u8, u8, u8, u8, u8, u8, u8, u8, u8),
+        },
+        Context {}
+    );
+
+    impl StateBehavior for KettleStates {
+        type State = KettleStates;
+        type Event = Events;
+        type Context = Context;
+
+        fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+            match event {
+                Events::Boil(target) => Some(KettleStates::Heating(*target)),
+                Events::Reached(_, true) | Events::Cancel | Events::Timeout => {
+                    Some(KettleStates::Idle)
+                }
+                Events::Reached(..) | Events::Probe(..) => None,
+            }
+        }
+    }
+
+    /// Public API accepting only the commands
+    fn boil(kettle: &mut Kettle, command: KettleCommand) {
+        kettle.command(command).unwrap();
+    }
+
+    #[test]
+    fn commands_test() {
+        let mut kettle = Kettle::new();
+        boil(&mut kettle, KettleCommand::Boil(95));
+        assert_eq!(kettle.get_current_state(), KettleStates::Heating(95));
+        assert_eq!(Events::from(KettleCommand::Cancel), Events::Cancel);
+
+        // The internal events convert into the events the machine handles
+        kettle
+            .dispatch(KettleInternal::Reached(60, false).into())
+            .unwrap();
+        assert_eq!(kettle.get_current_state(), KettleStates::Heating(95));
+        kettle
+            .dispatch(KettleInternal::Reached(95, true).into())
+            .unwrap();
+        assert_eq!(kettle.get_current_state(), KettleStates::Idle);
+        assert_eq!(Events::from(KettleInternal::Timeout), Events::Timeout);
+
+        // Any number of payload fields
+        assert_eq!(
+            Events::from(KettleInternal::Probe(1, 2, 3, 4, 5, 6, 7, 8, 9)),
+            Events::Probe(1, 2, 3, 4, 5, 6, 7, 8, 9)
+        );
+    }
+}
@@ -0,0 +1,84 @@
+use rustfsm::*;
+
+mod panel {
+    use rustfsm::*;
+
+    rustfsm!(
+        Panel,
+        PanelStates { Off, On },
+        Events {
+            Press,
+            Hold,
+            Release
+        },
+        Context {}
+    );
+
+    impl StateBehavior for PanelStates {
+        type State = PanelStates;
+        type Event = Events;
+        type Context = Context;
+
+        fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+            match event {
+                Events::Press => Some(PanelStates::On),
+                Events::Release => Some(PanelStates::Off),
+                Events::Hold => None,
+            }
+        }
+    }
+}
+
+mod lamp {
+    use rustfsm::*;
+
+    rustfsm!(
+        Lamp,
+        LampStates { Dark, Lit },
+        Events {
+            SwitchOn,
+            SwitchOff
+        },
+        Context {}
+    );
+
+    impl StateBehavior for LampStates {
+        type State = LampStates;
+        type Event = Events;
+        type Context = Context;
+
+        fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+            match event {
+                Events::SwitchOn => Some(LampStates::Lit),
+                Events::SwitchOff => Some(LampStates::Dark),
+            }
+        }
+    }
+}
+
+#[test]
+fn translate_test() {
+    let mut panel = panel::Panel::new();
+    let mut lamp = Translate::new(lamp::Lamp::new(), |event: panel::Events| match event {
+        panel::Events::Press => Some(lamp::Events::SwitchOn),
+        panel::Events::Release => Some(lamp::Events::SwitchOff),
+        panel::Events::Hold => None,
+    });
+
+    for event in [panel::Events::Press, panel::Events::Hold] {
+        panel.dispatch(event).unwrap();
+        let _ = lamp.dispatch(event);
+    }
+    assert_eq!(panel.get_current_state(), panel::PanelStates::On);
+    assert_eq!(lamp.machine().get_current_state(), lamp::LampStates::Lit);
+
+    assert_eq!(
+        lamp.dispatch(panel::Events::Hold),
+        Err(DispatchError::UnexpectedEvent)
+    );
+    assert_eq!(lamp.dispatch(panel::Events::Release), Ok(()));
+    assert_eq!(
+        lamp.into_inner().get_current_state(),
+        lamp::LampStates::Dark
+    );
+}
@@ -0,0 +1,39 @@
+use rustfsm::rustfsm_typestate;
+
+rustfsm_typestate!(
+    Connection,
+    States {
+        Idle,
+        Connecting,
+        Connected,
+    },
+    Transitions {
+        connect: Idle => Connecting,
+        established: Connecting => Connected,
+        timeout: Connecting => Idle,
+        close: Connected => Idle,
+    },
+    Context {
+        attempts: u8 = 0
+    }
+);
+
+#[test]
+fn typestate_test() {
+    let connection = Connection::new();
+    assert_eq!(connection.state_name(), "Idle");
+
+    let mut connection = connection.connect();
+    connection.context_mut().attempts += 1;
+    let connection = connection.timeout();
+    assert_eq!(connection.state_name(), "Idle");
+
+    let mut connection = connection.connect();
+    connection.context_mut().attempts += 1;
+    let connection: Connection<Connected> = connection.established();
+    assert_eq!(*connection.state(), Connected);
+    assert_eq!(*connection.context().attempts(), 2);
+
+    let connection = connection.close();
+    assert_eq!(connection.state_name(), "Idle");
+}
@@ -0,0 +1,21 @@
+// error: the name `Off` is defined multiple times
+use rustfsm::*;
+
+rustfsm!(
+    Lamp,
+    LampStates { Off, On, Off },
+    Events { Toggle },
+    Context {}
+);
+
+impl StateBehavior for LampStates {
+    type State = LampStates;
+    type Event = Events;
+    type Context = Context;
+
+    fn handle(&self, _event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+        None
+    }
+}
+
+fn main() {}
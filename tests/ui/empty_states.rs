@@ -0,0 +1,11 @@
+// error: `LampStates` must list at least one state
+use rustfsm::*;
+
+rustfsm!(
+    Lamp,
+    LampStates {},
+    Events { Toggle },
+    Context {}
+);
+
+fn main() {}
@@ -0,0 +1,6 @@
+// error: `Stats` keeps up to 255 transitions
+use rustfsm::stats::Stats;
+
+fn main() {
+    let _ = Stats::<256>::new(0, 0, 0, core::iter::empty());
+}
@@ -0,0 +1,21 @@
+// error: member `trace` is reserved by the machine
+use rustfsm::*;
+
+rustfsm!(
+    Lamp { trace: u8 = 0 },
+    LampStates { Off, On },
+    Events { Toggle },
+    Context {}
+);
+
+impl StateBehavior for LampStates {
+    type State = LampStates;
+    type Event = Events;
+    type Context = Context;
+
+    fn handle(&self, _event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+        None
+    }
+}
+
+fn main() {}
@@ -0,0 +1,21 @@
+// error: context field `level` shadows a member
+use rustfsm::*;
+
+rustfsm!(
+    Lamp { level: u8 = 0 },
+    LampStates { Off, On },
+    Events { Toggle },
+    Context { level: u8 = 0 }
+);
+
+impl StateBehavior for LampStates {
+    type State = LampStates;
+    type Event = Events;
+    type Context = Context;
+
+    fn handle(&self, _event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+        None
+    }
+}
+
+fn main() {}
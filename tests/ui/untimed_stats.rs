@@ -0,0 +1,24 @@
+// error: `stats` needs a `Clock` section
+use rustfsm::*;
+
+rustfsm!(
+    Lamp,
+    LampStates { Off, On },
+    Events { Toggle },
+    Context {},
+    Trace { depth: 4 }
+);
+
+impl StateBehavior for LampStates {
+    type State = LampStates;
+    type Event = Events;
+    type Context = Context;
+
+    fn handle(&self, _event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+        None
+    }
+}
+
+fn main() {
+    let _ = Lamp::new().stats::<4>();
+}
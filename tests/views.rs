@@ -0,0 +1,70 @@
+use rustfsm::*;
+
+rustfsm!(
+    Scale,
+    ScaleStates {
+        Idle,
+        Calibrating,
+        Weighing,
+    },
+    Events {
+        Calibrate(i32),
+        Weigh(i32),
+        Done,
+    },
+    Context {
+        offset: i32 = 0,
+        gain: i32 = 1,
+        weight: i32 = 0,
+        readings: u32 = 0,
+    },
+    Views {
+        Calibrating { offset, gain },
+        Weighing { offset, weight, readings },
+    }
+);
+
+fn calibrate(view: CalibratingView<'_>, zero: i32) -> Option<ScaleStates> {
+    *view.offset = zero;
+    *view.gain = 2;
+    Some(ScaleStates::Idle)
+}
+
+fn weigh(view: WeighingView<'_>, raw: i32) -> Option<ScaleStates> {
+    *view.weight = raw - *view.offset;
+    *view.readings += 1;
+    None
+}
+
+impl StateBehavior for ScaleStates {
+    type State = ScaleStates;
+    type Event = Events;
+    type Context = Context;
+
+    fn handle(&self, event: &Self::Event, context: &mut Self::Context) -> Option<Self::State> {
+        use ScaleStates::*;
+
+        match (self, event) {
+            (Idle, Events::Calibrate(_)) => Some(Calibrating),
+            (Idle, Events::Weigh(_)) => Some(Weighing),
+            (Calibrating, Events::Calibrate(zero)) => calibrate(context.calibrating_view(), *zero),
+            (Weighing, Events::Weigh(raw)) => weigh(context.weighing_view(), *raw),
+            (_, Events::Done) => Some(Idle),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn views_test() {
+    let mut scale = Scale::new();
+    scale.dispatch(Events::Calibrate(0)).unwrap();
+    scale.dispatch(Events::Calibrate(5)).unwrap();
+    assert_eq!(*scale.context().offset(), 5);
+    assert_eq!(*scale.context().gain(), 2);
+
+    scale.dispatch(Events::Weigh(0)).unwrap();
+    scale.dispatch(Events::Weigh(12)).unwrap();
+    assert_eq!(*scale.context().weight(), 7);
+    assert_eq!(*scale.context().readings(), 1);
+}
@@ -0,0 +1,43 @@
+#![cfg(feature = "wasm")]
+
+use rustfsm::*;
+
+rustfsm!(
+    Elevator,
+    ElevatorStates {
+        Ground,
+        Moving,
+        Floor(u8),
+    },
+    Events {
+        Call,
+        Arrive(u8),
+    },
+    Context {}
+);
+
+impl StateBehavior for ElevatorStates {
+    type State = ElevatorStates;
+    type Event = Events;
+    type Context = Context;
+
+    fn handle(&self, event: &Self::Event, _context: &mut Self::Context) -> Option<Self::State> {
+        match event {
+            Events::Call => Some(ElevatorStates::Moving),
+            Events::Arrive(floor) => Some(ElevatorStates::Floor(*floor)),
+        }
+    }
+}
+
+#[test]
+fn wasm_wrapper_test() {
+    let mut elevator = ElevatorWasm::new();
+    assert_eq!(elevator.current_state(), "Ground");
+    assert!(elevator.dispatch("Call").is_ok());
+    assert_eq!(elevator.current_state(), "Moving");
+    assert_eq!(elevator.current_state_id(), 1);
+    assert!(!elevator.is_finished());
+
+    elevator.reset();
+    assert_eq!(elevator.current_state_id(), 0);
+}